@@ -161,6 +161,9 @@ pub fn clip_map(map: &mut RawMap, timer: &mut Timer) {
     }
     map.areas = result_areas;
 
+    map.bike_share_docks
+        .retain(|dock| boundary_polygon.contains_pt(dock.pt));
+
     // TODO Don't touch parking lots. It'll be visually obvious if a clip intersects one of these.
     // The boundary should be manually adjusted.
 
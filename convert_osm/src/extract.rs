@@ -4,10 +4,10 @@ use abstutil::{retain_btreemap, Tags, Timer};
 use geom::{HashablePt2D, PolyLine, Polygon, Pt2D, Ring};
 use kml::{ExtraShape, ExtraShapes};
 use map_model::raw::{
-    OriginalBuilding, OriginalIntersection, RawArea, RawBuilding, RawBusRoute, RawBusStop, RawMap,
-    RawParkingLot, RawRoad, RestrictionType,
+    OriginalBuilding, OriginalIntersection, RawArea, RawBikeShareDock, RawBuilding, RawBusRoute,
+    RawBusStop, RawMap, RawParkingLot, RawRoad, RestrictionType,
 };
-use map_model::{osm, AreaType};
+use map_model::{osm, AreaType, Crossing};
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::error::Error;
 
@@ -16,6 +16,8 @@ pub struct OsmExtract {
     pub roads: Vec<(WayID, RawRoad)>,
     // Traffic signals to the direction they apply (or just true if unspecified)
     pub traffic_signals: HashMap<HashablePt2D, bool>,
+    // Nodes where pedestrians cross a road, from highway=crossing
+    pub crossings: HashMap<HashablePt2D, Crossing>,
     pub osm_node_ids: HashMap<HashablePt2D, NodeID>,
     // (ID, restriction type, from way ID, via node ID, to way ID)
     pub simple_turn_restrictions: Vec<(RestrictionType, WayID, NodeID, WayID)>,
@@ -23,10 +25,22 @@ pub struct OsmExtract {
     pub complicated_turn_restrictions: Vec<(RelationID, WayID, WayID, WayID)>,
     // (location, name, amenity type)
     pub amenities: Vec<(Pt2D, String, String)>,
+    // (landuse value, area). Only used to infer missing road attributes.
+    pub landuse: Vec<(String, Polygon)>,
 }
 
 pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmExtract {
-    let mut doc = crate::reader::read(&opts.osm_input, &map.gps_bounds, timer).unwrap();
+    let mut doc = if opts.osm_input.ends_with(".pbf") {
+        crate::pbf::read(
+            &opts.osm_input,
+            &map.gps_bounds,
+            opts.clip.as_ref().map(|_| &map.boundary_polygon),
+            timer,
+        )
+    } else {
+        crate::reader::read(&opts.osm_input, &map.gps_bounds, timer)
+    }
+    .unwrap();
     if opts.clip.is_none() {
         // Use the boundary from .osm.
         map.gps_bounds = doc.gps_bounds.clone();
@@ -36,10 +50,12 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
     let mut out = OsmExtract {
         roads: Vec::new(),
         traffic_signals: HashMap::new(),
+        crossings: HashMap::new(),
         osm_node_ids: HashMap::new(),
         simple_turn_restrictions: Vec::new(),
         complicated_turn_restrictions: Vec::new(),
         amenities: Vec::new(),
+        landuse: Vec::new(),
     };
 
     timer.start_iter("processing OSM nodes", doc.nodes.len());
@@ -52,6 +68,36 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
             out.traffic_signals
                 .insert(node.pt.to_hashable(), !backwards);
         }
+        if node.tags.is(osm::HIGHWAY, "crossing") && !node.tags.is("crossing", "no") {
+            out.crossings.insert(
+                node.pt.to_hashable(),
+                if node.tags.is("crossing", "unmarked") {
+                    Crossing::Unmarked
+                } else {
+                    Crossing::Marked
+                },
+            );
+        }
+        if node
+            .tags
+            .is_any("amenity", vec!["bicycle_rental", "kick-scooter_rental"])
+        {
+            map.bike_share_docks.push(RawBikeShareDock {
+                osm_node_id: id.0,
+                pt: node.pt,
+                name: node
+                    .tags
+                    .get("name")
+                    .cloned()
+                    .unwrap_or_else(|| "unnamed".to_string()),
+                // When the capacity isn't tagged, guess something typical
+                capacity: node
+                    .tags
+                    .get("capacity")
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .unwrap_or(10),
+            });
+        }
         if let Some(amenity) = node.tags.get("amenity") {
             out.amenities.push((
                 node.pt,
@@ -102,6 +148,7 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
                     osm_tags: way.tags.clone(),
                     turn_restrictions: Vec::new(),
                     complicated_turn_restrictions: Vec::new(),
+                    elevation: Vec::new(),
                 },
             ));
             continue;
@@ -135,6 +182,10 @@ pub fn extract_osm(map: &mut RawMap, opts: &Options, timer: &mut Timer) -> OsmEx
             continue;
         };
 
+        if let Some(landuse) = way.tags.get("landuse") {
+            out.landuse.push((landuse.clone(), polygon.clone()));
+        }
+
         if is_bldg(&way.tags) {
             map.buildings.insert(
                 OriginalBuilding { osm_way_id: id.0 },
@@ -450,7 +501,7 @@ fn is_road(tags: &mut Tags, opts: &Options) -> bool {
         {
             tags.insert(osm::SIDEWALK, "none");
         } else if tags.is("oneway", "yes") {
-            tags.insert(osm::SIDEWALK, "right");
+            tags.insert(osm::SIDEWALK, opts.map_config.driving_side.osm_sides().0);
             if tags.is_any(osm::HIGHWAY, vec!["residential", "living_street"])
                 && !tags.is("dual_carriageway", "yes")
             {
@@ -0,0 +1,575 @@
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use flate2::read::ZlibDecoder;
+use geom::{Distance, GPSBounds, LonLat, Pt2D};
+use std::error::Error;
+use std::io::Read;
+
+// Reads a digital elevation model from a single-band GeoTIFF, like the LIDAR-derived DEMs many
+// cities publish. The whole file is read into memory, but only the part of the raster covering the
+// map is decompressed and kept. For huge regional files, crop first with
+// `gdal_translate -projwin ...`.
+//
+// Supported:
+// - Classic (not Big) TIFF, either byte order, in strips or tiles
+// - Uncompressed or Deflate, with or without horizontal differencing for integer samples
+// - 8/16/32-bit integer and 32/64-bit float samples, in meters
+// - Rasters in WGS84 lon/lat. Reproject anything else first with
+//   `gdalwarp -t_srs EPSG:4326 -co COMPRESS=DEFLATE in.tif out.tif`
+pub struct Elevation {
+    // Only a window of the full raster is kept. Its pixel (0, 0) is (col_offset, row_offset) in
+    // the full raster.
+    col_offset: usize,
+    row_offset: usize,
+    width: usize,
+    height: usize,
+    data: Vec<f32>,
+    nodata: Option<f32>,
+
+    // Where the center of the full raster's pixel (0, 0) is, and the size of a pixel
+    lon0: f64,
+    lat0: f64,
+    lon_per_pixel: f64,
+    lat_per_pixel: f64,
+}
+
+impl Elevation {
+    pub fn load(path: &str, bounds: &GPSBounds) -> Result<Elevation, Box<dyn Error>> {
+        println!("Reading elevation data from {}", path);
+        let bytes = abstutil::slurp_file(path)?;
+        Elevation::from_bytes(&bytes, path, bounds)
+    }
+
+    fn from_bytes(
+        bytes: &[u8],
+        path: &str,
+        bounds: &GPSBounds,
+    ) -> Result<Elevation, Box<dyn Error>> {
+        let tiff = Tiff::new(bytes)?;
+        let ifd = tiff.first_ifd()?;
+
+        let width = ifd.must_get(&tiff, TAG_WIDTH)?[0] as usize;
+        let height = ifd.must_get(&tiff, TAG_HEIGHT)?[0] as usize;
+        if ifd
+            .get(&tiff, TAG_SAMPLES_PER_PIXEL)?
+            .map(|x| x[0] as usize)
+            .unwrap_or(1)
+            != 1
+        {
+            return Err(format!("{} has more than one band", path).into());
+        }
+        let bits = ifd
+            .get(&tiff, TAG_BITS_PER_SAMPLE)?
+            .map(|x| x[0])
+            .unwrap_or(1.0) as usize;
+        let sample_format = match ifd.get(&tiff, TAG_SAMPLE_FORMAT)?.map(|x| x[0] as usize) {
+            Some(3) => SampleFormat::Float,
+            Some(2) => SampleFormat::Int,
+            _ => SampleFormat::Uint,
+        };
+        let compression = ifd
+            .get(&tiff, TAG_COMPRESSION)?
+            .map(|x| x[0])
+            .unwrap_or(1.0) as usize;
+        if compression != 1 && compression != 8 && compression != 32946 {
+            return Err(format!(
+                "{} uses compression {}; only none and Deflate are supported. Try `gdal_translate \
+                 -co COMPRESS=DEFLATE`",
+                path, compression
+            )
+            .into());
+        }
+        let predictor = ifd.get(&tiff, TAG_PREDICTOR)?.map(|x| x[0]).unwrap_or(1.0) as usize;
+        if predictor > 2 || (predictor == 2 && sample_format == SampleFormat::Float) {
+            return Err(format!("{} uses unsupported predictor {}", path, predictor).into());
+        }
+
+        // Georeferencing
+        let scale = ifd.must_get(&tiff, TAG_MODEL_PIXEL_SCALE)?;
+        let tiepoint = ifd.must_get(&tiff, TAG_MODEL_TIEPOINT)?;
+        let mut pixel_is_point = false;
+        if let Some(keys) = ifd.get(&tiff, TAG_GEO_KEY_DIRECTORY)? {
+            // A header of 4 values, the last being the number of keys, then 4 values per key
+            if keys.len() < 4 || keys.len() < 4 + 4 * (keys[3] as usize) {
+                return Err(format!("{} has a truncated GeoKey directory", path).into());
+            }
+            for key in keys[4..].chunks(4).take(keys[3] as usize) {
+                match (key[0] as usize, key[3] as usize) {
+                    // GTModelTypeGeoKey: 1 is projected, 2 is geographic, 3 is geocentric
+                    (1024, x) if x != 2 => {
+                        return Err(format!(
+                            "{} isn't in WGS84. Reproject it with `gdalwarp -t_srs EPSG:4326`",
+                            path
+                        )
+                        .into());
+                    }
+                    // GTRasterTypeGeoKey
+                    (1025, 2) => {
+                        pixel_is_point = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let half = if pixel_is_point { 0.0 } else { 0.5 };
+        let lon_per_pixel = scale[0];
+        let lat_per_pixel = scale[1];
+        let lon0 = tiepoint[3] + (half - tiepoint[0]) * lon_per_pixel;
+        let lat0 = tiepoint[4] - (half - tiepoint[1]) * lat_per_pixel;
+
+        let nodata = ifd
+            .get_ascii(&tiff, TAG_GDAL_NODATA)?
+            .and_then(|x| x.trim().parse::<f32>().ok());
+
+        // Only decode the window covering the map, plus a pixel of slack for interpolation
+        let to_col = |lon: f64| (lon - lon0) / lon_per_pixel;
+        let to_row = |lat: f64| (lat0 - lat) / lat_per_pixel;
+        let clamp = |x: f64, max: usize| (x.max(0.0) as usize).min(max);
+        let corner1 = Pt2D::new(0.0, 0.0).to_gps(bounds);
+        let corner2 = bounds.get_max_world_pt().to_gps(bounds);
+        let (min_lon, max_lon) = (corner1.x().min(corner2.x()), corner1.x().max(corner2.x()));
+        let (min_lat, max_lat) = (corner1.y().min(corner2.y()), corner1.y().max(corner2.y()));
+        let col1 = clamp(to_col(min_lon).floor() - 1.0, width);
+        let col2 = clamp(to_col(max_lon).ceil() + 2.0, width);
+        let row1 = clamp(to_row(max_lat).floor() - 1.0, height);
+        let row2 = clamp(to_row(min_lat).ceil() + 2.0, height);
+        if col1 >= col2 || row1 >= row2 {
+            return Err(format!("{} doesn't cover the map", path).into());
+        }
+
+        let mut e = Elevation {
+            col_offset: col1,
+            row_offset: row1,
+            width: col2 - col1,
+            height: row2 - row1,
+            data: vec![std::f32::NAN; (col2 - col1) * (row2 - row1)],
+            nodata,
+            lon0,
+            lat0,
+            lon_per_pixel,
+            lat_per_pixel,
+        };
+
+        // Strips are just tiles as wide as the image
+        let (tile_width, tile_height, offsets, byte_counts) =
+            if let Some(tw) = ifd.get(&tiff, TAG_TILE_WIDTH)? {
+                (
+                    tw[0] as usize,
+                    ifd.must_get(&tiff, TAG_TILE_LENGTH)?[0] as usize,
+                    ifd.must_get(&tiff, TAG_TILE_OFFSETS)?,
+                    ifd.must_get(&tiff, TAG_TILE_BYTE_COUNTS)?,
+                )
+            } else {
+                (
+                    width,
+                    ifd.get(&tiff, TAG_ROWS_PER_STRIP)?
+                        .map(|x| x[0] as usize)
+                        .unwrap_or(height)
+                        .min(height),
+                    ifd.must_get(&tiff, TAG_STRIP_OFFSETS)?,
+                    ifd.must_get(&tiff, TAG_STRIP_BYTE_COUNTS)?,
+                )
+            };
+        let tiles_across = (width + tile_width - 1) / tile_width;
+        let bytes_per_sample = bits / 8;
+
+        for (idx, (offset, count)) in offsets.iter().zip(byte_counts.iter()).enumerate() {
+            let tile_col = (idx % tiles_across) * tile_width;
+            let tile_row = (idx / tiles_across) * tile_height;
+            if tile_col >= col2
+                || tile_col + tile_width <= col1
+                || tile_row >= row2
+                || tile_row + tile_height <= row1
+            {
+                continue;
+            }
+
+            let (offset, count) = (*offset as usize, *count as usize);
+            if offset + count > bytes.len() {
+                return Err(format!("{} is truncated", path).into());
+            }
+            let raw = &bytes[offset..offset + count];
+            let mut decoded;
+            let tile = if compression == 1 {
+                raw
+            } else {
+                decoded = Vec::new();
+                ZlibDecoder::new(raw).read_to_end(&mut decoded)?;
+                &decoded[..]
+            };
+
+            for y in 0..tile_height {
+                let row = tile_row + y;
+                if row >= height {
+                    break;
+                }
+                let mut prev = 0.0;
+                for x in 0..tile_width {
+                    let start = (y * tile_width + x) * bytes_per_sample;
+                    if start + bytes_per_sample > tile.len() {
+                        break;
+                    }
+                    let mut value =
+                        tiff.sample(&tile[start..start + bytes_per_sample], sample_format)?;
+                    if predictor == 2 {
+                        // Horizontal differencing wraps around at the sample's bit width
+                        value = wrap(prev + value, bits, sample_format);
+                        prev = value;
+                    }
+                    let col = tile_col + x;
+                    if col >= col1 && col < col2 && row >= row1 && row < row2 {
+                        e.data[(row - row1) * e.width + (col - col1)] = value as f32;
+                    }
+                }
+            }
+        }
+        Ok(e)
+    }
+
+    // Bilinear interpolation between the 4 surrounding pixels. None if the point is outside the
+    // raster or in a hole.
+    pub fn get(&self, pt: LonLat) -> Option<Distance> {
+        let col = (pt.x() - self.lon0) / self.lon_per_pixel - (self.col_offset as f64);
+        let row = (self.lat0 - pt.y()) / self.lat_per_pixel - (self.row_offset as f64);
+        if col < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (x, y) = (col.floor() as usize, row.floor() as usize);
+        let (fx, fy) = ((col - col.floor()) as f32, (row - row.floor()) as f32);
+        let v00 = self.pixel(x, y)?;
+        let v10 = self.pixel(x + 1, y).unwrap_or(v00);
+        let v01 = self.pixel(x, y + 1).unwrap_or(v00);
+        let v11 = self.pixel(x + 1, y + 1).unwrap_or(v00);
+        let top = v00 * (1.0 - fx) + v10 * fx;
+        let bottom = v01 * (1.0 - fx) + v11 * fx;
+        Some(Distance::meters(f64::from(top * (1.0 - fy) + bottom * fy)))
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        let value = self.data[y * self.width + x];
+        if value.is_nan() || Some(value) == self.nodata {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+const TAG_WIDTH: u16 = 256;
+const TAG_HEIGHT: u16 = 257;
+const TAG_BITS_PER_SAMPLE: u16 = 258;
+const TAG_COMPRESSION: u16 = 259;
+const TAG_STRIP_OFFSETS: u16 = 273;
+const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+const TAG_ROWS_PER_STRIP: u16 = 278;
+const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+const TAG_PREDICTOR: u16 = 317;
+const TAG_TILE_WIDTH: u16 = 322;
+const TAG_TILE_LENGTH: u16 = 323;
+const TAG_TILE_OFFSETS: u16 = 324;
+const TAG_TILE_BYTE_COUNTS: u16 = 325;
+const TAG_SAMPLE_FORMAT: u16 = 339;
+const TAG_MODEL_PIXEL_SCALE: u16 = 33550;
+const TAG_MODEL_TIEPOINT: u16 = 33922;
+const TAG_GEO_KEY_DIRECTORY: u16 = 34735;
+const TAG_GDAL_NODATA: u16 = 42113;
+
+#[derive(Clone, Copy, PartialEq)]
+enum SampleFormat {
+    Uint,
+    Int,
+    Float,
+}
+
+fn wrap(value: f64, bits: usize, format: SampleFormat) -> f64 {
+    let range = 2.0_f64.powi(bits as i32);
+    let mut value = value.rem_euclid(range);
+    if format == SampleFormat::Int && value >= range / 2.0 {
+        value -= range;
+    }
+    value
+}
+
+struct Tiff<'a> {
+    bytes: &'a [u8],
+    big_endian: bool,
+}
+
+struct Ifd {
+    // (tag, type, count, offset of the value or of the pointer to it)
+    entries: Vec<(u16, u16, usize, usize)>,
+}
+
+impl<'a> Tiff<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Tiff<'a>, Box<dyn Error>> {
+        let big_endian = match bytes.get(0..2) {
+            Some(b"II") => false,
+            Some(b"MM") => true,
+            _ => {
+                return Err("not a TIFF file".into());
+            }
+        };
+        let tiff = Tiff { bytes, big_endian };
+        match tiff.u16(2)? {
+            42 => Ok(tiff),
+            43 => {
+                Err("BigTIFF isn't supported; convert with `gdal_translate -co BIGTIFF=NO`".into())
+            }
+            x => Err(format!("bad TIFF version {}", x).into()),
+        }
+    }
+
+    fn first_ifd(&self) -> Result<Ifd, Box<dyn Error>> {
+        let start = self.u32(4)? as usize;
+        let num = self.u16(start)? as usize;
+        let mut entries = Vec::new();
+        for i in 0..num {
+            let entry = start + 2 + 12 * i;
+            let tag = self.u16(entry)?;
+            let field_type = self.u16(entry + 2)?;
+            let count = self.u32(entry + 4)? as usize;
+            let size = count * type_size(field_type);
+            let value_offset = if size <= 4 {
+                entry + 8
+            } else {
+                self.u32(entry + 8)? as usize
+            };
+            entries.push((tag, field_type, count, value_offset));
+        }
+        Ok(Ifd { entries })
+    }
+
+    fn slice(&self, offset: usize, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        self.bytes
+            .get(offset..offset + len)
+            .ok_or_else(|| "TIFF file is truncated".into())
+    }
+
+    fn u16(&self, offset: usize) -> Result<u16, Box<dyn Error>> {
+        let buf = self.slice(offset, 2)?;
+        Ok(if self.big_endian {
+            BigEndian::read_u16(buf)
+        } else {
+            LittleEndian::read_u16(buf)
+        })
+    }
+
+    fn u32(&self, offset: usize) -> Result<u32, Box<dyn Error>> {
+        let buf = self.slice(offset, 4)?;
+        Ok(if self.big_endian {
+            BigEndian::read_u32(buf)
+        } else {
+            LittleEndian::read_u32(buf)
+        })
+    }
+
+    fn f64(&self, offset: usize) -> Result<f64, Box<dyn Error>> {
+        let buf = self.slice(offset, 8)?;
+        Ok(if self.big_endian {
+            BigEndian::read_f64(buf)
+        } else {
+            LittleEndian::read_f64(buf)
+        })
+    }
+
+    fn sample(&self, buf: &[u8], format: SampleFormat) -> Result<f64, Box<dyn Error>> {
+        let be = self.big_endian;
+        Ok(match (buf.len(), format) {
+            (1, SampleFormat::Int) => f64::from(buf[0] as i8),
+            (1, _) => f64::from(buf[0]),
+            (2, SampleFormat::Int) => f64::from(if be {
+                BigEndian::read_i16(buf)
+            } else {
+                LittleEndian::read_i16(buf)
+            }),
+            (2, _) => f64::from(if be {
+                BigEndian::read_u16(buf)
+            } else {
+                LittleEndian::read_u16(buf)
+            }),
+            (4, SampleFormat::Int) => f64::from(if be {
+                BigEndian::read_i32(buf)
+            } else {
+                LittleEndian::read_i32(buf)
+            }),
+            (4, SampleFormat::Uint) => f64::from(if be {
+                BigEndian::read_u32(buf)
+            } else {
+                LittleEndian::read_u32(buf)
+            }),
+            (4, SampleFormat::Float) => f64::from(if be {
+                BigEndian::read_f32(buf)
+            } else {
+                LittleEndian::read_f32(buf)
+            }),
+            (8, SampleFormat::Float) => {
+                if be {
+                    BigEndian::read_f64(buf)
+                } else {
+                    LittleEndian::read_f64(buf)
+                }
+            }
+            (n, _) => {
+                return Err(format!("unsupported {}-byte samples", n).into());
+            }
+        })
+    }
+}
+
+impl Ifd {
+    // Numeric values of a tag, if it's present
+    fn get(&self, tiff: &Tiff, tag: u16) -> Result<Option<Vec<f64>>, Box<dyn Error>> {
+        let (field_type, count, offset) = match self.entries.iter().find(|e| e.0 == tag) {
+            Some(e) => (e.1, e.2, e.3),
+            None => {
+                return Ok(None);
+            }
+        };
+        let mut values = Vec::with_capacity(count);
+        for i in 0..count {
+            values.push(match field_type {
+                1 => f64::from(tiff.slice(offset + i, 1)?[0]),
+                3 => f64::from(tiff.u16(offset + 2 * i)?),
+                4 => f64::from(tiff.u32(offset + 4 * i)?),
+                12 => tiff.f64(offset + 8 * i)?,
+                x => {
+                    return Err(format!("unsupported type {} for TIFF tag {}", x, tag).into());
+                }
+            });
+        }
+        Ok(Some(values))
+    }
+
+    fn must_get(&self, tiff: &Tiff, tag: u16) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.get(tiff, tag)?
+            .ok_or_else(|| format!("TIFF is missing tag {}", tag).into())
+    }
+
+    fn get_ascii(&self, tiff: &Tiff, tag: u16) -> Result<Option<String>, Box<dyn Error>> {
+        match self.entries.iter().find(|e| e.0 == tag) {
+            Some((_, 2, count, offset)) => {
+                let buf = tiff.slice(*offset, *count)?;
+                Ok(Some(
+                    String::from_utf8_lossy(buf)
+                        .trim_end_matches('\0')
+                        .to_string(),
+                ))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+fn type_size(field_type: u16) -> usize {
+    match field_type {
+        1 | 2 | 6 | 7 => 1,
+        3 | 8 => 2,
+        4 | 9 | 11 => 4,
+        5 | 10 | 12 => 8,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A little-endian 2x2 raster of 16-bit samples in one strip. The top-left corner is at
+    // (-122.0, 47.02) and pixels are 0.01 degrees wide.
+    fn fixture(geo_keys: Vec<u16>) -> Vec<u8> {
+        let pixels: Vec<u16> = vec![10, 20, 30, 40];
+        let scale = vec![0.01, 0.01, 0.0];
+        let tiepoint = vec![0.0, 0.0, 0.0, -122.0, 47.02, 0.0];
+
+        let num_entries = 10;
+        let pixels_offset = 8 + 2 + 12 * num_entries + 4;
+        let scale_offset = pixels_offset + 2 * pixels.len();
+        let tiepoint_offset = scale_offset + 8 * scale.len();
+        let keys_offset = tiepoint_offset + 8 * tiepoint.len();
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"II");
+        bytes.extend_from_slice(&42_u16.to_le_bytes());
+        bytes.extend_from_slice(&8_u32.to_le_bytes());
+        bytes.extend_from_slice(&(num_entries as u16).to_le_bytes());
+        // Values of 4 bytes or less are stored inline. Otherwise, value is an offset.
+        let mut entry = |tag: u16, field_type: u16, count: usize, value: usize| {
+            bytes.extend_from_slice(&tag.to_le_bytes());
+            bytes.extend_from_slice(&field_type.to_le_bytes());
+            bytes.extend_from_slice(&(count as u32).to_le_bytes());
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        };
+        entry(TAG_WIDTH, 3, 1, 2);
+        entry(TAG_HEIGHT, 3, 1, 2);
+        entry(TAG_BITS_PER_SAMPLE, 3, 1, 16);
+        entry(TAG_COMPRESSION, 3, 1, 1);
+        entry(TAG_STRIP_OFFSETS, 4, 1, pixels_offset);
+        entry(TAG_ROWS_PER_STRIP, 3, 1, 2);
+        entry(TAG_STRIP_BYTE_COUNTS, 4, 1, 2 * pixels.len());
+        entry(TAG_MODEL_PIXEL_SCALE, 12, scale.len(), scale_offset);
+        entry(TAG_MODEL_TIEPOINT, 12, tiepoint.len(), tiepoint_offset);
+        entry(TAG_GEO_KEY_DIRECTORY, 3, geo_keys.len(), keys_offset);
+        // No more IFDs
+        bytes.extend_from_slice(&0_u32.to_le_bytes());
+
+        for x in pixels {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        for x in scale.into_iter().chain(tiepoint.into_iter()) {
+            bytes.extend_from_slice(&f64::to_le_bytes(x));
+        }
+        for x in geo_keys {
+            bytes.extend_from_slice(&x.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn bounds() -> GPSBounds {
+        GPSBounds::from(vec![
+            LonLat::new(-121.995, 47.005),
+            LonLat::new(-121.985, 47.015),
+        ])
+    }
+
+    #[test]
+    fn test_interpolation() {
+        // Geographic, pixel is area
+        let bytes = fixture(vec![1, 1, 0, 1, 1024, 0, 1, 2]);
+        let e = Elevation::from_bytes(&bytes, "fixture", &bounds()).unwrap();
+        let check = |lon: f64, lat: f64, expected: f64| {
+            let actual = e.get(LonLat::new(lon, lat)).unwrap().inner_meters();
+            assert!(
+                (actual - expected).abs() < 0.01,
+                "at ({}, {}), got {} instead of {}",
+                lon,
+                lat,
+                actual,
+                expected
+            );
+        };
+        // Just inside the centers of the top-left and bottom-right pixels
+        check(-121.995 + 1e-9, 47.015 - 1e-9, 10.0);
+        check(-121.985 - 1e-9, 47.005 + 1e-9, 40.0);
+        // Right between all 4
+        check(-121.99, 47.01, 25.0);
+        // Outside the raster
+        assert_eq!(e.get(LonLat::new(-122.01, 47.01)), None);
+    }
+
+    #[test]
+    fn test_bad_geo_keys() {
+        // Claims to have 2 keys, but only has 1
+        let bytes = fixture(vec![1, 1, 0, 2, 1024, 0, 1, 2]);
+        assert!(Elevation::from_bytes(&bytes, "fixture", &bounds()).is_err());
+        // Too short for the header
+        let bytes = fixture(vec![1, 1, 0]);
+        assert!(Elevation::from_bytes(&bytes, "fixture", &bounds()).is_err());
+        // Projected and geocentric
+        for model_type in vec![1, 3] {
+            let bytes = fixture(vec![1, 1, 0, 1, 1024, 0, 1, model_type]);
+            assert!(Elevation::from_bytes(&bytes, "fixture", &bounds()).is_err());
+        }
+    }
+}
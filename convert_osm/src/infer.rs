@@ -0,0 +1,392 @@
+use abstutil::{Tags, Timer};
+use geom::{Distance, FindClosest, PolyLine, Polygon};
+use map_model::raw::{OriginalRoad, RawMap};
+use map_model::{osm, NORMAL_LANE_THICKNESS};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Buildings closer than this to a road count towards its density.
+const BLDG_DENSITY_RADIUS: Distance = Distance::const_meters(50.0);
+
+// A guess about parking lanes and sidewalks for roads where OSM says nothing. Every condition
+// that's specified has to match the road. For each attribute, the first matching rule that sets it
+// wins.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InferenceRule {
+    // Recorded on every road this rule guesses something for, so the guess can be traced back
+    pub name: String,
+
+    // The road's highway tag has one of these values
+    #[serde(default)]
+    pub highway: Vec<String>,
+    // In meters, from the width tag or estimated from the number of lanes
+    #[serde(default)]
+    pub min_width: Option<f64>,
+    #[serde(default)]
+    pub max_width: Option<f64>,
+    // If set, the road must be one-way (true) or two-way (false)
+    #[serde(default)]
+    pub oneway: Option<bool>,
+    // The middle of the road is inside an area with one of these landuse values
+    #[serde(default)]
+    pub landuse: Vec<String>,
+    // The number of buildings along the road, per 100m
+    #[serde(default)]
+    pub min_bldg_density: Option<f64>,
+    #[serde(default)]
+    pub max_bldg_density: Option<f64>,
+
+    // A parking:lane value like "parallel" or "no_parking". Applies to both sides, except on
+    // one-ways, where it's just the side of the forwards lanes.
+    #[serde(default)]
+    pub parking: Option<String>,
+    // A sidewalk value like "both", "right", or "none"
+    #[serde(default)]
+    pub sidewalk: Option<String>,
+}
+
+// Only roads still marked with osm::INFERRED_PARKING or osm::INFERRED_SIDEWALKS are changed. The
+// marker stays, but its value becomes the name of the rule used.
+pub fn apply_rules(
+    map: &mut RawMap,
+    rules: &Vec<InferenceRule>,
+    landuse: Vec<(String, Polygon)>,
+    timer: &mut Timer,
+) {
+    if rules.is_empty() {
+        return;
+    }
+    timer.start("infer parking and sidewalks");
+
+    let need_landuse = rules.iter().any(|r| !r.landuse.is_empty());
+    let densities = bldg_densities(map);
+    let fwd_side = map.config.driving_side.osm_sides().0;
+    let mut parking_counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut sidewalk_counts: BTreeMap<String, usize> = BTreeMap::new();
+
+    for (id, r) in map.roads.iter_mut() {
+        let guess_parking = r.osm_tags.contains_key(osm::INFERRED_PARKING);
+        let guess_sidewalks = r.osm_tags.contains_key(osm::INFERRED_SIDEWALKS);
+        if !guess_parking && !guess_sidewalks {
+            continue;
+        }
+
+        let width = road_width(&r.osm_tags);
+        let oneway = r.osm_tags.is("oneway", "yes");
+        let density = densities.get(id).cloned().unwrap_or(0.0);
+        let road_landuse: Vec<&String> = if need_landuse {
+            let middle = PolyLine::unchecked_new(r.center_points.clone()).middle();
+            landuse
+                .iter()
+                .filter(|(_, poly)| poly.contains_pt(middle))
+                .map(|(lu, _)| lu)
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let matching = rules.iter().filter(|rule| {
+            (rule.highway.is_empty()
+                || r.osm_tags
+                    .get(osm::HIGHWAY)
+                    .map(|hwy| rule.highway.contains(hwy))
+                    .unwrap_or(false))
+                && rule.min_width.map(|w| width >= w).unwrap_or(true)
+                && rule.max_width.map(|w| width <= w).unwrap_or(true)
+                && rule.oneway.map(|x| x == oneway).unwrap_or(true)
+                && (rule.landuse.is_empty()
+                    || road_landuse.iter().any(|lu| rule.landuse.contains(lu)))
+                && rule.min_bldg_density.map(|d| density >= d).unwrap_or(true)
+                && rule.max_bldg_density.map(|d| density <= d).unwrap_or(true)
+        });
+
+        let mut parking = None;
+        let mut sidewalk = None;
+        for rule in matching {
+            if parking.is_none() {
+                if let Some(ref value) = rule.parking {
+                    parking = Some((rule.name.clone(), value.clone()));
+                }
+            }
+            if sidewalk.is_none() {
+                if let Some(ref value) = rule.sidewalk {
+                    sidewalk = Some((rule.name.clone(), value.clone()));
+                }
+            }
+        }
+
+        if let (true, Some((name, value))) = (guess_parking, parking) {
+            r.osm_tags.remove(osm::PARKING_BOTH);
+            r.osm_tags.remove(osm::PARKING_LEFT);
+            r.osm_tags.remove(osm::PARKING_RIGHT);
+            if oneway {
+                r.osm_tags
+                    .insert(format!("parking:lane:{}", fwd_side), value);
+            } else {
+                r.osm_tags.insert(osm::PARKING_BOTH, value);
+            }
+            *parking_counts.entry(name.clone()).or_insert(0) += 1;
+            r.osm_tags.insert(osm::INFERRED_PARKING, name);
+        }
+        if let (true, Some((name, value))) = (guess_sidewalks, sidewalk) {
+            r.osm_tags.insert(osm::SIDEWALK, value);
+            *sidewalk_counts.entry(name.clone()).or_insert(0) += 1;
+            r.osm_tags.insert(osm::INFERRED_SIDEWALKS, name);
+        }
+    }
+
+    for (name, count) in parking_counts {
+        timer.note(format!("Rule {} guessed parking for {} roads", name, count));
+    }
+    for (name, count) in sidewalk_counts {
+        timer.note(format!(
+            "Rule {} guessed sidewalks for {} roads",
+            name, count
+        ));
+    }
+    timer.stop("infer parking and sidewalks");
+}
+
+// Roads aren't trimmed or split into lanes yet, so this is rough.
+fn road_width(tags: &Tags) -> f64 {
+    if let Some(width) = tags
+        .get("width")
+        .and_then(|w| w.trim_end_matches('m').trim().parse::<f64>().ok())
+    {
+        return width;
+    }
+    let default_lanes = if tags.is("oneway", "yes") { 1 } else { 2 };
+    let lanes = tags
+        .get("lanes")
+        .and_then(|n| n.parse::<usize>().ok())
+        .unwrap_or(default_lanes);
+    (lanes as f64) * NORMAL_LANE_THICKNESS.inner_meters()
+}
+
+// Buildings per 100m of road. Each building only counts for the road closest to it.
+fn bldg_densities(map: &RawMap) -> BTreeMap<OriginalRoad, f64> {
+    let mut closest: FindClosest<OriginalRoad> = FindClosest::new(&map.gps_bounds.to_bounds());
+    for (id, r) in &map.roads {
+        closest.add(*id, &r.center_points);
+    }
+    let mut counts: BTreeMap<OriginalRoad, usize> = BTreeMap::new();
+    for b in map.buildings.values() {
+        if let Some((id, _)) = closest.closest_pt(b.polygon.center(), BLDG_DENSITY_RADIUS) {
+            *counts.entry(id).or_insert(0) += 1;
+        }
+    }
+
+    let mut densities = BTreeMap::new();
+    for (id, count) in counts {
+        let len = PolyLine::unchecked_new(map.roads[&id].center_points.clone()).length();
+        if len > Distance::ZERO {
+            densities.insert(id, (count as f64) / (len / Distance::meters(100.0)));
+        }
+    }
+    densities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geom::{GPSBounds, LonLat, Pt2D};
+    use map_model::raw::{DrivingSide, OriginalIntersection, RawRoad};
+
+    fn rule(
+        name: &str,
+        highway: Vec<&str>,
+        parking: Option<&str>,
+        sidewalk: Option<&str>,
+    ) -> InferenceRule {
+        InferenceRule {
+            name: name.to_string(),
+            highway: highway.into_iter().map(|x| x.to_string()).collect(),
+            min_width: None,
+            max_width: None,
+            oneway: None,
+            landuse: Vec::new(),
+            min_bldg_density: None,
+            max_bldg_density: None,
+            parking: parking.map(|x| x.to_string()),
+            sidewalk: sidewalk.map(|x| x.to_string()),
+        }
+    }
+
+    // Each road is a straight 100m line, tagged like extract.rs does when OSM says nothing.
+    fn map_with_roads(driving_side: DrivingSide, roads: Vec<Vec<(&str, &str)>>) -> RawMap {
+        let mut map = RawMap::blank("test", "test");
+        map.config.driving_side = driving_side;
+        map.gps_bounds =
+            GPSBounds::from(vec![LonLat::new(-122.0, 47.0), LonLat::new(-121.99, 47.01)]);
+        for (idx, tags) in roads.into_iter().enumerate() {
+            let mut osm_tags = Tags::new(BTreeMap::new());
+            osm_tags.insert(osm::PARKING_BOTH, "no_parking");
+            osm_tags.insert(osm::INFERRED_PARKING, "true");
+            osm_tags.insert(osm::INFERRED_SIDEWALKS, "true");
+            for (k, v) in tags {
+                osm_tags.insert(k, v);
+            }
+            let y = 10.0 * (idx as f64 + 1.0);
+            map.roads.insert(
+                OriginalRoad {
+                    osm_way_id: idx as i64,
+                    i1: OriginalIntersection {
+                        osm_node_id: 2 * idx as i64,
+                    },
+                    i2: OriginalIntersection {
+                        osm_node_id: 2 * idx as i64 + 1,
+                    },
+                },
+                RawRoad {
+                    center_points: vec![Pt2D::new(10.0, y), Pt2D::new(110.0, y)],
+                    osm_tags,
+                    turn_restrictions: Vec::new(),
+                    complicated_turn_restrictions: Vec::new(),
+                    elevation: Vec::new(),
+                },
+            );
+        }
+        map
+    }
+
+    fn tags(map: &RawMap, osm_way_id: i64) -> &Tags {
+        &map.roads
+            .iter()
+            .find(|(id, _)| id.osm_way_id == osm_way_id)
+            .unwrap()
+            .1
+            .osm_tags
+    }
+
+    #[test]
+    fn test_first_matching_rule_wins() {
+        let mut map = map_with_roads(
+            DrivingSide::Right,
+            vec![
+                vec![(osm::HIGHWAY, "primary")],
+                vec![(osm::HIGHWAY, "residential")],
+            ],
+        );
+        let rules = vec![
+            rule("arterials", vec!["primary"], None, Some("both")),
+            rule("everything", Vec::new(), Some("parallel"), Some("none")),
+            rule("residential", vec!["residential"], Some("diagonal"), None),
+        ];
+        apply_rules(&mut map, &rules, Vec::new(), &mut Timer::throwaway());
+
+        // Each attribute comes from the first rule that matches and sets it.
+        let primary = tags(&map, 0);
+        assert_eq!(primary.get(osm::SIDEWALK).unwrap(), "both");
+        assert_eq!(primary.get(osm::INFERRED_SIDEWALKS).unwrap(), "arterials");
+        assert_eq!(primary.get(osm::PARKING_BOTH).unwrap(), "parallel");
+        assert_eq!(primary.get(osm::INFERRED_PARKING).unwrap(), "everything");
+
+        // A later, more specific rule doesn't override an earlier, general one.
+        let residential = tags(&map, 1);
+        assert_eq!(residential.get(osm::SIDEWALK).unwrap(), "none");
+        assert_eq!(
+            residential.get(osm::INFERRED_SIDEWALKS).unwrap(),
+            "everything"
+        );
+        assert_eq!(residential.get(osm::PARKING_BOTH).unwrap(), "parallel");
+        assert_eq!(
+            residential.get(osm::INFERRED_PARKING).unwrap(),
+            "everything"
+        );
+    }
+
+    #[test]
+    fn test_only_inferred_roads_change() {
+        let mut map = map_with_roads(
+            DrivingSide::Right,
+            vec![
+                vec![(osm::HIGHWAY, "residential")],
+                vec![(osm::HIGHWAY, "residential")],
+            ],
+        );
+        // OSM had real data for the first road.
+        let r = map
+            .roads
+            .iter_mut()
+            .find(|(id, _)| id.osm_way_id == 0)
+            .unwrap()
+            .1;
+        r.osm_tags.remove(osm::INFERRED_PARKING);
+        r.osm_tags.remove(osm::INFERRED_SIDEWALKS);
+        r.osm_tags.insert(osm::PARKING_BOTH, "diagonal");
+        r.osm_tags.insert(osm::SIDEWALK, "left");
+
+        let rules = vec![rule(
+            "residential",
+            vec!["residential"],
+            Some("parallel"),
+            Some("both"),
+        )];
+        apply_rules(&mut map, &rules, Vec::new(), &mut Timer::throwaway());
+
+        let observed = tags(&map, 0);
+        assert_eq!(observed.get(osm::PARKING_BOTH).unwrap(), "diagonal");
+        assert_eq!(observed.get(osm::SIDEWALK).unwrap(), "left");
+        assert!(!observed.contains_key(osm::INFERRED_PARKING));
+        let guessed = tags(&map, 1);
+        assert_eq!(guessed.get(osm::PARKING_BOTH).unwrap(), "parallel");
+        assert_eq!(guessed.get(osm::SIDEWALK).unwrap(), "both");
+    }
+
+    #[test]
+    fn test_narrow_rule_skips_untagged_oneways() {
+        let mut map = map_with_roads(
+            DrivingSide::Right,
+            vec![
+                vec![(osm::HIGHWAY, "residential"), ("oneway", "yes")],
+                vec![(osm::HIGHWAY, "residential"), ("width", "4 m")],
+            ],
+        );
+        let mut narrow = rule("narrow", Vec::new(), Some("no_parking"), Some("right"));
+        narrow.max_width = Some(4.0);
+        narrow.oneway = Some(false);
+        let rules = vec![
+            narrow,
+            rule("everything", Vec::new(), Some("parallel"), Some("both")),
+        ];
+        apply_rules(&mut map, &rules, Vec::new(), &mut Timer::throwaway());
+
+        // A one-way is only estimated to be one lane wide, so it doesn't count as narrow.
+        assert_eq!(
+            tags(&map, 0).get(osm::INFERRED_SIDEWALKS).unwrap(),
+            "everything"
+        );
+        assert_eq!(
+            tags(&map, 1).get(osm::INFERRED_SIDEWALKS).unwrap(),
+            "narrow"
+        );
+        assert_eq!(tags(&map, 1).get(osm::PARKING_BOTH).unwrap(), "no_parking");
+    }
+
+    #[test]
+    fn test_oneway_parking_side() {
+        let rules = vec![rule("parking", Vec::new(), Some("parallel"), None)];
+        for (driving_side, fwd, back) in vec![
+            (DrivingSide::Right, osm::PARKING_RIGHT, osm::PARKING_LEFT),
+            (DrivingSide::Left, osm::PARKING_LEFT, osm::PARKING_RIGHT),
+        ] {
+            let mut map = map_with_roads(
+                driving_side,
+                vec![
+                    vec![(osm::HIGHWAY, "residential"), ("oneway", "yes")],
+                    vec![(osm::HIGHWAY, "residential")],
+                ],
+            );
+            apply_rules(&mut map, &rules, Vec::new(), &mut Timer::throwaway());
+
+            // One-ways only get parking on the side of the forwards lanes.
+            let oneway = tags(&map, 0);
+            assert_eq!(oneway.get(fwd).unwrap(), "parallel");
+            assert!(!oneway.contains_key(back));
+            assert!(!oneway.contains_key(osm::PARKING_BOTH));
+
+            let twoway = tags(&map, 1);
+            assert_eq!(twoway.get(osm::PARKING_BOTH).unwrap(), "parallel");
+            assert!(!twoway.contains_key(fwd));
+        }
+    }
+}
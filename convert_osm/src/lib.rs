@@ -1,16 +1,23 @@
 mod clip;
 mod extract;
+mod geotiff;
+mod infer;
 mod parking;
+mod pbf;
 mod reader;
 mod split_ways;
 mod srtm;
 
 use abstutil::Timer;
-use geom::{Distance, FindClosest, GPSBounds, LonLat, Pt2D, Ring};
+use geom::{Distance, FindClosest, GPSBounds, LonLat, PolyLine, Pt2D, Ring};
 use map_model::raw::{OriginalBuilding, RawMap};
 use map_model::MapConfig;
+use serde::{Deserialize, Serialize};
+
+pub use crate::infer::InferenceRule;
 
 pub struct Options {
+    // Either .osm or .osm.pbf
     pub osm_input: String,
     pub city_name: String,
     pub name: String,
@@ -22,15 +29,23 @@ pub struct Options {
     pub onstreet_parking: OnstreetParking,
     pub public_offstreet_parking: PublicOffstreetParking,
     pub private_offstreet_parking: PrivateOffstreetParking,
-    // If provided, pull elevation data from this SRTM file. The SRTM parser is incorrect, so the
-    // results will be nonsense.
+    // If provided, pull elevation data from this file. A GeoTIFF DEM (.tif) in WGS84 is best. SRTM
+    // .hgt files also work, but that parser is incorrect, so the results will be nonsense.
     pub elevation: Option<String>,
     // OSM railway=rail will be included as light rail if so. Cosmetic only.
     pub include_railroads: bool,
+    // If true, pedestrians can informally cross low-speed residential streets in the middle of
+    // long blocks and wherever two of these streets meet without a real intersection.
+    pub unmarked_crossings: bool,
+    // Guess parking lanes and sidewalks where OSM doesn't say, instead of assuming no parking and
+    // sidewalks on most roads. These run before onstreet_parking, which can still override them
+    // with better data.
+    pub inference_rules: Vec<InferenceRule>,
 }
 
 // What roads will have on-street parking lanes? Data from
 // https://wiki.openstreetmap.org/wiki/Key:parking:lane is always used if available.
+#[derive(Clone, Serialize, Deserialize)]
 pub enum OnstreetParking {
     // If not tagged, there won't be parking.
     JustOSM,
@@ -46,6 +61,7 @@ pub enum OnstreetParking {
 }
 
 // How many spots are available in public parking garages?
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PublicOffstreetParking {
     None,
     // Pull data from
@@ -56,6 +72,7 @@ pub enum PublicOffstreetParking {
 
 // If a building doesn't have anything from public_offstreet_parking, how many private spots should
 // it have?
+#[derive(Clone, Serialize, Deserialize)]
 pub enum PrivateOffstreetParking {
     FixedPerBldg(usize),
     // TODO Based on the number of residents?
@@ -63,6 +80,8 @@ pub enum PrivateOffstreetParking {
 
 pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
     let mut map = RawMap::blank(&opts.city_name, &opts.name);
+    // Some of the steps below depend on the driving side
+    map.config = opts.map_config.clone();
     if let Some(ref path) = opts.clip {
         let pts = LonLat::read_osmosis_polygon(path.to_string()).unwrap();
         let gps_bounds = GPSBounds::from(pts.clone());
@@ -70,8 +89,9 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
         map.gps_bounds = gps_bounds;
     }
 
-    let extract = extract::extract_osm(&mut map, &opts, timer);
-    let amenities = split_ways::split_up_roads(&mut map, extract, timer);
+    let mut extract = extract::extract_osm(&mut map, &opts, timer);
+    let landuse = std::mem::replace(&mut extract.landuse, Vec::new());
+    let amenities = split_ways::split_up_roads(&mut map, extract, &opts, timer);
     clip::clip_map(&mut map, timer);
 
     // Need to do a first pass of removing cul-de-sacs here, or we wind up with loop PolyLines when
@@ -80,13 +100,13 @@ pub fn convert(opts: Options, timer: &mut abstutil::Timer) -> RawMap {
 
     use_amenities(&mut map, amenities, timer);
 
+    infer::apply_rules(&mut map, &opts.inference_rules, landuse, timer);
     parking::apply_parking(&mut map, &opts, timer);
 
     if let Some(ref path) = opts.elevation {
         use_elevation(&mut map, path, timer);
     }
 
-    map.config = opts.map_config;
     map
 }
 
@@ -109,15 +129,49 @@ fn use_amenities(map: &mut RawMap, amenities: Vec<(Pt2D, String, String)>, timer
 }
 
 fn use_elevation(map: &mut RawMap, path: &str, timer: &mut Timer) {
-    timer.start("apply elevation data to intersections");
-    let elevation = srtm::Elevation::load(path).unwrap();
+    timer.start("apply elevation data");
+    let lookup: Box<dyn Fn(LonLat) -> Option<Distance>> =
+        if path.ends_with(".tif") || path.ends_with(".tiff") {
+            let dem = geotiff::Elevation::load(path, &map.gps_bounds).unwrap();
+            Box::new(move |pt| dem.get(pt))
+        } else {
+            let srtm = srtm::Elevation::load(path).unwrap();
+            Box::new(move |pt| Some(srtm.get(pt)))
+        };
+
     for i in map.intersections.values_mut() {
         // TODO Not sure why, but I've seen nodes from South Carolina wind up in the updated
         // Seattle extract. And I think there's a bug with clipping, because they survive to this
         // point. O_O
         if map.boundary_polygon.contains_pt(i.point) {
-            i.elevation = elevation.get(i.point.to_gps(&map.gps_bounds));
+            if let Some(e) = lookup(i.point.to_gps(&map.gps_bounds)) {
+                i.elevation = e;
+            }
+        }
+    }
+
+    // Sample along every road, so hills in the middle of long blocks aren't lost.
+    let step = Distance::meters(5.0);
+    timer.start_iter("sample elevation along roads", map.roads.len());
+    for r in map.roads.values_mut() {
+        timer.next();
+        r.elevation.clear();
+        let pl = match PolyLine::deduping_new(r.center_points.clone()) {
+            Ok(pl) => pl,
+            Err(_) => {
+                continue;
+            }
+        };
+        let mut dist = Distance::ZERO;
+        while dist <= pl.length() {
+            let pt = pl.must_dist_along(dist).0;
+            if map.boundary_polygon.contains_pt(pt) {
+                if let Some(e) = lookup(pt.to_gps(&map.gps_bounds)) {
+                    r.elevation.push((pt, e));
+                }
+            }
+            dist += step;
         }
     }
-    timer.stop("apply elevation data to intersections");
+    timer.stop("apply elevation data");
 }
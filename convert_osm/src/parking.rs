@@ -16,10 +16,12 @@ pub fn apply_parking(map: &mut RawMap, opts: &Options, timer: &mut Timer) {
         }
         OnstreetParking::SomeAdditionalWhereNoData { pct } => {
             let pct = pct as i64;
+            let fwd_side = map.config.driving_side.osm_sides().0;
             for (id, r) in map.roads.iter_mut() {
                 // The 20m minimum is a heuristic. PARKING_SPOT_LENGTH is only 8m, but we haven't
-                // trimmed roads between intersections yet.
-                if r.osm_tags.contains_key(osm::INFERRED_PARKING)
+                // trimmed roads between intersections yet. Leave alone roads that an inference rule
+                // already guessed.
+                if r.osm_tags.is(osm::INFERRED_PARKING, "true")
                     && r.osm_tags
                         .is_any(osm::HIGHWAY, vec!["residential", "tertiary"])
                     && !r.osm_tags.is("foot", "no")
@@ -29,7 +31,8 @@ pub fn apply_parking(map: &mut RawMap, opts: &Options, timer: &mut Timer) {
                 {
                     if r.osm_tags.is("oneway", "yes") {
                         r.osm_tags.remove(osm::PARKING_BOTH);
-                        r.osm_tags.insert(osm::PARKING_RIGHT, "parallel");
+                        r.osm_tags
+                            .insert(format!("parking:lane:{}", fwd_side), "parallel");
                     } else {
                         r.osm_tags.insert(osm::PARKING_BOTH, "parallel");
                     }
@@ -137,6 +140,8 @@ fn use_parking_hints(map: &mut RawMap, path: String, timer: &mut Timer) {
                 tags.remove(osm::PARKING_RIGHT).unwrap();
                 tags.insert(osm::PARKING_BOTH, value);
             }
+            // Still not from OSM, but better than a guess
+            tags.insert(osm::INFERRED_PARKING, "blockface");
         }
     }
     timer.stop("apply parking hints");
@@ -0,0 +1,929 @@
+use crate::reader::{skip_tag, Document, Member, Node, NodeID, Relation, RelationID, Way, WayID};
+use abstutil::{prettyprint_usize, Tags, Timer};
+use byteorder::{BigEndian, ReadBytesExt};
+use flate2::read::ZlibDecoder;
+use geom::{Bounds, Distance, GPSBounds, Line, LonLat, Polygon, Pt2D, Triangle};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+
+// Streams through an .osm.pbf file (https://wiki.openstreetmap.org/wiki/PBF_Format) without ever
+// holding the whole thing in memory, so huge regional extracts can be used directly.
+//
+// If a clipping polygon is provided, only nodes inside of it and ways touching or crossing it are
+// kept. The file is read twice: first to find everything in the polygon, then to fill in the nodes
+// of ways that leave it. Later, clip.rs trims those ways to the boundary. Relations are kept if any member
+// survives, but their members outside the polygon are dropped, just like with the XML reader.
+//
+// The result is the same Document that reader::read produces.
+pub fn read(
+    path: &str,
+    input_gps_bounds: &GPSBounds,
+    clip: Option<&Polygon>,
+    timer: &mut Timer,
+) -> Result<Document, Box<dyn Error>> {
+    let clip = clip.map(|polygon| Clip::new(polygon, input_gps_bounds));
+
+    let mut nodes: BTreeMap<NodeID, (LonLat, Tags)> = BTreeMap::new();
+    let mut ways: BTreeMap<WayID, (Vec<NodeID>, Tags)> = BTreeMap::new();
+    let mut relations: BTreeMap<RelationID, (Tags, Vec<(String, Member)>)> = BTreeMap::new();
+    // Nodes outside the clipping polygon that kept ways still need
+    let mut missing_nodes: BTreeSet<NodeID> = BTreeSet::new();
+    // Just the position of nodes outside the clipping polygon, but close to it. Used to find ways
+    // that cross the polygon without having a node inside.
+    let mut nearby_nodes: BTreeMap<NodeID, LonLat> = BTreeMap::new();
+
+    timer.start(format!("read {}", path));
+    let header_bounds = scan(path, |obj| {
+        match obj {
+            Element::Node(id, pt, tags) => match clip {
+                Some(ref c) => {
+                    if c.contains(pt) {
+                        nodes.insert(id, (pt, tags));
+                    } else if c.near.contains(pt) {
+                        nearby_nodes.insert(id, pt);
+                    }
+                }
+                None => {
+                    nodes.insert(id, (pt, tags));
+                }
+            },
+            Element::Way(id, refs, tags) => {
+                let crosses = |c: &Clip| {
+                    refs.windows(2).any(|pair| {
+                        match (nearby_nodes.get(&pair[0]), nearby_nodes.get(&pair[1])) {
+                            (Some(pt1), Some(pt2)) => c.crosses(*pt1, *pt2),
+                            _ => false,
+                        }
+                    })
+                };
+                if refs.iter().any(|n| nodes.contains_key(n))
+                    || clip.as_ref().map(crosses).unwrap_or(false)
+                {
+                    for n in &refs {
+                        if !nodes.contains_key(n) {
+                            missing_nodes.insert(*n);
+                        }
+                    }
+                    ways.insert(id, (refs, tags));
+                }
+            }
+            Element::Relation(id, tags, members) => {
+                let keep = members.iter().any(|(_, m)| match m {
+                    Member::Node(n) => nodes.contains_key(n),
+                    Member::Way(w) => ways.contains_key(w),
+                    Member::Relation(r) => relations.contains_key(r),
+                });
+                if keep {
+                    relations.insert(id, (tags, members));
+                }
+            }
+        }
+        Ok(())
+    })?;
+    timer.stop(format!("read {}", path));
+
+    if !missing_nodes.is_empty() {
+        timer.start(format!(
+            "read {} again for {} nodes outside the clipping polygon",
+            path,
+            prettyprint_usize(missing_nodes.len())
+        ));
+        scan(path, |obj| {
+            if let Element::Node(id, pt, tags) = obj {
+                if missing_nodes.contains(&id) {
+                    nodes.insert(id, (pt, tags));
+                }
+            }
+            Ok(())
+        })?;
+        timer.stop(format!(
+            "read {} again for {} nodes outside the clipping polygon",
+            path,
+            prettyprint_usize(missing_nodes.len())
+        ));
+    }
+
+    let gps_bounds = if *input_gps_bounds != GPSBounds::new() {
+        input_gps_bounds.clone()
+    } else if let Some(b) = header_bounds {
+        b
+    } else {
+        timer.warn(
+            "No clipping polygon provided and the .osm.pbf has no bounding box, so figuring out \
+             the bounds manually."
+                .to_string(),
+        );
+        let mut b = GPSBounds::new();
+        for (pt, _) in nodes.values() {
+            b.update(*pt);
+        }
+        b
+    };
+
+    let mut doc = Document {
+        gps_bounds,
+        nodes: BTreeMap::new(),
+        ways: BTreeMap::new(),
+        relations: BTreeMap::new(),
+    };
+    for (id, (pt, tags)) in nodes {
+        doc.nodes.insert(
+            id,
+            Node {
+                pt: Pt2D::from_gps(pt, &doc.gps_bounds),
+                tags,
+            },
+        );
+    }
+    for (id, (refs, tags)) in ways {
+        let mut nodes = Vec::new();
+        let mut pts = Vec::new();
+        for n in refs {
+            // Just skip missing nodes
+            if let Some(node) = doc.nodes.get(&n) {
+                nodes.push(n);
+                pts.push(node.pt);
+            }
+        }
+        if !nodes.is_empty() {
+            doc.ways.insert(id, Way { nodes, pts, tags });
+        }
+    }
+    for (id, (tags, members)) in relations {
+        let members = members
+            .into_iter()
+            .filter(|(_, m)| match m {
+                Member::Node(n) => doc.nodes.contains_key(n),
+                Member::Way(w) => doc.ways.contains_key(w),
+                Member::Relation(r) => doc.relations.contains_key(r),
+            })
+            .collect();
+        doc.relations.insert(id, Relation { tags, members });
+    }
+
+    timer.note(format!(
+        "Found {} nodes, {} ways, {} relations",
+        prettyprint_usize(doc.nodes.len()),
+        prettyprint_usize(doc.ways.len()),
+        prettyprint_usize(doc.relations.len())
+    ));
+    Ok(doc)
+}
+
+enum Element {
+    Node(NodeID, LonLat, Tags),
+    Way(WayID, Vec<NodeID>, Tags),
+    Relation(RelationID, Tags, Vec<(String, Member)>),
+}
+
+// Ways with a segment longer than this, crossing the clipping polygon with both ends outside of it,
+// can still be missed. Remembering every node in a regional extract would take too much memory.
+const CROSSING_MARGIN: Distance = Distance::const_meters(1000.0);
+
+struct Clip {
+    gps_bounds: GPSBounds,
+    bounds: Bounds,
+    triangles: Vec<Triangle>,
+    // The polygon's bounding box, plus CROSSING_MARGIN
+    near: GPSBounds,
+}
+
+impl Clip {
+    fn new(polygon: &Polygon, gps_bounds: &GPSBounds) -> Clip {
+        let bounds = polygon.get_bounds();
+        let margin = CROSSING_MARGIN.inner_meters();
+        let mut near = GPSBounds::new();
+        near.update(Pt2D::new(bounds.min_x - margin, bounds.min_y - margin).to_gps(gps_bounds));
+        near.update(Pt2D::new(bounds.max_x + margin, bounds.max_y + margin).to_gps(gps_bounds));
+        Clip {
+            gps_bounds: gps_bounds.clone(),
+            bounds,
+            triangles: polygon.triangles(),
+            near,
+        }
+    }
+
+    fn contains(&self, pt: LonLat) -> bool {
+        if !self.gps_bounds.contains(pt) {
+            return false;
+        }
+        let pt = Pt2D::from_gps(pt, &self.gps_bounds);
+        self.bounds.contains(pt) && self.triangles.iter().any(|tri| tri.contains_pt(pt))
+    }
+
+    // Assuming both points are outside the polygon, does the segment between them cross it?
+    fn crosses(&self, pt1: LonLat, pt2: LonLat) -> bool {
+        let pt1 = Pt2D::from_gps(pt1, &self.gps_bounds);
+        let pt2 = Pt2D::from_gps(pt2, &self.gps_bounds);
+        if pt1.x().max(pt2.x()) < self.bounds.min_x
+            || pt1.x().min(pt2.x()) > self.bounds.max_x
+            || pt1.y().max(pt2.y()) < self.bounds.min_y
+            || pt1.y().min(pt2.y()) > self.bounds.max_y
+        {
+            return false;
+        }
+        let segment = match Line::new(pt1, pt2) {
+            Some(l) => l,
+            None => {
+                return false;
+            }
+        };
+        self.triangles.iter().any(|tri| {
+            vec![(tri.pt1, tri.pt2), (tri.pt2, tri.pt3), (tri.pt3, tri.pt1)]
+                .into_iter()
+                .filter_map(|(a, b)| Line::new(a, b))
+                .any(|edge| edge.intersection(&segment).is_some())
+        })
+    }
+}
+
+// Calls the callback on every element in file order -- nodes, then ways, then relations. Returns
+// the bounding box from the file header, if there is one.
+fn scan<F: FnMut(Element) -> Result<(), Box<dyn Error>>>(
+    path: &str,
+    mut cb: F,
+) -> Result<Option<GPSBounds>, Box<dyn Error>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut bounds = None;
+    while let Some((blob_type, data)) = read_blob(&mut reader)? {
+        match blob_type.as_ref() {
+            "OSMHeader" => {
+                bounds = read_header_bounds(&data)?;
+            }
+            "OSMData" => {
+                read_primitive_block(&data, &mut cb)?;
+            }
+            // Unknown blobs are meant to be skipped
+            _ => {}
+        }
+    }
+    Ok(bounds)
+}
+
+// Returns the type and decompressed contents of the next blob, or None at the end of the file.
+fn read_blob(reader: &mut BufReader<File>) -> Result<Option<(String, Vec<u8>)>, Box<dyn Error>> {
+    let header_len = match reader.read_u32::<BigEndian>() {
+        Ok(x) => x as usize,
+        Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(err) => {
+            return Err(err.into());
+        }
+    };
+    let mut header = vec![0; header_len];
+    reader.read_exact(&mut header)?;
+
+    let mut blob_type = String::new();
+    let mut data_len = 0;
+    let mut msg = Message::new(&header);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Bytes(x)) => {
+                blob_type = String::from_utf8(x.to_vec())?;
+            }
+            (3, Field::Varint(x)) => {
+                data_len = x as usize;
+            }
+            _ => {}
+        }
+    }
+
+    let mut blob = vec![0; data_len];
+    reader.read_exact(&mut blob)?;
+    let mut msg = Message::new(&blob);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            // Uncompressed
+            (1, Field::Bytes(x)) => {
+                return Ok(Some((blob_type, x.to_vec())));
+            }
+            (3, Field::Bytes(x)) => {
+                let mut data = Vec::new();
+                ZlibDecoder::new(x).read_to_end(&mut data)?;
+                return Ok(Some((blob_type, data)));
+            }
+            (4, Field::Bytes(_)) | (5, Field::Bytes(_)) | (6, Field::Bytes(_)) => {
+                return Err("only uncompressed and zlib blobs are supported".into());
+            }
+            _ => {}
+        }
+    }
+    Err(format!("{} blob has no data", blob_type).into())
+}
+
+fn read_header_bounds(data: &[u8]) -> Result<Option<GPSBounds>, Box<dyn Error>> {
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        if let (1, Field::Bytes(bbox)) = (num, field) {
+            // In nanodegrees
+            let (mut left, mut right, mut top, mut bottom) = (0, 0, 0, 0);
+            let mut msg = Message::new(bbox);
+            while let Some((num, field)) = msg.next()? {
+                if let Field::Varint(x) = field {
+                    match num {
+                        1 => left = zigzag(x),
+                        2 => right = zigzag(x),
+                        3 => top = zigzag(x),
+                        4 => bottom = zigzag(x),
+                        _ => {}
+                    }
+                }
+            }
+            let mut b = GPSBounds::new();
+            b.update(LonLat::new(left as f64 * 1e-9, bottom as f64 * 1e-9));
+            b.update(LonLat::new(right as f64 * 1e-9, top as f64 * 1e-9));
+            return Ok(Some(b));
+        }
+    }
+    Ok(None)
+}
+
+struct Block {
+    strings: Vec<String>,
+    granularity: i64,
+    lat_offset: i64,
+    lon_offset: i64,
+}
+
+impl Block {
+    fn pt(&self, lon: i64, lat: i64) -> LonLat {
+        LonLat::new(
+            1e-9 * (self.lon_offset + self.granularity * lon) as f64,
+            1e-9 * (self.lat_offset + self.granularity * lat) as f64,
+        )
+    }
+
+    fn string(&self, idx: u64) -> Result<&str, Box<dyn Error>> {
+        self.strings
+            .get(idx as usize)
+            .map(|s| s.as_str())
+            .ok_or_else(|| format!("string table has no entry {}", idx).into())
+    }
+
+    fn tags(&self, keys: &[u64], vals: &[u64]) -> Result<Tags, Box<dyn Error>> {
+        let mut tags = Tags::new(BTreeMap::new());
+        for (k, v) in keys.iter().zip(vals.iter()) {
+            let key = self.string(*k)?;
+            if skip_tag(key) {
+                continue;
+            }
+            tags.insert(key, self.string(*v)?);
+        }
+        Ok(tags)
+    }
+}
+
+fn read_primitive_block<F: FnMut(Element) -> Result<(), Box<dyn Error>>>(
+    data: &[u8],
+    cb: &mut F,
+) -> Result<(), Box<dyn Error>> {
+    let mut block = Block {
+        strings: Vec::new(),
+        granularity: 100,
+        lat_offset: 0,
+        lon_offset: 0,
+    };
+    let mut groups = Vec::new();
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Bytes(x)) => {
+                let mut table = Message::new(x);
+                while let Some((num, field)) = table.next()? {
+                    if let (1, Field::Bytes(s)) = (num, field) {
+                        block.strings.push(String::from_utf8_lossy(s).into_owned());
+                    }
+                }
+            }
+            (2, Field::Bytes(x)) => {
+                groups.push(x);
+            }
+            (17, Field::Varint(x)) => {
+                block.granularity = x as i64;
+            }
+            (19, Field::Varint(x)) => {
+                block.lat_offset = x as i64;
+            }
+            (20, Field::Varint(x)) => {
+                block.lon_offset = x as i64;
+            }
+            _ => {}
+        }
+    }
+
+    for group in groups {
+        let mut msg = Message::new(group);
+        while let Some((num, field)) = msg.next()? {
+            match (num, field) {
+                (1, Field::Bytes(x)) => cb(read_node(&block, x)?)?,
+                (2, Field::Bytes(x)) => {
+                    for node in read_dense_nodes(&block, x)? {
+                        cb(node)?;
+                    }
+                }
+                (3, Field::Bytes(x)) => cb(read_way(&block, x)?)?,
+                (4, Field::Bytes(x)) => cb(read_relation(&block, x)?)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_node(block: &Block, data: &[u8]) -> Result<Element, Box<dyn Error>> {
+    let (mut id, mut lat, mut lon) = (0, 0, 0);
+    let mut keys = Vec::new();
+    let mut vals = Vec::new();
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Varint(x)) => id = zigzag(x),
+            (2, Field::Bytes(x)) => keys = packed_varints(x)?,
+            (3, Field::Bytes(x)) => vals = packed_varints(x)?,
+            (8, Field::Varint(x)) => lat = zigzag(x),
+            (9, Field::Varint(x)) => lon = zigzag(x),
+            _ => {}
+        }
+    }
+    Ok(Element::Node(
+        NodeID(id),
+        block.pt(lon, lat),
+        block.tags(&keys, &vals)?,
+    ))
+}
+
+fn read_dense_nodes(block: &Block, data: &[u8]) -> Result<Vec<Element>, Box<dyn Error>> {
+    let mut ids = Vec::new();
+    let mut lats = Vec::new();
+    let mut lons = Vec::new();
+    let mut keys_vals = Vec::new();
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Bytes(x)) => ids = packed_deltas(x)?,
+            (8, Field::Bytes(x)) => lats = packed_deltas(x)?,
+            (9, Field::Bytes(x)) => lons = packed_deltas(x)?,
+            (10, Field::Bytes(x)) => keys_vals = packed_varints(x)?,
+            _ => {}
+        }
+    }
+    if ids.len() != lats.len() || ids.len() != lons.len() {
+        return Err("DenseNodes with mismatched ids and coordinates".into());
+    }
+
+    // keys_vals is (key, value)* 0 for each node, or totally empty if no node has tags
+    let mut kv = keys_vals.into_iter();
+    let mut results = Vec::new();
+    for ((id, lat), lon) in ids.into_iter().zip(lats).zip(lons) {
+        let mut keys = Vec::new();
+        let mut vals = Vec::new();
+        while let Some(k) = kv.next() {
+            if k == 0 {
+                break;
+            }
+            keys.push(k);
+            vals.push(kv.next().ok_or("DenseNodes tag missing a value")?);
+        }
+        results.push(Element::Node(
+            NodeID(id),
+            block.pt(lon, lat),
+            block.tags(&keys, &vals)?,
+        ));
+    }
+    Ok(results)
+}
+
+fn read_way(block: &Block, data: &[u8]) -> Result<Element, Box<dyn Error>> {
+    let mut id = 0;
+    let mut keys = Vec::new();
+    let mut vals = Vec::new();
+    let mut refs = Vec::new();
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Varint(x)) => id = x as i64,
+            (2, Field::Bytes(x)) => keys = packed_varints(x)?,
+            (3, Field::Bytes(x)) => vals = packed_varints(x)?,
+            (8, Field::Bytes(x)) => refs = packed_deltas(x)?,
+            _ => {}
+        }
+    }
+    Ok(Element::Way(
+        WayID(id),
+        refs.into_iter().map(NodeID).collect(),
+        block.tags(&keys, &vals)?,
+    ))
+}
+
+fn read_relation(block: &Block, data: &[u8]) -> Result<Element, Box<dyn Error>> {
+    let mut id = 0;
+    let mut keys = Vec::new();
+    let mut vals = Vec::new();
+    let mut roles = Vec::new();
+    let mut member_ids = Vec::new();
+    let mut types = Vec::new();
+    let mut msg = Message::new(data);
+    while let Some((num, field)) = msg.next()? {
+        match (num, field) {
+            (1, Field::Varint(x)) => id = x as i64,
+            (2, Field::Bytes(x)) => keys = packed_varints(x)?,
+            (3, Field::Bytes(x)) => vals = packed_varints(x)?,
+            (8, Field::Bytes(x)) => roles = packed_varints(x)?,
+            (9, Field::Bytes(x)) => member_ids = packed_deltas(x)?,
+            (10, Field::Bytes(x)) => types = packed_varints(x)?,
+            _ => {}
+        }
+    }
+
+    let mut members = Vec::new();
+    for ((role, member), member_type) in roles.into_iter().zip(member_ids).zip(types) {
+        let member = match member_type {
+            0 => Member::Node(NodeID(member)),
+            1 => Member::Way(WayID(member)),
+            2 => Member::Relation(RelationID(member)),
+            x => {
+                return Err(format!("unknown relation member type {}", x).into());
+            }
+        };
+        members.push((block.string(role)?.to_string(), member));
+    }
+    Ok(Element::Relation(
+        RelationID(id),
+        block.tags(&keys, &vals)?,
+        members,
+    ))
+}
+
+// Just enough of the protobuf wire format to read OSM PBF.
+struct Message<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    // Fixed-width fields aren't used in OSM PBF
+    Fixed,
+}
+
+impl<'a> Message<'a> {
+    fn new(buf: &'a [u8]) -> Message<'a> {
+        Message { buf, pos: 0 }
+    }
+
+    fn next(&mut self) -> Result<Option<(u64, Field<'a>)>, Box<dyn Error>> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+        let key = self.varint()?;
+        let field = match key & 7 {
+            0 => Field::Varint(self.varint()?),
+            1 => {
+                self.take(8)?;
+                Field::Fixed
+            }
+            2 => {
+                let len = self.varint()? as usize;
+                Field::Bytes(self.take(len)?)
+            }
+            5 => {
+                self.take(4)?;
+                Field::Fixed
+            }
+            x => {
+                return Err(format!("unsupported protobuf wire type {}", x).into());
+            }
+        };
+        Ok(Some((key >> 3, field)))
+    }
+
+    fn varint(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = *self.buf.get(self.pos).ok_or("truncated varint")?;
+            self.pos += 1;
+            result |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+            if shift >= 64 {
+                return Err("varint is too long".into());
+            }
+        }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if self.pos + len > self.buf.len() {
+            return Err("truncated protobuf field".into());
+        }
+        let result = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(result)
+    }
+}
+
+fn zigzag(x: u64) -> i64 {
+    ((x >> 1) as i64) ^ -((x & 1) as i64)
+}
+
+fn packed_varints(buf: &[u8]) -> Result<Vec<u64>, Box<dyn Error>> {
+    let mut msg = Message::new(buf);
+    let mut results = Vec::new();
+    while msg.pos < buf.len() {
+        results.push(msg.varint()?);
+    }
+    Ok(results)
+}
+
+// Packed, zigzag-encoded, and each value is relative to the previous
+fn packed_deltas(buf: &[u8]) -> Result<Vec<i64>, Box<dyn Error>> {
+    let mut sum = 0;
+    Ok(packed_varints(buf)?
+        .into_iter()
+        .map(|x| {
+            sum += zigzag(x);
+            sum
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::ZlibEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    // Just enough of a protobuf encoder to build test fixtures
+
+    fn varint(mut x: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            if x < 0x80 {
+                out.push(x as u8);
+                return out;
+            }
+            out.push((x & 0x7f) as u8 | 0x80);
+            x >>= 7;
+        }
+    }
+
+    fn to_zigzag(x: i64) -> u64 {
+        ((x << 1) ^ (x >> 63)) as u64
+    }
+
+    fn field_varint(num: u64, x: u64) -> Vec<u8> {
+        let mut out = varint(num << 3);
+        out.extend(varint(x));
+        out
+    }
+
+    fn field_bytes(num: u64, bytes: &[u8]) -> Vec<u8> {
+        let mut out = varint((num << 3) | 2);
+        out.extend(varint(bytes.len() as u64));
+        out.extend_from_slice(bytes);
+        out
+    }
+
+    fn packed(xs: &[u64]) -> Vec<u8> {
+        xs.iter().flat_map(|x| varint(*x)).collect()
+    }
+
+    fn packed_delta(xs: &[i64]) -> Vec<u8> {
+        let mut prev = 0;
+        let mut out = Vec::new();
+        for x in xs {
+            out.extend(varint(to_zigzag(x - prev)));
+            prev = *x;
+        }
+        out
+    }
+
+    fn string_table(strings: &[&str]) -> Vec<u8> {
+        let mut table = Vec::new();
+        for s in strings {
+            table.extend(field_bytes(1, s.as_bytes()));
+        }
+        field_bytes(1, &table)
+    }
+
+    fn node_element(e: Element) -> (NodeID, LonLat, Tags) {
+        match e {
+            Element::Node(id, pt, tags) => (id, pt, tags),
+            _ => panic!("not a node"),
+        }
+    }
+
+    fn close(pt: LonLat, lon: f64, lat: f64) -> bool {
+        (pt.x() - lon).abs() < 1e-9 && (pt.y() - lat).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_varint_and_zigzag() {
+        assert_eq!(Message::new(&[0x96, 0x01]).varint().unwrap(), 150);
+        assert_eq!(Message::new(&[0xac, 0x02]).varint().unwrap(), 300);
+        assert_eq!(
+            Message::new(&varint(std::u64::MAX)).varint().unwrap(),
+            std::u64::MAX
+        );
+        assert!(Message::new(&[0x80]).varint().is_err());
+
+        assert_eq!(zigzag(0), 0);
+        assert_eq!(zigzag(1), -1);
+        assert_eq!(zigzag(2), 1);
+        assert_eq!(zigzag(3), -2);
+        assert_eq!(zigzag(4294967294), 2147483647);
+        assert_eq!(zigzag(4294967295), -2147483648);
+        for x in vec![0, 5, -5, std::i64::MAX, std::i64::MIN] {
+            assert_eq!(zigzag(to_zigzag(x)), x);
+        }
+
+        assert_eq!(
+            packed_deltas(&packed_delta(&[10, 11, 15, 3])).unwrap(),
+            vec![10, 11, 15, 3]
+        );
+    }
+
+    #[test]
+    fn test_dense_nodes() {
+        let block = Block {
+            strings: vec!["", "highway", "crossing", "name", "Main"]
+                .into_iter()
+                .map(|s| s.to_string())
+                .collect(),
+            granularity: 100,
+            lat_offset: 0,
+            lon_offset: 0,
+        };
+        let mut dense = field_bytes(1, &packed_delta(&[10, 11, 15]));
+        dense.extend(field_bytes(
+            8,
+            &packed_delta(&[470_000_000, 470_000_010, 469_999_990]),
+        ));
+        dense.extend(field_bytes(
+            9,
+            &packed_delta(&[-1_220_000_000, -1_220_000_000, -1_219_999_000]),
+        ));
+        // The second node has no tags
+        dense.extend(field_bytes(10, &packed(&[1, 2, 0, 0, 3, 4, 0])));
+
+        let nodes: Vec<(NodeID, LonLat, Tags)> = read_dense_nodes(&block, &dense)
+            .unwrap()
+            .into_iter()
+            .map(node_element)
+            .collect();
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(nodes[0].0, NodeID(10));
+        assert_eq!(nodes[1].0, NodeID(11));
+        assert_eq!(nodes[2].0, NodeID(15));
+        assert!(close(nodes[0].1, -122.0, 47.0));
+        assert!(close(nodes[1].1, -122.0, 47.000001));
+        assert!(close(nodes[2].1, -121.9999, 46.999999));
+        assert!(nodes[0].2.is("highway", "crossing"));
+        assert!(nodes[1].2.inner().is_empty());
+        assert!(nodes[2].2.is("name", "Main"));
+    }
+
+    #[test]
+    fn test_granularity_and_offsets() {
+        let mut node = field_varint(1, to_zigzag(-7));
+        node.extend(field_varint(8, to_zigzag(3)));
+        node.extend(field_varint(9, to_zigzag(-2)));
+        let group = field_bytes(1, &node);
+
+        let mut data = string_table(&[""]);
+        data.extend(field_bytes(2, &group));
+        data.extend(field_varint(17, 1000));
+        // Offsets are plain int64, so negative ones take up 10 bytes
+        data.extend(field_varint(19, 500));
+        data.extend(field_varint(20, -300_i64 as u64));
+
+        let mut nodes = Vec::new();
+        read_primitive_block(&data, &mut |e| {
+            nodes.push(node_element(e));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(nodes[0].0, NodeID(-7));
+        assert!(close(
+            nodes[0].1,
+            1e-9 * (-300.0 - 2000.0),
+            1e-9 * (500.0 + 3000.0)
+        ));
+    }
+
+    fn blob(blob_type: &str, data: &[u8], compress: bool) -> Vec<u8> {
+        let blob = if compress {
+            let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            let mut blob = field_varint(2, data.len() as u64);
+            blob.extend(field_bytes(3, &encoder.finish().unwrap()));
+            blob
+        } else {
+            field_bytes(1, data)
+        };
+        let mut header = field_bytes(1, blob_type.as_bytes());
+        header.extend(field_varint(3, blob.len() as u64));
+
+        let mut out = (header.len() as u32).to_be_bytes().to_vec();
+        out.extend(header);
+        out.extend(blob);
+        out
+    }
+
+    // Nodes 1 and 2 are inside of a 0.01 degree box. Nodes 3 and 4 are just west and east of it,
+    // and 5 and 6 are northwest of it.
+    fn fixture(path: &str) {
+        let mut bbox = field_varint(1, to_zigzag(0));
+        bbox.extend(field_varint(2, to_zigzag(10_000_000)));
+        bbox.extend(field_varint(3, to_zigzag(10_000_000)));
+        bbox.extend(field_varint(4, to_zigzag(0)));
+        let header = field_bytes(1, &bbox);
+
+        let mut dense = field_bytes(1, &packed_delta(&[1, 2, 3, 4, 5, 6]));
+        // Granularity is 100 nanodegrees
+        dense.extend(field_bytes(
+            8,
+            &packed_delta(&[20_000, 80_000, 50_000, 50_000, 120_000, 120_000]),
+        ));
+        dense.extend(field_bytes(
+            9,
+            &packed_delta(&[20_000, 80_000, -10_000, 110_000, -10_000, -20_000]),
+        ));
+        dense.extend(field_bytes(10, &packed(&[1, 2, 0, 0, 0, 0, 0, 0])));
+
+        let mut ways = Vec::new();
+        // Inside
+        let mut way = field_varint(1, 100);
+        way.extend(field_bytes(2, &packed(&[1])));
+        way.extend(field_bytes(3, &packed(&[3])));
+        way.extend(field_bytes(8, &packed_delta(&[1, 2])));
+        ways.extend(field_bytes(3, &way));
+        // Crosses the box, without any nodes inside
+        let mut way = field_varint(1, 101);
+        way.extend(field_bytes(8, &packed_delta(&[3, 4])));
+        ways.extend(field_bytes(3, &way));
+        // Totally outside
+        let mut way = field_varint(1, 102);
+        way.extend(field_bytes(8, &packed_delta(&[5, 6])));
+        ways.extend(field_bytes(3, &way));
+
+        let mut relation = field_varint(1, 200);
+        relation.extend(field_bytes(8, &packed(&[4, 4])));
+        relation.extend(field_bytes(9, &packed_delta(&[100, 102])));
+        relation.extend(field_bytes(10, &packed(&[1, 1])));
+
+        let mut data = string_table(&["", "highway", "crossing", "residential", "outer"]);
+        data.extend(field_bytes(2, &field_bytes(2, &dense)));
+        data.extend(field_bytes(2, &ways));
+        data.extend(field_bytes(2, &field_bytes(4, &relation)));
+
+        let mut file = blob("OSMHeader", &header, false);
+        file.extend(blob("OSMData", &data, true));
+        std::fs::write(path, file).unwrap();
+    }
+
+    #[test]
+    fn test_read_fixture() {
+        let path = std::env::temp_dir().join("convert_osm_test_read_fixture.osm.pbf");
+        let path = path.to_str().unwrap();
+        fixture(path);
+
+        // Everything
+        let doc = read(path, &GPSBounds::new(), None, &mut Timer::throwaway()).unwrap();
+        // From the header
+        assert!(doc.gps_bounds.contains(LonLat::new(0.005, 0.005)));
+        assert!(!doc.gps_bounds.contains(LonLat::new(0.011, 0.005)));
+        assert_eq!(doc.nodes.len(), 6);
+        assert!(doc.nodes[&NodeID(1)].tags.is("highway", "crossing"));
+        assert_eq!(doc.ways.len(), 3);
+        assert_eq!(doc.ways[&WayID(100)].nodes, vec![NodeID(1), NodeID(2)]);
+        assert!(doc.ways[&WayID(100)].tags.is("highway", "residential"));
+        assert_eq!(doc.relations[&RelationID(200)].members.len(), 2);
+
+        // Clipped to the box
+        let gps_bounds = doc.gps_bounds.clone();
+        let clip = gps_bounds.to_bounds().get_rectangle();
+        let doc = read(path, &gps_bounds, Some(&clip), &mut Timer::throwaway()).unwrap();
+        assert_eq!(
+            doc.ways.keys().cloned().collect::<Vec<_>>(),
+            vec![WayID(100), WayID(101)]
+        );
+        // The second pass filled in the nodes of the crossing way
+        assert_eq!(doc.ways[&WayID(101)].nodes, vec![NodeID(3), NodeID(4)]);
+        assert!(!doc.nodes.contains_key(&NodeID(5)));
+        // Only the member way that survived is left
+        let members = &doc.relations[&RelationID(200)].members;
+        assert_eq!(members.len(), 1);
+        assert_eq!(members[0].0, "outer");
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
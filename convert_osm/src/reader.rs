@@ -190,8 +190,7 @@ fn read_tags(obj: roxmltree::Node) -> Tags {
     for child in obj.children() {
         if child.tag_name().name() == "tag" {
             let key = child.attribute("k").unwrap();
-            // Filter out really useless data
-            if key.starts_with("tiger:") || key.starts_with("old_name:") {
+            if skip_tag(key) {
                 continue;
             }
             tags.insert(key, child.attribute("v").unwrap());
@@ -200,6 +199,11 @@ fn read_tags(obj: roxmltree::Node) -> Tags {
     tags
 }
 
+// Filter out really useless data
+pub fn skip_tag(key: &str) -> bool {
+    key.starts_with("tiger:") || key.starts_with("old_name:")
+}
+
 fn scrape_bounds(doc: &roxmltree::Document) -> GPSBounds {
     let mut b = GPSBounds::new();
     for obj in doc.descendants() {
@@ -1,14 +1,23 @@
 use crate::extract::OsmExtract;
+use crate::Options;
 use abstutil::{Counter, Timer};
 use geom::{Distance, HashablePt2D, Pt2D};
-use map_model::raw::{OriginalIntersection, OriginalRoad, RawIntersection, RawMap};
-use map_model::{osm, IntersectionType};
+use map_model::raw::{OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad};
+use map_model::{osm, Crossing, IntersectionType};
 use std::collections::HashMap;
 
+// Blocks on low-speed streets at least this long get an unmarked crossing in the middle, if
+// Options::unmarked_crossings is set.
+const MIN_BLOCK_FOR_UNMARKED_CROSSING: Distance = Distance::const_meters(100.0);
+// Crossings mapped closer than this to an intersection are just the crosswalks of that
+// intersection, so they don't split the road.
+const MIN_DIST_FROM_CROSSING_TO_INTERSECTION: Distance = Distance::const_meters(20.0);
+
 // Returns amenities
 pub fn split_up_roads(
     map: &mut RawMap,
     mut input: OsmExtract,
+    opts: &Options,
     timer: &mut Timer,
 ) -> Vec<(Pt2D, String, String)> {
     timer.start("splitting up roads");
@@ -32,6 +41,39 @@ pub fn split_up_roads(
         }
     }
 
+    // Mid-block crossings also split the road, so pedestrians can get across there.
+    for (_, r) in &input.roads {
+        if r.osm_tags.is(osm::SIDEWALK, "none") {
+            continue;
+        }
+        for pt in midblock_crossings(&r.center_points, &input.crossings, &pt_to_intersection) {
+            pt_to_intersection.insert(
+                pt,
+                OriginalIntersection {
+                    osm_node_id: input.osm_node_ids[&pt].0,
+                },
+            );
+        }
+    }
+
+    if opts.unmarked_crossings {
+        for (_, r) in &input.roads {
+            if !is_low_speed(r) {
+                continue;
+            }
+            for pt in midblock_points(&r.center_points, &pt_to_intersection) {
+                let pt = pt.to_hashable();
+                pt_to_intersection.insert(
+                    pt,
+                    OriginalIntersection {
+                        osm_node_id: input.osm_node_ids[&pt].0,
+                    },
+                );
+                input.crossings.entry(pt).or_insert(Crossing::Unmarked);
+            }
+        }
+    }
+
     for (pt, id) in &pt_to_intersection {
         map.intersections.insert(
             *id,
@@ -44,6 +86,7 @@ pub fn split_up_roads(
                 },
                 // Filled out later
                 elevation: Distance::ZERO,
+                midblock_crossing: input.crossings.get(pt).cloned(),
             },
         );
     }
@@ -187,10 +230,124 @@ pub fn split_up_roads(
         }
     }
 
+    // A traffic signal between just two roads only exists to let pedestrians cross.
+    let mut signalized_crossings = Vec::new();
+    for (id, i) in &map.intersections {
+        if i.intersection_type == IntersectionType::TrafficSignal
+            && map.roads_per_intersection(*id).len() == 2
+        {
+            signalized_crossings.push(*id);
+        }
+    }
+    for id in signalized_crossings {
+        map.intersections.get_mut(&id).unwrap().midblock_crossing = Some(Crossing::Marked);
+    }
+
+    if opts.unmarked_crossings {
+        let mut informal = Vec::new();
+        for (id, i) in &map.intersections {
+            let roads = map.roads_per_intersection(*id);
+            if i.midblock_crossing.is_none()
+                && roads.len() == 2
+                && roads.iter().all(|r| is_low_speed(&map.roads[r]))
+            {
+                informal.push(*id);
+            }
+        }
+        for id in informal {
+            map.intersections.get_mut(&id).unwrap().midblock_crossing = Some(Crossing::Unmarked);
+        }
+    }
+
     timer.stop("splitting up roads");
     input.amenities
 }
 
+fn is_low_speed(r: &RawRoad) -> bool {
+    if !r
+        .osm_tags
+        .is_any(osm::HIGHWAY, vec!["residential", "living_street"])
+    {
+        return false;
+    }
+    match r.osm_tags.get(osm::MAXSPEED) {
+        Some(limit) => {
+            if let Ok(kmph) = limit.parse::<f64>() {
+                kmph <= 40.0
+            } else if let Some(mph) = limit
+                .strip_suffix(" mph")
+                .and_then(|x| x.parse::<f64>().ok())
+            {
+                mph <= 25.0
+            } else {
+                false
+            }
+        }
+        None => true,
+    }
+}
+
+// Crossing nodes along a way that aren't already intersections, and aren't too close to one.
+fn midblock_crossings(
+    pts: &Vec<Pt2D>,
+    crossings: &HashMap<HashablePt2D, Crossing>,
+    pt_to_intersection: &HashMap<HashablePt2D, OriginalIntersection>,
+) -> Vec<HashablePt2D> {
+    // The distance of every point from the start of the way
+    let mut dists = vec![Distance::ZERO];
+    for pair in pts.windows(2) {
+        dists.push(*dists.last().unwrap() + pair[0].dist_to(pair[1]));
+    }
+    let mut splits: Vec<Distance> = pts
+        .iter()
+        .zip(dists.iter())
+        .filter(|(pt, _)| pt_to_intersection.contains_key(&pt.to_hashable()))
+        .map(|(_, dist)| *dist)
+        .collect();
+
+    let mut results = Vec::new();
+    for (pt, dist) in pts.iter().zip(dists.iter()) {
+        let pt = pt.to_hashable();
+        if !crossings.contains_key(&pt) || pt_to_intersection.contains_key(&pt) {
+            continue;
+        }
+        if splits
+            .iter()
+            .all(|d| (*d - *dist).abs() >= MIN_DIST_FROM_CROSSING_TO_INTERSECTION)
+        {
+            splits.push(*dist);
+            results.push(pt);
+        }
+    }
+    results
+}
+
+// For every long block along an unsplit way, find the interior point closest to the middle.
+fn midblock_points(
+    pts: &Vec<Pt2D>,
+    pt_to_intersection: &HashMap<HashablePt2D, OriginalIntersection>,
+) -> Vec<Pt2D> {
+    let mut results = Vec::new();
+    // The points in the current block, with the distance to each from the start of the block
+    let mut block: Vec<(Pt2D, Distance)> = vec![(pts[0], Distance::ZERO)];
+    for pair in pts.windows(2) {
+        let dist = block.last().unwrap().1 + pair[0].dist_to(pair[1]);
+        block.push((pair[1], dist));
+        if pt_to_intersection.contains_key(&pair[1].to_hashable()) {
+            let total = dist;
+            if total >= MIN_BLOCK_FOR_UNMARKED_CROSSING && block.len() > 2 {
+                let (pt, _) = block[1..block.len() - 1]
+                    .iter()
+                    .min_by_key(|(_, d)| (*d - total / 2.0).abs())
+                    .unwrap();
+                results.push(*pt);
+            }
+            block = vec![(pair[1], Distance::ZERO)];
+        }
+    }
+    results
+}
+
 // TODO Consider doing this in PolyLine::new always. extend() there does this too.
 fn dedupe_angles(pts: Vec<Pt2D>) -> Vec<Pt2D> {
     let mut result = Vec::new();
@@ -94,6 +94,7 @@ pub struct ColorScheme {
     pub unzoomed_car: Color,
     pub unzoomed_bike: Color,
     pub unzoomed_bus: Color,
+    pub unzoomed_ride_hail: Color,
     pub unzoomed_pedestrian: Color,
 
     // Agents
@@ -104,6 +105,7 @@ pub struct ColorScheme {
     pub bus_body: Color,
     pub bus_label: Color,
     pub train_body: Color,
+    pub ride_hail_body: Color,
     pub ped_head: Color,
     pub ped_foot: Color,
     pub ped_preparing_bike_body: Color,
@@ -195,6 +197,7 @@ impl ColorScheme {
             unzoomed_car: hex("#A32015"),
             unzoomed_bike: hex("#5D9630"),
             unzoomed_bus: hex("#12409D"),
+            unzoomed_ride_hail: hex("#8E44AD"),
             unzoomed_pedestrian: hex("#DF8C3D"),
 
             // Agents
@@ -211,6 +214,7 @@ impl ColorScheme {
             bus_body: Color::rgb(50, 133, 117),
             bus_label: Color::rgb(249, 206, 24),
             train_body: Color::hex("#42B6E9"),
+            ride_hail_body: hex("#8E44AD"),
             ped_head: Color::rgb(139, 69, 19),
             ped_foot: Color::BLACK,
             ped_preparing_bike_body: Color::rgb(255, 0, 144),
@@ -2,6 +2,7 @@ mod city_picker;
 mod colors;
 mod heatmap;
 mod isochrone;
+mod lasso;
 mod minimap;
 mod navigate;
 mod warp;
@@ -10,6 +11,7 @@ pub use self::city_picker::CityPicker;
 pub use self::colors::{ColorDiscrete, ColorLegend, ColorNetwork, ColorScale, DivergingScale};
 pub use self::heatmap::{make_heatmap, HeatmapOptions};
 pub use self::isochrone::IsochroneViewer;
+pub use self::lasso::Lasso;
 pub use self::minimap::Minimap;
 pub use self::warp::Warping;
 use crate::app::App;
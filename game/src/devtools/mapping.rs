@@ -8,7 +8,7 @@ use ezgui::{
     HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
 };
 use geom::{Distance, FindClosest, PolyLine, Polygon};
-use map_model::{osm, RoadID};
+use map_model::{osm, OsmTagChanges, RoadID};
 use sim::DontDrawAgents;
 use std::collections::{BTreeMap, HashSet};
 use std::error::Error;
@@ -393,9 +393,11 @@ impl State for ParkingMapper {
                     }
                     return match ctx.loading_screen("generate OsmChange file", |_, timer| {
                         generate_osmc(
-                            &self.data,
-                            self.composite
-                                .is_checked("max 3 days parking (default in Seattle)"),
+                            &parking_tags(
+                                &self.data,
+                                self.composite
+                                    .is_checked("max 3 days parking (default in Seattle)"),
+                            ),
                             timer,
                         )
                     }) {
@@ -447,26 +449,66 @@ impl State for ParkingMapper {
     }
 }
 
+fn parking_tags(data: &BTreeMap<i64, Value>, in_seattle: bool) -> OsmTagChanges {
+    let mut changes = BTreeMap::new();
+    for (way, value) in data {
+        if value == &Value::Complicated {
+            continue;
+        }
+
+        let mut tags = BTreeMap::new();
+        tags.insert(osm::PARKING_LEFT.to_string(), None);
+        tags.insert(osm::PARKING_RIGHT.to_string(), None);
+        tags.insert(osm::PARKING_BOTH.to_string(), None);
+        let mut set = |k: &str, v: &str| {
+            tags.insert(k.to_string(), Some(v.to_string()));
+        };
+        match value {
+            Value::BothSides => {
+                set(osm::PARKING_BOTH, "parallel");
+                if in_seattle {
+                    set("parking:condition:both:maxstay", "3 days");
+                }
+            }
+            Value::NoStopping => {
+                set(osm::PARKING_BOTH, "no_stopping");
+            }
+            Value::RightOnly => {
+                set(osm::PARKING_RIGHT, "parallel");
+                set(osm::PARKING_LEFT, "no_stopping");
+                if in_seattle {
+                    set("parking:condition:right:maxstay", "3 days");
+                }
+            }
+            Value::LeftOnly => {
+                set(osm::PARKING_LEFT, "parallel");
+                set(osm::PARKING_RIGHT, "no_stopping");
+                if in_seattle {
+                    set("parking:condition:left:maxstay", "3 days");
+                }
+            }
+            Value::Complicated => unreachable!(),
+        }
+        changes.insert(*way, tags);
+    }
+    changes
+}
+
 #[cfg(target_arch = "wasm32")]
-fn generate_osmc(_: &BTreeMap<i64, Value>, _: bool, _: &mut Timer) -> Result<(), Box<dyn Error>> {
+pub fn generate_osmc(_: &OsmTagChanges, _: &mut Timer) -> Result<(), Box<dyn Error>> {
     Err("Woops, mapping mode isn't supported on the web yet"
         .to_string()
         .into())
 }
 
+// Fetches the latest version of each way from OSM, changes its tags, and writes an OsmChange file
+// that can be reviewed and uploaded with JOSM.
 #[cfg(not(target_arch = "wasm32"))]
-fn generate_osmc(
-    data: &BTreeMap<i64, Value>,
-    in_seattle: bool,
-    timer: &mut Timer,
-) -> Result<(), Box<dyn Error>> {
+pub fn generate_osmc(changes: &OsmTagChanges, timer: &mut Timer) -> Result<(), Box<dyn Error>> {
     let mut modified_ways = Vec::new();
-    timer.start_iter("fetch latest OSM data per modified way", data.len());
-    for (way, value) in data {
+    timer.start_iter("fetch latest OSM data per modified way", changes.len());
+    for (way, tag_changes) in changes {
         timer.next();
-        if value == &Value::Complicated {
-            continue;
-        }
 
         let url = format!("https://api.openstreetmap.org/api/0.6/way/{}", way);
         timer.note(format!("Fetching {}", url));
@@ -487,34 +529,12 @@ fn generate_osmc(
         }
 
         // Fill out the tags.
-        osm_tags.remove(osm::PARKING_LEFT);
-        osm_tags.remove(osm::PARKING_RIGHT);
-        osm_tags.remove(osm::PARKING_BOTH);
-        match value {
-            Value::BothSides => {
-                osm_tags.insert(osm::PARKING_BOTH, "parallel");
-                if in_seattle {
-                    osm_tags.insert("parking:condition:both:maxstay", "3 days");
-                }
-            }
-            Value::NoStopping => {
-                osm_tags.insert(osm::PARKING_BOTH, "no_stopping");
-            }
-            Value::RightOnly => {
-                osm_tags.insert(osm::PARKING_RIGHT, "parallel");
-                osm_tags.insert(osm::PARKING_LEFT, "no_stopping");
-                if in_seattle {
-                    osm_tags.insert("parking:condition:right:maxstay", "3 days");
-                }
-            }
-            Value::LeftOnly => {
-                osm_tags.insert(osm::PARKING_LEFT, "parallel");
-                osm_tags.insert(osm::PARKING_RIGHT, "no_stopping");
-                if in_seattle {
-                    osm_tags.insert("parking:condition:left:maxstay", "3 days");
-                }
+        for (k, v) in tag_changes {
+            if let Some(v) = v {
+                osm_tags.insert(k, v);
+            } else {
+                osm_tags.remove(k);
             }
-            Value::Complicated => unreachable!(),
         }
 
         tree.children = other_children;
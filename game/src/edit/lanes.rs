@@ -3,7 +3,7 @@ use crate::common::CommonState;
 use crate::edit::zones::ZoneEditor;
 use crate::edit::{
     apply_map_edits, can_edit_lane, change_speed_limit, maybe_edit_intersection, try_change_lt,
-    try_reverse,
+    try_reverse, ParkingRulesEditor,
 };
 use crate::game::{State, Transition};
 use crate::helpers::ID;
@@ -13,7 +13,7 @@ use ezgui::{
     hotkey, Btn, Color, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Outcome,
     RewriteColor, TextExt, VerticalAlignment, Widget,
 };
-use map_model::{EditCmd, LaneID, LaneType};
+use map_model::{EditCmd, LaneID, LaneType, ParkingFacility};
 
 pub struct LaneEditor {
     l: LaneID,
@@ -74,13 +74,24 @@ impl LaneEditor {
         }
 
         let parent = app.primary.map.get_parent(l);
-        let col = vec![
+        let mut col = vec![
             format!("Convert this lane of {} to what type?", parent.get_name())
                 .draw_text(ctx)
                 .centered_horiz(),
             Widget::custom_row(row).centered(),
             change_speed_limit(ctx, parent.speed_limit),
             Btn::text_fg("Change access restrictions").build_def(ctx, hotkey(Key::A)),
+        ];
+        if lt == LaneType::Parking {
+            col.push(
+                Btn::text_fg(format!(
+                    "Change parking rules ({})",
+                    app.primary.map.get_l(l).parking_rules.describe()
+                ))
+                .build(ctx, "Change parking rules", hotkey(Key::M)),
+            );
+        }
+        col.push(
             Widget::custom_row(vec![
                 Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
                 // TODO Handle reverting speed limit too...
@@ -93,7 +104,7 @@ impl LaneEditor {
                 },
             ])
             .centered(),
-        ];
+        );
 
         let composite = Composite::new(Widget::col(col))
             .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -148,6 +159,13 @@ impl State for LaneEditor {
                         app.primary.map.get_l(self.l).parent,
                     ));
                 }
+                "Change parking rules" => {
+                    return Transition::Push(ParkingRulesEditor::new(
+                        ctx,
+                        app,
+                        ParkingFacility::Onstreet(self.l),
+                    ));
+                }
                 "Finish" => {
                     return Transition::Pop;
                 }
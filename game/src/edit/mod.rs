@@ -1,6 +1,7 @@
 mod bulk;
 mod cluster_traffic_signals;
 mod lanes;
+mod parking;
 mod routes;
 mod select;
 mod stop_signs;
@@ -10,6 +11,7 @@ mod zones;
 
 pub use self::cluster_traffic_signals::ClusterTrafficSignalEditor;
 pub use self::lanes::LaneEditor;
+pub use self::parking::ParkingRulesEditor;
 pub use self::routes::RouteEditor;
 pub use self::stop_signs::StopSignEditor;
 pub use self::traffic_signals::TrafficSignalEditor;
@@ -31,7 +33,9 @@ use ezgui::{
     Widget,
 };
 use geom::Speed;
-use map_model::{EditCmd, IntersectionID, LaneID, LaneType, MapEdits, PermanentMapEdits};
+use map_model::{
+    EditCmd, IntersectionID, LaneID, LaneType, MapEdits, ParkingFacility, PermanentMapEdits,
+};
 use sim::DontDrawAgents;
 use std::collections::BTreeSet;
 
@@ -151,6 +155,10 @@ impl State for EditMode {
                     app.primary.current_selection = None;
                 }
             } else if let Some(ID::Road(_)) = app.primary.current_selection {
+            } else if let Some(ID::ParkingLot(_)) = app.primary.current_selection {
+                if !self.mode.can_edit_lanes() {
+                    app.primary.current_selection = None;
+                }
             } else {
                 app.primary.current_selection = None;
             }
@@ -199,6 +207,9 @@ impl State for EditMode {
                         Some(Transition::Pop),
                     ));
                 }
+                "export to OSM" => {
+                    return Transition::Push(export_to_osm(ctx, app));
+                }
                 "undo" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     let maybe_id = cmd_to_id(&edits.commands.pop().unwrap());
@@ -262,6 +273,15 @@ impl State for EditMode {
                     )));
                 }
             }
+            if let Some(ID::ParkingLot(pl)) = app.primary.current_selection {
+                if app.per_obj.left_click(ctx, "edit parking rules") {
+                    return Transition::Push(ParkingRulesEditor::new(
+                        ctx,
+                        app,
+                        ParkingFacility::Lot(pl),
+                    ));
+                }
+            }
         }
 
         match self.tool_panel.event(ctx) {
@@ -704,6 +724,11 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         } else {
             Btn::text_fg("Autosaved!").inactive(ctx)
         },
+    ];
+    if app.opts.dev {
+        col.push(Btn::text_fg("export to OSM").build_def(ctx, None));
+    }
+    col.push(
         Text::from_multiline(vec![
             Line(format!("{} lane types changed", edits.original_lts.len())),
             Line(format!("{} lanes reversed", edits.reversed_lanes.len())),
@@ -717,7 +742,7 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
             )),
         ])
         .draw(ctx),
-    ];
+    );
 
     for (idx, cmd) in edits.commands.iter().rev().take(5).enumerate() {
         col.push(
@@ -737,6 +762,34 @@ fn make_changelist(ctx: &mut EventCtx, app: &App) -> Composite {
         .build(ctx)
 }
 
+// Lane and speed limit edits are often really fixing OSM, so let mappers upstream them.
+fn export_to_osm(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+    let (changes, mut warnings) = app
+        .primary
+        .map
+        .get_edits()
+        .osm_tag_changes(&app.primary.map);
+    if changes.is_empty() {
+        warnings.insert(0, "None of these edits change OSM tags".to_string());
+        return msg("Nothing to export", warnings);
+    }
+    match ctx.loading_screen("generate OsmChange file", |_, timer| {
+        crate::devtools::mapping::generate_osmc(&changes, timer)
+    }) {
+        Ok(()) => {
+            warnings.insert(
+                0,
+                format!(
+                    "diff.osc changes {} ways. Load it in JOSM, verify, and upload!",
+                    changes.len()
+                ),
+            );
+            msg("Diff generated", warnings)
+        }
+        Err(err) => msg("Error", vec![format!("{}", err)]),
+    }
+}
+
 // TODO Ideally a Tab.
 fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
     match cmd {
@@ -746,5 +799,10 @@ fn cmd_to_id(cmd: &EditCmd) -> Option<ID> {
         EditCmd::ChangeIntersection { i, .. } => Some(ID::Intersection(*i)),
         EditCmd::ChangeAccessRestrictions { id, .. } => Some(ID::Road(*id)),
         EditCmd::ChangeRouteSchedule { .. } => None,
+        EditCmd::ChangeParkingRules { id, .. } => match id {
+            ParkingFacility::Onstreet(l) => Some(ID::Lane(*l)),
+            ParkingFacility::Lot(pl) => Some(ID::ParkingLot(*pl)),
+        },
+        EditCmd::ChangeCrossing { i, .. } => Some(ID::Intersection(*i)),
     }
 }
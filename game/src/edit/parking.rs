@@ -0,0 +1,127 @@
+use crate::app::App;
+use crate::common::CommonState;
+use crate::edit::apply_map_edits;
+use crate::game::{State, Transition};
+use ezgui::{
+    hotkey, Btn, Choice, Composite, EventCtx, GfxCtx, HorizontalAlignment, Key, Line, Outcome,
+    TextExt, VerticalAlignment, Widget,
+};
+use geom::Duration;
+use map_model::{EditCmd, ParkingFacility, ParkingRules};
+
+pub struct ParkingRulesEditor {
+    composite: Composite,
+    id: ParkingFacility,
+}
+
+impl ParkingRulesEditor {
+    pub fn new(ctx: &mut EventCtx, app: &App, id: ParkingFacility) -> Box<dyn State> {
+        let rules = app.primary.map.get_parking_rules(id);
+        let name = match id {
+            ParkingFacility::Onstreet(l) => {
+                format!("Parking along {}", app.primary.map.get_parent(l).get_name())
+            }
+            ParkingFacility::Lot(pl) => pl.to_string(),
+        };
+
+        let mut prices = vec![
+            Choice::new("free", 0),
+            Choice::new("$0.50 per hour", 50),
+            Choice::new("$1 per hour", 100),
+            Choice::new("$2 per hour", 200),
+            Choice::new("$3 per hour", 300),
+            Choice::new("$4 per hour", 400),
+            Choice::new("$6 per hour", 600),
+            Choice::new("$8 per hour", 800),
+        ];
+        if !prices.iter().any(|c| c.data == rules.cents_per_hour) {
+            prices.push(Choice::new(
+                format!("${:.2} per hour", (rules.cents_per_hour as f64) / 100.0),
+                rules.cents_per_hour,
+            ));
+        }
+        let mut limits = vec![
+            Choice::new("no limit", None),
+            Choice::new("30 minutes", Some(Duration::minutes(30))),
+            Choice::new("1 hour", Some(Duration::hours(1))),
+            Choice::new("2 hours", Some(Duration::hours(2))),
+            Choice::new("4 hours", Some(Duration::hours(4))),
+        ];
+        if !limits.iter().any(|c| c.data == rules.max_stay) {
+            limits.push(Choice::new(
+                rules.max_stay.unwrap().to_string(),
+                rules.max_stay,
+            ));
+        }
+
+        Box::new(ParkingRulesEditor {
+            composite: Composite::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Parking rules").small_heading().draw(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                Line(name).draw(ctx),
+                Widget::row(vec![
+                    "Price:".draw_text(ctx).centered_vert(),
+                    Widget::dropdown(ctx, "price", rules.cents_per_hour, prices),
+                ]),
+                Widget::row(vec![
+                    "Time limit:".draw_text(ctx).centered_vert(),
+                    Widget::dropdown(ctx, "time limit", rules.max_stay, limits),
+                ]),
+                Btn::text_bg2("Apply").build_def(ctx, hotkey(Key::Enter)),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            id,
+        })
+    }
+}
+
+impl State for ParkingRulesEditor {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                "Apply" => {
+                    let new = ParkingRules {
+                        cents_per_hour: self.composite.dropdown_value("price"),
+                        max_stay: self.composite.dropdown_value("time limit"),
+                    };
+                    let old = app.primary.map.get_parking_rules(self.id);
+                    if new != old {
+                        let mut edits = app.primary.map.get_edits().clone();
+                        edits.commands.push(EditCmd::ChangeParkingRules {
+                            id: self.id,
+                            new,
+                            old,
+                        });
+                        apply_map_edits(ctx, app, edits);
+                    }
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+
+        Transition::Keep
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        if let ParkingFacility::Lot(pl) = self.id {
+            g.draw_polygon(
+                app.cs.perma_selected_object,
+                app.primary.map.get_pl(pl).polygon.clone(),
+            );
+        }
+        self.composite.draw(g);
+        CommonState::draw_osd(g, app);
+    }
+}
@@ -11,7 +11,8 @@ use ezgui::{
 };
 use geom::Polygon;
 use map_model::{
-    ControlStopSign, ControlTrafficSignal, EditCmd, EditIntersection, IntersectionID, RoadID,
+    ControlStopSign, ControlTrafficSignal, Crossing, EditCmd, EditIntersection, IntersectionID,
+    RoadID,
 };
 use std::collections::HashMap;
 
@@ -58,6 +59,21 @@ impl StopSignEditor {
             },
             Btn::text_fg("close intersection for construction").build_def(ctx, hotkey(Key::C)),
             Btn::text_fg("convert to traffic signal").build_def(ctx, None),
+            if app.primary.map.get_i(id).is_degenerate() {
+                let current = app.primary.map.get_i(id).midblock_crossing;
+                Widget::row(vec![
+                    "Mid-block crossing:".draw_text(ctx),
+                    crossing_btn(ctx, "marked crossing", current == Some(Crossing::Marked)),
+                    crossing_btn(
+                        ctx,
+                        "unmarked crossing",
+                        current == Some(Crossing::Unmarked),
+                    ),
+                    crossing_btn(ctx, "default crossing", current.is_none()),
+                ])
+            } else {
+                Widget::nothing()
+            },
             Btn::text_fg("Finish").build_def(ctx, hotkey(Key::Escape)),
         ]))
         .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
@@ -154,6 +170,30 @@ impl State for StopSignEditor {
                         return Transition::Pop;
                     }
                 }
+                "marked crossing" | "unmarked crossing" | "default crossing" => {
+                    let new = match x.as_ref() {
+                        "marked crossing" => Some(Crossing::Marked),
+                        "unmarked crossing" => Some(Crossing::Unmarked),
+                        _ => None,
+                    };
+                    let cmd = EditCmd::ChangeCrossing {
+                        i: self.id,
+                        old: app.primary.map.get_i(self.id).midblock_crossing,
+                        new,
+                    };
+                    if let Some(err) = check_sidewalk_connectivity(ctx, app, cmd.clone()) {
+                        return Transition::Push(err);
+                    }
+                    let mut edits = app.primary.map.get_edits().clone();
+                    edits.commands.push(cmd);
+                    apply_map_edits(ctx, app, edits);
+                    return Transition::Replace(Box::new(StopSignEditor::new(
+                        ctx,
+                        app,
+                        self.id,
+                        self.mode.clone(),
+                    )));
+                }
                 "convert to traffic signal" => {
                     let mut edits = app.primary.map.get_edits().clone();
                     edits.commands.push(EditCmd::ChangeIntersection {
@@ -217,3 +257,11 @@ impl State for StopSignEditor {
         }
     }
 }
+
+fn crossing_btn(ctx: &EventCtx, label: &str, current: bool) -> Widget {
+    if current {
+        Btn::text_fg(label).inactive(ctx)
+    } else {
+        Btn::text_fg(label).build_def(ctx, None)
+    }
+}
@@ -41,7 +41,7 @@ pub fn check_sidewalk_connectivity(
     let mut err_state = msg(
         "Error",
         vec![format!(
-            "Can't close this intersection; {} sidewalks disconnected",
+            "Can't make this change; {} sidewalks disconnected",
             newly_disconnected.len()
         )],
     );
@@ -7,7 +7,7 @@ use ezgui::{
     Text, Widget,
 };
 use geom::{ArrowCap, Distance, Duration, PolyLine, Time};
-use map_model::{IntersectionID, IntersectionType};
+use map_model::{Crossing, IntersectionID, IntersectionType};
 use sim::AgentType;
 use std::collections::{BTreeMap, BTreeSet};
 
@@ -24,6 +24,15 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: IntersectionID
         // TODO The spacing is ignored, so use -
         txt.add(Line(format!("- {}", r)));
     }
+    match i.midblock_crossing {
+        Some(Crossing::Marked) if i.is_degenerate() => {
+            txt.add(Line("Marked mid-block crossing"));
+        }
+        Some(Crossing::Unmarked) if i.is_degenerate() => {
+            txt.add(Line("Unmarked mid-block crossing"));
+        }
+        _ => {}
+    }
     rows.push(txt.draw(ctx));
 
     if app.opts.dev {
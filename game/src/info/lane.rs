@@ -1,8 +1,10 @@
 use crate::app::App;
+use crate::info::parking_lot::parking_usage;
 use crate::info::{header_btns, make_table, make_tabs, throughput, DataOptions, Details, Tab};
 use abstutil::prettyprint_usize;
-use ezgui::{Btn, EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Widget};
-use map_model::LaneID;
+use ezgui::{Btn, Color, EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Widget};
+use geom::{Duration, Time};
+use map_model::{LaneID, ParkingFacility};
 use std::collections::HashSet;
 
 pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec<Widget> {
@@ -20,6 +22,16 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
         // TODO Ideally the area name, and be more specific about access restrictions
         kv.push(("Access", "Private".to_string()));
     }
+    if let Some(how) = l.guessed_by(map) {
+        kv.push((
+            "Data source",
+            if how == "true" {
+                "Guessed; not in OpenStreetMap".to_string()
+            } else {
+                format!("Guessed by {}; not in OpenStreetMap", how)
+            },
+        ));
+    }
 
     if l.is_parking() {
         kv.push((
@@ -30,12 +42,34 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
                 l.number_parking_spots()
             ),
         ));
+        kv.push(("Rules", l.parking_rules.describe()));
     } else {
         kv.push(("Speed limit", r.speed_limit.to_string()));
     }
 
     kv.push(("Length", l.length().describe_rounded()));
 
+    if l.is_driving() {
+        let mut num_stops = 0;
+        let mut blocked = Duration::ZERO;
+        for (_, _, lane, dwell) in &app.primary.sim.get_analytics().freight_stops {
+            if *lane == l.id {
+                num_stops += 1;
+                blocked += *dwell;
+            }
+        }
+        if num_stops > 0 {
+            kv.push((
+                "Deliveries",
+                format!(
+                    "{} stops, blocking the lane for {}",
+                    prettyprint_usize(num_stops),
+                    blocked
+                ),
+            ));
+        }
+    }
+
     rows.extend(make_table(ctx, kv.into_iter()));
 
     if l.is_parking() {
@@ -60,6 +94,22 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
                 ),
             });
         }
+        if let Some(survey) = app.primary.sim.get_parking_survey() {
+            let mut pts = Vec::new();
+            for (hour, occupied) in survey.observed_for_lane(l.id, map) {
+                let free = capacity - (occupied.round() as usize).min(capacity);
+                let start = Time::START_OF_DAY + Duration::hours(hour);
+                pts.push((start, free));
+                pts.push((start + Duration::hours(1), free));
+            }
+            if !pts.is_empty() {
+                series.push(Series {
+                    label: "Surveyed".to_string(),
+                    color: Color::PURPLE,
+                    pts,
+                });
+            }
+        }
         rows.push("Parking spots available".draw_text(ctx));
         rows.push(LinePlot::new(
             ctx,
@@ -71,6 +121,12 @@ pub fn info(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Vec
                 disabled: HashSet::new(),
             },
         ));
+        rows.extend(parking_usage(
+            ctx,
+            app,
+            ParkingFacility::Onstreet(l.id),
+            capacity,
+        ));
     }
 
     rows
@@ -98,7 +154,7 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
     }
 
     if let Some(types) = l
-        .get_turn_restrictions(r)
+        .get_turn_restrictions(r, map.get_driving_side())
         .map(|types| types.collect::<Vec<_>>())
     {
         kv.push(("Turn restrictions".to_string(), format!("{:?}", types)));
@@ -123,6 +179,10 @@ pub fn debug(ctx: &EventCtx, app: &App, details: &mut Details, id: LaneID) -> Ve
         "Incline / grade".to_string(),
         format!("{:.1}%", r.percent_grade(map) * 100.0),
     ));
+    kv.push((
+        "Steepest grade".to_string(),
+        format!("{:.1}%", r.steepest_grade() * 100.0),
+    ));
     kv.push((
         "Elevation details".to_string(),
         format!(
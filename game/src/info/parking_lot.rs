@@ -1,8 +1,9 @@
 use crate::app::App;
 use crate::info::{header_btns, make_tabs, Details, Tab};
 use abstutil::prettyprint_usize;
-use ezgui::{EventCtx, Line, LinePlot, PlotOptions, Series, TextExt, Widget};
-use map_model::ParkingLotID;
+use ezgui::{EventCtx, Line, LinePlot, PlotOptions, Series, Text, TextExt, Widget};
+use geom::{Duration, Time};
+use map_model::{ParkingFacility, ParkingLotID};
 use std::collections::HashSet;
 
 pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: ParkingLotID) -> Vec<Widget> {
@@ -18,6 +19,7 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: ParkingLot
         )
         .draw_text(ctx),
     );
+    rows.push(format!("Rules: {}", pl.parking_rules.describe()).draw_text(ctx));
 
     let mut series = vec![Series {
         label: format!("After \"{}\"", app.primary.map.get_edits().edits_name),
@@ -50,10 +52,53 @@ pub fn info(ctx: &mut EventCtx, app: &App, details: &mut Details, id: ParkingLot
             disabled: HashSet::new(),
         },
     ));
+    rows.extend(parking_usage(
+        ctx,
+        app,
+        ParkingFacility::Lot(pl.id),
+        capacity,
+    ));
 
     rows
 }
 
+// Revenue and average occupancy per hour, for parking lanes and lots
+pub fn parking_usage(
+    ctx: &EventCtx,
+    app: &App,
+    facility: ParkingFacility,
+    capacity: usize,
+) -> Vec<Widget> {
+    let analytics = app.primary.sim.get_analytics();
+    let now = app.primary.sim.time();
+    let revenue = analytics.parking_revenue_per_hour(now, facility);
+    let occupancy = analytics.parking_occupancy_per_hour(now, facility, capacity);
+
+    let mut txt = Text::from(Line(format!(
+        "Revenue so far: {}",
+        describe_cents(revenue.iter().sum())
+    )));
+    for (hour, (cents, pct)) in revenue.into_iter().zip(occupancy.into_iter()).enumerate() {
+        if cents == 0 && pct == 0.0 {
+            continue;
+        }
+        txt.add(
+            Line(format!(
+                "{}: {}% full, {}",
+                (Time::START_OF_DAY + Duration::hours(hour)).ampm_tostring(),
+                (pct * 100.0).round(),
+                describe_cents(cents)
+            ))
+            .secondary(),
+        );
+    }
+    vec![txt.draw(ctx)]
+}
+
+fn describe_cents(cents: usize) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
 fn header(ctx: &EventCtx, details: &mut Details, id: ParkingLotID, tab: Tab) -> Vec<Widget> {
     vec![
         Widget::row(vec![
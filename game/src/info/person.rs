@@ -10,8 +10,8 @@ use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
 use rand_xorshift::XorShiftRng;
 use sim::{
-    AgentID, CarID, ParkingSpot, PedestrianID, Person, PersonID, PersonState, TripEndpoint, TripID,
-    TripMode, TripResult, VehicleType,
+    AgentID, CarID, Mobility, ParkingSpot, PedestrianID, Person, PersonID, PersonState,
+    TripEndpoint, TripID, TripMode, TripResult, VehicleType,
 };
 use std::collections::BTreeMap;
 
@@ -139,6 +139,7 @@ pub fn trips(
                         TripMode::Bike => "system/assets/meters/bike.svg",
                         TripMode::Drive => "system/assets/meters/car.svg",
                         TripMode::Transit => "system/assets/meters/bus.svg",
+                        TripMode::RideHail => "system/assets/meters/car.svg",
                     },
                 )
                 .color(RewriteColor::ChangeAll(color))
@@ -255,9 +256,24 @@ pub fn bio(
     rows.push(Widget::draw_batch(ctx, batch).centered_horiz());
 
     let nickname = petname::Petnames::default().generate(&mut rng, 2, " ");
-    let age = rng.gen_range(5, 100);
+    let age = if let Some(age) = person.attributes.age {
+        age.describe().to_string()
+    } else {
+        rng.gen_range(5, 100).to_string()
+    };
 
-    let mut table = vec![("Nickname", nickname), ("Age", age.to_string())];
+    let mut table = vec![("Nickname", nickname), ("Age", age)];
+    if let Some(income) = person.attributes.income {
+        table.push(("Household income", income.describe().to_string()));
+    }
+    if let Some(has_car) = person.attributes.has_car {
+        table.push(("Owns a car", if has_car { "yes" } else { "no" }.to_string()));
+    }
+    if let Some(mobility) = person.attributes.mobility {
+        if mobility != Mobility::Unrestricted {
+            table.push(("Mobility", mobility.describe().to_string()));
+        }
+    }
     if app.opts.dev {
         table.push(("Debug ID", format!("{:?}", person.orig_id)));
     }
@@ -444,9 +460,13 @@ pub fn parked_car(
     let mut rows = vec![];
 
     rows.push(Widget::row(vec![
-        Line(format!("Parked car #{}", id.0))
-            .small_heading()
-            .draw(ctx),
+        Line(if id.1.is_freight() || id.1 == VehicleType::RideHail {
+            id.to_string()
+        } else {
+            format!("Parked car #{}", id.0)
+        })
+        .small_heading()
+        .draw(ctx),
         Widget::row(vec![
             // Little indirect, but the handler of this action is actually the ContextualActions
             // for SandboxMode.
@@ -470,14 +490,42 @@ pub fn parked_car(
 
     // TODO how long idle, prev trips, next trips, etc
 
-    let p = app.primary.sim.get_owner_of_car(id).unwrap();
-    rows.push(Btn::text_bg2(format!("Owned by {}", p)).build_def(ctx, None));
-    details.hyperlinks.insert(
-        format!("Owned by {}", p),
-        Tab::PersonTrips(p, BTreeMap::new()),
-    );
+    // Cars seeded to match a parking survey, delivery vehicles, and ride-hail vehicles don't belong
+    // to anybody.
+    if let Some(p) = app.primary.sim.get_owner_of_car(id) {
+        rows.push(Btn::text_bg2(format!("Owned by {}", p)).build_def(ctx, None));
+        details.hyperlinks.insert(
+            format!("Owned by {}", p),
+            Tab::PersonTrips(p, BTreeMap::new()),
+        );
+    }
 
-    if let Some(p) = app.primary.sim.lookup_parked_car(id) {
+    if id.1.is_freight() {
+        if let Some(pt) = app
+            .primary
+            .sim
+            .canonical_pt_for_agent(AgentID::Car(id), &app.primary.map)
+        {
+            ctx.canvas.center_on_map_pt(pt);
+            rows.push("Making deliveries".draw_text(ctx));
+        } else {
+            rows.push("Left the map".draw_text(ctx));
+        }
+    } else if id.1 == VehicleType::RideHail {
+        if let Some(pt) = app
+            .primary
+            .sim
+            .canonical_pt_for_agent(AgentID::Car(id), &app.primary.map)
+        {
+            ctx.canvas.center_on_map_pt(pt);
+        }
+        rows.push(
+            app.primary
+                .sim
+                .describe_ride_hail_vehicle(id)
+                .draw_text(ctx),
+        );
+    } else if let Some(p) = app.primary.sim.lookup_parked_car(id) {
         match p.spot {
             ParkingSpot::Onstreet(_, _) | ParkingSpot::Lot(_, _) => {
                 ctx.canvas.center_on_map_pt(
@@ -532,8 +580,16 @@ fn header(
                     AgentID::Car(c) => match c.1 {
                         VehicleType::Car => ("driving", Some("system/assets/meters/car.svg")),
                         VehicleType::Bike => ("biking", Some("system/assets/meters/bike.svg")),
-                        VehicleType::Bus | VehicleType::Train => unreachable!(),
+                        VehicleType::Bus
+                        | VehicleType::Train
+                        | VehicleType::Van
+                        | VehicleType::Truck
+                        | VehicleType::RideHail => unreachable!(),
                     },
+                    AgentID::BusPassenger(_, c) if c.1 == VehicleType::RideHail => (
+                        "riding in a ride-hail vehicle",
+                        Some("system/assets/meters/car.svg"),
+                    ),
                     AgentID::BusPassenger(_, _) => {
                         ("riding a bus", Some("system/assets/meters/bus.svg"))
                     }
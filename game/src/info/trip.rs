@@ -292,6 +292,19 @@ pub fn finished(
                 .force_width_pct(ctx, col_width),
             waiting.to_string().draw_text(ctx),
         ]));
+
+        let analytics = if open_trips[&id].show_after {
+            app.primary.sim.get_analytics()
+        } else {
+            app.prebaked()
+        };
+        if let Some(e) = analytics.trip_emissions.get(&id) {
+            col.push(Widget::custom_row(vec![
+                Widget::custom_row(vec![Line("Emissions").secondary().draw(ctx)])
+                    .force_width_pct(ctx, col_width),
+                format!("{:.0} g CO2", e.co2_grams).draw_text(ctx),
+            ]));
+        }
     }
 
     col.push(make_timeline(
@@ -526,6 +539,10 @@ fn make_timeline(
                         "system/assets/timeline/waiting_for_bus.svg"
                     }
                     TripPhaseType::RidingBus(_, _, _) => "system/assets/timeline/riding_bus.svg",
+                    TripPhaseType::WaitingForRideHail => {
+                        "system/assets/timeline/waiting_for_bus.svg"
+                    }
+                    TripPhaseType::RidingRideHail(_) => "system/assets/timeline/driving.svg",
                     TripPhaseType::Aborted | TripPhaseType::Finished => unreachable!(),
                     TripPhaseType::DelayedStart => "system/assets/timeline/delayed_start.svg",
                     // TODO What icon should represent this?
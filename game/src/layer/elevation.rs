@@ -7,6 +7,9 @@ use ezgui::{
 };
 use geom::{ArrowCap, Distance, PolyLine};
 
+// Roads at least this steep get the most extreme color
+const STEEPEST_GRADE: f64 = 0.15;
+
 pub struct Elevation {
     unzoomed: Drawable,
     zoomed: Drawable,
@@ -44,13 +47,13 @@ impl Elevation {
 
         let mut max = 0.0_f64;
         for r in app.primary.map.all_roads() {
-            let pct = r.percent_grade(&app.primary.map).abs();
+            let pct = r.steepest_grade().abs();
             max = max.max(pct);
 
-            let color = app.cs.good_to_bad_red.eval(
-                // TODO Rescale based on a reasonable steepest grade, once the data doesn't suck
-                pct.max(0.0).min(1.0),
-            );
+            let color = app
+                .cs
+                .good_to_bad_red
+                .eval((pct / STEEPEST_GRADE).max(0.0).min(1.0));
             colorer.add_r(r.id, color);
         }
 
@@ -59,34 +62,36 @@ impl Elevation {
         // TODO Draw V's, not arrows.
         // TODO Or try gradient colors.
         for r in app.primary.map.all_roads() {
-            let mut pl = r.center_pts.clone();
-            let e1 = app.primary.map.get_i(r.src_i).elevation;
-            let e2 = app.primary.map.get_i(r.dst_i).elevation;
-            if (e1 - e2).abs() / pl.length() < 0.01 {
-                // Don't bother with ~flat roads
-                continue;
-            }
-            if e1 > e2 {
-                pl = pl.reversed();
-            }
+            // Each piece of the road can go up or down
+            let mut start = Distance::ZERO;
+            for (len, grade) in r.grade_profile() {
+                let mut pl = r.center_pts.exact_slice(start, start + len);
+                start += len;
+                if grade.abs() < 0.01 {
+                    // Don't bother with ~flat pieces
+                    continue;
+                }
+                if grade < 0.0 {
+                    pl = pl.reversed();
+                }
 
-            let arrow_len = Distance::meters(5.0);
-            let btwn = Distance::meters(10.0);
-            let thickness = Distance::meters(1.0);
-            let len = pl.length();
+                let arrow_len = Distance::meters(5.0);
+                let btwn = Distance::meters(10.0);
+                let thickness = Distance::meters(1.0);
 
-            let mut dist = arrow_len;
-            while dist + arrow_len <= len {
-                let (pt, angle) = pl.must_dist_along(dist);
-                batch.push(
-                    Color::BLACK,
-                    PolyLine::must_new(vec![
-                        pt.project_away(arrow_len / 2.0, angle.opposite()),
-                        pt.project_away(arrow_len / 2.0, angle),
-                    ])
-                    .make_arrow(thickness, ArrowCap::Triangle),
-                );
-                dist += btwn;
+                let mut dist = arrow_len;
+                while dist + arrow_len <= len {
+                    let (pt, angle) = pl.must_dist_along(dist);
+                    batch.push(
+                        Color::BLACK,
+                        PolyLine::must_new(vec![
+                            pt.project_away(arrow_len / 2.0, angle.opposite()),
+                            pt.project_away(arrow_len / 2.0, angle),
+                        ])
+                        .make_arrow(thickness, ArrowCap::Triangle),
+                    );
+                    dist += btwn;
+                }
             }
         }
         colorer.unzoomed.append(batch);
@@ -101,10 +106,17 @@ impl Elevation {
             ]),
             Text::from_multiline(vec![
                 Line(format!("Steepest road: {:.0}% grade", max * 100.0)),
-                Line("Note: elevation data is currently wrong!").secondary(),
+                Line("Grades are only as good as the elevation data used for this map").secondary(),
             ])
             .draw(ctx),
-            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["flat", "steep"]),
+            ColorLegend::gradient(
+                ctx,
+                &app.cs.good_to_bad_red,
+                vec![
+                    "flat".to_string(),
+                    format!("{:.0}%+", STEEPEST_GRADE * 100.0),
+                ],
+            ),
         ]))
         .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
         .build(ctx);
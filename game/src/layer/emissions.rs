@@ -0,0 +1,214 @@
+use crate::app::App;
+use crate::common::{ColorLegend, ColorNetwork, DivergingScale};
+use crate::layer::{Layer, LayerOutcome};
+use abstutil::Counter;
+use ezgui::{
+    hotkey, Btn, Checkbox, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key,
+    Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Time;
+use map_model::{IntersectionID, RoadID};
+use std::collections::{BTreeMap, BTreeSet};
+
+pub struct Emissions {
+    time: Time,
+    compare: bool,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for Emissions {
+    fn name(&self) -> Option<&'static str> {
+        Some("emissions")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = Emissions::new(ctx, app, self.compare);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                *self = Emissions::new(
+                    ctx,
+                    app,
+                    self.composite
+                        .maybe_is_checked("Compare before edits")
+                        .unwrap_or(false),
+                );
+                self.composite.align_above(ctx, minimap);
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl Emissions {
+    pub fn new(ctx: &mut EventCtx, app: &App, compare: bool) -> Emissions {
+        if compare {
+            return Emissions::compare_emissions(ctx, app);
+        }
+
+        let (per_road, per_intersection) = app
+            .primary
+            .sim
+            .get_analytics()
+            .emissions_until(app.primary.sim.time());
+
+        let mut colorer = ColorNetwork::new(app);
+        let mut roads = Counter::new();
+        for (r, e) in &per_road {
+            roads.add(*r, e.co2_grams as usize);
+        }
+        let mut intersections = Counter::new();
+        for (i, e) in &per_intersection {
+            intersections.add(*i, e.co2_grams as usize);
+        }
+        colorer.ranked_roads(roads, &app.cs.good_to_bad_red);
+        colorer.ranked_intersections(intersections, &app.cs.good_to_bad_red);
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Emissions".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(Line("Rough estimates of CO2 from all vehicles since midnight").secondary())
+                .wrap_to_pct(ctx, 15)
+                .draw(ctx),
+            {
+                let mut txt = Text::new();
+                summary(&mut txt, &per_road, &per_intersection);
+                txt.draw(ctx)
+            },
+            if app.has_prebaked().is_some() {
+                Checkbox::switch(ctx, "Compare before edits", None, false)
+            } else {
+                Widget::nothing()
+            },
+            ColorLegend::gradient(ctx, &app.cs.good_to_bad_red, vec!["lowest", "highest"]),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Emissions {
+            time: app.primary.sim.time(),
+            compare: false,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+
+    fn compare_emissions(ctx: &mut EventCtx, app: &App) -> Emissions {
+        let now = app.primary.sim.time();
+        let (after_road, after_intersection) = app.primary.sim.get_analytics().emissions_until(now);
+        let (before_road, before_intersection) = app.prebaked().emissions_until(now);
+
+        let mut colorer = ColorNetwork::new(app);
+        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+            .range(0.0, 2.0)
+            .ignore(0.7, 1.3);
+        for (r, ratio) in relative_co2(&before_road, &after_road) {
+            if let Some(c) = scale.eval(ratio) {
+                colorer.add_r(r, c);
+            }
+        }
+        for (i, ratio) in relative_co2(&before_intersection, &after_intersection) {
+            if let Some(c) = scale.eval(ratio) {
+                colorer.add_i(i, c);
+            }
+        }
+        let (unzoomed, zoomed) = colorer.build(ctx);
+
+        let mut txt = Text::from(Line("Before edits"));
+        summary(&mut txt, &before_road, &before_intersection);
+        txt.add(Line("After edits"));
+        summary(&mut txt, &after_road, &after_intersection);
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Relative Emissions".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            txt.draw(ctx),
+            Checkbox::switch(ctx, "Compare before edits", None, true),
+            scale.make_legend(ctx, vec!["less CO2", "same", "more"]),
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        Emissions {
+            time: now,
+            compare: true,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+}
+
+// After CO2 divided by before, for everything with traffic in either run. Anything that only has
+// traffic after the edits gets the top of the 0-2 scale.
+fn relative_co2<K: Copy + Ord>(
+    before: &BTreeMap<K, sim::Emissions>,
+    after: &BTreeMap<K, sim::Emissions>,
+) -> Vec<(K, f64)> {
+    let keys: BTreeSet<K> = before.keys().chain(after.keys()).cloned().collect();
+    let mut results = Vec::new();
+    for k in keys {
+        let b = before.get(&k).map(|e| e.co2_grams).unwrap_or(0.0);
+        let a = after.get(&k).map(|e| e.co2_grams).unwrap_or(0.0);
+        if b > 0.0 {
+            results.push((k, a / b));
+        } else if a > 0.0 {
+            results.push((k, 2.0));
+        }
+    }
+    results
+}
+
+fn summary(
+    txt: &mut Text,
+    per_road: &BTreeMap<RoadID, sim::Emissions>,
+    per_intersection: &BTreeMap<IntersectionID, sim::Emissions>,
+) {
+    let mut total = sim::Emissions::zero();
+    for e in per_road.values().chain(per_intersection.values()) {
+        total += *e;
+    }
+    txt.add(Line(format!("{:.1} kg CO2", total.co2_grams / 1000.0)));
+    txt.add(Line(format!("{:.1} liters of fuel", total.fuel_liters)));
+    txt.add(Line(format!("{:.1} g NOx", total.nox_grams)));
+    txt.add(Line(format!("{:.1} kWh if electric", total.electric_kwh)));
+}
@@ -1,7 +1,9 @@
 mod elevation;
+mod emissions;
 pub mod map;
 mod pandemic;
 mod parking;
+mod pedestrian;
 mod population;
 pub mod traffic;
 pub mod transit;
@@ -105,6 +107,7 @@ impl PickLayer {
                     btn("delay", Key::D),
                     btn("throughput", Key::T),
                     btn("traffic jams", Key::J),
+                    btn("emissions", Key::O),
                 ]),
                 Widget::col(vec![
                     "Map".draw_text(ctx),
@@ -114,6 +117,7 @@ impl PickLayer {
                     btn("transit network", Key::U),
                     btn("population map", Key::X),
                     btn("no sidewalks", Key::S),
+                    btn("pedestrian crowding", Key::W),
                 ]),
             ])
             .evenly_spaced(),
@@ -162,6 +166,9 @@ impl State for PickLayer {
                 "delay" => {
                     app.layer = Some(Box::new(traffic::Delay::new(ctx, app, false)));
                 }
+                "emissions" => {
+                    app.layer = Some(Box::new(emissions::Emissions::new(ctx, app, false)));
+                }
                 "elevation" => {
                     app.layer = Some(Box::new(elevation::Elevation::new(ctx, app)));
                 }
@@ -189,6 +196,13 @@ impl State for PickLayer {
                         ctx, app, true, true, true, false,
                     )));
                 }
+                "pedestrian crowding" => {
+                    app.layer = Some(Box::new(pedestrian::LevelOfService::new(
+                        ctx,
+                        app,
+                        app.primary.sim.time().get_hours(),
+                    )));
+                }
                 "population map" => {
                     app.layer = Some(Box::new(population::PopulationMap::new(
                         ctx,
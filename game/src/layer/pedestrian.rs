@@ -0,0 +1,132 @@
+use crate::app::App;
+use crate::common::ColorDiscrete;
+use crate::layer::{Layer, LayerOutcome};
+use ezgui::{
+    hotkey, Btn, Color, Composite, Drawable, EventCtx, GfxCtx, HorizontalAlignment, Key, Line,
+    Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::Time;
+
+// Pedestrian level of service from the Highway Capacity Manual, based on how many square meters
+// each person has on the sidewalk at the most crowded moment of the hour.
+pub struct LevelOfService {
+    time: Time,
+    hour: usize,
+    unzoomed: Drawable,
+    zoomed: Drawable,
+    composite: Composite,
+}
+
+impl Layer for LevelOfService {
+    fn name(&self) -> Option<&'static str> {
+        Some("pedestrian crowding")
+    }
+    fn event(
+        &mut self,
+        ctx: &mut EventCtx,
+        app: &mut App,
+        minimap: &Composite,
+    ) -> Option<LayerOutcome> {
+        if app.primary.sim.time() != self.time {
+            *self = LevelOfService::new(ctx, app, self.hour);
+        }
+
+        self.composite.align_above(ctx, minimap);
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Some(LayerOutcome::Close);
+                }
+                _ => unreachable!(),
+            },
+            Outcome::Changed => {
+                *self = LevelOfService::new(ctx, app, self.composite.spinner("hour") as usize);
+                self.composite.align_above(ctx, minimap);
+            }
+            _ => {}
+        }
+        None
+    }
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        self.composite.draw(g);
+        if g.canvas.cam_zoom < app.opts.min_zoom_for_detail {
+            g.redraw(&self.unzoomed);
+        } else {
+            g.redraw(&self.zoomed);
+        }
+    }
+    fn draw_minimap(&self, g: &mut GfxCtx) {
+        g.redraw(&self.unzoomed);
+    }
+}
+
+impl LevelOfService {
+    pub fn new(ctx: &mut EventCtx, app: &App, hour: usize) -> LevelOfService {
+        let mut colorer = ColorDiscrete::new(
+            app,
+            vec![
+                ("A (free flow)", Color::hex("#1A9850")),
+                ("B", Color::hex("#91CF60")),
+                ("C", Color::hex("#D9EF8B")),
+                ("D", Color::hex("#FEE08B")),
+                ("E", Color::hex("#FC8D59")),
+                ("F (jammed)", Color::hex("#D73027")),
+            ],
+        );
+        for (l, space) in app
+            .primary
+            .sim
+            .get_analytics()
+            .sidewalk_space_per_ped(hour, &app.primary.map)
+        {
+            colorer.add_l(
+                l,
+                if space > 5.6 {
+                    "A (free flow)"
+                } else if space > 3.7 {
+                    "B"
+                } else if space > 2.2 {
+                    "C"
+                } else if space > 1.4 {
+                    "D"
+                } else if space > 0.75 {
+                    "E"
+                } else {
+                    "F (jammed)"
+                },
+            );
+        }
+        let (unzoomed, zoomed, legend) = colorer.build(ctx);
+
+        let composite = Composite::new(Widget::col(vec![
+            Widget::row(vec![
+                Widget::draw_svg(ctx, "system/assets/tools/layers.svg"),
+                "Pedestrian level of service".draw_text(ctx),
+                Btn::plaintext("X")
+                    .build(ctx, "close", hotkey(Key::Escape))
+                    .align_right(),
+            ]),
+            Text::from(
+                Line("Based on the most crowded moment on each sidewalk during the hour")
+                    .secondary(),
+            )
+            .wrap_to_pct(ctx, 15)
+            .draw(ctx),
+            Widget::row(vec![
+                "Hour:".draw_text(ctx),
+                Spinner::new(ctx, (0, 23), hour as isize).named("hour"),
+            ]),
+            legend,
+        ]))
+        .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+        .build(ctx);
+
+        LevelOfService {
+            time: app.primary.sim.time(),
+            hour,
+            unzoomed,
+            zoomed,
+            composite,
+        }
+    }
+}
@@ -465,6 +465,7 @@ pub struct UnzoomedAgents {
     pub parking_color: Color,
     pub bike_color: Color,
     pub bus_color: Color,
+    pub ride_hail_color: Color,
     pub ped_color: Color,
 }
 
@@ -480,13 +481,14 @@ impl UnzoomedAgents {
             parking_color: cs.parking_trip.alpha(0.8),
             bike_color: cs.unzoomed_bike.alpha(0.8),
             bus_color: cs.unzoomed_bus.alpha(0.8),
+            ride_hail_color: cs.unzoomed_ride_hail.alpha(0.8),
             ped_color: cs.unzoomed_pedestrian.alpha(0.8),
         }
     }
 
     fn color(&self, agent: &UnzoomedAgent) -> Option<Color> {
         match agent.vehicle_type {
-            Some(VehicleType::Car) => {
+            Some(VehicleType::Car) | Some(VehicleType::Van) | Some(VehicleType::Truck) => {
                 if self.cars {
                     if agent.parking {
                         Some(self.parking_color)
@@ -497,6 +499,14 @@ impl UnzoomedAgents {
                     None
                 }
             }
+            // Toggled along with cars
+            Some(VehicleType::RideHail) => {
+                if self.cars {
+                    Some(self.ride_hail_color)
+                } else {
+                    None
+                }
+            }
             Some(VehicleType::Bike) => {
                 if self.bikes {
                     Some(self.bike_color)
@@ -47,13 +47,13 @@ struct CompositeState<'a> {
 }
 
 // Group many buildings into a single block
-struct Block {
+pub(super) struct Block {
     id: BlockID,
     // A block is either some buildings or a single border. Might be worth expressing that more
     // clearly.
-    bldgs: HashSet<BuildingID>,
+    pub(super) bldgs: HashSet<BuildingID>,
     borders: HashSet<IntersectionID>,
-    shape: Polygon,
+    pub(super) shape: Polygon,
 }
 
 #[derive(PartialEq)]
@@ -66,7 +66,7 @@ struct Filter {
     modes: BTreeSet<TripMode>,
 }
 
-type BlockID = usize;
+pub(super) type BlockID = usize;
 
 impl CommuterPatterns {
     pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
@@ -459,7 +459,7 @@ impl State for CommuterPatterns {
 // region bounded by 4 roads. But there are plenty of places with stranger shapes, or buildings
 // near the border of the map. The fallback is currently to just group those buildings that share
 // the same sidewalk.
-fn group_bldgs(
+pub(super) fn group_bldgs(
     app: &App,
 ) -> (
     HashMap<BuildingID, BlockID>,
@@ -0,0 +1,520 @@
+use crate::app::{App, ShowEverything};
+use crate::common::{CommonState, DivergingScale};
+use crate::game::{msg, DrawBaselayer, State, Transition};
+use crate::render::DrawOptions;
+use crate::sandbox::dashboards::commuter::group_bldgs;
+use crate::sandbox::dashboards::DashTab;
+use abstutil::prettyprint_usize;
+use ezgui::{
+    hotkey, Btn, Choice, Color, Composite, Drawable, EventCtx, GeomBatch, GfxCtx,
+    HorizontalAlignment, Key, Line, Outcome, Text, TextExt, VerticalAlignment, Widget,
+};
+use geom::{Duration, LonLat, Polygon};
+use map_model::{BuildingID, Map};
+use sim::{AgeBand, DontDrawAgents, IncomeBand, Mobility, TripPurpose};
+use std::collections::{BTreeMap, HashMap};
+
+// Who benefits and who loses from the current edits? Splits the change in trip times by where
+// people live, who they are, and why they're traveling.
+pub struct EquityDashboard {
+    composite: Composite,
+    group_by: GroupBy,
+    neighborhoods: Neighborhoods,
+    groups: Vec<Group>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum GroupBy {
+    Neighborhood,
+    Age,
+    Income,
+    CarOwnership,
+    Mobility,
+    Purpose,
+}
+
+// Finished trips, before and after the edits
+struct Group {
+    name: String,
+    // Only for neighborhoods
+    shape: Option<Polygon>,
+    trips: usize,
+    faster: usize,
+    slower: usize,
+    before: Duration,
+    after: Duration,
+}
+
+impl Group {
+    fn new(name: String, shape: Option<Polygon>) -> Group {
+        Group {
+            name,
+            shape,
+            trips: 0,
+            faster: 0,
+            slower: 0,
+            before: Duration::ZERO,
+            after: Duration::ZERO,
+        }
+    }
+
+    fn avg_change(&self) -> Duration {
+        if self.trips == 0 {
+            Duration::ZERO
+        } else {
+            (self.after - self.before) / (self.trips as f64)
+        }
+    }
+
+    // Relative to the time before, so 1.0 means no change
+    fn ratio(&self) -> f64 {
+        if self.before == Duration::ZERO {
+            1.0
+        } else {
+            self.after / self.before
+        }
+    }
+}
+
+impl EquityDashboard {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let neighborhoods =
+            ctx.loading_screen("find neighborhoods", |_, _| Neighborhoods::new(app));
+        let group_by = GroupBy::Neighborhood;
+        let groups = group_trips(app, group_by, &neighborhoods);
+        Box::new(EquityDashboard {
+            composite: make_composite(ctx, app, group_by, &groups, &neighborhoods),
+            group_by,
+            neighborhoods,
+            groups,
+        })
+    }
+
+    fn export(&self, app: &App) -> Result<String, std::io::Error> {
+        let path = format!(
+            "equity_{}_{}_by_{:?}.csv",
+            app.primary.map.get_name(),
+            app.primary.map.get_edits().edits_name,
+            self.group_by
+        )
+        .to_lowercase()
+        .replace(" ", "_");
+        let mut out = vec!["group,trips,faster,slower,before_seconds,after_seconds".to_string()];
+        for g in &self.groups {
+            out.push(format!(
+                "\"{}\",{},{},{},{},{}",
+                g.name.replace("\"", "\"\""),
+                g.trips,
+                g.faster,
+                g.slower,
+                g.before.inner_seconds(),
+                g.after.inner_seconds()
+            ));
+        }
+        std::fs::write(&path, out.join("\n") + "\n")?;
+        Ok(path)
+    }
+}
+
+impl State for EquityDashboard {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "export to CSV" => {
+                    return Transition::Push(match self.export(app) {
+                        Ok(path) => msg("Export", vec![format!("Saved to {}", path)]),
+                        Err(err) => msg("Export failed", vec![err.to_string()]),
+                    });
+                }
+                "show on map" => {
+                    return Transition::Push(Choropleth::new(ctx, app, &self.groups));
+                }
+                _ => {
+                    return DashTab::Equity.transition(ctx, app, &x);
+                }
+            },
+            Outcome::Changed => {
+                self.group_by = self.composite.dropdown_value("group by");
+                self.groups = group_trips(app, self.group_by, &self.neighborhoods);
+                let mut new =
+                    make_composite(ctx, app, self.group_by, &self.groups, &self.neighborhoods);
+                new.restore(ctx, &self.composite);
+                self.composite = new;
+            }
+            _ => {}
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
+
+fn make_composite(
+    ctx: &mut EventCtx,
+    app: &App,
+    group_by: GroupBy,
+    groups: &Vec<Group>,
+    neighborhoods: &Neighborhoods,
+) -> Composite {
+    let mut controls = vec![
+        "Group by".draw_text(ctx),
+        Widget::dropdown(
+            ctx,
+            "group by",
+            group_by,
+            vec![
+                Choice::new("home neighborhood", GroupBy::Neighborhood),
+                Choice::new("age", GroupBy::Age),
+                Choice::new("household income", GroupBy::Income),
+                Choice::new("car ownership", GroupBy::CarOwnership),
+                Choice::new("mobility", GroupBy::Mobility),
+                Choice::new("trip purpose", GroupBy::Purpose),
+            ],
+        ),
+        Btn::text_fg("export to CSV").build_def(ctx, None),
+    ];
+    if group_by == GroupBy::Neighborhood {
+        controls.push(Btn::text_fg("show on map").build_def(ctx, None));
+    }
+
+    let mut col = vec![
+        DashTab::Equity.picker(ctx, app),
+        Widget::row(controls).centered_horiz(),
+    ];
+    if group_by == GroupBy::Neighborhood && !neighborhoods.from_file {
+        col.push(
+            Line(format!(
+                "No neighborhood boundaries found at {}, so grouping buildings into blocks",
+                neighborhoods_path(&app.primary.map)
+            ))
+            .secondary()
+            .draw(ctx)
+            .centered_horiz(),
+        );
+    }
+    col.push(make_table(ctx, groups));
+
+    Composite::new(Widget::col(col))
+        .exact_size_percent(90, 90)
+        .build(ctx)
+}
+
+fn make_table(ctx: &mut EventCtx, groups: &Vec<Group>) -> Widget {
+    if groups.is_empty() {
+        return "No trips have finished yet".draw_text(ctx).centered_horiz();
+    }
+
+    let headers = vec![
+        "Group",
+        "Trips",
+        "Faster",
+        "Slower",
+        "Total change",
+        "Average change",
+    ];
+    let mut columns: Vec<Text> = headers
+        .into_iter()
+        .map(|h| Text::from(Line(h).small_heading()))
+        .collect();
+    for g in groups {
+        let change = g.after - g.before;
+        let color = if change < Duration::ZERO {
+            Color::GREEN
+        } else if change > Duration::ZERO {
+            Color::RED
+        } else {
+            Color::WHITE
+        };
+        columns[0].add(Line(&g.name));
+        columns[1].add(Line(prettyprint_usize(g.trips)));
+        columns[2].add(Line(prettyprint_usize(g.faster)));
+        columns[3].add(Line(prettyprint_usize(g.slower)));
+        columns[4].add(Line(change.to_string()).fg(color));
+        columns[5].add(Line(g.avg_change().to_string()).fg(color));
+    }
+
+    Widget::row(
+        columns
+            .into_iter()
+            .map(|txt| txt.draw(ctx).margin_right(30))
+            .collect(),
+    )
+    .centered_horiz()
+}
+
+fn group_trips(app: &App, group_by: GroupBy, neighborhoods: &Neighborhoods) -> Vec<Group> {
+    // Keyed by a sort order, so the groups show up in a sensible order
+    let mut groups: BTreeMap<usize, Group> = BTreeMap::new();
+    let unknown = std::usize::MAX;
+
+    for (id, before, after, _) in app
+        .primary
+        .sim
+        .get_analytics()
+        .both_finished_trips(app.primary.sim.time(), app.prebaked())
+    {
+        let attribs = &app
+            .primary
+            .sim
+            .get_person(app.primary.sim.trip_to_person(id))
+            .attributes;
+        let key = match group_by {
+            GroupBy::Neighborhood => attribs
+                .home
+                .and_then(|b| neighborhoods.bldg_to_neighborhood.get(&b).cloned()),
+            GroupBy::Age => attribs
+                .age
+                .and_then(|x| AgeBand::all().into_iter().position(|y| x == y)),
+            GroupBy::Income => attribs
+                .income
+                .and_then(|x| IncomeBand::all().into_iter().position(|y| x == y)),
+            GroupBy::CarOwnership => attribs.has_car.map(|x| if x { 0 } else { 1 }),
+            GroupBy::Mobility => attribs
+                .mobility
+                .and_then(|x| Mobility::all().into_iter().position(|y| x == y)),
+            GroupBy::Purpose => {
+                let purpose = app.primary.sim.trip_info(id).purpose;
+                TripPurpose::all().into_iter().position(|y| purpose == y)
+            }
+        }
+        .unwrap_or(unknown);
+
+        let group = groups.entry(key).or_insert_with(|| {
+            if key == unknown {
+                return Group::new("unknown".to_string(), None);
+            }
+            match group_by {
+                GroupBy::Neighborhood => {
+                    let (ref name, ref shape) = neighborhoods.shapes[key];
+                    Group::new(name.clone(), Some(shape.clone()))
+                }
+                GroupBy::Age => Group::new(AgeBand::all()[key].describe().to_string(), None),
+                GroupBy::Income => Group::new(IncomeBand::all()[key].describe().to_string(), None),
+                GroupBy::CarOwnership => Group::new(
+                    if key == 0 { "has a car" } else { "no car" }.to_string(),
+                    None,
+                ),
+                GroupBy::Mobility => Group::new(Mobility::all()[key].describe().to_string(), None),
+                GroupBy::Purpose => {
+                    Group::new(TripPurpose::all()[key].describe().to_string(), None)
+                }
+            }
+        });
+        group.trips += 1;
+        if after < before {
+            group.faster += 1;
+        } else if after > before {
+            group.slower += 1;
+        }
+        group.before += before;
+        group.after += after;
+    }
+
+    groups.into_iter().map(|(_, g)| g).collect()
+}
+
+// Where people live. Cities can provide official neighborhood boundaries; otherwise buildings are
+// grouped into blocks.
+struct Neighborhoods {
+    shapes: Vec<(String, Polygon)>,
+    bldg_to_neighborhood: HashMap<BuildingID, usize>,
+    from_file: bool,
+}
+
+impl Neighborhoods {
+    fn new(app: &App) -> Neighborhoods {
+        let map = &app.primary.map;
+        if let Some(shapes) = read_neighborhoods(&neighborhoods_path(map), map) {
+            let mut bldg_to_neighborhood = HashMap::new();
+            for b in map.all_buildings() {
+                if let Some(idx) = shapes
+                    .iter()
+                    .position(|(_, poly)| poly.contains_pt(b.label_center))
+                {
+                    bldg_to_neighborhood.insert(b.id, idx);
+                }
+            }
+            return Neighborhoods {
+                shapes,
+                bldg_to_neighborhood,
+                from_file: true,
+            };
+        }
+
+        let (bldg_to_block, _, blocks) = group_bldgs(app);
+        Neighborhoods {
+            // Borders are also blocks, but nobody lives there
+            shapes: blocks
+                .into_iter()
+                .enumerate()
+                .map(|(idx, b)| (format!("block #{}", idx + 1), b.shape))
+                .collect(),
+            bldg_to_neighborhood: bldg_to_block,
+            from_file: false,
+        }
+    }
+}
+
+fn neighborhoods_path(map: &Map) -> String {
+    abstutil::path(format!("system/neighborhoods/{}.geojson", map.get_name()))
+}
+
+// A GeoJSON FeatureCollection of Polygons or MultiPolygons in WGS84, each with a "name" property.
+// None if the file doesn't exist.
+fn read_neighborhoods(path: &str, map: &Map) -> Option<Vec<(String, Polygon)>> {
+    let raw = String::from_utf8(abstutil::slurp_file(path).ok()?).unwrap();
+    let collection = match raw.parse::<geojson::GeoJson>().unwrap() {
+        geojson::GeoJson::FeatureCollection(collection) => collection,
+        _ => panic!("{} isn't a FeatureCollection", path),
+    };
+
+    let gps_bounds = map.get_gps_bounds();
+    let mut results = Vec::new();
+    for (idx, feature) in collection.features.into_iter().enumerate() {
+        let name = match feature
+            .properties
+            .as_ref()
+            .and_then(|props| props.get("name"))
+        {
+            Some(geojson::JsonValue::String(name)) => name.clone(),
+            _ => format!("neighborhood #{}", idx + 1),
+        };
+        let list = match feature.geometry.map(|g| g.value) {
+            Some(geojson::Value::Polygon(rings)) => vec![rings],
+            Some(geojson::Value::MultiPolygon(list)) => list,
+            _ => panic!("{} in {} isn't a polygon", name, path),
+        };
+        let polygons: Vec<Polygon> = list
+            .into_iter()
+            .map(|rings| {
+                let map_space: Vec<Vec<Vec<f64>>> = rings
+                    .into_iter()
+                    .map(|ring| {
+                        let pts: Vec<LonLat> =
+                            ring.iter().map(|pt| LonLat::new(pt[0], pt[1])).collect();
+                        gps_bounds
+                            .convert(&pts)
+                            .into_iter()
+                            .map(|pt| vec![pt.x(), pt.y()])
+                            .collect()
+                    })
+                    .collect();
+                Polygon::from_geojson(&map_space)
+            })
+            .collect();
+        if !polygons.is_empty() {
+            results.push((name, Polygon::union_all(polygons)));
+        }
+    }
+    Some(results)
+}
+
+// Color each neighborhood by how much trip times changed for people living there.
+struct Choropleth {
+    composite: Composite,
+    draw: Drawable,
+    groups: Vec<(String, Polygon, Text)>,
+    hovering: Option<usize>,
+}
+
+impl Choropleth {
+    fn new(ctx: &mut EventCtx, app: &App, groups: &Vec<Group>) -> Box<dyn State> {
+        let scale = DivergingScale::new(Color::hex("#5D9630"), Color::WHITE, Color::hex("#A32015"))
+            .range(0.5, 1.5);
+
+        let mut batch = GeomBatch::new();
+        let mut hoverable = Vec::new();
+        for g in groups {
+            if let Some(ref shape) = g.shape {
+                if let Some(color) = scale.eval(g.ratio()) {
+                    batch.push(color.alpha(0.8), shape.clone());
+                }
+                hoverable.push((
+                    g.name.clone(),
+                    shape.clone(),
+                    Text::from_multiline(vec![
+                        Line(&g.name),
+                        Line(format!(
+                            "{} trips by people living here",
+                            prettyprint_usize(g.trips)
+                        )),
+                        Line(format!("{} faster, {} slower", g.faster, g.slower)),
+                        Line(format!("Average change: {}", g.avg_change())),
+                    ]),
+                ));
+            }
+        }
+
+        Box::new(Choropleth {
+            composite: Composite::new(Widget::col(vec![
+                Widget::row(vec![
+                    Line("Trip time changes by home").small_heading().draw(ctx),
+                    Btn::plaintext("X")
+                        .build(ctx, "close", hotkey(Key::Escape))
+                        .align_right(),
+                ]),
+                scale.make_legend(ctx, vec!["faster", "same", "slower"]),
+            ]))
+            .aligned(HorizontalAlignment::Right, VerticalAlignment::Center)
+            .build(ctx),
+            draw: ctx.upload(batch),
+            groups: hoverable,
+            hovering: None,
+        })
+    }
+}
+
+impl State for Choropleth {
+    fn event(&mut self, ctx: &mut EventCtx, _: &mut App) -> Transition {
+        ctx.canvas_movement();
+        if ctx.redo_mouseover() {
+            self.hovering = ctx.canvas.get_cursor_in_map_space().and_then(|pt| {
+                self.groups
+                    .iter()
+                    .position(|(_, poly, _)| poly.contains_pt(pt))
+            });
+        }
+
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "close" => {
+                    return Transition::Pop;
+                }
+                _ => unreachable!(),
+            },
+            _ => {}
+        }
+        Transition::Keep
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        app.draw(
+            g,
+            DrawOptions::new(),
+            &DontDrawAgents {},
+            &ShowEverything::new(),
+        );
+        g.redraw(&self.draw);
+        self.composite.draw(g);
+
+        if let Some(idx) = self.hovering {
+            let (_, ref poly, ref txt) = self.groups[idx];
+            if let Ok(outline) = poly.to_outline(geom::Distance::meters(5.0)) {
+                g.draw_polygon(Color::BLACK, outline);
+            }
+            g.draw_mouse_tooltip(txt.clone());
+        }
+        CommonState::draw_osd(g, app);
+    }
+}
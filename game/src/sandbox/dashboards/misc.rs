@@ -8,7 +8,9 @@ use ezgui::{
     Autocomplete, Btn, Composite, EventCtx, GfxCtx, Line, LinePlot, Outcome, PlotOptions, Series,
     TextExt, Widget,
 };
-use map_model::BusRouteID;
+use geom::{Distance, Duration, Time};
+use map_model::{BikeShareDockID, BusRouteID};
+use std::collections::BTreeMap;
 
 pub struct ActiveTraffic {
     composite: Composite,
@@ -196,3 +198,174 @@ impl State for TransitRoutes {
         self.composite.draw(g);
     }
 }
+
+pub struct RideHailing {
+    composite: Composite,
+}
+
+impl RideHailing {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let (fleet, unassigned) = app.primary.sim.ride_hail_fleet();
+        let rides = &app.primary.sim.get_analytics().ride_hail_rides;
+
+        let mut col = vec![
+            DashTab::RideHailing.picker(ctx, app),
+            Line("Ride-hailing").small_heading().draw(ctx),
+            format!("{} vehicles in the fleet", prettyprint_usize(fleet)).draw_text(ctx),
+            format!(
+                "{} people currently waiting for a vehicle to be dispatched",
+                prettyprint_usize(unassigned)
+            )
+            .draw_text(ctx),
+            format!("{} rides completed", prettyprint_usize(rides.len())).draw_text(ctx),
+        ];
+        if !rides.is_empty() {
+            let mut total_wait = Duration::ZERO;
+            let mut max_wait = Duration::ZERO;
+            let mut total_deadhead = Distance::ZERO;
+            let mut total_busy = Duration::ZERO;
+            for (_, _, wait, deadhead, busy) in rides {
+                total_wait += *wait;
+                max_wait = max_wait.max(*wait);
+                total_deadhead += *deadhead;
+                total_busy += *busy;
+            }
+            col.push(
+                format!(
+                    "Passengers waited {} on average, {} at most",
+                    total_wait / (rides.len() as f64),
+                    max_wait
+                )
+                .draw_text(ctx),
+            );
+            col.push(
+                format!(
+                    "Vehicles drove {} empty to reach passengers ({} per ride)",
+                    total_deadhead,
+                    total_deadhead / (rides.len() as f64)
+                )
+                .draw_text(ctx),
+            );
+            // Vehicles exist for the whole day, so compare against all the time they could've
+            // been working so far.
+            let elapsed = app.primary.sim.time() - Time::START_OF_DAY;
+            if fleet > 0 && elapsed > Duration::ZERO {
+                col.push(
+                    format!(
+                        "Fleet utilization: {:.1}%",
+                        100.0 * (total_busy / (elapsed * (fleet as f64)))
+                    )
+                    .draw_text(ctx),
+                );
+            }
+        }
+
+        Box::new(RideHailing {
+            composite: Composite::new(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State for RideHailing {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => DashTab::RideHailing.transition(ctx, app, &x),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
+
+pub struct BikeShare {
+    composite: Composite,
+}
+
+impl BikeShare {
+    pub fn new(ctx: &mut EventCtx, app: &App) -> Box<dyn State> {
+        let analytics = app.primary.sim.get_analytics();
+        let mut unavailable = Counter::new();
+        for (_, dock) in &analytics.bike_share_unavailable {
+            unavailable.inc(*dock);
+        }
+        let needs: BTreeMap<BikeShareDockID, isize> = app
+            .primary
+            .sim
+            .bike_share_rebalancing_needs()
+            .into_iter()
+            .collect();
+
+        let mut col = vec![
+            DashTab::BikeShare.picker(ctx, app),
+            Line("Bike-share docks").small_heading().draw(ctx),
+            format!(
+                "{} people found a dock empty and walked instead",
+                prettyprint_usize(analytics.bike_share_unavailable.len())
+            )
+            .draw_text(ctx),
+        ];
+        if app.primary.map.all_bike_share_docks().is_empty() {
+            col.push("This map doesn't have any bike-share docks".draw_text(ctx));
+        }
+        for dock in app.primary.map.all_bike_share_docks() {
+            let (bikes, capacity) = app.primary.sim.bike_share_dock_status(dock.id);
+            let changes = analytics
+                .bike_share_dock_changes
+                .get(&dock.id)
+                .cloned()
+                .unwrap_or_else(Vec::new);
+            let mut txt = format!(
+                "{}: {} / {} bikes. Ran out {} times, filled up {} times, turned away {} people.",
+                dock.name,
+                bikes,
+                capacity,
+                changes.iter().filter(|(_, empty)| *empty).count(),
+                changes.iter().filter(|(_, empty)| !*empty).count(),
+                unavailable.get(dock.id)
+            );
+            match needs.get(&dock.id) {
+                Some(n) if *n > 0 => {
+                    txt.push_str(&format!(" Needs {} more bikes.", n));
+                }
+                Some(n) if *n < 0 => {
+                    txt.push_str(&format!(" Needs {} bikes hauled away.", -n));
+                }
+                _ => {}
+            }
+            col.push(txt.draw_text(ctx));
+        }
+
+        Box::new(BikeShare {
+            composite: Composite::new(Widget::col(col))
+                .exact_size_percent(90, 90)
+                .build(ctx),
+        })
+    }
+}
+
+impl State for BikeShare {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => DashTab::BikeShare.transition(ctx, app, &x),
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw_baselayer(&self) -> DrawBaselayer {
+        DrawBaselayer::Custom
+    }
+
+    fn draw(&self, g: &mut GfxCtx, app: &App) {
+        g.clear(app.cs.grass);
+        self.composite.draw(g);
+    }
+}
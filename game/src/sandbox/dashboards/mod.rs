@@ -1,4 +1,5 @@
 mod commuter;
+mod equity;
 mod misc;
 mod parking_overhead;
 mod summaries;
@@ -14,9 +15,12 @@ pub use trip_table::TripTable;
 pub enum DashTab {
     TripTable,
     TripSummaries,
+    Equity,
     ParkingOverhead,
     ActiveTraffic,
     TransitRoutes,
+    RideHailing,
+    BikeShare,
     CommuterPatterns,
 }
 
@@ -26,12 +30,17 @@ impl DashTab {
         for (name, tab) in vec![
             ("trip table", DashTab::TripTable),
             ("trip summaries", DashTab::TripSummaries),
+            ("equity", DashTab::Equity),
             ("parking overhead", DashTab::ParkingOverhead),
             ("active traffic", DashTab::ActiveTraffic),
             ("transit routes", DashTab::TransitRoutes),
+            ("ride-hailing", DashTab::RideHailing),
+            ("bike-share", DashTab::BikeShare),
             ("commuter patterns", DashTab::CommuterPatterns),
         ] {
-            if tab == DashTab::TripSummaries && app.has_prebaked().is_none() {
+            if (tab == DashTab::TripSummaries || tab == DashTab::Equity)
+                && app.has_prebaked().is_none()
+            {
                 continue;
             }
             if self == tab {
@@ -59,11 +68,14 @@ impl DashTab {
                 app,
                 summaries::Filter::new(),
             )),
+            "equity" => Transition::Replace(equity::EquityDashboard::new(ctx, app)),
             "parking overhead" => {
                 Transition::Replace(parking_overhead::ParkingOverhead::new(ctx, app))
             }
             "active traffic" => Transition::Replace(misc::ActiveTraffic::new(ctx, app)),
             "transit routes" => Transition::Replace(misc::TransitRoutes::new(ctx, app)),
+            "ride-hailing" => Transition::Replace(misc::RideHailing::new(ctx, app)),
+            "bike-share" => Transition::Replace(misc::BikeShare::new(ctx, app)),
             "commuter patterns" => Transition::Replace(commuter::CommuterPatterns::new(ctx, app)),
             _ => unreachable!(),
         }
@@ -89,7 +89,7 @@ fn summary(ctx: &mut EventCtx, app: &App, filter: &Filter) -> Widget {
     let mut num_slower = 0;
     let mut sum_faster = Duration::ZERO;
     let mut sum_slower = Duration::ZERO;
-    for (b, a, mode) in app
+    for (_, b, a, mode) in app
         .primary
         .sim
         .get_analytics()
@@ -325,7 +325,7 @@ impl Filter {
 
     fn get_trips(&self, app: &App) -> Vec<(Duration, Duration)> {
         let mut points = Vec::new();
-        for (b, a, mode) in app
+        for (_, b, a, mode) in app
             .primary
             .sim
             .get_analytics()
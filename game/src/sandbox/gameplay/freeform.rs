@@ -18,8 +18,8 @@ use map_model::{
 use rand::seq::SliceRandom;
 use rand::Rng;
 use sim::{
-    DontDrawAgents, DrivingGoal, IndividTrip, PersonID, PersonSpec, Scenario, SidewalkSpot,
-    SpawnTrip, TripEndpoint, TripMode, TripSpec,
+    DontDrawAgents, DrivingGoal, IndividTrip, PersonAttributes, PersonID, PersonSpec, Scenario,
+    SidewalkSpot, SpawnTrip, TripEndpoint, TripMode, TripPurpose, TripSpec,
 };
 
 // TODO Maybe remember what things were spawned, offer to replay this later
@@ -260,7 +260,12 @@ impl State for AgentSpawner {
                             scenario.people.push(PersonSpec {
                                 id: PersonID(app.primary.sim.get_all_people().len() + i),
                                 orig_id: None,
-                                trips: vec![IndividTrip::new(app.primary.sim.time(), trip)],
+                                trips: vec![IndividTrip::new(
+                                    app.primary.sim.time(),
+                                    TripPurpose::Unknown,
+                                    trip,
+                                )],
+                                attributes: PersonAttributes::default(),
                             });
                         }
                     }
@@ -443,7 +448,7 @@ fn path_request(
         start: pos(from, mode, true, map)?,
         end: pos(to, mode, false, map)?,
         constraints: match mode {
-            TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
+            TripMode::Walk | TripMode::Transit | TripMode::RideHail => PathConstraints::Pedestrian,
             TripMode::Drive => PathConstraints::Car,
             TripMode::Bike => PathConstraints::Bike,
         },
@@ -453,7 +458,9 @@ fn path_request(
 fn pos(endpt: TripEndpoint, mode: TripMode, from: bool, map: &Map) -> Option<Position> {
     match endpt {
         TripEndpoint::Bldg(b) => match mode {
-            TripMode::Walk | TripMode::Transit => Some(map.get_b(b).sidewalk_pos),
+            TripMode::Walk | TripMode::Transit | TripMode::RideHail => {
+                Some(map.get_b(b).sidewalk_pos)
+            }
             TripMode::Bike => Some(DrivingGoal::ParkNear(b).goal_pos(PathConstraints::Bike, map)?),
             TripMode::Drive => Some(
                 DrivingGoal::ParkNear(b)
@@ -462,7 +469,7 @@ fn pos(endpt: TripEndpoint, mode: TripMode, from: bool, map: &Map) -> Option<Pos
             ),
         },
         TripEndpoint::Border(i, _) => match mode {
-            TripMode::Walk | TripMode::Transit => if from {
+            TripMode::Walk | TripMode::Transit | TripMode::RideHail => if from {
                 SidewalkSpot::start_at_border(i, None, map)
             } else {
                 SidewalkSpot::end_at_border(i, None, map)
@@ -538,6 +545,7 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
                         origin: None,
                     },
                     TripEndpoint::Border(lane.src_i, None),
+                    TripPurpose::Unknown,
                     false,
                     false,
                     map,
@@ -560,6 +568,7 @@ pub fn spawn_agents_around(i: IntersectionID, app: &mut App) {
                         ),
                     },
                     TripEndpoint::Border(lane.src_i, None),
+                    TripPurpose::Unknown,
                     false,
                     false,
                     map,
@@ -160,7 +160,9 @@ impl GameplayMode {
                 EditCmd::ChangeLaneType { .. }
                 | EditCmd::ReverseLane { .. }
                 | EditCmd::ChangeSpeedLimit { .. }
-                | EditCmd::ChangeAccessRestrictions { .. } => {
+                | EditCmd::ChangeAccessRestrictions { .. }
+                | EditCmd::ChangeParkingRules { .. }
+                | EditCmd::ChangeCrossing { .. } => {
                     if !self.can_edit_lanes() {
                         return false;
                     }
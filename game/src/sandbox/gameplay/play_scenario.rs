@@ -1,5 +1,5 @@
 use crate::app::App;
-use crate::common::CityPicker;
+use crate::common::{CityPicker, Lasso};
 use crate::edit::EditMode;
 use crate::game::{msg, State, Transition, WizardState};
 use crate::helpers::{checkbox_per_mode, nice_map_name};
@@ -10,9 +10,10 @@ use ezgui::{
     hotkey, lctrl, AreaSlider, Btn, Choice, Color, Composite, EventCtx, GeomBatch, GfxCtx,
     HorizontalAlignment, Key, Line, Outcome, Spinner, Text, TextExt, VerticalAlignment, Widget,
 };
-use geom::Polygon;
+use geom::{Duration, Polygon, Ring, Time};
+use map_model::BuildingID;
 use maplit::btreeset;
-use sim::{ScenarioModifier, TripMode};
+use sim::{DevelopmentSize, LandUse, NewDevelopment, ScenarioModifier, TripMode};
 use std::collections::BTreeSet;
 
 pub struct PlayScenario {
@@ -193,6 +194,11 @@ impl EditScenarioModifiers {
             Btn::text_bg2("New modification").build_def(ctx, None),
             Btn::text_bg2("Change trip mode").build_def(ctx, None),
         ]));
+        rows.push(Widget::row(vec![
+            Btn::text_bg2("Add a new development").build_def(ctx, None),
+            Btn::text_bg2("Redirect trips").build_def(ctx, None),
+            Btn::text_bg2("Only people passing through an area").build_def(ctx, None),
+        ]));
         rows.push(
             Widget::row(vec![
                 Btn::text_bg2("Apply").build_def(ctx, hotkey(Key::Enter)),
@@ -243,6 +249,30 @@ impl State for EditScenarioModifiers {
                         self.modifiers.clone(),
                     ));
                 }
+                "Add a new development" => {
+                    return Transition::Push(SelectArea::new(
+                        ctx,
+                        AreaModifier::NewDevelopment,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Redirect trips" => {
+                    return Transition::Push(SelectArea::new(
+                        ctx,
+                        AreaModifier::RedirectFrom,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
+                "Only people passing through an area" => {
+                    return Transition::Push(SelectArea::new(
+                        ctx,
+                        AreaModifier::OnlyPeopleCrossing,
+                        self.scenario_name.clone(),
+                        self.modifiers.clone(),
+                    ));
+                }
                 x => {
                     if let Some(x) = x.strip_prefix("delete modifier ") {
                         self.modifiers.remove(x.parse::<usize>().unwrap() - 1);
@@ -274,7 +304,14 @@ fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<
         let mut wizard = wiz.wrap(ctx);
         let new_mod = match wizard
             .choose_string("", || {
-                vec!["repeat days", "cancel all trips for some people"]
+                vec![
+                    "repeat days",
+                    "cancel all trips for some people",
+                    "scale the number of people",
+                    "spread out departures",
+                    "change the ride-hail fleet size",
+                    "use bike-share for some bike trips",
+                ]
             })?
             .as_str()
         {
@@ -284,6 +321,40 @@ fn new_modifier(scenario_name: String, modifiers: Vec<ScenarioModifier>) -> Box<
             x if x == "cancel all trips for some people" => ScenarioModifier::CancelPeople(
                 wizard.input_percent("What percent of people should cancel trips? (0 to 100)")?,
             ),
+            x if x == "scale the number of people" => {
+                ScenarioModifier::ScaleDemand(wizard.input_usize(
+                    "Scale the number of people to what percent? (100 is no change)",
+                )?)
+            }
+            x if x == "spread out departures" => {
+                let (start_hr, end_hr) = match wizard
+                    .choose_string("Spread out trips departing when?", || {
+                        vec!["morning peak (7-9am)", "evening peak (4-6pm)", "all day"]
+                    })?
+                    .as_str()
+                {
+                    "morning peak (7-9am)" => (7, 9),
+                    "evening peak (4-6pm)" => (16, 18),
+                    "all day" => (0, 24),
+                    _ => unreachable!(),
+                };
+                let minutes = wizard.input_usize("Shift departures by up to how many minutes?")?;
+                ScenarioModifier::SpreadDepartures {
+                    departure_filter: (
+                        Time::START_OF_DAY + Duration::hours(start_hr),
+                        Time::START_OF_DAY + Duration::hours(end_hr),
+                    ),
+                    max_shift: Duration::minutes(minutes),
+                }
+            }
+            x if x == "change the ride-hail fleet size" => {
+                ScenarioModifier::RideHailFleet(wizard.input_usize("How many ride-hail vehicles?")?)
+            }
+            x if x == "use bike-share for some bike trips" => {
+                ScenarioModifier::BikeShare(wizard.input_percent(
+                    "What percent of bike trips should use shared bikes? (0 to 100)",
+                )?)
+            }
             _ => unreachable!(),
         };
         let mut mods = modifiers.clone();
@@ -413,3 +484,204 @@ impl State for ChangeMode {
         self.composite.draw(g);
     }
 }
+
+#[derive(Clone, Copy, PartialEq)]
+enum AreaModifier {
+    NewDevelopment,
+    RedirectFrom,
+    RedirectTo,
+    OnlyPeopleCrossing,
+}
+
+// Draw an area on the map with the mouse, then finish describing the modifier.
+struct SelectArea {
+    composite: Composite,
+    lasso: Option<Lasso>,
+    kind: AreaModifier,
+    // When redirecting trips, the buildings chosen in the first step
+    redirect_from: BTreeSet<BuildingID>,
+    scenario_name: String,
+    modifiers: Vec<ScenarioModifier>,
+}
+
+impl SelectArea {
+    fn new(
+        ctx: &mut EventCtx,
+        kind: AreaModifier,
+        scenario_name: String,
+        modifiers: Vec<ScenarioModifier>,
+    ) -> Box<dyn State> {
+        SelectArea::with_redirect_from(ctx, kind, BTreeSet::new(), scenario_name, modifiers)
+    }
+
+    fn with_redirect_from(
+        ctx: &mut EventCtx,
+        kind: AreaModifier,
+        redirect_from: BTreeSet<BuildingID>,
+        scenario_name: String,
+        modifiers: Vec<ScenarioModifier>,
+    ) -> Box<dyn State> {
+        let instructions = match kind {
+            AreaModifier::NewDevelopment => "Draw around the buildings in the new development",
+            AreaModifier::RedirectFrom => {
+                "Draw around the destinations to redirect trips away from"
+            }
+            AreaModifier::RedirectTo => "Draw around the new destinations",
+            AreaModifier::OnlyPeopleCrossing => "Draw around the area people must pass through",
+        };
+        Box::new(SelectArea {
+            composite: Composite::new(Widget::col(vec![
+                Line("Select an area").small_heading().draw(ctx),
+                instructions.draw_text(ctx),
+                Btn::text_bg2("Cancel").build_def(ctx, hotkey(Key::Escape)),
+            ]))
+            .aligned(HorizontalAlignment::Center, VerticalAlignment::Top)
+            .build(ctx),
+            lasso: None,
+            kind,
+            redirect_from,
+            scenario_name,
+            modifiers,
+        })
+    }
+
+    fn finish(&self, ctx: &mut EventCtx, app: &App, ring: Ring) -> Transition {
+        let map = &app.primary.map;
+        if self.kind == AreaModifier::OnlyPeopleCrossing {
+            let modifier = ScenarioModifier::OnlyPeopleCrossing(
+                map.get_gps_bounds().convert_back(ring.points()),
+            );
+            if let Err(err) = modifier.validate(map) {
+                return Transition::Push(msg("Error", vec![err]));
+            }
+            let mut mods = self.modifiers.clone();
+            mods.push(modifier);
+            return Transition::PopThenReplace(EditScenarioModifiers::new(
+                ctx,
+                self.scenario_name.clone(),
+                mods,
+            ));
+        }
+
+        let polygon = ring.to_polygon();
+        let bldgs: BTreeSet<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| polygon.contains_pt(b.label_center))
+            .map(|b| b.id)
+            .collect();
+        if bldgs.is_empty() {
+            return Transition::Push(msg("Error", vec!["There aren't any buildings there"]));
+        }
+
+        let scenario_name = self.scenario_name.clone();
+        let modifiers = self.modifiers.clone();
+        match self.kind {
+            AreaModifier::NewDevelopment => {
+                Transition::Replace(WizardState::new(Box::new(move |wiz, ctx, _| {
+                    let mut wizard = wiz.wrap(ctx);
+                    let land_use = match wizard
+                        .choose_string("What kind of development?", || {
+                            vec!["housing", "offices", "retail"]
+                        })?
+                        .as_str()
+                    {
+                        "housing" => LandUse::Residential,
+                        "offices" => LandUse::Office,
+                        "retail" => LandUse::Retail,
+                        _ => unreachable!(),
+                    };
+                    let units = match land_use {
+                        LandUse::Residential => "dwelling units",
+                        LandUse::Office => "employees",
+                        LandUse::Retail => "shops",
+                    };
+                    let size = if wizard
+                        .choose_string("Describe the size by?", || vec![units, "floor area"])?
+                        == "floor area"
+                    {
+                        DevelopmentSize::FloorArea(
+                            wizard.input_usize("How many square meters of floor area?")?,
+                        )
+                    } else {
+                        DevelopmentSize::Units(wizard.input_usize(&format!("How many {}?", units))?)
+                    };
+                    let mut mods = modifiers.clone();
+                    mods.push(ScenarioModifier::NewDevelopment(NewDevelopment {
+                        bldgs: bldgs.clone(),
+                        land_use,
+                        size,
+                    }));
+                    Some(Transition::PopThenReplace(EditScenarioModifiers::new(
+                        ctx,
+                        scenario_name.clone(),
+                        mods,
+                    )))
+                })))
+            }
+            AreaModifier::RedirectFrom => Transition::Replace(SelectArea::with_redirect_from(
+                ctx,
+                AreaModifier::RedirectTo,
+                bldgs,
+                scenario_name,
+                modifiers,
+            )),
+            AreaModifier::RedirectTo => {
+                let from = self.redirect_from.clone();
+                Transition::Replace(WizardState::new(Box::new(move |wiz, ctx, app| {
+                    let pct_trips = wiz
+                        .wrap(ctx)
+                        .input_percent("What percent of trips should be redirected? (0 to 100)")?;
+                    let modifier = ScenarioModifier::RedirectDestinations {
+                        from: from.clone(),
+                        to: bldgs.clone(),
+                        pct_trips,
+                    };
+                    if let Err(err) = modifier.validate(&app.primary.map) {
+                        return Some(Transition::Push(msg("Error", vec![err])));
+                    }
+                    let mut mods = modifiers.clone();
+                    mods.push(modifier);
+                    Some(Transition::PopThenReplace(EditScenarioModifiers::new(
+                        ctx,
+                        scenario_name.clone(),
+                        mods,
+                    )))
+                })))
+            }
+            AreaModifier::OnlyPeopleCrossing => unreachable!(),
+        }
+    }
+}
+
+impl State for SelectArea {
+    fn event(&mut self, ctx: &mut EventCtx, app: &mut App) -> Transition {
+        ctx.canvas_movement();
+
+        if let Some(ref mut lasso) = self.lasso {
+            if let Some(ring) = lasso.event(ctx) {
+                self.lasso = None;
+                return self.finish(ctx, app, ring);
+            }
+        } else if ctx.canvas.get_cursor_in_map_space().is_some()
+            && ctx.input.left_mouse_button_pressed()
+        {
+            self.lasso = Some(Lasso::new(ctx.canvas.get_cursor_in_map_space().unwrap()));
+        }
+
+        match self.composite.event(ctx) {
+            Outcome::Clicked(x) => match x.as_ref() {
+                "Cancel" => Transition::Pop,
+                _ => unreachable!(),
+            },
+            _ => Transition::Keep,
+        }
+    }
+
+    fn draw(&self, g: &mut GfxCtx, _: &App) {
+        if let Some(ref lasso) = self.lasso {
+            lasso.draw(g);
+        }
+        self.composite.draw(g);
+    }
+}
@@ -20,7 +20,8 @@ use map_model::raw::{OriginalIntersection, OriginalRoad};
 use map_model::{BuildingID, Map, OriginalLane, Position};
 use sim::{
     AgentID, Analytics, BorderSpawnOverTime, CarID, DrivingGoal, IndividTrip, OriginDestination,
-    PersonID, PersonSpec, Scenario, ScenarioGenerator, SpawnOverTime, SpawnTrip, VehicleType,
+    PersonAttributes, PersonID, PersonSpec, Scenario, ScenarioGenerator, SpawnOverTime, SpawnTrip,
+    TripPurpose, VehicleType,
 };
 use std::collections::BTreeSet;
 
@@ -253,7 +254,7 @@ impl Tutorial {
             if app.primary.sim.is_done() {
                 let mut before = Duration::ZERO;
                 let mut after = Duration::ZERO;
-                for (b, a, _) in app
+                for (_, b, a, _) in app
                     .primary
                     .sim
                     .get_analytics()
@@ -1076,6 +1077,7 @@ impl TutorialState {
                         orig_id: None,
                         trips: vec![IndividTrip::new(
                             Time::START_OF_DAY,
+                            TripPurpose::Unknown,
                             SpawnTrip::VehicleAppearing {
                                 start: Position::new(
                                     start_lane,
@@ -1085,6 +1087,7 @@ impl TutorialState {
                                 is_bike: false,
                             },
                         )],
+                        attributes: PersonAttributes::default(),
                     });
                     // Will definitely get there first
                     for i in 0..map.get_b(goal_bldg).num_parking_spots() {
@@ -1093,6 +1096,7 @@ impl TutorialState {
                             orig_id: None,
                             trips: vec![IndividTrip::new(
                                 Time::START_OF_DAY,
+                                TripPurpose::Unknown,
                                 SpawnTrip::VehicleAppearing {
                                     start: Position::new(
                                         lane_near_bldg,
@@ -1102,6 +1106,7 @@ impl TutorialState {
                                     is_bike: false,
                                 },
                             )],
+                            attributes: PersonAttributes::default(),
                         });
                     }
                     let mut rng = app.primary.current_flags.sim_flags.make_rng();
@@ -1174,6 +1179,7 @@ impl TutorialState {
                             percent_use_transit: 0.0,
                         }],
                         border_spawn_over_time: Vec::new(),
+                        freight: Vec::new(),
                     }
                     .generate(
                         &app.primary.map,
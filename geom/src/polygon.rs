@@ -387,7 +387,7 @@ impl Triangle {
         Triangle { pt1, pt2, pt3 }
     }
 
-    fn contains_pt(&self, pt: Pt2D) -> bool {
+    pub fn contains_pt(&self, pt: Pt2D) -> bool {
         let x1 = self.pt1.x();
         let y1 = self.pt1.y();
         let x2 = self.pt2.x();
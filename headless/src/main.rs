@@ -12,6 +12,11 @@ fn main() {
     let num_days = args
         .optional_parse("--days", |s| s.parse::<usize>())
         .unwrap_or(1);
+    let scenario_name = args
+        .optional("--scenario")
+        .unwrap_or_else(|| "weekday".to_string());
+    // A JSON list of ScenarioModifiers, applied in order before repeating days
+    let modifiers_path = args.optional("--modifiers");
     args.done();
 
     let mut sim_flags = SimFlags::synthetic_test("montlake", "pandemic");
@@ -25,13 +30,24 @@ fn main() {
     // ParkingSimState are out of sync.
     let mut sim = Sim::new(&map, sim_flags.opts.clone(), &mut timer);
 
-    let base_scenario: Scenario = abstutil::read_binary(
-        abstutil::path_scenario(map.get_name(), "weekday"),
+    let mut scenario: Scenario = abstutil::read_binary(
+        abstutil::path_scenario(map.get_name(), &scenario_name),
         &mut timer,
     );
-    ScenarioModifier::RepeatDays(num_days)
-        .apply(&map, base_scenario, &mut rng)
-        .instantiate(&mut sim, &map, &mut rng, &mut timer);
+    let mut modifiers: Vec<ScenarioModifier> = if let Some(path) = modifiers_path {
+        abstutil::read_json(path, &mut timer)
+    } else {
+        Vec::new()
+    };
+    modifiers.push(ScenarioModifier::RepeatDays(num_days));
+    for m in modifiers {
+        if let Err(err) = m.validate(&map) {
+            panic!("Can't apply modifier {}: {}", m.describe(), err);
+        }
+        timer.note(format!("Applying modifier: {}", m.describe()));
+        scenario = m.apply(&map, scenario, &mut rng);
+    }
+    scenario.instantiate(&mut sim, &map, &mut rng, &mut timer);
     timer.done();
 
     run_experiment(&map, &mut sim);
@@ -55,4 +71,20 @@ fn run_experiment(map: &Map, sim: &mut Sim) {
     );
     timer.done();
     println!("Done at {}", sim.time());
+
+    if let Some(survey) = sim.get_parking_survey() {
+        let results = survey.compare(map, sim.get_analytics(), sim.time());
+        if !results.is_empty() {
+            let total_error: f64 = results
+                .iter()
+                .map(|r| (r.simulated - r.observed).abs())
+                .sum();
+            println!(
+                "Parking survey: {} observations, simulated occupancy is off by {:.1} cars on \
+                 average",
+                prettyprint_usize(results.len()),
+                total_error / (results.len() as f64)
+            );
+        }
+    }
 }
@@ -65,6 +65,7 @@ pub fn osm_to_raw(name: &str, timer: &mut Timer) {
             map_config: map_model::MapConfig {
                 driving_side: map_model::raw::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                bike_hill_aversion: 1.0,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
@@ -72,6 +73,8 @@ pub fn osm_to_raw(name: &str, timer: &mut Timer) {
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(3),
             elevation: None,
             include_railroads: true,
+            unmarked_crossings: false,
+            inference_rules: Vec::new(),
         },
         timer,
     );
@@ -0,0 +1,350 @@
+use geojson::{Feature, FeatureCollection, GeoJson, Geometry, JsonObject, Value};
+use geom::{Duration, GPSBounds, Pt2D};
+use map_model::raw::{OriginalBuilding, OriginalIntersection, OriginalRoad, RawMap};
+use map_model::{Building, ControlTrafficSignal, Intersection, Map, Road};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+
+// Compares two builds of the same map, matching objects by their OSM IDs. Prints a summary and
+// writes a GeoJSON file with one feature per difference, for visual review.
+pub fn maps(old: &Map, new: &Map, output: &str) {
+    let mut diff = Diff::new(old.get_gps_bounds(), new.get_gps_bounds());
+
+    let old_roads: BTreeMap<OriginalRoad, &Road> =
+        old.all_roads().iter().map(|r| (r.orig_id, r)).collect();
+    let new_roads: BTreeMap<OriginalRoad, &Road> =
+        new.all_roads().iter().map(|r| (r.orig_id, r)).collect();
+    diff.compare(
+        "road",
+        &old_roads,
+        &new_roads,
+        |r| r.center_pts.points().clone(),
+        |r1, r2| {
+            let mut details = Vec::new();
+            let lanes = |r: &Road| {
+                format!(
+                    "{:?} / {:?}",
+                    r.children_backwards
+                        .iter()
+                        .map(|(_, lt)| *lt)
+                        .collect::<Vec<_>>(),
+                    r.children_forwards
+                        .iter()
+                        .map(|(_, lt)| *lt)
+                        .collect::<Vec<_>>()
+                )
+            };
+            changed(
+                &mut details,
+                "lanes (backwards / forwards)",
+                lanes(r1),
+                lanes(r2),
+            );
+            changed(&mut details, "speed limit", r1.speed_limit, r2.speed_limit);
+            changed(&mut details, "name", r1.get_name(), r2.get_name());
+            details
+        },
+    );
+
+    let old_intersections: BTreeMap<OriginalIntersection, &Intersection> = old
+        .all_intersections()
+        .iter()
+        .map(|i| (i.orig_id, i))
+        .collect();
+    let new_intersections: BTreeMap<OriginalIntersection, &Intersection> = new
+        .all_intersections()
+        .iter()
+        .map(|i| (i.orig_id, i))
+        .collect();
+    diff.compare(
+        "intersection",
+        &old_intersections,
+        &new_intersections,
+        |i| i.polygon.points().clone(),
+        |i1, i2| {
+            let mut details = Vec::new();
+            changed(
+                &mut details,
+                "type",
+                format!("{:?}", i1.intersection_type),
+                format!("{:?}", i2.intersection_type),
+            );
+            changed(&mut details, "turns", i1.turns.len(), i2.turns.len());
+            if let (Some(ts1), Some(ts2)) = (
+                old.maybe_get_traffic_signal(i1.id),
+                new.maybe_get_traffic_signal(i2.id),
+            ) {
+                let cycle_length = |ts: &ControlTrafficSignal| {
+                    ts.phases.iter().fold(Duration::ZERO, |sum, p| {
+                        sum + p.phase_type.simple_duration()
+                    })
+                };
+                // Turn groups refer to roads by ID, so compare the version using OSM IDs
+                if ts1.export(old) != ts2.export(new) {
+                    details.push(format!(
+                        "signal plan changed: {} phases with a {} cycle, now {} phases with a {} \
+                         cycle",
+                        ts1.phases.len(),
+                        cycle_length(ts1),
+                        ts2.phases.len(),
+                        cycle_length(ts2)
+                    ));
+                }
+            }
+            details
+        },
+    );
+
+    let old_bldgs: BTreeMap<OriginalBuilding, &Building> = old
+        .all_buildings()
+        .iter()
+        .map(|b| {
+            (
+                OriginalBuilding {
+                    osm_way_id: b.osm_way_id,
+                },
+                b,
+            )
+        })
+        .collect();
+    let new_bldgs: BTreeMap<OriginalBuilding, &Building> = new
+        .all_buildings()
+        .iter()
+        .map(|b| {
+            (
+                OriginalBuilding {
+                    osm_way_id: b.osm_way_id,
+                },
+                b,
+            )
+        })
+        .collect();
+    diff.compare(
+        "building",
+        &old_bldgs,
+        &new_bldgs,
+        |b| b.polygon.points().clone(),
+        |b1, b2| {
+            let mut details = Vec::new();
+            changed(
+                &mut details,
+                "parking",
+                format!("{:?}", b1.parking),
+                format!("{:?}", b2.parking),
+            );
+            changed(
+                &mut details,
+                "type",
+                format!("{:?}", b1.bldg_type),
+                format!("{:?}", b2.bldg_type),
+            );
+            details
+        },
+    );
+
+    diff.finish(output);
+}
+
+// Like maps, but for the intermediate format, before roads and intersections are merged and lanes
+// are generated.
+pub fn raw_maps(old: &RawMap, new: &RawMap, output: &str) {
+    let mut diff = Diff::new(&old.gps_bounds, &new.gps_bounds);
+
+    diff.compare(
+        "road",
+        &old.roads.iter().collect(),
+        &new.roads.iter().collect(),
+        |r| r.center_points.clone(),
+        |r1, r2| {
+            let mut details = Vec::new();
+            let keys: BTreeSet<&String> = r1
+                .osm_tags
+                .inner()
+                .keys()
+                .chain(r2.osm_tags.inner().keys())
+                .collect();
+            for k in keys {
+                let show = |v: Option<&String>| v.cloned().unwrap_or_else(|| "missing".to_string());
+                changed(
+                    &mut details,
+                    k,
+                    show(r1.osm_tags.get(k)),
+                    show(r2.osm_tags.get(k)),
+                );
+            }
+            changed(
+                &mut details,
+                "turn restrictions",
+                r1.turn_restrictions.len() + r1.complicated_turn_restrictions.len(),
+                r2.turn_restrictions.len() + r2.complicated_turn_restrictions.len(),
+            );
+            details
+        },
+    );
+
+    diff.compare(
+        "intersection",
+        &old.intersections.iter().collect(),
+        &new.intersections.iter().collect(),
+        |i| vec![i.point],
+        |i1, i2| {
+            let mut details = Vec::new();
+            changed(
+                &mut details,
+                "type",
+                format!("{:?}", i1.intersection_type),
+                format!("{:?}", i2.intersection_type),
+            );
+            changed(
+                &mut details,
+                "crossing",
+                format!("{:?}", i1.midblock_crossing),
+                format!("{:?}", i2.midblock_crossing),
+            );
+            details
+        },
+    );
+
+    diff.compare(
+        "building",
+        &old.buildings.iter().collect(),
+        &new.buildings.iter().collect(),
+        |b| b.polygon.points().clone(),
+        |b1, b2| {
+            let mut details = Vec::new();
+            changed(
+                &mut details,
+                "parking spots",
+                b1.num_parking_spots,
+                b2.num_parking_spots,
+            );
+            changed(
+                &mut details,
+                "public garage",
+                format!("{:?}", b1.public_garage_name),
+                format!("{:?}", b2.public_garage_name),
+            );
+            details
+        },
+    );
+
+    diff.finish(output);
+}
+
+fn changed<T: Display + PartialEq>(details: &mut Vec<String>, what: &str, old: T, new: T) {
+    if old != new {
+        details.push(format!("{}: {} -> {}", what, old, new));
+    }
+}
+
+struct Diff<'a> {
+    old_gps: &'a GPSBounds,
+    new_gps: &'a GPSBounds,
+    // (category, added/removed/modified) -> count
+    summary: BTreeMap<(&'static str, &'static str), usize>,
+    features: Vec<Feature>,
+}
+
+impl<'a> Diff<'a> {
+    fn new(old_gps: &'a GPSBounds, new_gps: &'a GPSBounds) -> Diff<'a> {
+        Diff {
+            old_gps,
+            new_gps,
+            summary: BTreeMap::new(),
+            features: Vec::new(),
+        }
+    }
+
+    fn compare<K: Ord + Display, T, G: Fn(&T) -> Vec<Pt2D>, M: Fn(&T, &T) -> Vec<String>>(
+        &mut self,
+        category: &'static str,
+        old: &BTreeMap<K, T>,
+        new: &BTreeMap<K, T>,
+        geometry: G,
+        modified: M,
+    ) {
+        for (id, obj) in old {
+            if !new.contains_key(id) {
+                self.add(category, "removed", id, Vec::new(), geometry(obj), true);
+            }
+        }
+        for (id, obj) in new {
+            match old.get(id) {
+                Some(old_obj) => {
+                    let details = modified(old_obj, obj);
+                    if !details.is_empty() {
+                        self.add(category, "modified", id, details, geometry(obj), false);
+                    }
+                }
+                None => {
+                    self.add(category, "added", id, Vec::new(), geometry(obj), false);
+                }
+            }
+        }
+    }
+
+    fn add<K: Display>(
+        &mut self,
+        category: &'static str,
+        change: &'static str,
+        id: K,
+        details: Vec<String>,
+        pts: Vec<Pt2D>,
+        use_old_map: bool,
+    ) {
+        *self.summary.entry((category, change)).or_insert(0) += 1;
+
+        let gps_bounds = if use_old_map {
+            self.old_gps
+        } else {
+            self.new_gps
+        };
+        let coords: Vec<Vec<f64>> = pts
+            .into_iter()
+            .map(|pt| {
+                let gps = pt.to_gps(gps_bounds);
+                vec![gps.x(), gps.y()]
+            })
+            .collect();
+        // Don't bother with polygons; outlines are easier to see on top of a basemap anyway
+        let value = if coords.len() == 1 {
+            Value::Point(coords[0].clone())
+        } else {
+            Value::LineString(coords)
+        };
+
+        let mut props = JsonObject::new();
+        props.insert("category".to_string(), category.into());
+        props.insert("change".to_string(), change.into());
+        props.insert("id".to_string(), id.to_string().into());
+        props.insert("details".to_string(), details.into());
+        self.features.push(Feature {
+            bbox: None,
+            geometry: Some(Geometry::new(value)),
+            id: None,
+            properties: Some(props),
+            foreign_members: None,
+        });
+    }
+
+    fn finish(self, output: &str) {
+        if self.summary.is_empty() {
+            println!("No differences");
+        }
+        for ((category, change), count) in &self.summary {
+            println!(
+                "- {} {}s {}",
+                abstutil::prettyprint_usize(*count),
+                category,
+                change
+            );
+        }
+        let geojson = GeoJson::from(FeatureCollection {
+            bbox: None,
+            features: self.features,
+            foreign_members: None,
+        });
+        std::fs::write(output, abstutil::to_json(&geojson)).unwrap();
+        println!("Wrote {}", output);
+    }
+}
@@ -0,0 +1,268 @@
+use abstutil::Timer;
+use geojson::{Feature, FeatureCollection, GeoJson, Geometry, JsonObject, Value};
+use geom::{GPSBounds, PolyLine, Polygon, Pt2D};
+use map_model::{BuildingType, Map, OffstreetParking, ParkingRules, PathStep};
+
+// Writes the final map as a directory of GeoJSON layers in WGS84, one per kind of object, so
+// they can be opened in QGIS or similar. Objects keep their OSM IDs and tags where they have them,
+// so the layers can be joined back to OSM. The map should already have any edits applied.
+pub fn geojson(map: &Map, dir: &str, timer: &mut Timer) {
+    std::fs::create_dir_all(dir).unwrap();
+    let gps = map.get_gps_bounds();
+
+    timer.start("export roads");
+    let mut roads = Vec::new();
+    for r in map.all_roads() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), r.id.0.into());
+        props.insert("osm_way_id".to_string(), r.orig_id.osm_way_id.into());
+        props.insert("osm_node1".to_string(), r.orig_id.i1.osm_node_id.into());
+        props.insert("osm_node2".to_string(), r.orig_id.i2.osm_node_id.into());
+        props.insert("name".to_string(), r.get_name().into());
+        props.insert(
+            "speed_limit_mps".to_string(),
+            r.speed_limit.inner_meters_per_second().into(),
+        );
+        props.insert("zorder".to_string(), r.zorder.into());
+        props.insert("src_i".to_string(), r.src_i.0.into());
+        props.insert("dst_i".to_string(), r.dst_i.0.into());
+        props.insert(
+            "lanes_forwards".to_string(),
+            r.children_forwards.len().into(),
+        );
+        props.insert(
+            "lanes_backwards".to_string(),
+            r.children_backwards.len().into(),
+        );
+        let mut tags = JsonObject::new();
+        for (k, v) in r.osm_tags.inner() {
+            tags.insert(k.clone(), v.clone().into());
+        }
+        props.insert("osm_tags".to_string(), tags.into());
+        roads.push(feature(linestring(&r.center_pts, gps), props));
+    }
+    write(dir, "roads", roads);
+    timer.stop("export roads");
+
+    timer.start("export lanes");
+    let mut lanes = Vec::new();
+    for l in map.all_lanes() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), l.id.0.into());
+        props.insert("road".to_string(), l.parent.0.into());
+        props.insert("lane_type".to_string(), format!("{:?}", l.lane_type).into());
+        props.insert("width_m".to_string(), l.width.inner_meters().into());
+        props.insert("src_i".to_string(), l.src_i.0.into());
+        props.insert("dst_i".to_string(), l.dst_i.0.into());
+        if l.is_parking() {
+            parking_rules(&mut props, l.parking_rules);
+        }
+        lanes.push(feature(linestring(&l.lane_center_pts, gps), props));
+    }
+    write(dir, "lanes", lanes);
+    timer.stop("export lanes");
+
+    timer.start("export intersections");
+    let mut intersections = Vec::new();
+    for i in map.all_intersections() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), i.id.0.into());
+        props.insert("osm_node_id".to_string(), i.orig_id.osm_node_id.into());
+        props.insert(
+            "control".to_string(),
+            format!("{:?}", i.intersection_type).into(),
+        );
+        if let Some(crossing) = i.midblock_crossing {
+            props.insert("crossing".to_string(), format!("{:?}", crossing).into());
+        }
+        props.insert("elevation_m".to_string(), i.elevation.inner_meters().into());
+        intersections.push(feature(polygon(&i.polygon, gps), props));
+    }
+    write(dir, "intersections", intersections);
+    timer.stop("export intersections");
+
+    timer.start("export buildings");
+    let mut buildings = Vec::new();
+    for b in map.all_buildings() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), b.id.0.into());
+        props.insert("osm_way_id".to_string(), b.osm_way_id.into());
+        props.insert("address".to_string(), b.address.clone().into());
+        if let Some(ref name) = b.name {
+            props.insert("name".to_string(), name.clone().into());
+        }
+        let (bldg_type, residents) = match b.bldg_type {
+            BuildingType::Residential(n) => ("Residential", n),
+            BuildingType::ResidentialCommercial(n) => ("ResidentialCommercial", n),
+            BuildingType::Commercial => ("Commercial", 0),
+            BuildingType::Empty => ("Empty", 0),
+        };
+        props.insert("building_type".to_string(), bldg_type.into());
+        props.insert("residents".to_string(), residents.into());
+        match b.parking {
+            OffstreetParking::PublicGarage(ref name, spots) => {
+                props.insert("parking".to_string(), "PublicGarage".into());
+                props.insert("parking_name".to_string(), name.clone().into());
+                props.insert("parking_spots".to_string(), spots.into());
+            }
+            OffstreetParking::Private(spots) => {
+                props.insert("parking".to_string(), "Private".into());
+                props.insert("parking_spots".to_string(), spots.into());
+            }
+        }
+        props.insert("sidewalk".to_string(), b.sidewalk_pos.lane().0.into());
+        buildings.push(feature(polygon(&b.polygon, gps), props));
+    }
+    write(dir, "buildings", buildings);
+    timer.stop("export buildings");
+
+    timer.start("export parking lots");
+    let mut lots = Vec::new();
+    for pl in map.all_parking_lots() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), pl.id.0.into());
+        props.insert("osm_id".to_string(), pl.osm_id.into());
+        props.insert("spots".to_string(), pl.spots.len().into());
+        parking_rules(&mut props, pl.parking_rules);
+        lots.push(feature(polygon(&pl.polygon, gps), props));
+    }
+    write(dir, "parking_lots", lots);
+    timer.stop("export parking lots");
+
+    timer.start("export bus stops");
+    let mut stops = Vec::new();
+    for bs in map.all_bus_stops().values() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), bs.id.to_string().into());
+        props.insert("name".to_string(), bs.name.clone().into());
+        props.insert("is_train_stop".to_string(), bs.is_train_stop.into());
+        props.insert(
+            "routes".to_string(),
+            map.get_routes_serving_stop(bs.id)
+                .into_iter()
+                .map(|br| br.full_name.clone())
+                .collect::<Vec<_>>()
+                .into(),
+        );
+        stops.push(feature(point(bs.sidewalk_pos.pt(map), gps), props));
+    }
+    write(dir, "bus_stops", stops);
+    timer.stop("export bus stops");
+
+    timer.start("export bus routes");
+    let mut routes = Vec::new();
+    for br in map.all_bus_routes() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), br.id.0.into());
+        props.insert("osm_rel_id".to_string(), br.osm_rel_id.into());
+        props.insert("full_name".to_string(), br.full_name.clone().into());
+        props.insert("short_name".to_string(), br.short_name.clone().into());
+        props.insert(
+            "route_type".to_string(),
+            format!("{:?}", br.route_type).into(),
+        );
+        props.insert("stops".to_string(), br.stops.len().into());
+        props.insert("daily_trips".to_string(), br.spawn_times.len().into());
+        // Trace the path between each pair of stops. Some pieces might not be reachable after
+        // edits; just skip those.
+        let mut lines = Vec::new();
+        for req in br.all_steps(map) {
+            if let Some(path) = map.pathfind(req) {
+                let mut pts = Vec::new();
+                for step in path.get_steps() {
+                    if let PathStep::Lane(l) = step {
+                        pts.extend(map.get_l(*l).lane_center_pts.points().clone());
+                    }
+                }
+                if let Ok(pl) = PolyLine::deduping_new(pts) {
+                    lines.push(to_gps(pl.points(), gps));
+                }
+            }
+        }
+        routes.push(feature(Value::MultiLineString(lines), props));
+    }
+    write(dir, "bus_routes", routes);
+    timer.stop("export bus routes");
+
+    timer.start("export zones");
+    let mut zones = Vec::new();
+    for (idx, zone) in map.all_zones().iter().enumerate() {
+        let mut props = JsonObject::new();
+        props.insert("id".to_string(), idx.into());
+        props.insert(
+            "allow_through_traffic".to_string(),
+            zone.allow_through_traffic
+                .iter()
+                .map(|c| format!("{:?}", c))
+                .collect::<Vec<_>>()
+                .into(),
+        );
+        props.insert(
+            "roads".to_string(),
+            zone.members.iter().map(|r| r.0).collect::<Vec<_>>().into(),
+        );
+        let lines = zone
+            .members
+            .iter()
+            .map(|r| to_gps(map.get_r(*r).center_pts.points(), gps))
+            .collect();
+        zones.push(feature(Value::MultiLineString(lines), props));
+    }
+    write(dir, "zones", zones);
+    timer.stop("export zones");
+}
+
+fn parking_rules(props: &mut JsonObject, rules: ParkingRules) {
+    props.insert("cents_per_hour".to_string(), rules.cents_per_hour.into());
+    if let Some(max_stay) = rules.max_stay {
+        props.insert("max_stay_s".to_string(), max_stay.inner_seconds().into());
+    }
+}
+
+fn feature(value: Value, props: JsonObject) -> Feature {
+    Feature {
+        bbox: None,
+        geometry: Some(Geometry::new(value)),
+        id: None,
+        properties: Some(props),
+        foreign_members: None,
+    }
+}
+
+fn to_gps(pts: &Vec<Pt2D>, gps: &GPSBounds) -> Vec<Vec<f64>> {
+    pts.iter()
+        .map(|pt| {
+            let gps = pt.to_gps(gps);
+            vec![gps.x(), gps.y()]
+        })
+        .collect()
+}
+
+fn point(pt: Pt2D, gps: &GPSBounds) -> Value {
+    let gps = pt.to_gps(gps);
+    Value::Point(vec![gps.x(), gps.y()])
+}
+
+fn linestring(pl: &PolyLine, gps: &GPSBounds) -> Value {
+    Value::LineString(to_gps(pl.points(), gps))
+}
+
+fn polygon(poly: &Polygon, gps: &GPSBounds) -> Value {
+    // GeoJSON rings have to be closed
+    let mut ring = to_gps(poly.points(), gps);
+    if ring[0] != *ring.last().unwrap() {
+        ring.push(ring[0].clone());
+    }
+    Value::Polygon(vec![ring])
+}
+
+fn write(dir: &str, layer: &str, features: Vec<Feature>) {
+    let path = format!("{}/{}.geojson", dir, layer);
+    println!("- Writing {} features to {}", features.len(), path);
+    let geojson = GeoJson::from(FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    });
+    std::fs::write(&path, abstutil::to_json(&geojson)).unwrap();
+}
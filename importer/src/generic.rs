@@ -0,0 +1,119 @@
+use crate::od;
+use crate::utils::{download, osmconvert};
+use abstutil::Timer;
+use convert_osm::{
+    InferenceRule, OnstreetParking, PrivateOffstreetParking, PublicOffstreetParking,
+};
+use map_model::{Map, MapConfig};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Everything needed to import a city that doesn't need any special code. This lives in
+// importer/config/$city/cfg.json. All paths are relative to data/.
+#[derive(Serialize, Deserialize)]
+pub struct GenericCityImporter {
+    // An .osm or .osm.pbf file covering every map in the city, like an extract from Geofabrik.
+    // It's downloaded once and clipped separately for each map.
+    pub osm_url: String,
+    // Each map to import, with the path to its Osmosis clipping polygon. If empty, every polygon
+    // in input/$city/polygons/ becomes a map.
+    #[serde(default)]
+    pub clip_polygons: BTreeMap<String, String>,
+    pub map_config: MapConfig,
+
+    pub onstreet_parking: OnstreetParking,
+    pub public_offstreet_parking: PublicOffstreetParking,
+    pub private_offstreet_parking: PrivateOffstreetParking,
+    // A GeoTIFF DEM covering the city
+    #[serde(default)]
+    pub elevation: Option<String>,
+    pub include_railroads: bool,
+    #[serde(default)]
+    pub unmarked_crossings: bool,
+    // Guesses for parking lanes and sidewalks where OSM doesn't say; see
+    // convert_osm::InferenceRule.
+    #[serde(default)]
+    pub inference_rules: Vec<InferenceRule>,
+
+    // Origin-destination matrices to expand into scenarios for every map, keyed by scenario name
+    #[serde(default)]
+    pub scenarios: BTreeMap<String, ODMatrixSource>,
+}
+
+// See the od module for the file formats.
+#[derive(Serialize, Deserialize)]
+pub struct ODMatrixSource {
+    pub zones: String,
+    pub flows: String,
+}
+
+impl GenericCityImporter {
+    // None if the city has no config file
+    pub fn load(city: &str) -> Option<GenericCityImporter> {
+        let path = format!("importer/config/{}/cfg.json", city);
+        if !abstutil::file_exists(path.clone()) {
+            return None;
+        }
+        Some(abstutil::read_json(path, &mut Timer::throwaway()))
+    }
+
+    pub fn map_names(&self, city: &str) -> Vec<String> {
+        if self.clip_polygons.is_empty() {
+            abstutil::list_all_objects(abstutil::path(format!("input/{}/polygons", city)))
+        } else {
+            self.clip_polygons.keys().cloned().collect()
+        }
+    }
+
+    pub fn osm_to_raw(&self, city: &str, name: &str, timer: &mut Timer) {
+        let osm_input = format!(
+            "input/{}/osm/{}",
+            city,
+            self.osm_url.rsplit('/').next().unwrap()
+        );
+        download(&osm_input, &self.osm_url);
+        let clip = self
+            .clip_polygons
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| format!("input/{}/polygons/{}.poly", city, name));
+        // The whole extract is usually huge, so clip it first.
+        let clipped_osm = format!("input/{}/osm/{}.osm", city, name);
+        osmconvert(&osm_input, clip.clone(), clipped_osm.clone());
+
+        let map = convert_osm::convert(
+            convert_osm::Options {
+                osm_input: abstutil::path(clipped_osm),
+                city_name: city.to_string(),
+                name: name.to_string(),
+
+                clip: Some(abstutil::path(clip)),
+                map_config: self.map_config.clone(),
+
+                onstreet_parking: self.onstreet_parking.clone(),
+                public_offstreet_parking: self.public_offstreet_parking.clone(),
+                private_offstreet_parking: self.private_offstreet_parking.clone(),
+                elevation: self.elevation.as_ref().map(abstutil::path),
+                include_railroads: self.include_railroads,
+                unmarked_crossings: self.unmarked_crossings,
+                inference_rules: self.inference_rules.clone(),
+            },
+            timer,
+        );
+        let output = abstutil::path(format!("input/raw_maps/{}.bin", name));
+        abstutil::write_binary(output, &map);
+    }
+
+    pub fn make_scenarios(&self, map: &Map, timer: &mut Timer) {
+        for (scenario_name, src) in &self.scenarios {
+            od::import(
+                map,
+                &abstutil::path(&src.zones),
+                &abstutil::path(&src.flows),
+                scenario_name,
+                timer,
+            )
+            .save();
+        }
+    }
+}
@@ -1,5 +1,9 @@
 mod berlin;
-mod krakow;
+mod diff;
+mod export;
+mod generic;
+mod od;
+mod parking_survey;
 mod seattle;
 #[cfg(feature = "scenarios")]
 mod soundcast;
@@ -21,6 +25,22 @@ struct Job {
     oneshot: Option<String>,
     oneshot_clip: Option<String>,
     oneshot_drive_on_left: bool,
+    oneshot_elevation: Option<String>,
+
+    od_import: Option<String>,
+    od_export: Option<String>,
+    od_zones: Option<String>,
+    od_flows: Option<String>,
+
+    calibrate_parking: Option<String>,
+    parking_survey: Option<String>,
+
+    export_geojson: Option<String>,
+    edits: Option<String>,
+
+    diff_old: Option<String>,
+    diff_new: Option<String>,
+    diff_output: String,
 }
 
 fn main() {
@@ -43,10 +63,38 @@ fn main() {
         // data/input/$city/polygons/.
         only_map: args.optional_free(),
 
-        // Ignore other arguments and just convert the given .osm file to a Map.
+        // Ignore other arguments and just convert the given .osm or .osm.pbf file to a Map.
         oneshot: args.optional("--oneshot"),
         oneshot_clip: args.optional("--oneshot_clip"),
         oneshot_drive_on_left: args.enabled("--oneshot_drive_on_left"),
+        // A GeoTIFF DEM in WGS84 covering the map
+        oneshot_elevation: args.optional("--oneshot_elevation"),
+
+        // Expand an origin-destination matrix into a new scenario with this name. Zones come from
+        // the GeoJSON file --od_zones, and flows from the CSV file --od_flows.
+        od_import: args.optional("--od_import"),
+        // Write the OD matrix implied by this scenario to --od_flows, using --od_zones.
+        od_export: args.optional("--od_export"),
+        od_zones: args.optional("--od_zones"),
+        od_flows: args.optional("--od_flows"),
+
+        // Attach the block-face parking occupancy survey from the CSV file --parking_survey to the
+        // scenario with this name.
+        calibrate_parking: args.optional("--calibrate_parking"),
+        parking_survey: args.optional("--parking_survey"),
+
+        // Write the final map as GeoJSON layers into this directory. If --edits is given, apply
+        // those edits first.
+        export_geojson: args.optional("--export_geojson"),
+        edits: args.optional("--edits"),
+
+        // Compare two builds of a map, either both in data/system/maps or both in
+        // data/input/raw_maps. Writes differences to --diff_output.
+        diff_old: args.optional("--diff_old"),
+        diff_new: args.optional("--diff_new"),
+        diff_output: args
+            .optional("--diff_output")
+            .unwrap_or("map_diff.geojson".to_string()),
     };
     args.done();
     if !job.osm_to_raw
@@ -54,22 +102,112 @@ fn main() {
         && !job.scenario
         && !job.scenario_everyone
         && job.oneshot.is_none()
+        && job.od_import.is_none()
+        && job.od_export.is_none()
+        && job.calibrate_parking.is_none()
+        && job.export_geojson.is_none()
+        && job.diff_old.is_none()
     {
         println!(
             "Nothing to do! Pass some combination of --raw, --map, --scenario, \
-             --scenario_everyone or --oneshot"
+             --scenario_everyone, --oneshot, --od_import, --od_export, --calibrate_parking, \
+             --export_geojson, or --diff_old"
         );
         std::process::exit(1);
     }
 
     if let Some(path) = job.oneshot {
-        oneshot(path, job.oneshot_clip, !job.oneshot_drive_on_left);
+        oneshot(
+            path,
+            job.oneshot_clip,
+            !job.oneshot_drive_on_left,
+            job.oneshot_elevation,
+        );
+        return;
+    }
+
+    if job.od_import.is_some() || job.od_export.is_some() {
+        let name = job
+            .only_map
+            .expect("--od_import and --od_export need a single map name");
+        let zones = job.od_zones.expect("--od_zones is required");
+        let flows = job.od_flows.expect("--od_flows is required");
+        let mut timer = abstutil::Timer::new(format!("OD matrix for {}", name));
+        let map = map_model::Map::new(abstutil::path_map(&name), &mut timer);
+        if let Some(scenario_name) = job.od_import {
+            od::import(&map, &zones, &flows, &scenario_name, &mut timer).save();
+        }
+        if let Some(scenario_name) = job.od_export {
+            od::export(&map, &scenario_name, &zones, &flows, &mut timer);
+        }
+        return;
+    }
+
+    if let Some(scenario_name) = job.calibrate_parking {
+        let name = job
+            .only_map
+            .expect("--calibrate_parking needs a single map name");
+        let survey = job.parking_survey.expect("--parking_survey is required");
+        let mut timer = abstutil::Timer::new(format!("calibrate parking for {}", name));
+        let map = map_model::Map::new(abstutil::path_map(&name), &mut timer);
+        parking_survey::calibrate(&map, &scenario_name, &survey, &mut timer).save();
+        return;
+    }
+
+    if let Some(dir) = job.export_geojson {
+        let name = job
+            .only_map
+            .expect("--export_geojson needs a single map name");
+        let mut timer = abstutil::Timer::new(format!("export {} to GeoJSON", name));
+        let mut map = map_model::Map::new(abstutil::path_map(&name), &mut timer);
+        if let Some(edits_name) = job.edits {
+            let edits = map_model::MapEdits::load(&map, &edits_name, &mut timer).unwrap();
+            map.must_apply_edits(edits, &mut timer);
+            map.recalculate_pathfinding_after_edits(&mut timer);
+        }
+        export::geojson(&map, &dir, &mut timer);
         return;
     }
 
+    if let Some(old) = job.diff_old {
+        let new = job.diff_new.expect("--diff_new is required");
+        let mut timer = abstutil::Timer::new("diff maps");
+        if old.contains("raw_maps") {
+            let old: map_model::raw::RawMap = abstutil::read_binary(old, &mut timer);
+            let new: map_model::raw::RawMap = abstutil::read_binary(new, &mut timer);
+            diff::raw_maps(&old, &new, &job.diff_output);
+        } else {
+            let old = map_model::Map::new(old, &mut timer);
+            let new = map_model::Map::new(new, &mut timer);
+            diff::maps(&old, &new, &job.diff_output);
+        }
+        return;
+    }
+
+    // Cities without their own module are described by a config file
+    let generic = match job.city.as_ref() {
+        "berlin" | "seattle" => None,
+        city => Some(generic::GenericCityImporter::load(city).unwrap_or_else(|| {
+            panic!(
+                "Unknown city {}; write importer/config/{}/cfg.json",
+                city, city
+            )
+        })),
+    };
+    if generic.is_some() && job.scenario_everyone {
+        panic!(
+            "--scenario_everyone only works for Seattle. Scenarios for {} come from \
+             importer/config/{}/cfg.json; use --scenario",
+            job.city, job.city
+        );
+    }
+
     let names = if let Some(n) = job.only_map {
         println!("- Just working on {}", n);
         vec![n]
+    } else if let Some(ref generic) = generic {
+        println!("- Working on all {} maps", job.city);
+        generic.map_names(&job.city)
     } else {
         println!("- Working on all {} maps", job.city);
         abstutil::list_all_objects(abstutil::path(format!("input/{}/polygons", job.city)))
@@ -77,7 +215,9 @@ fn main() {
 
     let mut timer = abstutil::Timer::new("import map data");
 
-    let (maybe_popdat, maybe_huge_map) = if job.scenario || job.scenario_everyone {
+    // Generic cities get scenarios from their config instead
+    let needs_popdat = (job.scenario || job.scenario_everyone) && generic.is_none();
+    let (maybe_popdat, maybe_huge_map) = if needs_popdat {
         assert_eq!(job.city, "seattle");
 
         #[cfg(feature = "scenarios")]
@@ -103,14 +243,17 @@ fn main() {
         if job.osm_to_raw {
             match job.city.as_ref() {
                 "berlin" => berlin::osm_to_raw(&name, &mut timer),
-                "krakow" => krakow::osm_to_raw(&name, &mut timer),
                 "seattle" => seattle::osm_to_raw(&name, &mut timer),
-                x => panic!("Unknown city {}", x),
+                city => generic
+                    .as_ref()
+                    .unwrap()
+                    .osm_to_raw(city, &name, &mut timer),
             }
         }
 
         let mut maybe_map = if job.raw_to_map {
             let mut map = utils::raw_to_map(&name, !job.skip_ch, &mut timer);
+            utils::migrate_edits(&map, &mut timer);
 
             // Another strange step in the pipeline.
             if name == "berlin_center" {
@@ -136,6 +279,15 @@ fn main() {
             None
         };
 
+        if let Some(ref generic) = generic {
+            if job.scenario {
+                timer.start(format!("scenarios for {}", name));
+                generic.make_scenarios(maybe_map.as_ref().unwrap(), &mut timer);
+                timer.stop(format!("scenarios for {}", name));
+            }
+            continue;
+        }
+
         #[cfg(feature = "scenarios")]
         if job.scenario {
             timer.start(format!("scenario for {}", name));
@@ -170,10 +322,18 @@ fn main() {
     }
 }
 
-fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
+fn oneshot(
+    osm_path: String,
+    clip: Option<String>,
+    drive_on_right: bool,
+    elevation: Option<String>,
+) {
     let mut timer = abstutil::Timer::new("oneshot");
     println!("- Running convert_osm on {}", osm_path);
-    let name = abstutil::basename(&osm_path);
+    // Geofabrik extracts are named like foo.osm.pbf
+    let name = abstutil::basename(&osm_path)
+        .trim_end_matches(".osm")
+        .to_string();
     let raw = convert_osm::convert(
         convert_osm::Options {
             osm_input: osm_path,
@@ -188,13 +348,16 @@ fn oneshot(osm_path: String, clip: Option<String>, drive_on_right: bool) {
                     map_model::raw::DrivingSide::Left
                 },
                 bikes_can_use_bus_lanes: true,
+                bike_hill_aversion: 1.0,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::JustOSM,
             public_offstreet_parking: convert_osm::PublicOffstreetParking::None,
             private_offstreet_parking: convert_osm::PrivateOffstreetParking::FixedPerBldg(1),
-            elevation: None,
+            elevation,
             include_railroads: true,
+            unmarked_crossings: false,
+            inference_rules: Vec::new(),
         },
         &mut timer,
     );
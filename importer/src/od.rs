@@ -0,0 +1,190 @@
+use abstutil::Timer;
+use geojson::{GeoJson, Value};
+use geom::{Duration, LonLat, Polygon, Time};
+use map_model::Map;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use sim::{ODFlow, ODMatrix, ODZone, Scenario, TripMode};
+use std::fs::File;
+
+// Zones are a GeoJSON FeatureCollection of Polygons or MultiPolygons in WGS84. Each feature needs
+// a "name" property (or an ID) matching the origin and destination columns of the flows CSV.
+pub fn read_zones(path: &str, map: &Map) -> Vec<ODZone> {
+    let raw = String::from_utf8(abstutil::slurp_file(path).unwrap()).unwrap();
+    let collection = match raw.parse::<GeoJson>().unwrap() {
+        GeoJson::FeatureCollection(collection) => collection,
+        _ => panic!("{} isn't a FeatureCollection", path),
+    };
+
+    let gps_bounds = map.get_gps_bounds();
+    let mut zones = Vec::new();
+    for feature in collection.features {
+        let name = match feature
+            .properties
+            .as_ref()
+            .and_then(|props| props.get("name"))
+        {
+            Some(geojson::JsonValue::String(name)) => name.clone(),
+            Some(x) => x.to_string(),
+            None => match feature.id {
+                Some(geojson::feature::Id::String(ref id)) => id.clone(),
+                Some(geojson::feature::Id::Number(ref id)) => id.to_string(),
+                None => panic!("A zone in {} has no name property or ID", path),
+            },
+        };
+
+        let mut polygons = Vec::new();
+        match feature.geometry.map(|g| g.value) {
+            Some(Value::Polygon(rings)) => {
+                polygons.push(to_polygon(&rings, gps_bounds));
+            }
+            Some(Value::MultiPolygon(list)) => {
+                for rings in list {
+                    polygons.push(to_polygon(&rings, gps_bounds));
+                }
+            }
+            _ => panic!("Zone {} in {} isn't a polygon", name, path),
+        }
+        zones.push(ODZone {
+            name,
+            polygon: Polygon::union_all(polygons),
+        });
+    }
+    zones
+}
+
+fn to_polygon(rings: &Vec<Vec<Vec<f64>>>, gps_bounds: &geom::GPSBounds) -> Polygon {
+    let map_space: Vec<Vec<Vec<f64>>> = rings
+        .iter()
+        .map(|ring| {
+            let pts: Vec<LonLat> = ring.iter().map(|pt| LonLat::new(pt[0], pt[1])).collect();
+            gps_bounds
+                .convert(&pts)
+                .into_iter()
+                .map(|pt| vec![pt.x(), pt.y()])
+                .collect()
+        })
+        .collect();
+    Polygon::from_geojson(&map_space)
+}
+
+// One row per (origin, destination, mode, period). Times are like 07:30:00.
+#[derive(Debug, Deserialize, Serialize)]
+struct FlowRecord {
+    origin: String,
+    destination: String,
+    // walk, bike, transit, or drive
+    mode: String,
+    start: String,
+    end: String,
+    count: usize,
+}
+
+pub fn read_flows(path: &str) -> Vec<ODFlow> {
+    let mut flows = Vec::new();
+    for rec in csv::Reader::from_reader(File::open(path).unwrap()).deserialize() {
+        let rec: FlowRecord = rec.unwrap();
+        let mode = parse_mode(&rec.mode);
+        let start = Time::parse(&rec.start).unwrap();
+        let end = Time::parse(&rec.end).unwrap();
+        if end <= start {
+            panic!("Flow {:?} has a bad time period", rec);
+        }
+        flows.push(ODFlow {
+            origin: rec.origin,
+            destination: rec.destination,
+            mode,
+            period: (start, end),
+            count: rec.count,
+        });
+    }
+    flows
+}
+
+fn parse_mode(mode: &str) -> TripMode {
+    match mode {
+        "walk" => TripMode::Walk,
+        "bike" => TripMode::Bike,
+        "transit" => TripMode::Transit,
+        "drive" => TripMode::Drive,
+        "ride_hail" => TripMode::RideHail,
+        _ => panic!("Unknown mode {}", mode),
+    }
+}
+
+fn mode_name(mode: TripMode) -> &'static str {
+    match mode {
+        TripMode::Walk => "walk",
+        TripMode::Bike => "bike",
+        TripMode::Transit => "transit",
+        TripMode::Drive => "drive",
+        TripMode::RideHail => "ride_hail",
+    }
+}
+
+fn write_flows(path: &str, flows: &Vec<ODFlow>) {
+    let mut writer = csv::Writer::from_path(path).unwrap();
+    for flow in flows {
+        writer
+            .serialize(FlowRecord {
+                origin: flow.origin.clone(),
+                destination: flow.destination.clone(),
+                mode: mode_name(flow.mode).to_string(),
+                start: flow.period.0.to_string(),
+                end: flow.period.1.to_string(),
+                count: flow.count,
+            })
+            .unwrap();
+    }
+    writer.flush().unwrap();
+    println!("Wrote {}", path);
+}
+
+// Expands an OD matrix into a new scenario for the map.
+pub fn import(
+    map: &Map,
+    zones_path: &str,
+    flows_path: &str,
+    scenario_name: &str,
+    timer: &mut Timer,
+) -> Scenario {
+    timer.start(format!(
+        "read OD matrix from {} and {}",
+        zones_path, flows_path
+    ));
+    let od = ODMatrix {
+        zones: read_zones(zones_path, map),
+        flows: read_flows(flows_path),
+    };
+    timer.stop(format!(
+        "read OD matrix from {} and {}",
+        zones_path, flows_path
+    ));
+
+    // Deterministic, so that re-importing the same matrix produces the same people
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+    od.make_scenario(scenario_name, map, &mut rng, timer)
+}
+
+// Writes the hourly OD matrix implied by an existing scenario, using the given zones.
+pub fn export(
+    map: &Map,
+    scenario_name: &str,
+    zones_path: &str,
+    flows_path: &str,
+    timer: &mut Timer,
+) {
+    let scenario: Scenario = abstutil::read_binary(
+        abstutil::path_scenario(map.get_name(), scenario_name),
+        timer,
+    );
+    let od = ODMatrix::from_scenario(
+        &scenario,
+        read_zones(zones_path, map),
+        Duration::hours(1),
+        map,
+        timer,
+    );
+    write_flows(flows_path, &od.flows);
+}
@@ -0,0 +1,65 @@
+use abstutil::Timer;
+use map_model::Map;
+use serde::Deserialize;
+use sim::{ParkingObservation, ParkingSurvey, Scenario};
+use std::fs::File;
+
+// One row per (OSM way, hour). Hours are 0 to 23. capacity may be blank if the survey only counted
+// occupied spots.
+#[derive(Debug, Deserialize)]
+struct SurveyRecord {
+    osm_way_id: i64,
+    hour: usize,
+    occupied: usize,
+    capacity: Option<usize>,
+}
+
+pub fn read_survey(path: &str) -> ParkingSurvey {
+    let mut observations = Vec::new();
+    for rec in csv::Reader::from_reader(File::open(path).unwrap()).deserialize() {
+        let rec: SurveyRecord = rec.unwrap();
+        if rec.hour >= 24 {
+            panic!("Observation {:?} has a bad hour", rec);
+        }
+        observations.push(ParkingObservation {
+            osm_way_id: rec.osm_way_id,
+            hour: rec.hour,
+            occupied: rec.occupied,
+            capacity: rec.capacity,
+        });
+    }
+    ParkingSurvey { observations }
+}
+
+// Attaches a parking occupancy survey to an existing scenario, so instantiating it seeds
+// background parked cars.
+pub fn calibrate(map: &Map, scenario_name: &str, survey_path: &str, timer: &mut Timer) -> Scenario {
+    let mut scenario: Scenario = abstutil::read_binary(
+        abstutil::path_scenario(map.get_name(), scenario_name),
+        timer,
+    );
+    let survey = read_survey(survey_path);
+
+    let matched = survey.lanes_per_way(map);
+    let mut unmatched = 0;
+    for obs in &survey.observations {
+        if !matched.contains_key(&obs.osm_way_id) {
+            unmatched += 1;
+        }
+    }
+    if unmatched > 0 {
+        timer.warn(format!(
+            "{} observations don't match any road with parking in {}",
+            unmatched,
+            map.get_name()
+        ));
+    }
+    timer.note(format!(
+        "Parking survey covers {} ways; {} background cars will be seeded",
+        matched.len(),
+        survey.background_cars(map).values().sum::<usize>()
+    ));
+
+    scenario.parking_survey = Some(survey);
+    scenario
+}
@@ -72,6 +72,7 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer) {
             map_config: map_model::MapConfig {
                 driving_side: map_model::raw::DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                bike_hill_aversion: 1.0,
             },
 
             onstreet_parking: convert_osm::OnstreetParking::Blockface(abstutil::path(
@@ -93,6 +94,8 @@ pub fn osm_to_raw(name: &str, timer: &mut abstutil::Timer) {
             elevation: Some(abstutil::path("input/seattle/N47W122.hgt")),
             // They mess up 16th and E Marginal badly enough to cause gridlock.
             include_railroads: false,
+            unmarked_crossings: false,
+            inference_rules: Vec::new(),
         },
         timer,
     );
@@ -3,23 +3,25 @@ use geom::{Distance, Duration, FindClosest, LonLat, Pt2D, Time};
 use kml::{ExtraShape, ExtraShapes};
 use map_model::Map;
 use serde::{Deserialize, Serialize};
-use sim::{OrigPersonID, TripMode};
-use std::collections::{BTreeMap, HashMap, HashSet};
+use sim::{OrigPersonID, TripMode, TripPurpose};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 #[derive(Serialize, Deserialize)]
 pub struct PopDat {
     pub trips: Vec<OrigTrip>,
+    pub people: BTreeMap<OrigPersonID, OrigPerson>,
 }
 
 // Extract trip demand data from PSRC's Soundcast outputs.
 pub fn import_data(huge_map: &Map, timer: &mut Timer) -> PopDat {
-    let trips = import_trips(huge_map, timer);
-    let popdat = PopDat { trips };
+    let (trips, parcels) = import_trips(huge_map, timer);
+    let people = import_people(&parcels, timer);
+    let popdat = PopDat { trips, people };
     abstutil::write_binary(abstutil::path_popdat(), &popdat);
     popdat
 }
 
-fn import_trips(huge_map: &Map, timer: &mut Timer) -> Vec<OrigTrip> {
+fn import_trips(huge_map: &Map, timer: &mut Timer) -> (Vec<OrigTrip>, HashMap<usize, Endpoint>) {
     let (parcels, mut keyed_shapes) = import_parcels(huge_map, timer);
 
     let mut trips = Vec::new();
@@ -105,7 +107,74 @@ fn import_trips(huge_map: &Map, timer: &mut Timer) -> Vec<OrigTrip> {
         &ExtraShapes { shapes },
     );
 
-    trips
+    (trips, parcels)
+}
+
+// Demographics are optional; if the person and household files are missing, they'll be
+// synthesized later.
+fn import_people(
+    parcels: &HashMap<usize, Endpoint>,
+    timer: &mut Timer,
+) -> BTreeMap<OrigPersonID, OrigPerson> {
+    let households_path = abstutil::path("input/seattle/households_2014.csv");
+    let persons_path = abstutil::path("input/seattle/persons_2014.csv");
+    if !abstutil::file_exists(households_path.clone())
+        || !abstutil::file_exists(persons_path.clone())
+    {
+        timer.warn(format!(
+            "{} or {} missing, so people won't have imported demographics",
+            households_path, persons_path
+        ));
+        return BTreeMap::new();
+    }
+
+    let mut households: HashMap<usize, RawHousehold> = HashMap::new();
+    let (reader, done) = FileWithProgress::new(&households_path).unwrap();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: RawHousehold = rec.unwrap();
+        households.insert(rec.hhno as usize, rec);
+    }
+    done(timer);
+
+    let mut people = BTreeMap::new();
+    let mut missing_households = BTreeSet::new();
+    let (reader, done) = FileWithProgress::new(&persons_path).unwrap();
+    for rec in csv::Reader::from_reader(reader).deserialize() {
+        let rec: RawPerson = rec.unwrap();
+        let hh = if let Some(hh) = households.get(&(rec.hhno as usize)) {
+            hh
+        } else {
+            missing_households.insert(rec.hhno as usize);
+            continue;
+        };
+        people.insert(
+            OrigPersonID(rec.hhno as usize, rec.pno as usize),
+            OrigPerson {
+                home: parcels.get(&(hh.hhparcel as usize)).cloned(),
+                age: rec.pagey as usize,
+                // Negative means unknown
+                household_income: if hh.hhincome >= 0.0 {
+                    Some(hh.hhincome as usize)
+                } else {
+                    None
+                },
+                household_vehicles: hh.hhvehs as usize,
+            },
+        );
+    }
+    done(timer);
+    if !missing_households.is_empty() {
+        timer.warn(format!(
+            "Skipped people in {} households that aren't in {}",
+            prettyprint_usize(missing_households.len()),
+            households_path
+        ));
+    }
+    timer.note(format!(
+        "Demographics for {} people",
+        prettyprint_usize(people.len())
+    ));
+    people
 }
 
 // TODO Do we also need the zone ID, or is parcel ID globally unique?
@@ -218,19 +287,19 @@ fn import_parcels(
 }
 
 // From https://github.com/psrc/soundcast/wiki/Outputs#trip-file-_triptsv, opurp and dpurp
-fn get_purpose(code: &str) -> Purpose {
+fn get_purpose(code: &str) -> TripPurpose {
     match code {
-        "0.0" => Purpose::Home,
-        "1.0" => Purpose::Work,
-        "2.0" => Purpose::School,
-        "3.0" => Purpose::Escort,
-        "4.0" => Purpose::PersonalBusiness,
-        "5.0" => Purpose::Shopping,
-        "6.0" => Purpose::Meal,
-        "7.0" => Purpose::Social,
-        "8.0" => Purpose::Recreation,
-        "9.0" => Purpose::Medical,
-        "10.0" => Purpose::ParkAndRideTransfer,
+        "0.0" => TripPurpose::Home,
+        "1.0" => TripPurpose::Work,
+        "2.0" => TripPurpose::School,
+        "3.0" => TripPurpose::Escort,
+        "4.0" => TripPurpose::PersonalBusiness,
+        "5.0" => TripPurpose::Shopping,
+        "6.0" => TripPurpose::Meal,
+        "7.0" => TripPurpose::Social,
+        "8.0" => TripPurpose::Recreation,
+        "9.0" => TripPurpose::Medical,
+        "10.0" => TripPurpose::ParkAndRideTransfer,
         _ => panic!("Unknown opurp/dpurp {}", code),
     }
 }
@@ -268,6 +337,23 @@ struct RawTrip {
     tseg: f64,
 }
 
+// See https://github.com/psrc/soundcast/wiki/Outputs#household-file-_householdtsv
+#[derive(Debug, Deserialize)]
+struct RawHousehold {
+    hhno: f64,
+    hhvehs: f64,
+    hhincome: f64,
+    hhparcel: f64,
+}
+
+// See https://github.com/psrc/soundcast/wiki/Outputs#person-file-_persontsv
+#[derive(Debug, Deserialize)]
+struct RawPerson {
+    hhno: f64,
+    pno: f64,
+    pagey: f64,
+}
+
 // See https://github.com/psrc/soundcast/wiki/Outputs#buffered-parcel-file-buffered_parcelsdat
 #[derive(Debug, Deserialize)]
 struct RawParcel {
@@ -290,29 +376,23 @@ pub struct OrigTrip {
     pub person: OrigPersonID,
     // (tour, false is to destination and true is back from dst, trip within half-tour)
     pub seq: (usize, bool, usize),
-    pub purpose: (Purpose, Purpose),
+    pub purpose: (TripPurpose, TripPurpose),
     pub trip_time: Duration,
     pub trip_dist: Distance,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrigPerson {
+    pub home: Option<Endpoint>,
+    pub age: usize,
+    // In 2014 dollars
+    pub household_income: Option<usize>,
+    pub household_vehicles: usize,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Endpoint {
     pub pos: LonLat,
     pub osm_building: Option<i64>,
     pub parcel_id: usize,
 }
-
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
-pub enum Purpose {
-    Home,
-    Work,
-    School,
-    Escort,
-    PersonalBusiness,
-    Shopping,
-    Meal,
-    Social,
-    Recreation,
-    Medical,
-    ParkAndRideTransfer,
-}
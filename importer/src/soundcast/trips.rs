@@ -2,9 +2,11 @@ use crate::soundcast::popdat::{Endpoint, OrigTrip, PopDat};
 use abstutil::{prettyprint_usize, MultiMap, Parallelism, Timer};
 use geom::LonLat;
 use map_model::{BuildingID, IntersectionID, Map, PathConstraints, PathRequest, PathStep};
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
 use sim::{
-    IndividTrip, OffMapLocation, OrigPersonID, PersonID, PersonSpec, Scenario, SpawnTrip,
-    TripEndpoint, TripMode,
+    AgeBand, IncomeBand, IndividTrip, OffMapLocation, OrigPersonID, PersonAttributes, PersonID,
+    PersonSpec, Scenario, SpawnTrip, TripEndpoint, TripMode,
 };
 use std::collections::HashMap;
 
@@ -221,14 +223,16 @@ fn clip_trips(map: &Map, popdat: &PopDat, huge_map: &Map, timer: &mut Timer) ->
                 map,
                 &osm_id_to_bldg,
                 match orig.mode {
-                    TripMode::Walk | TripMode::Transit => {
+                    TripMode::Walk | TripMode::Transit | TripMode::RideHail => {
                         (&incoming_borders_walking, &outgoing_borders_walking)
                     }
                     TripMode::Drive => (&incoming_borders_driving, &outgoing_borders_driving),
                     TripMode::Bike => (&incoming_borders_biking, &outgoing_borders_biking),
                 },
                 match orig.mode {
-                    TripMode::Walk | TripMode::Transit => PathConstraints::Pedestrian,
+                    TripMode::Walk | TripMode::Transit | TripMode::RideHail => {
+                        PathConstraints::Pedestrian
+                    }
                     TripMode::Drive => PathConstraints::Car,
                     TripMode::Bike => PathConstraints::Bike,
                 },
@@ -265,7 +269,7 @@ pub fn make_weekday_scenario(
     // person -> (trip seq, index into individ_trips)
     let mut trips_per_person: MultiMap<OrigPersonID, ((usize, bool, usize), usize)> =
         MultiMap::new();
-    for (trip, depart, person, seq) in timer.parallelize(
+    for (trip, depart, purpose, person, seq) in timer.parallelize(
         "turn Soundcast trips into SpawnTrips",
         Parallelism::Polite,
         trips,
@@ -273,6 +277,7 @@ pub fn make_weekday_scenario(
             (
                 SpawnTrip::new(trip.from, trip.to, trip.orig.mode, map),
                 trip.orig.depart_at,
+                trip.orig.purpose.1,
                 trip.orig.person,
                 trip.orig.seq,
             )
@@ -280,7 +285,7 @@ pub fn make_weekday_scenario(
     ) {
         if let Some(trip) = trip {
             let idx = individ_trips.len();
-            individ_trips.push(Some(IndividTrip::new(depart, trip)));
+            individ_trips.push(Some(IndividTrip::new(depart, purpose, trip)));
             trips_per_person.insert(person, (seq, idx));
         }
     }
@@ -291,6 +296,11 @@ pub fn make_weekday_scenario(
         prettyprint_usize(trips_per_person.len())
     ));
 
+    let osm_id_to_bldg: HashMap<i64, BuildingID> = map
+        .all_buildings()
+        .iter()
+        .map(|b| (b.osm_way_id, b.id))
+        .collect();
     let mut people = Vec::new();
     for (orig_id, seq_trips) in trips_per_person.consume() {
         let id = PersonID(people.len());
@@ -306,6 +316,7 @@ pub fn make_weekday_scenario(
             id,
             orig_id: Some(orig_id),
             trips,
+            attributes: import_attributes(popdat, orig_id, &osm_id_to_bldg),
         });
     }
     for maybe_t in individ_trips {
@@ -314,13 +325,19 @@ pub fn make_weekday_scenario(
         }
     }
 
-    Scenario {
+    let mut scenario = Scenario {
         scenario_name: "weekday".to_string(),
         map_name: map.get_name().to_string(),
         people,
         only_seed_buses: None,
+        parking_survey: None,
+        freight: Vec::new(),
+        ride_hail_fleet: 0,
     }
-    .remove_weird_schedules(map)
+    .remove_weird_schedules(map);
+    // Deterministic, so that re-importing produces the same demographics
+    scenario.synthesize_attributes(map, &mut XorShiftRng::from_seed([42; 16]));
+    scenario
 }
 
 pub fn make_weekday_scenario_with_everyone(
@@ -348,7 +365,11 @@ pub fn make_weekday_scenario_with_everyone(
             mode: orig_trip.mode,
         };
         let idx = individ_trips.len();
-        individ_trips.push(Some(IndividTrip::new(orig_trip.depart_at, trip)));
+        individ_trips.push(Some(IndividTrip::new(
+            orig_trip.depart_at,
+            orig_trip.purpose.1,
+            trip,
+        )));
         trips_per_person.insert(orig_trip.person, (orig_trip.seq, idx));
     }
 
@@ -358,6 +379,11 @@ pub fn make_weekday_scenario_with_everyone(
         prettyprint_usize(trips_per_person.len())
     ));
 
+    let osm_id_to_bldg: HashMap<i64, BuildingID> = map
+        .all_buildings()
+        .iter()
+        .map(|b| (b.osm_way_id, b.id))
+        .collect();
     let mut people = Vec::new();
     for (orig_id, seq_trips) in trips_per_person.consume() {
         let id = PersonID(people.len());
@@ -373,6 +399,7 @@ pub fn make_weekday_scenario_with_everyone(
             id,
             orig_id: Some(orig_id),
             trips,
+            attributes: import_attributes(popdat, orig_id, &osm_id_to_bldg),
         });
     }
     for maybe_t in individ_trips {
@@ -381,11 +408,44 @@ pub fn make_weekday_scenario_with_everyone(
         }
     }
 
-    Scenario {
+    let mut scenario = Scenario {
         scenario_name: "everyone_weekday".to_string(),
         map_name: map.get_name().to_string(),
         people,
         only_seed_buses: None,
+        parking_survey: None,
+        freight: Vec::new(),
+        ride_hail_fleet: 0,
+    }
+    .remove_weird_schedules(map);
+    // Deterministic, so that re-importing produces the same demographics
+    scenario.synthesize_attributes(map, &mut XorShiftRng::from_seed([42; 16]));
+    scenario
+}
+
+// Whatever Soundcast knows about someone. Anything missing is synthesized later.
+fn import_attributes(
+    popdat: &PopDat,
+    id: OrigPersonID,
+    osm_id_to_bldg: &HashMap<i64, BuildingID>,
+) -> PersonAttributes {
+    let person = match popdat.people.get(&id) {
+        Some(p) => p,
+        None => {
+            return PersonAttributes::default();
+        }
+    };
+    PersonAttributes {
+        home: person
+            .home
+            .as_ref()
+            .and_then(|endpt| endpt.osm_building)
+            .and_then(|id| osm_id_to_bldg.get(&id))
+            .cloned(),
+        age: Some(AgeBand::from_years(person.age)),
+        income: person.household_income.map(IncomeBand::from_dollars),
+        has_car: Some(person.household_vehicles > 0),
+        // Soundcast doesn't model this
+        mobility: None,
     }
-    .remove_weird_schedules(map)
 }
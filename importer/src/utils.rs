@@ -140,3 +140,44 @@ pub fn raw_to_map(name: &str, build_ch: bool, timer: &mut Timer) -> map_model::M
 
     map
 }
+
+// After re-importing a map, OSM IDs might've changed. Re-anchor any player edits and proposals
+// for this map, and leave a report next to the player's edits describing what happened.
+pub fn migrate_edits(map: &map_model::Map, timer: &mut Timer) {
+    timer.start(format!("migrate edits for {}", map.get_name()));
+    let mut files = Vec::new();
+    for (name, perma) in abstutil::load_all_objects::<map_model::PermanentMapEdits>(
+        abstutil::path_all_edits(map.get_name()),
+    ) {
+        files.push((abstutil::path_edits(map.get_name(), &name), perma));
+    }
+    for (name, perma) in abstutil::load_all_objects::<map_model::PermanentMapEdits>(abstutil::path(
+        "system/proposals",
+    )) {
+        if perma.map_name == map.get_name() {
+            files.push((
+                abstutil::path(format!("system/proposals/{}.json", name)),
+                perma,
+            ));
+        }
+    }
+
+    for (path, perma) in files {
+        let (edits, report) = perma.migrate(map);
+        if !report.changed_anything() {
+            continue;
+        }
+        for line in report.describe() {
+            timer.warn(format!("{}: {}", path, line));
+        }
+        abstutil::write_json(path, &edits);
+        abstutil::write_json(
+            abstutil::path(format!(
+                "player/edit_migrations/{}/{}.json",
+                report.map_name, report.edits_name
+            )),
+            &report,
+        );
+    }
+    timer.stop(format!("migrate edits for {}", map.get_name()));
+}
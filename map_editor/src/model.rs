@@ -226,6 +226,7 @@ impl Model {
                 // TODO If this isn't a synthetic map, load the elevation data and grab a real
                 // value.
                 elevation: Distance::ZERO,
+                midblock_crossing: None,
             },
         );
         self.intersection_added(id, ctx);
@@ -299,6 +300,7 @@ impl Model {
                 osm_tags,
                 turn_restrictions: Vec::new(),
                 complicated_turn_restrictions: Vec::new(),
+                elevation: Vec::new(),
             },
         );
         self.road_added(id, ctx);
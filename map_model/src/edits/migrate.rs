@@ -0,0 +1,585 @@
+use crate::edits::{
+    OriginalLane, PermanentEditCmd, PermanentEditIntersection, PermanentMapEdits,
+    PermanentParkingFacility,
+};
+use crate::raw::{OriginalIntersection, OriginalRoad};
+use crate::{osm, Map, Road};
+use geom::{Distance, LonLat, PolyLine, Pt2D};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// When OSM data is refreshed, ways get split, merged, and renumbered. Edits refer to things by OSM
+// IDs, so they stop matching. Before giving up on an edit, try to find what it used to refer to
+// by geometry, falling back to following the same OSM way.
+
+// Part of a road or intersection this far from where it used to be is considered the same thing.
+const MAX_DRIFT: Distance = Distance::const_meters(10.0);
+// A road that got split must be mostly covered by the new pieces.
+const MIN_COVERAGE: f64 = 0.5;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(super) struct Anchors {
+    roads: Vec<(OriginalRoad, RoadAnchor)>,
+    intersections: Vec<(OriginalIntersection, LonLat)>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct RoadAnchor {
+    center: Vec<LonLat>,
+    name: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MigrationReport {
+    pub map_name: String,
+    pub edits_name: String,
+    // Edits that were re-anchored to different roads or intersections
+    pub migrated: Vec<String>,
+    // Edits that refer to something that can't be found anymore
+    pub dropped: Vec<String>,
+    // Edits that might refer to several things now. These're also dropped, but a person should
+    // look at them.
+    pub ambiguous: Vec<String>,
+    // The edits before migration, so nothing is lost
+    pub original: PermanentMapEdits,
+}
+
+impl MigrationReport {
+    pub fn changed_anything(&self) -> bool {
+        !self.migrated.is_empty() || !self.dropped.is_empty() || !self.ambiguous.is_empty()
+    }
+
+    pub fn describe(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        for x in &self.migrated {
+            lines.push(format!("Migrated: {}", x));
+        }
+        for x in &self.dropped {
+            lines.push(format!("Dropped: {}", x));
+        }
+        for x in &self.ambiguous {
+            lines.push(format!("Ambiguous, dropped: {}", x));
+        }
+        lines
+    }
+}
+
+impl Anchors {
+    pub(super) fn new(commands: &Vec<PermanentEditCmd>, map: &Map) -> Anchors {
+        let mut roads = BTreeSet::new();
+        let mut intersections = BTreeSet::new();
+        for cmd in commands {
+            cmd.references(&mut roads, &mut intersections);
+        }
+
+        let gps_bounds = map.get_gps_bounds();
+        Anchors {
+            roads: roads
+                .into_iter()
+                .filter_map(|id| {
+                    let r = map.get_r(
+                        map.find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id))
+                            .ok()?,
+                    );
+                    Some((
+                        id,
+                        RoadAnchor {
+                            center: r
+                                .center_pts
+                                .points()
+                                .iter()
+                                .map(|pt| pt.to_gps(gps_bounds))
+                                .collect(),
+                            name: r.osm_tags.get(osm::NAME).cloned(),
+                        },
+                    ))
+                })
+                .collect(),
+            intersections: intersections
+                .into_iter()
+                .filter_map(|id| {
+                    let i = map.get_i(map.find_i_by_osm_id(id.osm_node_id).ok()?);
+                    Some((id, i.polygon.center().to_gps(gps_bounds)))
+                })
+                .collect(),
+        }
+    }
+}
+
+// What happened to one road or intersection
+enum Resolved<T> {
+    Same(T),
+    Migrated(T),
+    Dropped(String),
+    Ambiguous(String),
+}
+
+// A road in the current map, and whether it points the opposite way as the original.
+type Piece = (OriginalRoad, bool);
+
+struct Migrator<'a> {
+    map: &'a Map,
+    roads: BTreeMap<OriginalRoad, PolyLine>,
+    road_names: BTreeMap<OriginalRoad, Option<String>>,
+    intersections: BTreeMap<OriginalIntersection, Pt2D>,
+}
+
+impl PermanentMapEdits {
+    // Re-anchor edits that refer to roads or intersections that don't exist in this map anymore.
+    // Edits that can't be re-anchored are dropped, and everything is described in the report.
+    pub fn migrate(self, map: &Map) -> (PermanentMapEdits, MigrationReport) {
+        let mut migrator = Migrator {
+            map,
+            roads: BTreeMap::new(),
+            road_names: BTreeMap::new(),
+            intersections: BTreeMap::new(),
+        };
+        if let Some(ref anchors) = self.anchors {
+            let gps_bounds = map.get_gps_bounds();
+            for (id, anchor) in &anchors.roads {
+                let pts: Vec<Pt2D> = anchor
+                    .center
+                    .iter()
+                    .map(|pt| Pt2D::from_gps(*pt, gps_bounds))
+                    .collect();
+                if let Ok(pl) = PolyLine::deduping_new(pts) {
+                    migrator.roads.insert(*id, pl);
+                    migrator.road_names.insert(*id, anchor.name.clone());
+                }
+            }
+            for (id, pt) in &anchors.intersections {
+                migrator
+                    .intersections
+                    .insert(*id, Pt2D::from_gps(*pt, gps_bounds));
+            }
+        }
+
+        let mut report = MigrationReport {
+            map_name: map.get_name().to_string(),
+            edits_name: self.edits_name.clone(),
+            migrated: Vec::new(),
+            dropped: Vec::new(),
+            ambiguous: Vec::new(),
+            original: self.clone(),
+        };
+        let mut commands = Vec::new();
+        for cmd in self.commands {
+            let label = cmd.describe();
+            match migrator.migrate_cmd(cmd) {
+                Resolved::Same(cmds) => {
+                    commands.extend(cmds);
+                }
+                Resolved::Migrated(cmds) => {
+                    report
+                        .migrated
+                        .push(format!("{} (now {} edits)", label, cmds.len()));
+                    commands.extend(cmds);
+                }
+                Resolved::Dropped(why) => {
+                    report.dropped.push(format!("{}: {}", label, why));
+                }
+                Resolved::Ambiguous(why) => {
+                    report.ambiguous.push(format!("{}: {}", label, why));
+                }
+            }
+        }
+
+        let mut result = PermanentMapEdits {
+            map_name: map.get_name().to_string(),
+            edits_name: self.edits_name,
+            commands,
+            proposal_description: self.proposal_description,
+            proposal_link: self.proposal_link,
+            anchors: None,
+        };
+        result.anchors = Some(Anchors::new(&result.commands, map));
+        (result, report)
+    }
+}
+
+impl<'a> Migrator<'a> {
+    fn migrate_cmd(&self, cmd: PermanentEditCmd) -> Resolved<Vec<PermanentEditCmd>> {
+        match cmd {
+            PermanentEditCmd::ChangeLaneType { id, lt, orig_lt } => self.lanes(id, |id| {
+                PermanentEditCmd::ChangeLaneType { id, lt, orig_lt }
+            }),
+            PermanentEditCmd::ReverseLane { l, dst_i } => {
+                let dst_i = match self.intersection(dst_i) {
+                    Resolved::Same(i) | Resolved::Migrated(i) => i,
+                    Resolved::Dropped(why) => return Resolved::Dropped(why),
+                    Resolved::Ambiguous(why) => return Resolved::Ambiguous(why),
+                };
+                match self.lanes(l, |l| PermanentEditCmd::ReverseLane { l, dst_i }) {
+                    Resolved::Migrated(ref cmds) if cmds.len() > 1 => Resolved::Ambiguous(
+                        "the road was split, so the reversed lane can't point to one place"
+                            .to_string(),
+                    ),
+                    x => x,
+                }
+            }
+            PermanentEditCmd::ChangeSpeedLimit { id, new, old } => {
+                self.roads(id, |id| PermanentEditCmd::ChangeSpeedLimit { id, new, old })
+            }
+            PermanentEditCmd::ChangeAccessRestrictions {
+                id,
+                new_allow_through_traffic,
+                old_allow_through_traffic,
+            } => self.roads(id, |id| PermanentEditCmd::ChangeAccessRestrictions {
+                id,
+                new_allow_through_traffic,
+                old_allow_through_traffic,
+            }),
+            PermanentEditCmd::ChangeIntersection { i, new, old } => {
+                let (i, mut migrated) = match self.intersection(i) {
+                    Resolved::Same(i) => (i, false),
+                    Resolved::Migrated(i) => (i, true),
+                    Resolved::Dropped(why) => return Resolved::Dropped(why),
+                    Resolved::Ambiguous(why) => return Resolved::Ambiguous(why),
+                };
+                let mut translate = |x: PermanentEditIntersection| match x {
+                    PermanentEditIntersection::StopSign { must_stop } => {
+                        let mut translated = BTreeMap::new();
+                        for (r, stop) in must_stop {
+                            match self.road(r) {
+                                Resolved::Same(pieces) => {
+                                    translated.insert(pieces[0].0, stop);
+                                }
+                                Resolved::Migrated(pieces) => {
+                                    // Only the piece touching the intersection matters
+                                    let touching: Vec<OriginalRoad> = pieces
+                                        .into_iter()
+                                        .map(|(r, _)| r)
+                                        .filter(|r| r.i1 == i || r.i2 == i)
+                                        .collect();
+                                    if touching.len() != 1 {
+                                        return Err(Resolved::Ambiguous(format!(
+                                            "can't tell which road the stop sign for {} is on \
+                                             now",
+                                            r
+                                        )));
+                                    }
+                                    translated.insert(touching[0], stop);
+                                    migrated = true;
+                                }
+                                Resolved::Dropped(why) => return Err(Resolved::Dropped(why)),
+                                Resolved::Ambiguous(why) => {
+                                    return Err(Resolved::Ambiguous(why));
+                                }
+                            }
+                        }
+                        Ok(PermanentEditIntersection::StopSign {
+                            must_stop: translated,
+                        })
+                    }
+                    x => Ok(x),
+                };
+                let new = match translate(new) {
+                    Ok(x) => x,
+                    Err(x) => return x,
+                };
+                let old = match translate(old) {
+                    Ok(x) => x,
+                    Err(x) => return x,
+                };
+                let cmds = vec![PermanentEditCmd::ChangeIntersection { i, new, old }];
+                if migrated {
+                    Resolved::Migrated(cmds)
+                } else {
+                    Resolved::Same(cmds)
+                }
+            }
+            // Bus routes and parking lots don't have anything to fall back on.
+            PermanentEditCmd::ChangeRouteSchedule {
+                osm_rel_id,
+                old,
+                new,
+            } => Resolved::Same(vec![PermanentEditCmd::ChangeRouteSchedule {
+                osm_rel_id,
+                old,
+                new,
+            }]),
+            PermanentEditCmd::ChangeParkingRules {
+                id: PermanentParkingFacility::Lot(osm_id),
+                new,
+                old,
+            } => Resolved::Same(vec![PermanentEditCmd::ChangeParkingRules {
+                id: PermanentParkingFacility::Lot(osm_id),
+                new,
+                old,
+            }]),
+            PermanentEditCmd::ChangeParkingRules {
+                id: PermanentParkingFacility::Onstreet(l),
+                new,
+                old,
+            } => self.lanes(l, |l| PermanentEditCmd::ChangeParkingRules {
+                id: PermanentParkingFacility::Onstreet(l),
+                new,
+                old,
+            }),
+            PermanentEditCmd::ChangeCrossing { i, new, old } => match self.intersection(i) {
+                Resolved::Same(i) => {
+                    Resolved::Same(vec![PermanentEditCmd::ChangeCrossing { i, new, old }])
+                }
+                Resolved::Migrated(i) => {
+                    Resolved::Migrated(vec![PermanentEditCmd::ChangeCrossing { i, new, old }])
+                }
+                Resolved::Dropped(why) => Resolved::Dropped(why),
+                Resolved::Ambiguous(why) => Resolved::Ambiguous(why),
+            },
+        }
+    }
+
+    // Produces one command per piece of the road
+    fn roads<F: Fn(OriginalRoad) -> PermanentEditCmd>(
+        &self,
+        id: OriginalRoad,
+        make_cmd: F,
+    ) -> Resolved<Vec<PermanentEditCmd>> {
+        match self.road(id) {
+            Resolved::Same(pieces) => {
+                Resolved::Same(pieces.into_iter().map(|(r, _)| make_cmd(r)).collect())
+            }
+            Resolved::Migrated(pieces) => {
+                Resolved::Migrated(pieces.into_iter().map(|(r, _)| make_cmd(r)).collect())
+            }
+            Resolved::Dropped(why) => Resolved::Dropped(why),
+            Resolved::Ambiguous(why) => Resolved::Ambiguous(why),
+        }
+    }
+
+    // Produces one command per piece of the road, as long as the lanes still line up
+    fn lanes<F: Fn(OriginalLane) -> PermanentEditCmd>(
+        &self,
+        id: OriginalLane,
+        make_cmd: F,
+    ) -> Resolved<Vec<PermanentEditCmd>> {
+        let (pieces, migrated) = match self.road(id.parent) {
+            Resolved::Same(pieces) => (pieces, false),
+            Resolved::Migrated(pieces) => (pieces, true),
+            Resolved::Dropped(why) => return Resolved::Dropped(why),
+            Resolved::Ambiguous(why) => return Resolved::Ambiguous(why),
+        };
+        let mut cmds = Vec::new();
+        for (r, reversed) in pieces {
+            let (num_fwd, num_back, fwd) = if reversed {
+                (id.num_back, id.num_fwd, !id.fwd)
+            } else {
+                (id.num_fwd, id.num_back, id.fwd)
+            };
+            if migrated {
+                let road = self.find_road(r);
+                if road.children_forwards.len() != num_fwd
+                    || road.children_backwards.len() != num_back
+                {
+                    return Resolved::Dropped(format!("number of lanes has changed on {}", r));
+                }
+            }
+            cmds.push(make_cmd(OriginalLane {
+                parent: r,
+                num_fwd,
+                num_back,
+                fwd,
+                idx: id.idx,
+            }));
+        }
+        if migrated {
+            Resolved::Migrated(cmds)
+        } else {
+            Resolved::Same(cmds)
+        }
+    }
+
+    fn find_road(&self, id: OriginalRoad) -> &'a Road {
+        self.map.get_r(
+            self.map
+                .find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id))
+                .unwrap(),
+        )
+    }
+
+    fn road(&self, id: OriginalRoad) -> Resolved<Vec<Piece>> {
+        if self
+            .map
+            .find_r_by_osm_id(id.osm_way_id, (id.i1.osm_node_id, id.i2.osm_node_id))
+            .is_ok()
+        {
+            return Resolved::Same(vec![(id, false)]);
+        }
+        if let Some(pl) = self.roads.get(&id) {
+            return self.road_by_geometry(pl, &self.road_names[&id]);
+        }
+        self.road_by_way(id)
+    }
+
+    // Find all roads lying along the old one
+    fn road_by_geometry(&self, old: &PolyLine, name: &Option<String>) -> Resolved<Vec<Piece>> {
+        let mut pieces = Vec::new();
+        let mut covered = Distance::ZERO;
+        for r in self.map.all_roads() {
+            if name.is_some() && r.osm_tags.get(osm::NAME) != name.as_ref() {
+                continue;
+            }
+            let pts = r.center_pts.points();
+            if pts.iter().all(|pt| dist_to_polyline(old, *pt) <= MAX_DRIFT) {
+                let reversed = old.first_pt().dist_to(r.center_pts.first_pt())
+                    > old.first_pt().dist_to(r.center_pts.last_pt());
+                pieces.push((r.orig_id, reversed));
+                covered += r.center_pts.length();
+            }
+        }
+        if pieces.is_empty() {
+            return Resolved::Dropped("no road is there anymore".to_string());
+        }
+        if covered < MIN_COVERAGE * old.length() {
+            return Resolved::Ambiguous(format!(
+                "only {} of {} still matches",
+                covered,
+                old.length()
+            ));
+        }
+        Resolved::Migrated(pieces)
+    }
+
+    // Without the old geometry, the best we can do is follow the same OSM way from one of the old
+    // endpoints to the other, in case it was split.
+    fn road_by_way(&self, id: OriginalRoad) -> Resolved<Vec<Piece>> {
+        let candidates: Vec<OriginalRoad> = self
+            .map
+            .all_roads()
+            .iter()
+            .map(|r| r.orig_id)
+            .filter(|r| r.osm_way_id == id.osm_way_id)
+            .collect();
+        if candidates.is_empty() {
+            return Resolved::Dropped(format!("{} isn't in the map anymore", id));
+        }
+
+        let mut pieces = Vec::new();
+        let mut at = id.i1;
+        let mut used = BTreeSet::new();
+        while at != id.i2 {
+            let next = candidates
+                .iter()
+                .find(|r| !used.contains(*r) && (r.i1 == at || r.i2 == at));
+            match next {
+                Some(r) => {
+                    used.insert(*r);
+                    if r.i1 == at {
+                        pieces.push((*r, false));
+                        at = r.i2;
+                    } else {
+                        pieces.push((*r, true));
+                        at = r.i1;
+                    }
+                }
+                None => {
+                    return Resolved::Ambiguous(format!(
+                        "{} still has {} roads, but they don't connect the old endpoints",
+                        id,
+                        candidates.len()
+                    ));
+                }
+            }
+        }
+        Resolved::Migrated(pieces)
+    }
+
+    fn intersection(&self, id: OriginalIntersection) -> Resolved<OriginalIntersection> {
+        if self.map.find_i_by_osm_id(id.osm_node_id).is_ok() {
+            return Resolved::Same(id);
+        }
+        let pt = match self.intersections.get(&id) {
+            Some(pt) => *pt,
+            None => {
+                return Resolved::Dropped(format!("{} isn't in the map anymore", id));
+            }
+        };
+        let nearby: Vec<OriginalIntersection> = self
+            .map
+            .all_intersections()
+            .iter()
+            .filter(|i| i.polygon.center().dist_to(pt) <= MAX_DRIFT)
+            .map(|i| i.orig_id)
+            .collect();
+        match nearby.len() {
+            0 => Resolved::Dropped(format!("no intersection is near {} anymore", id)),
+            1 => Resolved::Migrated(nearby[0]),
+            n => Resolved::Ambiguous(format!("{} intersections are near {}", n, id)),
+        }
+    }
+}
+
+impl PermanentEditCmd {
+    fn references(
+        &self,
+        roads: &mut BTreeSet<OriginalRoad>,
+        intersections: &mut BTreeSet<OriginalIntersection>,
+    ) {
+        match self {
+            PermanentEditCmd::ChangeLaneType { id, .. } => {
+                roads.insert(id.parent);
+            }
+            PermanentEditCmd::ReverseLane { l, dst_i } => {
+                roads.insert(l.parent);
+                intersections.insert(*dst_i);
+            }
+            PermanentEditCmd::ChangeSpeedLimit { id, .. }
+            | PermanentEditCmd::ChangeAccessRestrictions { id, .. } => {
+                roads.insert(*id);
+            }
+            PermanentEditCmd::ChangeIntersection { i, new, old } => {
+                intersections.insert(*i);
+                for x in vec![new, old] {
+                    if let PermanentEditIntersection::StopSign { must_stop } = x {
+                        roads.extend(must_stop.keys().cloned());
+                    }
+                }
+            }
+            PermanentEditCmd::ChangeRouteSchedule { .. } => {}
+            PermanentEditCmd::ChangeParkingRules { id, .. } => {
+                if let PermanentParkingFacility::Onstreet(l) = id {
+                    roads.insert(l.parent);
+                }
+            }
+            PermanentEditCmd::ChangeCrossing { i, .. } => {
+                intersections.insert(*i);
+            }
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            PermanentEditCmd::ChangeLaneType { id, lt, .. } => {
+                format!("change lane to {:?} on {}", lt, id.parent)
+            }
+            PermanentEditCmd::ReverseLane { l, .. } => format!("reverse lane on {}", l.parent),
+            PermanentEditCmd::ChangeSpeedLimit { id, new, .. } => {
+                format!("change speed limit to {} on {}", new, id)
+            }
+            PermanentEditCmd::ChangeAccessRestrictions { id, .. } => {
+                format!("change access restrictions on {}", id)
+            }
+            PermanentEditCmd::ChangeIntersection { i, .. } => format!("change {}", i),
+            PermanentEditCmd::ChangeRouteSchedule { osm_rel_id, .. } => {
+                format!("reschedule route {}", osm_rel_id)
+            }
+            PermanentEditCmd::ChangeParkingRules { id, .. } => match id {
+                PermanentParkingFacility::Onstreet(l) => {
+                    format!("change parking rules on {}", l.parent)
+                }
+                PermanentParkingFacility::Lot(osm_id) => {
+                    format!("change parking rules for lot {}", osm_id)
+                }
+            },
+            PermanentEditCmd::ChangeCrossing { i, .. } => format!("change crossing at {}", i),
+        }
+    }
+}
+
+fn dist_to_polyline(pl: &PolyLine, pt: Pt2D) -> Distance {
+    pl.lines()
+        .into_iter()
+        .map(|l| pt.dist_to(l.project_pt(pt)))
+        .min()
+        .unwrap()
+}
@@ -1,7 +1,14 @@
+mod migrate;
+mod upstream;
+
+use self::migrate::Anchors;
+pub use self::migrate::MigrationReport;
+pub use self::upstream::OsmTagChanges;
 use crate::raw::{OriginalIntersection, OriginalRoad};
 use crate::{
-    connectivity, BusRouteID, ControlStopSign, ControlTrafficSignal, IntersectionID,
-    IntersectionType, LaneID, LaneType, Map, PathConstraints, RoadID, TurnID, Zone,
+    connectivity, BusRouteID, ControlStopSign, ControlTrafficSignal, Crossing, IntersectionID,
+    IntersectionType, LaneID, LaneType, Map, ParkingFacility, ParkingRules, PathConstraints,
+    RoadID, TurnID, Zone,
 };
 use abstutil::{deserialize_btreemap, retain_btreemap, retain_btreeset, serialize_btreemap, Timer};
 use enumset::EnumSet;
@@ -21,6 +28,8 @@ pub struct MapEdits {
     pub changed_speed_limits: BTreeSet<RoadID>,
     pub changed_access_restrictions: BTreeSet<RoadID>,
     pub changed_routes: BTreeSet<BusRouteID>,
+    pub changed_parking: BTreeSet<ParkingFacility>,
+    pub original_crossings: BTreeMap<IntersectionID, Option<Crossing>>,
 
     // Edits without these are player generated.
     pub proposal_description: Vec<String>,
@@ -70,6 +79,17 @@ pub enum EditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeParkingRules {
+        id: ParkingFacility,
+        new: ParkingRules,
+        old: ParkingRules,
+    },
+    // Only for degenerate intersections
+    ChangeCrossing {
+        i: IntersectionID,
+        new: Option<Crossing>,
+        old: Option<Crossing>,
+    },
 }
 
 pub struct EditEffects {
@@ -94,6 +114,8 @@ impl MapEdits {
             changed_speed_limits: BTreeSet::new(),
             changed_access_restrictions: BTreeSet::new(),
             changed_routes: BTreeSet::new(),
+            changed_parking: BTreeSet::new(),
+            original_crossings: BTreeMap::new(),
         }
     }
 
@@ -101,10 +123,26 @@ impl MapEdits {
         if edits_name == "untitled edits" {
             return Ok(MapEdits::new());
         }
-        PermanentMapEdits::from_permanent(
-            abstutil::read_json(abstutil::path_edits(map.get_name(), edits_name), timer),
-            map,
-        )
+        let perma: PermanentMapEdits =
+            abstutil::read_json(abstutil::path_edits(map.get_name(), edits_name), timer);
+        match PermanentMapEdits::from_permanent(perma.clone(), map) {
+            Ok(edits) => Ok(edits),
+            Err(err) => {
+                // The map was probably re-imported since the edits were made. Try to salvage
+                // what we can.
+                timer.warn(format!(
+                    "{} don't match {} anymore ({}), migrating them",
+                    edits_name,
+                    map.get_name(),
+                    err
+                ));
+                let (migrated, report) = perma.migrate(map);
+                for line in report.describe() {
+                    timer.warn(line);
+                }
+                PermanentMapEdits::from_permanent(migrated, map)
+            }
+        }
     }
 
     // TODO Version these? Or it's unnecessary, since we have a command stack.
@@ -124,6 +162,8 @@ impl MapEdits {
         let mut changed_speed_limits = BTreeSet::new();
         let mut changed_access_restrictions = BTreeSet::new();
         let mut changed_routes = BTreeSet::new();
+        let mut changed_parking = BTreeSet::new();
+        let mut orig_crossings = BTreeMap::new();
 
         for cmd in &self.commands {
             match cmd {
@@ -153,6 +193,14 @@ impl MapEdits {
                 EditCmd::ChangeRouteSchedule { id, .. } => {
                     changed_routes.insert(*id);
                 }
+                EditCmd::ChangeParkingRules { id, .. } => {
+                    changed_parking.insert(*id);
+                }
+                EditCmd::ChangeCrossing { i, old, .. } => {
+                    if !orig_crossings.contains_key(i) {
+                        orig_crossings.insert(*i, *old);
+                    }
+                }
             }
         }
 
@@ -171,6 +219,12 @@ impl MapEdits {
             let r = map.get_br(*br);
             r.spawn_times != r.orig_spawn_times
         });
+        retain_btreeset(&mut changed_parking, |id| {
+            map.get_parking_rules(*id) != ParkingRules::free()
+        });
+        retain_btreemap(&mut orig_crossings, |i, orig| {
+            map.get_i(*i).midblock_crossing != *orig
+        });
 
         self.original_lts = orig_lts;
         self.reversed_lanes = reversed_lanes;
@@ -178,6 +232,8 @@ impl MapEdits {
         self.changed_speed_limits = changed_speed_limits;
         self.changed_access_restrictions = changed_access_restrictions;
         self.changed_routes = changed_routes;
+        self.changed_parking = changed_parking;
+        self.original_crossings = orig_crossings;
     }
 
     // Assumes update_derived has been called.
@@ -224,6 +280,20 @@ impl MapEdits {
                 old: r.orig_spawn_times.clone(),
             });
         }
+        for id in &self.changed_parking {
+            self.commands.push(EditCmd::ChangeParkingRules {
+                id: *id,
+                new: map.get_parking_rules(*id),
+                old: ParkingRules::free(),
+            });
+        }
+        for (i, old) in &self.original_crossings {
+            self.commands.push(EditCmd::ChangeCrossing {
+                i: *i,
+                new: map.get_i(*i).midblock_crossing,
+                old: *old,
+            });
+        }
     }
 }
 
@@ -257,6 +327,10 @@ pub struct PermanentMapEdits {
     pub proposal_description: Vec<String>,
     // The link is optional even for proposals
     pub proposal_link: Option<String>,
+
+    // Where the roads and intersections referenced above were, so the edits can be migrated when
+    // OSM IDs change. Older edits don't have this.
+    anchors: Option<Anchors>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -314,62 +388,96 @@ enum PermanentEditCmd {
         old: Vec<Time>,
         new: Vec<Time>,
     },
+    ChangeParkingRules {
+        id: PermanentParkingFacility,
+        new: ParkingRules,
+        old: ParkingRules,
+    },
+    ChangeCrossing {
+        i: OriginalIntersection,
+        new: Option<Crossing>,
+        old: Option<Crossing>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+enum PermanentParkingFacility {
+    Onstreet(OriginalLane),
+    // The OSM way ID
+    Lot(i64),
 }
 
 impl PermanentMapEdits {
     pub fn to_permanent(edits: &MapEdits, map: &Map) -> PermanentMapEdits {
+        let commands: Vec<PermanentEditCmd> = edits
+            .commands
+            .iter()
+            .map(|cmd| match cmd {
+                EditCmd::ChangeLaneType { id, lt, orig_lt } => PermanentEditCmd::ChangeLaneType {
+                    id: OriginalLane::to_permanent(*id, map),
+                    lt: *lt,
+                    orig_lt: *orig_lt,
+                },
+                EditCmd::ReverseLane { l, dst_i } => PermanentEditCmd::ReverseLane {
+                    l: OriginalLane::to_permanent(*l, map),
+                    dst_i: map.get_i(*dst_i).orig_id,
+                },
+                EditCmd::ChangeSpeedLimit { id, new, old } => PermanentEditCmd::ChangeSpeedLimit {
+                    id: map.get_r(*id).orig_id,
+                    new: *new,
+                    old: *old,
+                },
+                EditCmd::ChangeIntersection { i, new, old } => {
+                    PermanentEditCmd::ChangeIntersection {
+                        i: map.get_i(*i).orig_id,
+                        new: new.to_permanent(map),
+                        old: old.to_permanent(map),
+                    }
+                }
+                EditCmd::ChangeAccessRestrictions {
+                    id,
+                    new_allow_through_traffic,
+                    old_allow_through_traffic,
+                } => PermanentEditCmd::ChangeAccessRestrictions {
+                    id: map.get_r(*id).orig_id,
+                    new_allow_through_traffic: *new_allow_through_traffic,
+                    old_allow_through_traffic: *old_allow_through_traffic,
+                },
+                EditCmd::ChangeRouteSchedule { id, old, new } => {
+                    PermanentEditCmd::ChangeRouteSchedule {
+                        osm_rel_id: map.get_br(*id).osm_rel_id,
+                        old: old.clone(),
+                        new: new.clone(),
+                    }
+                }
+                EditCmd::ChangeParkingRules { id, new, old } => {
+                    PermanentEditCmd::ChangeParkingRules {
+                        id: match id {
+                            ParkingFacility::Onstreet(l) => PermanentParkingFacility::Onstreet(
+                                OriginalLane::to_permanent(*l, map),
+                            ),
+                            ParkingFacility::Lot(pl) => {
+                                PermanentParkingFacility::Lot(map.get_pl(*pl).osm_id)
+                            }
+                        },
+                        new: *new,
+                        old: *old,
+                    }
+                }
+                EditCmd::ChangeCrossing { i, new, old } => PermanentEditCmd::ChangeCrossing {
+                    i: map.get_i(*i).orig_id,
+                    new: *new,
+                    old: *old,
+                },
+            })
+            .collect();
         PermanentMapEdits {
             map_name: map.get_name().to_string(),
             edits_name: edits.edits_name.clone(),
             proposal_description: edits.proposal_description.clone(),
             proposal_link: edits.proposal_link.clone(),
-            commands: edits
-                .commands
-                .iter()
-                .map(|cmd| match cmd {
-                    EditCmd::ChangeLaneType { id, lt, orig_lt } => {
-                        PermanentEditCmd::ChangeLaneType {
-                            id: OriginalLane::to_permanent(*id, map),
-                            lt: *lt,
-                            orig_lt: *orig_lt,
-                        }
-                    }
-                    EditCmd::ReverseLane { l, dst_i } => PermanentEditCmd::ReverseLane {
-                        l: OriginalLane::to_permanent(*l, map),
-                        dst_i: map.get_i(*dst_i).orig_id,
-                    },
-                    EditCmd::ChangeSpeedLimit { id, new, old } => {
-                        PermanentEditCmd::ChangeSpeedLimit {
-                            id: map.get_r(*id).orig_id,
-                            new: *new,
-                            old: *old,
-                        }
-                    }
-                    EditCmd::ChangeIntersection { i, new, old } => {
-                        PermanentEditCmd::ChangeIntersection {
-                            i: map.get_i(*i).orig_id,
-                            new: new.to_permanent(map),
-                            old: old.to_permanent(map),
-                        }
-                    }
-                    EditCmd::ChangeAccessRestrictions {
-                        id,
-                        new_allow_through_traffic,
-                        old_allow_through_traffic,
-                    } => PermanentEditCmd::ChangeAccessRestrictions {
-                        id: map.get_r(*id).orig_id,
-                        new_allow_through_traffic: *new_allow_through_traffic,
-                        old_allow_through_traffic: *old_allow_through_traffic,
-                    },
-                    EditCmd::ChangeRouteSchedule { id, old, new } => {
-                        PermanentEditCmd::ChangeRouteSchedule {
-                            osm_rel_id: map.get_br(*id).osm_rel_id,
-                            old: old.clone(),
-                            new: new.clone(),
-                        }
-                    }
-                })
-                .collect(),
+            anchors: Some(Anchors::new(&commands, map)),
+            commands,
         }
     }
 
@@ -445,6 +553,27 @@ impl PermanentMapEdits {
                         ))?;
                         Ok(EditCmd::ChangeRouteSchedule { id, old, new })
                     }
+                    PermanentEditCmd::ChangeParkingRules { id, new, old } => {
+                        let id = match id {
+                            PermanentParkingFacility::Onstreet(l) => {
+                                ParkingFacility::Onstreet(l.from_permanent(map)?)
+                            }
+                            PermanentParkingFacility::Lot(osm_id) => ParkingFacility::Lot(
+                                map.find_pl_by_osm_id(osm_id).ok_or(format!(
+                                    "can't find parking lot https://www.openstreetmap.org/way/{}",
+                                    osm_id
+                                ))?,
+                            ),
+                        };
+                        Ok(EditCmd::ChangeParkingRules { id, new, old })
+                    }
+                    PermanentEditCmd::ChangeCrossing { i, new, old } => {
+                        let id = map.find_i_by_osm_id(i.osm_node_id)?;
+                        if !map.get_i(id).is_degenerate() {
+                            return Err(format!("{} isn't degenerate anymore", i));
+                        }
+                        Ok(EditCmd::ChangeCrossing { i: id, new, old })
+                    }
                 })
                 .collect::<Result<Vec<EditCmd>, String>>()?,
 
@@ -454,6 +583,8 @@ impl PermanentMapEdits {
             changed_speed_limits: BTreeSet::new(),
             changed_access_restrictions: BTreeSet::new(),
             changed_routes: BTreeSet::new(),
+            changed_parking: BTreeSet::new(),
+            original_crossings: BTreeMap::new(),
         };
         edits.update_derived(map);
         Ok(edits)
@@ -559,6 +690,14 @@ impl EditCmd {
             EditCmd::ChangeRouteSchedule { id, .. } => {
                 format!("reschedule route {}", map.get_br(*id).short_name)
             }
+            EditCmd::ChangeParkingRules { id, new, .. } => {
+                format!("{} for {}", new.describe(), id)
+            }
+            EditCmd::ChangeCrossing { i, new, .. } => match new {
+                Some(Crossing::Marked) => format!("marked crossing at {}", i),
+                Some(Crossing::Unmarked) => format!("unmarked crossing at {}", i),
+                None => format!("remove crossing at {}", i),
+            },
         }
     }
 
@@ -687,6 +826,28 @@ impl EditCmd {
                 map.bus_routes[id.0].spawn_times = new.clone();
                 true
             }
+            EditCmd::ChangeParkingRules { id, new, .. } => {
+                // Nothing else needs to be recalculated; the simulation looks up the current rules
+                // whenever somebody parks.
+                let rules = match id {
+                    ParkingFacility::Onstreet(l) => &mut map.lanes[l.0].parking_rules,
+                    ParkingFacility::Lot(pl) => &mut map.parking_lots[pl.0].parking_rules,
+                };
+                if *rules == *new {
+                    return false;
+                }
+                *rules = *new;
+                true
+            }
+            EditCmd::ChangeCrossing { i, new, .. } => {
+                if map.intersections[i.0].midblock_crossing == *new {
+                    return false;
+                }
+                map.intersections[i.0].midblock_crossing = *new;
+                effects.changed_intersections.insert(*i);
+                recalculate_turns(*i, map, effects, timer);
+                true
+            }
         }
     }
 
@@ -747,6 +908,18 @@ impl EditCmd {
                 new: old.clone(),
             }
             .apply(effects, map, timer),
+            EditCmd::ChangeParkingRules { id, old, new } => EditCmd::ChangeParkingRules {
+                id: *id,
+                old: *new,
+                new: *old,
+            }
+            .apply(effects, map, timer),
+            EditCmd::ChangeCrossing { i, old, new } => EditCmd::ChangeCrossing {
+                i: *i,
+                old: *new,
+                new: *old,
+            }
+            .apply(effects, map, timer),
         }
     }
 }
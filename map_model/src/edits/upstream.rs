@@ -0,0 +1,214 @@
+use crate::raw::DrivingSide;
+use crate::{osm, LaneType, Map, MapEdits, Road, RoadID};
+use std::collections::{BTreeMap, BTreeSet};
+
+// For each OSM way, the tags to set (or remove, if None)
+pub type OsmTagChanges = BTreeMap<i64, BTreeMap<String, Option<String>>>;
+
+// Each group is rewritten together when anything in it changes, so stale left/right/both
+// variants don't linger.
+const LANE_KEYS: [&str; 3] = ["lanes", "lanes:forward", "lanes:backward"];
+const PARKING_KEYS: [&str; 3] = [osm::PARKING_BOTH, osm::PARKING_LEFT, osm::PARKING_RIGHT];
+const CYCLEWAY_KEYS: [&str; 4] = [
+    "cycleway",
+    "cycleway:both",
+    "cycleway:left",
+    "cycleway:right",
+];
+
+impl MapEdits {
+    // Many edits really fix bad OSM data. Express lane and speed limit changes as OSM tags, so they
+    // can be reviewed and uploaded. OSM ways are split into many roads here; if the pieces of one
+    // way now disagree, the way has to be split by hand first, so it's skipped with a warning.
+    pub fn osm_tag_changes(&self, map: &Map) -> (OsmTagChanges, Vec<String>) {
+        let mut edited_ways = BTreeSet::new();
+        for l in self.original_lts.keys().chain(self.reversed_lanes.iter()) {
+            edited_ways.insert(map.get_parent(*l).orig_id.osm_way_id);
+        }
+        for r in &self.changed_speed_limits {
+            edited_ways.insert(map.get_r(*r).orig_id.osm_way_id);
+        }
+
+        let mut pieces: BTreeMap<i64, Vec<RoadID>> = BTreeMap::new();
+        for r in map.all_roads() {
+            if edited_ways.contains(&r.orig_id.osm_way_id) {
+                pieces
+                    .entry(r.orig_id.osm_way_id)
+                    .or_insert_with(Vec::new)
+                    .push(r.id);
+            }
+        }
+
+        let mut changes = BTreeMap::new();
+        let mut warnings = Vec::new();
+        for (way, roads) in pieces {
+            let mut per_piece: Vec<BTreeMap<String, Option<String>>> = roads
+                .iter()
+                .map(|r| self.road_tag_changes(map.get_r(*r), map))
+                .collect();
+            per_piece.sort();
+            per_piece.dedup();
+            if per_piece.len() > 1 {
+                warnings.push(format!(
+                    "https://www.openstreetmap.org/way/{} was edited differently along its \
+                     length; split it manually",
+                    way
+                ));
+                continue;
+            }
+            let tags = per_piece.pop().unwrap();
+            if !tags.is_empty() {
+                changes.insert(way, tags);
+            }
+        }
+        (changes, warnings)
+    }
+
+    fn road_tag_changes(&self, r: &Road, map: &Map) -> BTreeMap<String, Option<String>> {
+        // Reconstruct the lanes before any edits
+        let mut orig_fwd = Vec::new();
+        let mut orig_back = Vec::new();
+        for (fwds, children) in vec![(true, &r.children_forwards), (false, &r.children_backwards)] {
+            for (l, lt) in children {
+                let lt = self.original_lts.get(l).cloned().unwrap_or(*lt);
+                if fwds != self.reversed_lanes.contains(l) {
+                    orig_fwd.push(lt);
+                } else {
+                    orig_back.push(lt);
+                }
+            }
+        }
+        let fwd: Vec<LaneType> = r.children_forwards.iter().map(|(_, lt)| *lt).collect();
+        let back: Vec<LaneType> = r.children_backwards.iter().map(|(_, lt)| *lt).collect();
+        // Roads point the same way as their OSM way. Forwards is on the right when driving on the
+        // right.
+        let right_is_fwd = map.config.driving_side == DrivingSide::Right;
+
+        let mut changes = BTreeMap::new();
+        for (keys, before, after) in vec![
+            (
+                &LANE_KEYS[..],
+                lane_tags(&orig_fwd, &orig_back),
+                lane_tags(&fwd, &back),
+            ),
+            (
+                &PARKING_KEYS[..],
+                side_tags(
+                    "parking:lane",
+                    &orig_fwd,
+                    &orig_back,
+                    right_is_fwd,
+                    LaneType::Parking,
+                    "parallel",
+                    "no_parking",
+                ),
+                side_tags(
+                    "parking:lane",
+                    &fwd,
+                    &back,
+                    right_is_fwd,
+                    LaneType::Parking,
+                    "parallel",
+                    "no_parking",
+                ),
+            ),
+            (
+                &CYCLEWAY_KEYS[..],
+                side_tags(
+                    "cycleway",
+                    &orig_fwd,
+                    &orig_back,
+                    right_is_fwd,
+                    LaneType::Biking,
+                    "lane",
+                    "no",
+                ),
+                side_tags(
+                    "cycleway",
+                    &fwd,
+                    &back,
+                    right_is_fwd,
+                    LaneType::Biking,
+                    "lane",
+                    "no",
+                ),
+            ),
+        ] {
+            if before == after {
+                continue;
+            }
+            for key in keys {
+                changes.insert(key.to_string(), after.get(*key).cloned());
+            }
+        }
+
+        if self.changed_speed_limits.contains(&r.id) {
+            // Keep whatever units were used before. OSM assumes km/h without a unit.
+            let speed = if r
+                .osm_tags
+                .get(osm::MAXSPEED)
+                .map(|x| x.ends_with("mph"))
+                .unwrap_or(false)
+            {
+                r.speed_limit.to_string()
+            } else {
+                format!(
+                    "{}",
+                    (r.speed_limit.inner_meters_per_second() * 3.6).round()
+                )
+            };
+            if r.osm_tags.get(osm::MAXSPEED) != Some(&speed) {
+                changes.insert(osm::MAXSPEED.to_string(), Some(speed));
+            }
+        }
+
+        changes
+    }
+}
+
+fn lane_tags(fwd: &Vec<LaneType>, back: &Vec<LaneType>) -> BTreeMap<String, String> {
+    // OSM counts general purpose, bus, and center turn lanes
+    let count = |lts: &Vec<LaneType>| {
+        lts.iter()
+            .filter(|lt| {
+                **lt == LaneType::Driving
+                    || **lt == LaneType::Bus
+                    || **lt == LaneType::SharedLeftTurn
+            })
+            .count()
+    };
+    let mut tags = BTreeMap::new();
+    let (num_fwd, num_back) = (count(fwd), count(back));
+    tags.insert("lanes".to_string(), (num_fwd + num_back).to_string());
+    if num_fwd > 0 && num_back > 0 {
+        tags.insert("lanes:forward".to_string(), num_fwd.to_string());
+        tags.insert("lanes:backward".to_string(), num_back.to_string());
+    }
+    tags
+}
+
+// Describe which sides of the road have a certain lane type, like parking:lane:right=parallel
+fn side_tags(
+    prefix: &str,
+    fwd: &Vec<LaneType>,
+    back: &Vec<LaneType>,
+    right_is_fwd: bool,
+    lt: LaneType,
+    yes: &str,
+    no: &str,
+) -> BTreeMap<String, String> {
+    let value = |lts: &Vec<LaneType>| if lts.contains(&lt) { yes } else { no };
+    let (right, left) = if right_is_fwd {
+        (value(fwd), value(back))
+    } else {
+        (value(back), value(fwd))
+    };
+    let mut tags = BTreeMap::new();
+    if left == right {
+        tags.insert(format!("{}:both", prefix), right.to_string());
+    } else {
+        tags.insert(format!("{}:left", prefix), left.to_string());
+        tags.insert(format!("{}:right", prefix), right.to_string());
+    }
+    tags
+}
@@ -11,17 +11,19 @@ mod traversable;
 
 pub use crate::city::City;
 pub use crate::edits::{
-    EditCmd, EditEffects, EditIntersection, MapEdits, OriginalLane, PermanentMapEdits,
+    EditCmd, EditEffects, EditIntersection, MapEdits, MigrationReport, OriginalLane, OsmTagChanges,
+    PermanentMapEdits,
 };
 pub use crate::map::MapConfig;
 pub use crate::objects::area::{Area, AreaID, AreaType};
+pub use crate::objects::bike_share::{BikeShareDock, BikeShareDockID};
 pub use crate::objects::building::{Building, BuildingID, BuildingType, OffstreetParking};
 pub use crate::objects::bus_stop::{BusRoute, BusRouteID, BusStop, BusStopID};
-pub use crate::objects::intersection::{Intersection, IntersectionID, IntersectionType};
+pub use crate::objects::intersection::{Crossing, Intersection, IntersectionID, IntersectionType};
 pub use crate::objects::lane::{
     Lane, LaneID, LaneType, PARKING_LOT_SPOT_LENGTH, PARKING_SPOT_LENGTH,
 };
-pub use crate::objects::parking_lot::{ParkingLot, ParkingLotID};
+pub use crate::objects::parking_lot::{ParkingFacility, ParkingLot, ParkingLotID, ParkingRules};
 pub use crate::objects::road::{DirectedRoadID, Road, RoadID};
 pub use crate::objects::stop_signs::{ControlStopSign, RoadWithStopSign};
 pub use crate::objects::traffic_signals::{ControlTrafficSignal, Phase, PhaseType};
@@ -29,7 +31,9 @@ pub use crate::objects::turn::{Turn, TurnGroup, TurnGroupID, TurnID, TurnPriorit
 pub use crate::objects::zone::Zone;
 pub use crate::pathfind::uber_turns::{IntersectionCluster, UberTurn, UberTurnGroup};
 use crate::pathfind::Pathfinder;
-pub use crate::pathfind::{Path, PathConstraints, PathRequest, PathStep};
+pub use crate::pathfind::{
+    biking_speed_on_grade, walking_speed_on_grade, Path, PathConstraints, PathRequest, PathStep,
+};
 pub use crate::traversable::{Position, Traversable};
 use abstutil::Cloneable;
 use abstutil::{deserialize_btreemap, serialize_btreemap};
@@ -69,6 +73,7 @@ pub struct Map {
     bus_routes: Vec<BusRoute>,
     areas: Vec<Area>,
     parking_lots: Vec<ParkingLot>,
+    bike_share_docks: Vec<BikeShareDock>,
     boundary_polygon: Polygon,
 
     // Note that border nodes belong in neither!
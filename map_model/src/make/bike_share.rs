@@ -0,0 +1,53 @@
+use crate::make::match_points_to_lanes;
+use crate::raw::RawBikeShareDock;
+use crate::{BikeShareDock, BikeShareDockID, Map};
+use abstutil::Timer;
+use geom::{Distance, HashablePt2D};
+use std::collections::HashSet;
+
+pub fn make_all_bike_share_docks(
+    input: &Vec<RawBikeShareDock>,
+    map: &Map,
+    timer: &mut Timer,
+) -> Vec<BikeShareDock> {
+    timer.start("convert bike-share docks");
+    let mut query: HashSet<HashablePt2D> = HashSet::new();
+    for dock in input {
+        query.insert(dock.pt.to_hashable());
+    }
+    // Docks are usually mapped right on the sidewalk or in a parking lane, so they should be close.
+    let sidewalk_pts = match_points_to_lanes(
+        map.get_bounds(),
+        query,
+        map.all_lanes(),
+        |l| l.is_walkable(),
+        // Don't put docks right at intersections
+        Distance::meters(5.0),
+        Distance::meters(100.0),
+        timer,
+    );
+
+    let mut results = Vec::new();
+    for dock in input {
+        if let Some(sidewalk_pos) = sidewalk_pts.get(&dock.pt.to_hashable()) {
+            let id = BikeShareDockID(results.len());
+            let result = BikeShareDock {
+                id,
+                name: dock.name.clone(),
+                osm_node_id: dock.osm_node_id,
+                capacity: dock.capacity,
+                sidewalk_pos: *sidewalk_pos,
+            };
+            if result.biking_connection(map).is_some() {
+                results.push(result);
+                continue;
+            }
+        }
+        timer.warn(format!(
+            "Skipping bike-share dock {} ({}); it's not near both a sidewalk and somewhere to bike",
+            dock.osm_node_id, dock.name
+        ));
+    }
+    timer.stop("convert bike-share docks");
+    results
+}
@@ -1,3 +1,4 @@
+use crate::raw::DrivingSide;
 use crate::{osm, LaneType, NORMAL_LANE_THICKNESS, SHOULDER_THICKNESS, SIDEWALK_THICKNESS};
 use abstutil::Tags;
 use geom::Distance;
@@ -54,7 +55,7 @@ impl LaneSpec {
 // direction flips.
 //
 // TODO This is ripe for unit testing.
-pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
+pub fn get_lane_specs(tags: &Tags, driving_side: DrivingSide) -> Vec<LaneSpec> {
     // Easy special cases first.
     if tags.is_any("railway", vec!["light_rail", "rail"]) {
         return LaneSpec::normal(vec![LaneType::LightRail], Vec::new());
@@ -138,6 +139,11 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
         return LaneSpec::normal(fwd_side, back_side);
     }
 
+    // Tags like cycleway:right describe a side of the way, and the forwards lanes are on the left
+    // when driving on the left. OSM also lists lanes from left to right, which starts from the
+    // outermost forwards lane there.
+    let (fwd_tag, back_tag) = driving_side.osm_sides();
+
     let fwd_bus_spec = if let Some(s) = tags.get("bus:lanes:forward") {
         s
     } else if let Some(s) = tags.get("psv:lanes:forward") {
@@ -154,7 +160,10 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
         ""
     };
     {
-        let parts: Vec<&str> = fwd_bus_spec.split("|").collect();
+        let mut parts: Vec<&str> = fwd_bus_spec.split("|").collect();
+        if driving_side == DrivingSide::Left {
+            parts.reverse();
+        }
         let offset = if fwd_side[0] == LaneType::SharedLeftTurn {
             1
         } else {
@@ -172,7 +181,10 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
         .get("bus:lanes:backward")
         .or_else(|| tags.get("psv:lanes:backward"))
     {
-        let parts: Vec<&str> = spec.split("|").collect();
+        let mut parts: Vec<&str> = spec.split("|").collect();
+        if driving_side == DrivingSide::Left {
+            parts.reverse();
+        }
         if parts.len() == back_side.len() {
             for (idx, part) in parts.into_iter().enumerate() {
                 if part == "designated" {
@@ -191,11 +203,13 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
         fwd_side.push(LaneType::Biking);
         back_side.push(LaneType::Biking);
     } else {
-        if tags.is("cycleway:right", "lane") {
+        if tags.is(&format!("cycleway:{}", fwd_tag), "lane") {
             fwd_side.push(LaneType::Biking);
         }
-        if tags.is_any("cycleway:left", vec!["lane", "opposite_lane"])
-            || tags.is("cycleway", "opposite_lane")
+        if tags.is_any(
+            &format!("cycleway:{}", back_tag),
+            vec!["lane", "opposite_lane"],
+        ) || tags.is("cycleway", "opposite_lane")
         {
             back_side.push(LaneType::Biking);
         }
@@ -208,9 +222,11 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
 
     if driving_lane == LaneType::Driving {
         let has_parking = vec!["parallel", "diagonal", "perpendicular"];
-        let parking_lane_fwd = tags.is_any(osm::PARKING_RIGHT, has_parking.clone())
+        let parking_lane_fwd = tags
+            .is_any(&format!("parking:lane:{}", fwd_tag), has_parking.clone())
             || tags.is_any(osm::PARKING_BOTH, has_parking.clone());
-        let parking_lane_back = tags.is_any(osm::PARKING_LEFT, has_parking.clone())
+        let parking_lane_back = tags
+            .is_any(&format!("parking:lane:{}", back_tag), has_parking.clone())
             || tags.is_any(osm::PARKING_BOTH, has_parking);
         if parking_lane_fwd {
             fwd_side.push(LaneType::Parking);
@@ -229,9 +245,9 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
         if !back_side.is_empty() {
             back_side.push(LaneType::Sidewalk);
         }
-    } else if tags.is(osm::SIDEWALK, "right") {
+    } else if tags.is(osm::SIDEWALK, fwd_tag) {
         fwd_side.push(LaneType::Sidewalk);
-    } else if tags.is(osm::SIDEWALK, "left") {
+    } else if tags.is(osm::SIDEWALK, back_tag) {
         back_side.push(LaneType::Sidewalk);
     }
 
@@ -284,3 +300,83 @@ pub fn get_lane_specs(tags: &Tags) -> Vec<LaneSpec> {
 
     specs
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn specs(tags: Vec<(&str, &str)>, driving_side: DrivingSide) -> Vec<(LaneType, bool)> {
+        let mut map = BTreeMap::new();
+        for (k, v) in tags {
+            map.insert(k.to_string(), v.to_string());
+        }
+        get_lane_specs(&Tags::new(map), driving_side)
+            .into_iter()
+            .map(|s| (s.lane_type, s.reverse_pts))
+            .collect()
+    }
+
+    #[test]
+    fn test_side_tags() {
+        let tags = vec![
+            ("highway", "residential"),
+            ("sidewalk", "left"),
+            ("cycleway:right", "lane"),
+            ("parking:lane:right", "parallel"),
+        ];
+
+        assert_eq!(
+            specs(tags.clone(), DrivingSide::Right),
+            vec![
+                (LaneType::Driving, false),
+                (LaneType::Biking, false),
+                (LaneType::Parking, false),
+                (LaneType::Shoulder, false),
+                (LaneType::Driving, true),
+                (LaneType::Sidewalk, true),
+            ]
+        );
+        // The right side of the way has the backwards lanes when driving on the left
+        assert_eq!(
+            specs(tags, DrivingSide::Left),
+            vec![
+                (LaneType::Driving, false),
+                (LaneType::Sidewalk, false),
+                (LaneType::Driving, true),
+                (LaneType::Biking, true),
+                (LaneType::Parking, true),
+                (LaneType::Shoulder, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bus_lanes() {
+        let tags = vec![
+            ("highway", "primary"),
+            ("oneway", "yes"),
+            ("lanes", "2"),
+            ("bus:lanes", "designated|yes"),
+        ];
+
+        // The leftmost lane is next to the center line when driving on the right, and next to the
+        // curb when driving on the left.
+        assert_eq!(
+            specs(tags.clone(), DrivingSide::Right),
+            vec![
+                (LaneType::Bus, false),
+                (LaneType::Driving, false),
+                (LaneType::Shoulder, false),
+            ]
+        );
+        assert_eq!(
+            specs(tags, DrivingSide::Left),
+            vec![
+                (LaneType::Driving, false),
+                (LaneType::Bus, false),
+                (LaneType::Shoulder, false),
+            ]
+        );
+    }
+}
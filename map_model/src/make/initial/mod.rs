@@ -3,7 +3,7 @@ pub mod lane_specs;
 
 pub use self::geometry::intersection_polygon;
 use crate::raw::{DrivingSide, OriginalIntersection, OriginalRoad, RawMap, RawRoad};
-use crate::IntersectionType;
+use crate::{Crossing, IntersectionType};
 use abstutil::{Tags, Timer};
 use geom::{Bounds, Distance, PolyLine, Pt2D};
 use lane_specs::LaneSpec;
@@ -30,7 +30,7 @@ pub struct Road {
 
 impl Road {
     pub fn new(id: OriginalRoad, r: &RawRoad, driving_side: DrivingSide) -> Road {
-        let lane_specs = lane_specs::get_lane_specs(&r.osm_tags);
+        let lane_specs = lane_specs::get_lane_specs(&r.osm_tags, driving_side);
         let (trimmed_center_pts, total_width) = r.get_geometry(id, driving_side);
 
         Road {
@@ -52,6 +52,7 @@ pub struct Intersection {
     pub roads: BTreeSet<OriginalRoad>,
     pub intersection_type: IntersectionType,
     pub elevation: Distance,
+    pub midblock_crossing: Option<Crossing>,
 }
 
 impl InitialMap {
@@ -71,6 +72,7 @@ impl InitialMap {
                     roads: BTreeSet::new(),
                     intersection_type: i.intersection_type,
                     elevation: i.elevation,
+                    midblock_crossing: i.midblock_crossing,
                 },
             );
         }
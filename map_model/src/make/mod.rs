@@ -1,8 +1,11 @@
+mod bike_share;
 mod bridges;
 mod buildings;
 pub mod initial;
 mod parking_lots;
 mod remove_disconnected;
+#[cfg(test)]
+mod tests;
 pub mod traffic_signals;
 mod transit;
 pub mod turns;
@@ -12,12 +15,14 @@ use crate::pathfind::Pathfinder;
 use crate::raw::{OriginalIntersection, OriginalRoad, RawMap};
 use crate::{
     connectivity, osm, Area, AreaID, ControlStopSign, ControlTrafficSignal, Intersection,
-    IntersectionID, IntersectionType, Lane, LaneID, Map, MapEdits, PathConstraints, Position, Road,
-    RoadID, Zone,
+    IntersectionID, IntersectionType, Lane, LaneID, Map, MapEdits, ParkingRules, PathConstraints,
+    Position, Road, RoadID, Zone,
 };
 use abstutil::{Parallelism, Timer};
 use enumset::EnumSet;
-use geom::{Bounds, Distance, FindClosest, HashablePt2D, Ring, Speed, EPSILON_DIST};
+use geom::{
+    Bounds, Distance, FindClosest, HashablePt2D, PolyLine, Pt2D, Ring, Speed, EPSILON_DIST,
+};
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
 impl Map {
@@ -41,6 +46,7 @@ impl Map {
             bus_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
+            bike_share_docks: Vec::new(),
             zones: Vec::new(),
             boundary_polygon: raw.boundary_polygon.clone(),
             stop_signs: BTreeMap::new(),
@@ -73,6 +79,7 @@ impl Map {
                 // Might change later
                 intersection_type: i.intersection_type,
                 orig_id: i.id,
+                midblock_crossing: i.midblock_crossing,
                 incoming_lanes: Vec::new(),
                 outgoing_lanes: Vec::new(),
                 roads: i.roads.iter().map(|id| road_id_mapping[id]).collect(),
@@ -129,6 +136,12 @@ impl Map {
                     0
                 },
                 allow_through_traffic: EnumSet::new(),
+                elevation_profile: elevation_profile(
+                    &r.trimmed_center_pts,
+                    &raw.roads[&r.id].elevation,
+                    map.intersections[i1.0].elevation,
+                    map.intersections[i2.0].elevation,
+                ),
             };
             road.speed_limit = road.speed_limit_from_osm();
             road.allow_through_traffic = road.access_restrictions_from_osm();
@@ -184,6 +197,7 @@ impl Map {
                     bus_stops: BTreeSet::new(),
                     driving_blackhole: false,
                     biking_blackhole: false,
+                    parking_rules: ParkingRules::free(),
                 });
             }
             if road.get_name() == "???" {
@@ -274,6 +288,9 @@ impl Map {
             timer,
         );
 
+        map.bike_share_docks =
+            bike_share::make_all_bike_share_docks(&raw.bike_share_docks, &map, timer);
+
         map.zones = Zone::make_all(&map);
 
         for (idx, a) in raw.areas.iter().enumerate() {
@@ -409,3 +426,48 @@ fn match_points_to_lanes<F: Fn(&Lane) -> bool>(
         .flatten()
         .collect()
 }
+
+// Project elevation samples from the original road onto the trimmed center line. Samples past the
+// ends were cut off by the intersections, so the ends just use the intersections' elevation.
+fn elevation_profile(
+    pl: &PolyLine,
+    samples: &Vec<(Pt2D, Distance)>,
+    start: Distance,
+    end: Distance,
+) -> Vec<(Distance, Distance)> {
+    // The center line may be shifted from the original OSM way a bit
+    let max_offset = Distance::meters(10.0);
+    // Don't let samples right next to the ends produce wild grades
+    let min_gap = Distance::meters(1.0);
+
+    let len = pl.length();
+    let lines = pl.lines();
+    let mut projected_samples = Vec::new();
+    for (pt, elevation) in samples {
+        let mut best: Option<(Distance, Distance)> = None;
+        let mut dist_so_far = Distance::ZERO;
+        for l in &lines {
+            let projected = l.project_pt(*pt);
+            let offset = projected.dist_to(*pt);
+            if best.map(|(o, _)| offset < o).unwrap_or(true) {
+                best = Some((offset, dist_so_far + l.pt1().dist_to(projected)));
+            }
+            dist_so_far += l.length();
+        }
+        if let Some((offset, dist)) = best {
+            if offset <= max_offset && dist >= min_gap && dist <= len - min_gap {
+                projected_samples.push((dist, *elevation));
+            }
+        }
+    }
+    projected_samples.sort();
+
+    let mut profile = vec![(Distance::ZERO, start)];
+    for (dist, elevation) in projected_samples {
+        if dist - profile.last().unwrap().0 >= min_gap {
+            profile.push((dist, elevation));
+        }
+    }
+    profile.push((len, end));
+    profile
+}
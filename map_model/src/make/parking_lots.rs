@@ -1,7 +1,7 @@
 use crate::make::match_points_to_lanes;
 use crate::raw::RawParkingLot;
 use crate::{
-    Map, ParkingLot, ParkingLotID, PathConstraints, Position, NORMAL_LANE_THICKNESS,
+    Map, ParkingLot, ParkingLotID, ParkingRules, PathConstraints, Position, NORMAL_LANE_THICKNESS,
     PARKING_LOT_SPOT_LENGTH,
 };
 use abstutil::Timer;
@@ -86,6 +86,8 @@ pub fn make_all_parking_lots(
                     driving_pos,
                     sidewalk_line,
                     sidewalk_pos: *sidewalk_pos,
+
+                    parking_rules: ParkingRules::free(),
                 });
             } else {
                 // TODO Plumb WayID forward
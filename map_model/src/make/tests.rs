@@ -0,0 +1,369 @@
+// Small synthetic maps, built for both driving sides, to check that everything mirrors properly.
+
+use crate::make::traffic_signals::get_possible_policies;
+use crate::raw::{
+    DrivingSide, OriginalIntersection, OriginalRoad, RawIntersection, RawMap, RawRoad,
+};
+use crate::{
+    osm, IntersectionCluster, IntersectionID, IntersectionType, LaneType, Map, RoadID, TurnType,
+};
+use abstutil::{Tags, Timer};
+use geom::{Distance, GPSBounds, LonLat, Pt2D};
+use std::collections::BTreeMap;
+
+const SIDES: [DrivingSide; 2] = [DrivingSide::Right, DrivingSide::Left];
+
+// Node ID, position, type
+type TestIntersection = (i64, (f64, f64), IntersectionType);
+// Way ID, from node ID, to node ID, extra tags
+type TestRoad = (i64, i64, i64, Vec<(&'static str, &'static str)>);
+
+fn build_map(
+    driving_side: DrivingSide,
+    intersections: Vec<TestIntersection>,
+    roads: Vec<TestRoad>,
+) -> Map {
+    let mut raw = RawMap::blank("test", "test");
+    raw.config.driving_side = driving_side;
+    raw.gps_bounds = GPSBounds::from(vec![LonLat::new(0.0, 0.0), LonLat::new(0.01, 0.01)]);
+    raw.boundary_polygon = raw.gps_bounds.to_bounds().get_rectangle();
+
+    for (id, (x, y), intersection_type) in intersections {
+        raw.intersections.insert(
+            OriginalIntersection { osm_node_id: id },
+            RawIntersection {
+                point: Pt2D::new(x, y),
+                intersection_type,
+                elevation: Distance::ZERO,
+                midblock_crossing: None,
+            },
+        );
+    }
+    for (way, i1, i2, extra_tags) in roads {
+        let i1 = OriginalIntersection { osm_node_id: i1 };
+        let i2 = OriginalIntersection { osm_node_id: i2 };
+        let mut osm_tags = Tags::new(BTreeMap::new());
+        osm_tags.insert(osm::OSM_WAY_ID, way.to_string());
+        osm_tags.insert(osm::NAME, format!("Road {}", way));
+        osm_tags.insert(osm::HIGHWAY, "residential");
+        osm_tags.insert(osm::SIDEWALK, "both");
+        for (k, v) in extra_tags {
+            osm_tags.insert(k, v);
+        }
+        raw.roads.insert(
+            OriginalRoad {
+                osm_way_id: way,
+                i1,
+                i2,
+            },
+            RawRoad {
+                center_points: vec![raw.intersections[&i1].point, raw.intersections[&i2].point],
+                osm_tags,
+                turn_restrictions: Vec::new(),
+                complicated_turn_restrictions: Vec::new(),
+                elevation: Vec::new(),
+            },
+        );
+    }
+
+    Map::create_from_raw(raw, false, &mut Timer::throwaway())
+}
+
+// A signalized four-way at node 1. Every road points towards it, from the south (way 10), north
+// (way 11), east (way 12), and west (way 13). The south road has two lanes each way with lane
+// markings, and the north road has parking.
+fn four_way(driving_side: DrivingSide) -> Map {
+    build_map(
+        driving_side,
+        vec![
+            (1, (200.0, 200.0), IntersectionType::TrafficSignal),
+            (2, (200.0, 300.0), IntersectionType::Border),
+            (3, (200.0, 100.0), IntersectionType::Border),
+            (4, (300.0, 200.0), IntersectionType::Border),
+            (5, (100.0, 200.0), IntersectionType::Border),
+        ],
+        vec![
+            (
+                10,
+                2,
+                1,
+                vec![
+                    ("lanes", "4"),
+                    ("turn:lanes:forward", "left|through;right"),
+                    (osm::ENDPT_FWD, "true"),
+                ],
+            ),
+            (11, 3, 1, vec![(osm::PARKING_BOTH, "parallel")]),
+            (12, 4, 1, Vec::new()),
+            (13, 5, 1, Vec::new()),
+        ],
+    )
+}
+
+// The four-way without the west road
+fn three_way(driving_side: DrivingSide) -> Map {
+    build_map(
+        driving_side,
+        vec![
+            (1, (200.0, 200.0), IntersectionType::TrafficSignal),
+            (2, (200.0, 300.0), IntersectionType::Border),
+            (3, (200.0, 100.0), IntersectionType::Border),
+            (4, (300.0, 200.0), IntersectionType::Border),
+        ],
+        vec![
+            (10, 2, 1, Vec::new()),
+            (11, 3, 1, Vec::new()),
+            (12, 4, 1, Vec::new()),
+        ],
+    )
+}
+
+// Two signalized four-ways at nodes 1 and 2, joined by way 20
+fn two_signals(driving_side: DrivingSide) -> Map {
+    build_map(
+        driving_side,
+        vec![
+            (1, (200.0, 200.0), IntersectionType::TrafficSignal),
+            (2, (300.0, 200.0), IntersectionType::TrafficSignal),
+            (3, (200.0, 300.0), IntersectionType::Border),
+            (4, (200.0, 100.0), IntersectionType::Border),
+            (5, (100.0, 200.0), IntersectionType::Border),
+            (6, (300.0, 300.0), IntersectionType::Border),
+            (7, (300.0, 100.0), IntersectionType::Border),
+            (8, (400.0, 200.0), IntersectionType::Border),
+        ],
+        vec![
+            (20, 1, 2, Vec::new()),
+            (21, 3, 1, Vec::new()),
+            (22, 4, 1, Vec::new()),
+            (23, 5, 1, Vec::new()),
+            (24, 6, 2, Vec::new()),
+            (25, 7, 2, Vec::new()),
+            (26, 8, 2, Vec::new()),
+        ],
+    )
+}
+
+fn road(map: &Map, osm_way_id: i64) -> RoadID {
+    map.all_roads()
+        .iter()
+        .find(|r| r.orig_id.osm_way_id == osm_way_id)
+        .unwrap()
+        .id
+}
+
+fn center(map: &Map) -> IntersectionID {
+    map.find_i_by_osm_id(1).unwrap()
+}
+
+#[test]
+fn test_lanes_on_correct_side() {
+    for driving_side in SIDES.iter().cloned() {
+        let map = four_way(driving_side);
+        // The south road heads north, so its forwards lanes are east of the center line when
+        // driving on the right.
+        for (l, _) in map.get_r(road(&map, 10)).children(true) {
+            let east = map.get_l(*l).lane_center_pts.middle().x() > 200.0;
+            assert_eq!(east, driving_side == DrivingSide::Right);
+        }
+    }
+}
+
+#[test]
+fn test_turn_types() {
+    for driving_side in SIDES.iter().cloned() {
+        let map = four_way(driving_side);
+        let (east, west, north) = (road(&map, 12), road(&map, 13), road(&map, 11));
+        for (l, lt) in map.get_r(road(&map, 10)).children(true) {
+            if *lt != LaneType::Driving {
+                continue;
+            }
+            for turn in map.get_turns_from_lane(*l) {
+                let dst = map.get_l(turn.id.dst).parent;
+                let expected = if dst == east {
+                    TurnType::Right
+                } else if dst == west {
+                    TurnType::Left
+                } else {
+                    assert_eq!(dst, north);
+                    TurnType::Straight
+                };
+                assert_eq!(turn.turn_type, expected, "{:?} {}", driving_side, turn.id);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_turn_lanes() {
+    for driving_side in SIDES.iter().cloned() {
+        let map = four_way(driving_side);
+        let r = map.get_r(road(&map, 10));
+        let lanes: Vec<_> = r
+            .children(true)
+            .iter()
+            .filter(|(_, lt)| *lt == LaneType::Driving)
+            .map(|(l, _)| *l)
+            .collect();
+        assert_eq!(lanes.len(), 2);
+
+        // turn:lanes goes from left to right. The centermost lane is on the left when driving on
+        // the right.
+        let (left_lane, right_lane) = match driving_side {
+            DrivingSide::Right => (lanes[0], lanes[1]),
+            DrivingSide::Left => (lanes[1], lanes[0]),
+        };
+        for (l, expected) in vec![
+            (left_lane, vec![TurnType::Left]),
+            (right_lane, vec![TurnType::Straight, TurnType::Right]),
+        ] {
+            let restrictions: Vec<TurnType> = map
+                .get_l(l)
+                .get_turn_restrictions(r, driving_side)
+                .unwrap()
+                .collect();
+            assert_eq!(restrictions, expected, "{:?}", driving_side);
+            for turn in map.get_turns_from_lane(l) {
+                assert!(expected.contains(&turn.turn_type), "{:?}", driving_side);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_crosswalks_jut_into_intersection() {
+    for driving_side in SIDES.iter().cloned() {
+        for map in vec![four_way(driving_side), three_way(driving_side)] {
+            let i = map.get_i(center(&map));
+            let middle = i.polygon.center();
+            let mut crosswalks = 0;
+            for turn in map.get_turns_in_intersection(i.id) {
+                if turn.turn_type != TurnType::Crosswalk {
+                    continue;
+                }
+                crosswalks += 1;
+                let straight_across =
+                    Pt2D::center(&vec![turn.geom.first_pt(), turn.geom.last_pt()]);
+                assert!(
+                    turn.geom.middle().dist_to(middle) < straight_across.dist_to(middle),
+                    "{:?} {} juts away from the intersection",
+                    driving_side,
+                    turn.id
+                );
+            }
+            assert!(crosswalks > 0);
+        }
+    }
+}
+
+#[test]
+fn test_signal_policies() {
+    for driving_side in SIDES.iter().cloned() {
+        let (curb_turn, crossing_turn) = match driving_side {
+            DrivingSide::Right => (TurnType::Right, TurnType::Left),
+            DrivingSide::Left => (TurnType::Left, TurnType::Right),
+        };
+
+        for (map, policy, protect_crossing_turns) in vec![
+            (four_way(driving_side), "four-phase", true),
+            (four_way(driving_side), "two-phase", false),
+            (three_way(driving_side), "three-phase", false),
+        ] {
+            let i = center(&map);
+            let (_, ts) = get_possible_policies(&map, i, &mut Timer::throwaway())
+                .into_iter()
+                .find(|(name, _)| name == policy)
+                .unwrap_or_else(|| panic!("{:?} has no {} policy", driving_side, policy));
+
+            let mut protected_crossing = false;
+            for group in ts.turn_groups.values() {
+                if group.turn_type != TurnType::Left && group.turn_type != TurnType::Right {
+                    continue;
+                }
+                let protected = ts
+                    .phases
+                    .iter()
+                    .any(|p| p.protected_groups.contains(&group.id));
+                let yielded = ts.phases.iter().any(|p| p.yield_groups.contains(&group.id));
+                assert!(protected || yielded, "{:?} {:?}", driving_side, group.id);
+
+                if group.turn_type == curb_turn {
+                    assert!(!protected, "{:?} {} {:?}", driving_side, policy, group.id);
+                }
+                if group.turn_type == crossing_turn {
+                    protected_crossing |= protected;
+                    if !protect_crossing_turns {
+                        assert!(!protected, "{:?} {} {:?}", driving_side, policy, group.id);
+                    }
+                }
+            }
+            assert_eq!(
+                protected_crossing, protect_crossing_turns,
+                "{:?} {}",
+                driving_side, policy
+            );
+        }
+    }
+}
+
+#[test]
+fn test_parking_lane_offsets() {
+    for driving_side in SIDES.iter().cloned() {
+        let map = four_way(driving_side);
+        let r = map.get_r(road(&map, 11));
+        for driving in r.all_lanes() {
+            if !map.get_l(driving).is_driving() {
+                continue;
+            }
+            for parking in r.all_lanes() {
+                if !map.get_l(parking).is_parking() {
+                    continue;
+                }
+                // Cars sliding into a spot shift their body this far to the right
+                let diff = r.lanes_to_the_right(driving, parking, &map);
+                let shifted = map
+                    .get_l(driving)
+                    .lane_center_pts
+                    .shift_right(map.get_l(parking).width * (diff as f64))
+                    .unwrap();
+                let expected = map.get_l(parking).lane_center_pts.middle();
+                assert!(
+                    shifted.middle().dist_to(expected) < Distance::meters(0.1),
+                    "{:?} from {} to {}",
+                    driving_side,
+                    driving,
+                    parking
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_uber_turn_groups() {
+    for driving_side in SIDES.iter().cloned() {
+        let map = two_signals(driving_side);
+        let members = vec![
+            map.find_i_by_osm_id(1).unwrap(),
+            map.find_i_by_osm_id(2).unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let (cluster, _) = IntersectionCluster::new(members, &map);
+        assert!(!cluster.uber_turns.is_empty());
+
+        for group in cluster.uber_turn_groups(&map) {
+            // The start of the group's center line should be over the lanes it starts from
+            let (pl, width) = group.src_center_and_width(&map);
+            for ut in &group.members {
+                let entry = map.get_l(ut.entry());
+                assert!(
+                    entry.last_pt().dist_to(pl.first_pt()) <= width / 2.0 + Distance::meters(0.1),
+                    "{:?} {:?}",
+                    driving_side,
+                    ut
+                );
+            }
+        }
+    }
+}
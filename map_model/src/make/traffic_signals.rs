@@ -1,3 +1,4 @@
+use crate::raw::DrivingSide;
 use crate::{
     ControlTrafficSignal, IntersectionCluster, IntersectionID, Map, Phase, PhaseType, RoadID,
     TurnGroup, TurnGroupID, TurnPriority, TurnType,
@@ -169,6 +170,7 @@ fn three_way(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
     roads.remove(&north);
     roads.remove(&south);
     let east = roads.into_iter().next().unwrap();
+    let (curb_turn, crossing_turn) = curb_and_crossing_turns(map);
 
     // Two-phase with no protected lefts, right turn on red, turning cars yield to peds
     let phases = make_phases(
@@ -177,15 +179,15 @@ fn three_way(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Left, YIELD),
-                (vec![east], TurnType::Right, YIELD),
+                (vec![north, south], curb_turn, YIELD),
+                (vec![north, south], crossing_turn, YIELD),
+                (vec![east], curb_turn, YIELD),
             ],
             vec![
                 (vec![east], TurnType::Straight, PROTECTED),
-                (vec![east], TurnType::Right, YIELD),
-                (vec![east], TurnType::Left, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east], curb_turn, YIELD),
+                (vec![east], crossing_turn, YIELD),
+                (vec![north, south], curb_turn, YIELD),
             ],
         ],
     );
@@ -209,6 +211,7 @@ fn four_way_four_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSig
         .get_i(i)
         .get_roads_sorted_by_incoming_angle(map.all_roads());
     let (north, west, south, east) = (roads[0], roads[1], roads[2], roads[3]);
+    let (curb_turn, crossing_turn) = curb_and_crossing_turns(map);
 
     // Four-phase with protected lefts, right turn on red (except for the protected lefts),
     // turning cars yield to peds
@@ -218,16 +221,16 @@ fn four_way_four_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSig
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![east, west], TurnType::Right, YIELD),
+                (vec![north, south], curb_turn, YIELD),
+                (vec![east, west], curb_turn, YIELD),
             ],
-            vec![(vec![north, south], TurnType::Left, PROTECTED)],
+            vec![(vec![north, south], crossing_turn, PROTECTED)],
             vec![
                 (vec![east, west], TurnType::Straight, PROTECTED),
-                (vec![east, west], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east, west], curb_turn, YIELD),
+                (vec![north, south], curb_turn, YIELD),
             ],
-            vec![(vec![east, west], TurnType::Left, PROTECTED)],
+            vec![(vec![east, west], crossing_turn, PROTECTED)],
         ],
     );
 
@@ -250,6 +253,7 @@ fn four_way_two_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSign
         .get_i(i)
         .get_roads_sorted_by_incoming_angle(map.all_roads());
     let (north, west, south, east) = (roads[0], roads[1], roads[2], roads[3]);
+    let (curb_turn, crossing_turn) = curb_and_crossing_turns(map);
 
     // Two-phase with no protected lefts, right turn on red, turning cars yielding to peds
     let phases = make_phases(
@@ -258,15 +262,15 @@ fn four_way_two_phase(map: &Map, i: IntersectionID) -> Option<ControlTrafficSign
         vec![
             vec![
                 (vec![north, south], TurnType::Straight, PROTECTED),
-                (vec![north, south], TurnType::Right, YIELD),
-                (vec![north, south], TurnType::Left, YIELD),
-                (vec![east, west], TurnType::Right, YIELD),
+                (vec![north, south], curb_turn, YIELD),
+                (vec![north, south], crossing_turn, YIELD),
+                (vec![east, west], curb_turn, YIELD),
             ],
             vec![
                 (vec![east, west], TurnType::Straight, PROTECTED),
-                (vec![east, west], TurnType::Right, YIELD),
-                (vec![east, west], TurnType::Left, YIELD),
-                (vec![north, south], TurnType::Right, YIELD),
+                (vec![east, west], curb_turn, YIELD),
+                (vec![east, west], crossing_turn, YIELD),
+                (vec![north, south], curb_turn, YIELD),
             ],
         ],
     );
@@ -296,6 +300,7 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
     }
     let r1 = incomings[0];
     let r2 = incomings[1];
+    let (curb_turn, crossing_turn) = curb_and_crossing_turns(map);
 
     // TODO This may not generalize...
     let phases = make_phases(
@@ -306,18 +311,18 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
                 (vec![r1], TurnType::Straight, PROTECTED),
                 // TODO Technically, upgrade to protected if there's no opposing crosswalk --
                 // even though it doesn't matter much.
-                (vec![r1], TurnType::Right, YIELD),
-                (vec![r1], TurnType::Left, YIELD),
-                (vec![r1], TurnType::Right, YIELD),
+                (vec![r1], curb_turn, YIELD),
+                (vec![r1], crossing_turn, YIELD),
+                (vec![r1], curb_turn, YIELD),
                 // TODO Refactor
             ],
             vec![
                 (vec![r2], TurnType::Straight, PROTECTED),
                 // TODO Technically, upgrade to protected if there's no opposing crosswalk --
                 // even though it doesn't matter much.
-                (vec![r2], TurnType::Right, YIELD),
-                (vec![r2], TurnType::Left, YIELD),
-                (vec![r2], TurnType::Right, YIELD),
+                (vec![r2], curb_turn, YIELD),
+                (vec![r2], crossing_turn, YIELD),
+                (vec![r2], curb_turn, YIELD),
             ],
         ],
     );
@@ -331,6 +336,16 @@ fn four_oneways(map: &Map, i: IntersectionID) -> Option<ControlTrafficSignal> {
     ts.validate().ok()
 }
 
+// The policies above are described for driving on the right: "right turn on red" and "protected
+// lefts". When driving on the left, the roles of the two turns swap. Returns the turn that stays
+// along the curb, then the turn that crosses opposing traffic.
+fn curb_and_crossing_turns(map: &Map) -> (TurnType, TurnType) {
+    match map.get_driving_side() {
+        DrivingSide::Right => (TurnType::Right, TurnType::Left),
+        DrivingSide::Left => (TurnType::Left, TurnType::Right),
+    }
+}
+
 fn all_walk_all_yield(map: &Map, i: IntersectionID) -> ControlTrafficSignal {
     let turn_groups = TurnGroup::for_i(i, map);
 
@@ -32,7 +32,7 @@ pub fn make_all_turns(
             continue;
         }
 
-        if is_turn_allowed(&turn, roads, lanes) {
+        if is_turn_allowed(driving_side, &turn, roads, lanes) {
             final_turns.push(turn);
         } else {
             filtered_turns
@@ -102,10 +102,15 @@ fn ensure_unique(turns: Vec<Turn>) -> Vec<Turn> {
     keep
 }
 
-fn is_turn_allowed(turn: &Turn, roads: &Vec<Road>, lanes: &Vec<Lane>) -> bool {
+fn is_turn_allowed(
+    driving_side: DrivingSide,
+    turn: &Turn,
+    roads: &Vec<Road>,
+    lanes: &Vec<Lane>,
+) -> bool {
     let l = &lanes[turn.id.src.0];
     let r = &roads[l.parent.0];
-    if let Some(mut types) = l.get_turn_restrictions(r) {
+    if let Some(mut types) = l.get_turn_restrictions(r, driving_side) {
         types.any(|turn_type| turn_type == turn.turn_type)
     } else {
         true
@@ -82,7 +82,11 @@ pub fn make_walking_turns(
         if let Some(l1) = get_sidewalk(lanes, roads[idx1].incoming_lanes(i.id)) {
             // Make the crosswalk to the other side
             if let Some(l2) = get_sidewalk(lanes, roads[idx1].outgoing_lanes(i.id)) {
-                result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                result.extend(
+                    make_crosswalks(driving_side, i.id, l1, l2)
+                        .into_iter()
+                        .flatten(),
+                );
             }
 
             // Find the shared corner
@@ -111,7 +115,11 @@ pub fn make_walking_turns(
             ) {
                 // Adjacent road is missing a sidewalk on the near side, but has one on the far
                 // side
-                result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                result.extend(
+                    make_crosswalks(driving_side, i.id, l1, l2)
+                        .into_iter()
+                        .flatten(),
+                );
             } else {
                 // We may need to add a crosswalk over this intermediate road that has no
                 // sidewalks at all. There might be a few in the way -- think highway onramps.
@@ -120,19 +128,31 @@ pub fn make_walking_turns(
                     lanes,
                     wraparound_get(&roads, (idx1 as isize) + 2 * idx_offset).outgoing_lanes(i.id),
                 ) {
-                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                    result.extend(
+                        make_crosswalks(driving_side, i.id, l1, l2)
+                            .into_iter()
+                            .flatten(),
+                    );
                 } else if let Some(l2) = get_sidewalk(
                     lanes,
                     wraparound_get(&roads, (idx1 as isize) + 2 * idx_offset).incoming_lanes(i.id),
                 ) {
-                    result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                    result.extend(
+                        make_crosswalks(driving_side, i.id, l1, l2)
+                            .into_iter()
+                            .flatten(),
+                    );
                 } else if roads.len() > 3 {
                     if let Some(l2) = get_sidewalk(
                         lanes,
                         wraparound_get(&roads, (idx1 as isize) + 3 * idx_offset)
                             .outgoing_lanes(i.id),
                     ) {
-                        result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+                        result.extend(
+                            make_crosswalks(driving_side, i.id, l1, l2)
+                                .into_iter()
+                                .flatten(),
+                        );
                     }
                 }
             }
@@ -229,7 +249,11 @@ fn _new_make_walking_turns(
         // adj stays true
         } else {
             // TODO Just one for degenerate intersections
-            result.extend(make_crosswalks(i.id, l1, l2).into_iter().flatten());
+            result.extend(
+                make_crosswalks(driving_side, i.id, l1, l2)
+                    .into_iter()
+                    .flatten(),
+            );
             from = Some(l2);
             adj = true;
         }
@@ -243,15 +267,25 @@ fn _new_make_walking_turns(
     result
 }
 
-fn make_crosswalks(i: IntersectionID, l1: &Lane, l2: &Lane) -> Option<Vec<Turn>> {
+fn make_crosswalks(
+    driving_side: DrivingSide,
+    i: IntersectionID,
+    l1: &Lane,
+    l2: &Lane,
+) -> Option<Vec<Turn>> {
     let l1_pt = l1.endpoint(i);
     let l2_pt = l2.endpoint(i);
     // TODO Not sure this is always right.
-    let direction = if (l1.dst_i == i) == (l2.dst_i == i) {
+    let mut direction = if (l1.dst_i == i) == (l2.dst_i == i) {
         -1.0
     } else {
         1.0
     };
+    // l1 is usually an incoming sidewalk, which is on the other side of the road when driving on
+    // the left.
+    if driving_side == DrivingSide::Left {
+        direction *= -1.0;
+    }
     // Jut out a bit into the intersection, cross over, then jut back in. Assumes sidewalks are the
     // same width.
     let line = Line::new(l1_pt, l2_pt)?.shift_either_direction(direction * l1.width / 2.0);
@@ -1,9 +1,10 @@
 use crate::raw::{DrivingSide, RawMap};
 use crate::{
-    Area, AreaID, Building, BuildingID, BuildingType, BusRoute, BusRouteID, BusStop, BusStopID,
-    ControlStopSign, ControlTrafficSignal, Intersection, IntersectionID, Lane, LaneID, LaneType,
-    Map, MapEdits, OffstreetParking, ParkingLot, ParkingLotID, Path, PathConstraints, PathRequest,
-    Position, Road, RoadID, Turn, TurnGroupID, TurnID, TurnType,
+    Area, AreaID, BikeShareDock, BikeShareDockID, Building, BuildingID, BuildingType, BusRoute,
+    BusRouteID, BusStop, BusStopID, ControlStopSign, ControlTrafficSignal, Intersection,
+    IntersectionID, Lane, LaneID, LaneType, Map, MapEdits, OffstreetParking, ParkingFacility,
+    ParkingLot, ParkingLotID, ParkingRules, Path, PathConstraints, PathRequest, Position, Road,
+    RoadID, Turn, TurnGroupID, TurnID, TurnType, Zone,
 };
 use abstutil::Timer;
 use geom::{Angle, Bounds, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Ring, Time};
@@ -16,6 +17,9 @@ pub struct MapConfig {
     // (Australia).
     pub driving_side: DrivingSide,
     pub bikes_can_use_bus_lanes: bool,
+    // How much cyclists avoid climbing when choosing a route. 0 ignores hills entirely; 1 weighs a
+    // climb by the extra time it takes; higher values detour further to stay flat.
+    pub bike_hill_aversion: f64,
 }
 
 impl Map {
@@ -125,6 +129,7 @@ impl Map {
             bus_routes: Vec::new(),
             areas: Vec::new(),
             parking_lots: Vec::new(),
+            bike_share_docks: Vec::new(),
             zones: Vec::new(),
             boundary_polygon: Ring::must_new(vec![
                 Pt2D::new(0.0, 0.0),
@@ -139,6 +144,7 @@ impl Map {
             config: MapConfig {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                bike_hill_aversion: 1.0,
             },
             pathfinder: None,
             pathfinder_dirty: false,
@@ -176,6 +182,14 @@ impl Map {
         &self.parking_lots
     }
 
+    pub fn all_bike_share_docks(&self) -> &Vec<BikeShareDock> {
+        &self.bike_share_docks
+    }
+
+    pub fn all_zones(&self) -> &Vec<Zone> {
+        &self.zones
+    }
+
     pub fn maybe_get_r(&self, id: RoadID) -> Option<&Road> {
         self.roads.get(id.0)
     }
@@ -253,6 +267,17 @@ impl Map {
         &self.parking_lots[id.0]
     }
 
+    pub fn get_bsd(&self, id: BikeShareDockID) -> &BikeShareDock {
+        &self.bike_share_docks[id.0]
+    }
+
+    pub fn get_parking_rules(&self, id: ParkingFacility) -> ParkingRules {
+        match id {
+            ParkingFacility::Onstreet(l) => self.get_l(l).parking_rules,
+            ParkingFacility::Lot(pl) => self.get_pl(pl).parking_rules,
+        }
+    }
+
     pub fn get_stop_sign(&self, id: IntersectionID) -> &ControlStopSign {
         &self.stop_signs[&id]
     }
@@ -585,6 +610,15 @@ impl Map {
         None
     }
 
+    pub fn find_pl_by_osm_id(&self, osm_id: i64) -> Option<ParkingLotID> {
+        for pl in self.all_parking_lots() {
+            if pl.osm_id == osm_id {
+                return Some(pl.id);
+            }
+        }
+        None
+    }
+
     pub fn find_br(&self, osm_rel_id: i64) -> Option<BusRouteID> {
         for br in self.all_bus_routes() {
             if br.osm_rel_id == osm_rel_id {
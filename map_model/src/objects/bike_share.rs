@@ -0,0 +1,36 @@
+use crate::{Map, Position};
+use abstutil::{deserialize_usize, serialize_usize};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BikeShareDockID(
+    #[serde(
+        serialize_with = "serialize_usize",
+        deserialize_with = "deserialize_usize"
+    )]
+    pub usize,
+);
+
+impl fmt::Display for BikeShareDockID {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Bike-share dock #{}", self.0)
+    }
+}
+
+// Where people can pick up and return shared bikes or scooters
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BikeShareDock {
+    pub id: BikeShareDockID,
+    pub name: String,
+    pub osm_node_id: i64,
+    pub capacity: usize,
+    pub sidewalk_pos: Position,
+}
+
+impl BikeShareDock {
+    // The bikeable position to start or stop riding at. Like buildings, this depends on edits.
+    pub fn biking_connection(&self, map: &Map) -> Option<Position> {
+        crate::objects::building::sidewalk_to_bike(self.sidewalk_pos, map).map(|(pos, _)| pos)
+    }
+}
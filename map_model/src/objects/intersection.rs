@@ -29,6 +29,16 @@ pub enum IntersectionType {
     Construction,
 }
 
+// What kind of crossing pedestrians use away from a real intersection. These only make sense at
+// degenerate intersections, where exactly two roads meet.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Crossing {
+    // Painted or signed, from OSM highway=crossing. Drivers yield to pedestrians.
+    Marked,
+    // Pedestrians informally cross here, waiting for a gap in traffic.
+    Unmarked,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Intersection {
     pub id: IntersectionID,
@@ -39,6 +49,9 @@ pub struct Intersection {
 
     pub intersection_type: IntersectionType,
     pub orig_id: OriginalIntersection,
+    // Every degenerate intersection gets a crosswalk. This just says how it's marked, which
+    // changes who yields.
+    pub midblock_crossing: Option<Crossing>,
 
     // Note that a lane may belong to both incoming_lanes and outgoing_lanes.
     // TODO narrow down when and why. is it just sidewalks in weird cases?
@@ -72,6 +85,10 @@ impl Intersection {
         self.intersection_type == IntersectionType::TrafficSignal
     }
 
+    pub fn is_degenerate(&self) -> bool {
+        self.roads.len() == 2
+    }
+
     pub fn is_light_rail(&self, map: &Map) -> bool {
         self.roads.iter().all(|r| map.get_r(*r).is_light_rail())
     }
@@ -1,6 +1,8 @@
 use crate::pathfind;
+use crate::raw::DrivingSide;
 use crate::{
-    osm, BusStopID, DirectedRoadID, IntersectionID, Map, PathConstraints, Road, RoadID, TurnType,
+    osm, BusStopID, DirectedRoadID, IntersectionID, Map, ParkingRules, PathConstraints, Road,
+    RoadID, TurnType,
 };
 use abstutil::{deserialize_usize, serialize_usize};
 use geom::{Distance, Line, PolyLine, Pt2D};
@@ -118,6 +120,9 @@ pub struct Lane {
     // graph, because this is near a border.
     pub driving_blackhole: bool,
     pub biking_blackhole: bool,
+
+    // Only meaningful for parking lanes, but kept when the lane type changes
+    pub parking_rules: ParkingRules,
 }
 
 impl Lane {
@@ -229,6 +234,7 @@ impl Lane {
     pub fn get_turn_restrictions<'a>(
         &'a self,
         road: &'a Road,
+        driving_side: DrivingSide,
     ) -> Option<impl Iterator<Item = TurnType> + 'a> {
         if !self.is_driving() {
             return None;
@@ -245,8 +251,13 @@ impl Lane {
             return None;
         };
         let parts: Vec<&str> = all.split('|').collect();
+        // OSM lists lanes from left to right. When driving on the left, that starts from the
+        // outermost lane.
         // TODO Verify the number of lanes matches up
-        let part = parts.get(offset)?;
+        let part = match driving_side {
+            DrivingSide::Right => parts.get(offset)?,
+            DrivingSide::Left => parts.get(parts.len().checked_sub(offset + 1)?)?,
+        };
         // TODO Probably the target lane should get marked as LaneType::Bus
         if part == &"no" || part == &"none" || part == &"yes" || part == &"psv" || part == &"bus" {
             return None;
@@ -275,6 +286,48 @@ impl Lane {
         }))
     }
 
+    // Parking lanes, sidewalks, and shoulders are often guessed, because OSM doesn't say. If so,
+    // this describes how: "true" for the built-in defaults, otherwise the name of an inference rule
+    // or other data source. Lanes changed by edits aren't guesses anymore.
+    pub fn guessed_by<'a>(&self, map: &'a Map) -> Option<&'a String> {
+        if map.get_edits().original_lts.contains_key(&self.id) {
+            return None;
+        }
+        let tags = &map.get_r(self.parent).osm_tags;
+        match self.lane_type {
+            LaneType::Parking => tags.get(osm::INFERRED_PARKING),
+            LaneType::Sidewalk | LaneType::Shoulder => tags.get(osm::INFERRED_SIDEWALKS),
+            _ => None,
+        }
+    }
+
+    // How many times longer it takes to cross this lane than flat ground, for somebody whose speed
+    // on some grade is speed_on_grade(grade) times their speed on flat ground. Grades are positive
+    // uphill in the direction of the lane, or the opposite direction if contraflow.
+    pub fn grade_time_factor<F: Fn(f64) -> f64>(
+        &self,
+        contraflow: bool,
+        speed_on_grade: F,
+        map: &Map,
+    ) -> f64 {
+        let road = map.get_r(self.parent);
+        let sign = if road.is_forwards(self.id) != contraflow {
+            1.0
+        } else {
+            -1.0
+        };
+        let mut total_dist = 0.0;
+        let mut total_time = 0.0;
+        for (dist, grade) in road.grade_profile() {
+            total_dist += dist.inner_meters();
+            total_time += dist.inner_meters() / speed_on_grade(sign * grade);
+        }
+        if total_dist == 0.0 {
+            return 1.0;
+        }
+        total_time / total_dist
+    }
+
     pub fn get_max_cost(&self, constraints: PathConstraints, map: &Map) -> usize {
         map.get_turns_to_lane(self.id)
             .into_iter()
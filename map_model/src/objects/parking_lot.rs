@@ -1,6 +1,6 @@
-use crate::Position;
+use crate::{LaneID, Position};
 use abstutil::{deserialize_usize, serialize_usize};
-use geom::{Angle, Line, PolyLine, Polygon, Pt2D};
+use geom::{Angle, Duration, Line, PolyLine, Polygon, Pt2D};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 
@@ -46,4 +46,59 @@ pub struct ParkingLot {
     // Lot to sidewalk
     pub sidewalk_line: Line,
     pub sidewalk_pos: Position,
+
+    pub parking_rules: ParkingRules,
+}
+
+// Pricing and time limits for an on-street parking lane or a parking lot. Nothing is imported from
+// OSM yet, so everything starts free and unlimited; map edits can change this.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParkingRules {
+    // 0 means free
+    pub cents_per_hour: usize,
+    // None means unlimited. Not enforced; drivers just avoid spots they'd overstay.
+    pub max_stay: Option<Duration>,
+}
+
+impl ParkingRules {
+    pub fn free() -> ParkingRules {
+        ParkingRules {
+            cents_per_hour: 0,
+            max_stay: None,
+        }
+    }
+
+    // Rounded up to the nearest cent
+    pub fn cost(&self, stay: Duration) -> usize {
+        ((self.cents_per_hour as f64) * stay.inner_seconds() / 3600.0).ceil() as usize
+    }
+
+    pub fn describe(&self) -> String {
+        let price = if self.cents_per_hour == 0 {
+            "free".to_string()
+        } else {
+            format!("${:.2} per hour", (self.cents_per_hour as f64) / 100.0)
+        };
+        if let Some(max) = self.max_stay {
+            format!("{}, {} limit", price, max)
+        } else {
+            price
+        }
+    }
+}
+
+// Somewhere with ParkingRules. Private spots in buildings are always free.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum ParkingFacility {
+    Onstreet(LaneID),
+    Lot(ParkingLotID),
+}
+
+impl fmt::Display for ParkingFacility {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParkingFacility::Onstreet(l) => write!(f, "parking on {}", l),
+            ParkingFacility::Lot(pl) => write!(f, "{}", pl),
+        }
+    }
 }
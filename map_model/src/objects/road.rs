@@ -1,4 +1,4 @@
-use crate::raw::{OriginalRoad, RestrictionType};
+use crate::raw::{DrivingSide, OriginalRoad, RestrictionType};
 use crate::{osm, BusStopID, IntersectionID, Lane, LaneID, LaneType, Map, PathConstraints, Zone};
 use abstutil::{deserialize_usize, serialize_usize, Tags};
 use enumset::EnumSet;
@@ -109,6 +109,9 @@ pub struct Road {
     pub center_pts: PolyLine,
     pub src_i: IntersectionID,
     pub dst_i: IntersectionID,
+    // (distance along center_pts, elevation), ordered and always including both ends. Unless the
+    // map was imported with detailed elevation data, this is just the two intersections.
+    pub elevation_profile: Vec<(Distance, Distance)>,
 }
 
 type HomogenousTuple2<T> = (T, T);
@@ -221,6 +224,22 @@ impl Road {
             .unwrap()
     }
 
+    // How many lanes over `to` is from `from`, counting to the right of somebody traveling along
+    // `from`. Negative means `to` is on their left.
+    pub fn lanes_to_the_right(&self, from: LaneID, to: LaneID, map: &Map) -> isize {
+        let mut diff =
+            (self.offset_from_left(to) as isize) - (self.offset_from_left(from) as isize);
+        if !self.is_forwards(from) {
+            diff *= -1;
+        }
+        // offset_from_left starts with the backwards lanes, which are on the right side of the
+        // road when driving on the left.
+        if map.get_driving_side() == DrivingSide::Left {
+            diff *= -1;
+        }
+        diff
+    }
+
     // Includes off-side
     // TODO Specialize a variant for PathConstraints.can_use. Only one caller needs something
     // fancier.
@@ -367,6 +386,33 @@ impl Road {
         grade
     }
 
+    // Each piece of the road between elevation samples, as (length, grade). The grade is positive
+    // when going uphill in the direction of center_pts.
+    pub fn grade_profile(&self) -> Vec<(Distance, f64)> {
+        self.elevation_profile
+            .windows(2)
+            .filter_map(|pair| {
+                let run = pair[1].0 - pair[0].0;
+                if run <= Distance::ZERO {
+                    return None;
+                }
+                let grade = (pair[1].1 - pair[0].1) / run;
+                Some((run, grade.max(-1.0).min(1.0)))
+            })
+            .collect()
+    }
+
+    // The steepest piece of the road, in the same units as grade_profile.
+    pub fn steepest_grade(&self) -> f64 {
+        let mut steepest = 0.0_f64;
+        for (_, grade) in self.grade_profile() {
+            if grade.abs() > steepest.abs() {
+                steepest = grade;
+            }
+        }
+        steepest
+    }
+
     pub fn is_light_rail(&self) -> bool {
         !self.children_forwards.is_empty() && self.children_forwards[0].1 == LaneType::LightRail
     }
@@ -1,4 +1,4 @@
-use crate::{osm, IntersectionID, LaneID, Map, RoadID, TurnID, TurnPriority, TurnType};
+use crate::{osm, Crossing, IntersectionID, LaneID, Map, RoadID, TurnID, TurnPriority, TurnType};
 use abstutil::{deserialize_btreemap, serialize_btreemap};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
@@ -104,7 +104,14 @@ impl ControlStopSign {
         match map.get_t(turn).turn_type {
             TurnType::SharedSidewalkCorner => TurnPriority::Protected,
             // TODO This actually feels like a policy bit that should be flippable.
-            TurnType::Crosswalk => TurnPriority::Protected,
+            TurnType::Crosswalk => {
+                // Without markings, pedestrians wait for a gap in traffic.
+                if map.get_i(self.id).midblock_crossing == Some(Crossing::Unmarked) {
+                    TurnPriority::Yield
+                } else {
+                    TurnPriority::Protected
+                }
+            }
             _ => {
                 if self.roads[&map.get_l(turn.src).parent].must_stop {
                     TurnPriority::Yield
@@ -1,4 +1,4 @@
-use crate::pathfind::{driving_cost, walking_cost, WalkingNode};
+use crate::pathfind::{driving_cost, walking_cost, walking_cost_along, WalkingNode};
 use crate::{
     IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep, RoadID, TurnID,
 };
@@ -88,11 +88,10 @@ impl Zone {
             for l in map.get_r(*r).all_lanes() {
                 let l = map.get_l(l);
                 if l.is_walkable() {
-                    let cost = walking_cost(l.length());
                     let n1 = WalkingNode::SidewalkEndpoint(l.id, true);
                     let n2 = WalkingNode::SidewalkEndpoint(l.id, false);
-                    graph.add_edge(n1, n2, cost);
-                    graph.add_edge(n2, n1, cost);
+                    graph.add_edge(n1, n2, walking_cost_along(l, true, map));
+                    graph.add_edge(n2, n1, walking_cost_along(l, false, map));
 
                     for turn in map.get_turns_for(l.id, PathConstraints::Pedestrian) {
                         if self.members.contains(&map.get_l(turn.id.dst).parent) {
@@ -213,6 +213,16 @@ fn make_input_graph(
     input_graph
 }
 
+// Relative to the speed on flat ground. Climbing slows people down a lot. Going downhill is a bit
+// faster, but most people brake on steep descents.
+pub fn biking_speed_on_grade(grade: f64) -> f64 {
+    if grade > 0.0 {
+        (1.0 / (1.0 + 10.0 * grade)).max(0.2)
+    } else {
+        (1.0 - 3.0 * grade).min(1.5)
+    }
+}
+
 pub fn driving_cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map: &Map) -> usize {
     // TODO Could cost turns differently.
 
@@ -226,7 +236,6 @@ pub fn driving_cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map:
         PathConstraints::Bike => {
             // Speed limits don't matter, bikes are usually constrained by their own speed limit.
             let dist = lane.length() + turn.geom.length();
-            // TODO Elevation gain is bad, loss is good.
             // TODO If we're on a driving lane, higher speed limit is worse.
             // TODO Bike lanes next to parking is dangerous.
 
@@ -241,8 +250,16 @@ pub fn driving_cost(lane: &Lane, turn: &Turn, constraints: PathConstraints, map:
                 1.5
             };
 
+            // Climbs are slow and tiring, descents a little faster. With no aversion, hills don't
+            // matter; with 1, they cost about as much as the extra time they take.
+            let hill_penalty = 1.0
+                + map.config.bike_hill_aversion
+                    * (lane.grade_time_factor(false, biking_speed_on_grade, map) - 1.0);
+
             // 1m resolution is fine
-            (lt_penalty * dist).inner_meters().round() as usize
+            (lt_penalty * hill_penalty.max(0.1) * dist)
+                .inner_meters()
+                .round() as usize
         }
         PathConstraints::Bus => {
             // Like Car, but prefer bus lanes.
@@ -4,10 +4,10 @@ mod node_map;
 pub mod uber_turns;
 mod walking;
 
-pub use self::driving::driving_cost;
 use self::driving::VehiclePathfinder;
+pub use self::driving::{biking_speed_on_grade, driving_cost};
 use self::walking::{one_step_walking_path, walking_path_to_steps, SidewalkPathfinder};
-pub use self::walking::{walking_cost, WalkingNode};
+pub use self::walking::{walking_cost, walking_cost_along, walking_speed_on_grade, WalkingNode};
 use crate::{
     osm, BusRouteID, BusStopID, Intersection, Lane, LaneID, LaneType, Map, Position, Traversable,
     TurnID, UberTurn, Zone,
@@ -257,6 +257,16 @@ impl Path {
         self.steps.push_back(step);
     }
 
+    // Continue this path with another one that starts where this one ends.
+    pub fn append(&mut self, other: Path, map: &Map) {
+        assert_eq!(other.crossed_so_far, Distance::ZERO);
+        for step in other.steps {
+            self.add(step, map);
+        }
+        self.end_dist = other.end_dist;
+        self.uber_turns.extend(other.uber_turns);
+    }
+
     // TODO This is a brittle, tied to exactly what opportunistically_lanechange does.
     pub fn approaching_uber_turn(&self) -> bool {
         if self.steps.len() < 5 || self.uber_turns.is_empty() {
@@ -291,6 +301,15 @@ impl Path {
         }
     }
 
+    // Like modify_step, but for a vehicle switching to a sibling lane partway along the current
+    // step. Also trusting the caller.
+    pub fn modify_current_step(&mut self, step: PathStep, map: &Map) {
+        assert!(self.currently_inside_ut.is_none());
+        self.total_length -= self.steps[0].as_traversable().length(map);
+        self.steps[0] = step;
+        self.total_length += self.steps[0].as_traversable().length(map);
+    }
+
     pub fn current_step(&self) -> PathStep {
         self.steps[0]
     }
@@ -1,7 +1,7 @@
 use crate::pathfind::driving::VehiclePathfinder;
 use crate::pathfind::node_map::{deserialize_nodemap, NodeMap};
 use crate::{
-    BusRoute, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathConstraints,
+    BusRoute, BusRouteID, BusStopID, IntersectionID, Lane, LaneID, Map, Path, PathConstraints,
     PathRequest, PathStep, Position,
 };
 use fast_paths::{deserialize_32, serialize_32, FastGraph, InputGraph, PathCalculator};
@@ -232,15 +232,17 @@ fn make_input_graph(
                 .allow_through_traffic
                 .contains(PathConstraints::Pedestrian)
         {
-            let mut cost = walking_cost(l.length());
+            let mut fwd_cost = walking_cost_along(l, false, map);
+            let mut back_cost = walking_cost_along(l, true, map);
             // TODO Tune this penalty, along with many others.
             if l.is_shoulder() {
-                cost *= 2;
+                fwd_cost *= 2;
+                back_cost *= 2;
             }
             let n1 = nodes.get(WalkingNode::SidewalkEndpoint(l.id, true));
             let n2 = nodes.get(WalkingNode::SidewalkEndpoint(l.id, false));
-            input_graph.add_edge(n1, n2, cost);
-            input_graph.add_edge(n2, n1, cost);
+            input_graph.add_edge(n1, n2, back_cost);
+            input_graph.add_edge(n2, n1, fwd_cost);
         }
     }
 
@@ -377,6 +379,17 @@ fn transit_input_graph(
     }
 }
 
+// Like walking_cost, but for walking the full length of a sidewalk, up or down any hills.
+pub fn walking_cost_along(lane: &Lane, contraflow: bool, map: &Map) -> usize {
+    walking_cost(lane.length() * lane.grade_time_factor(contraflow, walking_speed_on_grade, map))
+}
+
+// Tobler's hiking function, relative to the speed on flat ground. People walk fastest on a slight
+// downhill, and slow down on steep grades in either direction.
+pub fn walking_speed_on_grade(grade: f64) -> f64 {
+    (-3.5 * (grade + 0.05).abs()).exp() / (-3.5 * 0.05_f64).exp()
+}
+
 pub fn walking_cost(dist: Distance) -> usize {
     let walking_speed = Speed::meters_per_second(1.34);
     let time = dist / walking_speed;
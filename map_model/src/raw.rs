@@ -1,5 +1,5 @@
 use crate::make::initial::lane_specs::get_lane_specs;
-use crate::{osm, AreaType, IntersectionType, LaneType, MapConfig};
+use crate::{osm, AreaType, Crossing, IntersectionType, LaneType, MapConfig};
 use abstutil::{deserialize_btreemap, serialize_btreemap, Tags, Timer};
 use geom::{Angle, Distance, GPSBounds, Line, PolyLine, Polygon, Pt2D, Ring};
 use petgraph::graphmap::DiGraphMap;
@@ -30,6 +30,7 @@ pub struct RawMap {
     pub areas: Vec<RawArea>,
     pub parking_lots: Vec<RawParkingLot>,
     pub parking_aisles: Vec<Vec<Pt2D>>,
+    pub bike_share_docks: Vec<RawBikeShareDock>,
 
     pub boundary_polygon: Polygon,
     pub gps_bounds: GPSBounds,
@@ -123,12 +124,14 @@ impl RawMap {
             areas: Vec::new(),
             parking_lots: Vec::new(),
             parking_aisles: Vec::new(),
+            bike_share_docks: Vec::new(),
             // Some nonsense thing
             boundary_polygon: Polygon::rectangle(1.0, 1.0),
             gps_bounds: GPSBounds::new(),
             config: MapConfig {
                 driving_side: DrivingSide::Right,
                 bikes_can_use_bus_lanes: true,
+                bike_hill_aversion: 1.0,
             },
         }
     }
@@ -193,6 +196,7 @@ impl RawMap {
             roads: self.roads_per_intersection(id).into_iter().collect(),
             intersection_type: self.intersections[&id].intersection_type,
             elevation: self.intersections[&id].elevation,
+            midblock_crossing: self.intersections[&id].midblock_crossing,
         };
         let mut roads = BTreeMap::new();
         for r in &i.roads {
@@ -293,6 +297,9 @@ pub struct RawRoad {
     pub turn_restrictions: Vec<(RestrictionType, OriginalRoad)>,
     // (via, to). For turn restrictions where 'via' is an entire road. Only BanTurns.
     pub complicated_turn_restrictions: Vec<(OriginalRoad, OriginalRoad)>,
+    // Sampled along center_points, if elevation data was used. After editing the road, these may
+    // not lie exactly on it anymore.
+    pub elevation: Vec<(Pt2D, Distance)>,
 }
 
 impl RawRoad {
@@ -302,7 +309,7 @@ impl RawRoad {
         id: OriginalRoad,
         driving_side: DrivingSide,
     ) -> (PolyLine, Distance) {
-        let lane_specs = get_lane_specs(&self.osm_tags);
+        let lane_specs = get_lane_specs(&self.osm_tags, driving_side);
         let mut total_width = Distance::ZERO;
         let mut sidewalk_right = None;
         let mut sidewalk_left = None;
@@ -349,6 +356,7 @@ pub struct RawIntersection {
     pub point: Pt2D,
     pub intersection_type: IntersectionType,
     pub elevation: Distance,
+    pub midblock_crossing: Option<Crossing>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -375,6 +383,16 @@ pub struct RawParkingLot {
     pub osm_id: i64,
 }
 
+// A station for shared bikes or scooters
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RawBikeShareDock {
+    pub osm_node_id: i64,
+    pub pt: Pt2D,
+    pub name: String,
+    // How many vehicles can be docked here at once
+    pub capacity: usize,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum RestrictionType {
     BanTurns,
@@ -428,6 +446,15 @@ impl DrivingSide {
         .unwrap()
     }
 
+    // OSM tags like sidewalk=left and parking:lane:right name a physical side of the way. Returns
+    // the side with the forwards lanes, then the side with the backwards lanes.
+    pub fn osm_sides(self) -> (&'static str, &'static str) {
+        match self {
+            DrivingSide::Right => ("right", "left"),
+            DrivingSide::Left => ("left", "right"),
+        }
+    }
+
     pub fn right_shift_line(self, line: Line, width: Distance) -> Line {
         match self {
             DrivingSide::Right => line.shift_right(width),
@@ -1,9 +1,13 @@
-use crate::{AgentType, AlertLocation, CarID, Event, ParkingSpot, TripID, TripMode, TripPhaseType};
-use abstutil::Counter;
+use crate::mechanics::walkable_area;
+use crate::{
+    AgentID, AgentType, AlertLocation, CarID, Emissions, Event, ParkingSpot, TripID, TripMode,
+    TripPhaseType, VehicleType,
+};
+use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Distance, Duration, Time};
 use map_model::{
-    BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingLotID, Path, PathRequest, RoadID,
-    Traversable, TurnGroupID,
+    BikeShareDockID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, ParkingFacility,
+    ParkingLotID, Path, PathRequest, RoadID, Traversable, TurnGroupID,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
@@ -35,6 +39,43 @@ pub struct Analytics {
     // Per parking lane or lot, when does a spot become filled (true) or free (false)
     pub parking_lane_changes: BTreeMap<LaneID, Vec<(Time, bool)>>,
     pub parking_lot_changes: BTreeMap<ParkingLotID, Vec<(Time, bool)>>,
+    // When a car leaves a spot, how much did it pay, in cents? Cars that were already parked when
+    // the simulation started aren't counted.
+    pub parking_payments: Vec<(Time, ParkingFacility, usize)>,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    parking_started: BTreeMap<ParkingSpot, Time>,
+    // When did a delivery vehicle start blocking a lane, and for how long?
+    pub freight_stops: Vec<(Time, CarID, LaneID, Duration)>,
+    // Per completed ride-hail trip: dropoff time, vehicle, how long the passenger waited for
+    // pickup, how far the vehicle drove empty to reach them, and how long the vehicle was busy
+    pub ride_hail_rides: Vec<(Time, CarID, Duration, Distance, Duration)>,
+    // When does a bike-share dock run out of bikes (true) or fill up (false)?
+    pub bike_share_dock_changes: BTreeMap<BikeShareDockID, Vec<(Time, bool)>>,
+    // When did somebody arrive at an empty dock?
+    pub bike_share_unavailable: Vec<(Time, BikeShareDockID)>,
+    // When did a vehicle finish crossing a road or intersection, and what did it emit doing so?
+    // This includes time spent waiting at the end of the road.
+    pub road_emissions: BTreeMap<RoadID, Vec<(Time, Emissions)>>,
+    pub intersection_emissions: BTreeMap<IntersectionID, Vec<(Time, Emissions)>>,
+    // Summed over all of the driving legs of a trip. The vehicle's very last lane isn't counted.
+    pub trip_emissions: BTreeMap<TripID, Emissions>,
+    // (Sidewalk, hour block) -> the most people on that sidewalk at once during that hour
+    pub sidewalk_peak_occupancy: BTreeMap<(LaneID, usize), usize>,
+    // When did each vehicle start its current lane or turn?
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    vehicle_steps: BTreeMap<CarID, (Time, Traversable)>,
+    // Emissions for the vehicle's current trip leg so far
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    vehicle_emissions: BTreeMap<CarID, Emissions>,
 
     pub(crate) alerts: Vec<(Time, AlertLocation, String)>,
 
@@ -59,6 +100,18 @@ impl Analytics {
             intersection_delays: BTreeMap::new(),
             parking_lane_changes: BTreeMap::new(),
             parking_lot_changes: BTreeMap::new(),
+            parking_payments: Vec::new(),
+            parking_started: BTreeMap::new(),
+            freight_stops: Vec::new(),
+            ride_hail_rides: Vec::new(),
+            bike_share_dock_changes: BTreeMap::new(),
+            bike_share_unavailable: Vec::new(),
+            road_emissions: BTreeMap::new(),
+            intersection_emissions: BTreeMap::new(),
+            trip_emissions: BTreeMap::new(),
+            sidewalk_peak_occupancy: BTreeMap::new(),
+            vehicle_steps: BTreeMap::new(),
+            vehicle_emissions: BTreeMap::new(),
             alerts: Vec::new(),
             record_anything: true,
         }
@@ -102,6 +155,11 @@ impl Analytics {
                 }
             };
         }
+        // Emissions
+        if let Event::AgentEntersTraversable(AgentID::Car(car), to, _) = ev {
+            self.record_vehicle_step(time, car, to, map);
+        }
+
         match ev {
             Event::PersonLeavesMap(_, maybe_a, i, _) => {
                 // Ignore aborted trips
@@ -175,6 +233,7 @@ impl Analytics {
                     .or_insert_with(Vec::new)
                     .push((time, true));
             }
+            self.parking_started.insert(spot, time);
         }
         if let Event::CarLeftParkingSpot(_, spot) = ev {
             if let ParkingSpot::Onstreet(l, _) = spot {
@@ -188,6 +247,14 @@ impl Analytics {
                     .or_insert_with(Vec::new)
                     .push((time, false));
             }
+            if let Some(started) = self.parking_started.remove(&spot) {
+                if let Some(facility) = spot.facility() {
+                    let cents = map.get_parking_rules(facility).cost(time - started);
+                    if cents > 0 {
+                        self.parking_payments.push((time, facility, cents));
+                    }
+                }
+            }
         }
 
         // TODO Kinda hacky, but these all consume the event, so kinda bundle em.
@@ -208,10 +275,149 @@ impl Analytics {
             Event::Alert(loc, msg) => {
                 self.alerts.push((time, loc, msg));
             }
+            Event::FreightStop(car, _, pos, dwell) => {
+                self.freight_stops.push((time, car, pos.lane(), dwell));
+            }
+            Event::RideHailDropoff {
+                car,
+                wait,
+                deadhead,
+                busy,
+                ..
+            } => {
+                self.ride_hail_rides.push((time, car, wait, deadhead, busy));
+            }
+            Event::BikeShareDockEmpty(dock) => {
+                self.bike_share_dock_changes
+                    .entry(dock)
+                    .or_insert_with(Vec::new)
+                    .push((time, true));
+            }
+            Event::BikeShareDockFull(dock) => {
+                self.bike_share_dock_changes
+                    .entry(dock)
+                    .or_insert_with(Vec::new)
+                    .push((time, false));
+            }
+            Event::BikeShareUnavailable(dock) => {
+                self.bike_share_unavailable.push((time, dock));
+            }
+            Event::SidewalkOccupancy(l, count) => {
+                let peak = self
+                    .sidewalk_peak_occupancy
+                    .entry((l, time.get_hours()))
+                    .or_insert(0);
+                *peak = (*peak).max(count);
+            }
+            Event::VehicleFinishedTripLeg(car, trip) => {
+                self.vehicle_steps.remove(&car);
+                if let Some(e) = self.vehicle_emissions.remove(&car) {
+                    *self
+                        .trip_emissions
+                        .entry(trip)
+                        .or_insert_with(Emissions::zero) += e;
+                }
+            }
+            Event::CarReachedParkingSpot(car, _) | Event::RideHailDropoff { car, .. } => {
+                // The vehicle might vanish for a while after this.
+                self.vehicle_steps.remove(&car);
+            }
             _ => {}
         }
     }
 
+    fn record_vehicle_step(&mut self, now: Time, car: CarID, to: Traversable, map: &Map) {
+        if car.1 == VehicleType::Bike {
+            return;
+        }
+        let (start, from) = match self.vehicle_steps.insert(car, (now, to)) {
+            Some(pair) => pair,
+            None => {
+                return;
+            }
+        };
+        // Only count the previous step if the vehicle went straight from it to this one. A lane
+        // change in the middle of the block doesn't produce an event, so just check the road.
+        let contiguous = match (from, to) {
+            (Traversable::Lane(l), Traversable::Turn(t)) => {
+                map.get_l(l).parent == map.get_l(t.src).parent
+            }
+            (Traversable::Turn(t), Traversable::Lane(l)) => t.dst == l,
+            _ => false,
+        };
+        if !contiguous {
+            return;
+        }
+
+        let e = Emissions::estimate(car.1, from.length(map), now - start);
+        match from {
+            Traversable::Lane(l) => {
+                self.road_emissions
+                    .entry(map.get_l(l).parent)
+                    .or_insert_with(Vec::new)
+                    .push((now, e));
+            }
+            Traversable::Turn(t) => {
+                self.intersection_emissions
+                    .entry(t.parent)
+                    .or_insert_with(Vec::new)
+                    .push((now, e));
+            }
+        }
+        *self
+            .vehicle_emissions
+            .entry(car)
+            .or_insert_with(Emissions::zero) += e;
+    }
+
+    // Sum emissions per road and intersection, up to some time.
+    pub fn emissions_until(
+        &self,
+        now: Time,
+    ) -> (
+        BTreeMap<RoadID, Emissions>,
+        BTreeMap<IntersectionID, Emissions>,
+    ) {
+        let mut per_road = BTreeMap::new();
+        for (r, list) in &self.road_emissions {
+            let mut sum = Emissions::zero();
+            for (t, e) in list {
+                if *t > now {
+                    break;
+                }
+                sum += *e;
+            }
+            per_road.insert(*r, sum);
+        }
+        let mut per_intersection = BTreeMap::new();
+        for (i, list) in &self.intersection_emissions {
+            let mut sum = Emissions::zero();
+            for (t, e) in list {
+                if *t > now {
+                    break;
+                }
+                sum += *e;
+            }
+            per_intersection.insert(*i, sum);
+        }
+        (per_road, per_intersection)
+    }
+
+    // For every sidewalk that anybody used during some hour, how many square meters did each
+    // person have at the most crowded moment? This is the basis for pedestrian level of service.
+    pub fn sidewalk_space_per_ped(&self, hour: usize, map: &Map) -> Vec<(LaneID, f64)> {
+        let mut results = Vec::new();
+        for ((l, hr), count) in &self.sidewalk_peak_occupancy {
+            if *hr == hour && *count > 0 {
+                results.push((
+                    *l,
+                    walkable_area(Traversable::Lane(*l), map) / (*count as f64),
+                ));
+            }
+        }
+        results
+    }
+
     pub fn record_demand(&mut self, path: &Path, map: &Map) {
         for step in path.get_steps() {
             if let Traversable::Turn(t) = step.as_traversable() {
@@ -240,12 +446,12 @@ impl Analytics {
         None
     }
 
-    // Returns pairs of trip times for finished trips in both worlds. (before, after, mode)
+    // Returns pairs of trip times for finished trips in both worlds. (ID, before, after, mode)
     pub fn both_finished_trips(
         &self,
         now: Time,
         before: &Analytics,
-    ) -> Vec<(Duration, Duration, TripMode)> {
+    ) -> Vec<(TripID, Duration, Duration, TripMode)> {
         let mut a = BTreeMap::new();
         for (t, id, maybe_mode, dt) in &self.finished_trips {
             if *t > now {
@@ -263,7 +469,7 @@ impl Analytics {
             }
             if let Some(mode) = maybe_mode {
                 if let Some(dt1) = a.remove(id) {
-                    results.push((*dt, dt1, *mode));
+                    results.push((*id, *dt, dt1, *mode));
                 }
             }
         }
@@ -422,6 +628,55 @@ impl Analytics {
         }
     }
 
+    // Per hour, returns the revenue in cents
+    pub fn parking_revenue_per_hour(&self, now: Time, facility: ParkingFacility) -> Vec<usize> {
+        let mut per_hour = vec![0; now.get_hours() + 1];
+        for (t, f, cents) in &self.parking_payments {
+            if *t > now {
+                break;
+            }
+            if *f == facility {
+                per_hour[t.get_hours()] += cents;
+            }
+        }
+        per_hour
+    }
+
+    // Per hour, returns the average fraction of spots that were filled
+    pub fn parking_occupancy_per_hour(
+        &self,
+        now: Time,
+        facility: ParkingFacility,
+        capacity: usize,
+    ) -> Vec<f64> {
+        if capacity == 0 {
+            return Vec::new();
+        }
+        let pts = match facility {
+            ParkingFacility::Onstreet(l) => self.parking_lane_availability(now, l, capacity),
+            ParkingFacility::Lot(pl) => self.parking_lot_availability(now, pl, capacity),
+        };
+        let mut filled_time = vec![Duration::ZERO; now.get_hours() + 1];
+        // Integrate the step function, splitting each step at hour boundaries
+        for pair in pts.windows(2) {
+            let (t1, free) = pair[0];
+            let t2 = pair[1].0;
+            let filled = (capacity - free) as f64;
+            let mut t = t1;
+            while t < t2 {
+                let hour = t.get_hours();
+                let end_of_hour = Time::START_OF_DAY + Duration::hours(hour + 1);
+                let step_end = end_of_hour.min(t2);
+                filled_time[hour] += filled * (step_end - t);
+                t = step_end;
+            }
+        }
+        filled_time
+            .into_iter()
+            .map(|d| d / (Duration::hours(1) * (capacity as f64)))
+            .collect()
+    }
+
     fn parking_spot_availability(
         now: Time,
         changes: &Vec<(Time, bool)>,
@@ -446,6 +701,12 @@ impl Analytics {
                 }
                 cnt -= 1;
             } else {
+                if cnt == capacity {
+                    panic!(
+                        "parking_spot_availability at {} went above capacity {}",
+                        t, capacity
+                    );
+                }
                 cnt += 1;
             }
         }
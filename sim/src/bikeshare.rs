@@ -0,0 +1,101 @@
+use crate::{
+    CarID, Event, PersonID, Vehicle, VehicleSpec, VehicleType, BIKE_ACCEL, BIKE_DECEL, BIKE_LENGTH,
+};
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::Speed;
+use map_model::{BikeShareDockID, Map};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// At the start of the day, docks are this full
+const INITIAL_FILL: f64 = 0.5;
+
+// Tracks how many shared bikes are sitting at each dock. The bikes themselves are
+// interchangeable; they only get a CarID while somebody's riding one.
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct BikeShareSimState {
+    // (bikes docked, capacity). There's no good way to model riders circling around looking for an
+    // open dock, so they squeeze bikes into full docks, and the count can exceed capacity.
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    docks: BTreeMap<BikeShareDockID, (usize, usize)>,
+
+    events: Vec<Event>,
+}
+
+impl BikeShareSimState {
+    pub fn new(map: &Map) -> BikeShareSimState {
+        let mut docks = BTreeMap::new();
+        for dock in map.all_bike_share_docks() {
+            docks.insert(dock.id, (initial_bikes(dock.capacity), dock.capacity));
+        }
+        BikeShareSimState {
+            docks,
+            events: Vec::new(),
+        }
+    }
+
+    // Shared bikes are all the same, and a bit slower than the bikes people own.
+    pub fn make_bike(id: CarID, rider: PersonID) -> Vehicle {
+        VehicleSpec {
+            vehicle_type: VehicleType::Bike,
+            length: BIKE_LENGTH,
+            max_speed: Some(Speed::miles_per_hour(8.0)),
+            max_accel: BIKE_ACCEL,
+            max_decel: BIKE_DECEL,
+        }
+        .make(id, Some(rider))
+    }
+
+    // Returns false if the dock is empty.
+    pub fn take_bike(&mut self, dock: BikeShareDockID) -> bool {
+        let (bikes, _) = self.docks.get_mut(&dock).unwrap();
+        if *bikes == 0 {
+            self.events.push(Event::BikeShareUnavailable(dock));
+            return false;
+        }
+        *bikes -= 1;
+        if *bikes == 0 {
+            self.events.push(Event::BikeShareDockEmpty(dock));
+        }
+        true
+    }
+
+    pub fn return_bike(&mut self, dock: BikeShareDockID) {
+        let (bikes, capacity) = self.docks.get_mut(&dock).unwrap();
+        *bikes += 1;
+        if *bikes == *capacity {
+            self.events.push(Event::BikeShareDockFull(dock));
+        }
+    }
+
+    // (bikes docked, capacity)
+    pub fn dock_status(&self, dock: BikeShareDockID) -> (usize, usize) {
+        self.docks[&dock]
+    }
+
+    // For every empty or full dock, how many bikes should an operator drop off (positive) or
+    // haul away (negative) to restore the starting fill?
+    pub fn rebalancing_needs(&self) -> Vec<(BikeShareDockID, isize)> {
+        let mut needs = Vec::new();
+        for (dock, (bikes, capacity)) in &self.docks {
+            if *bikes == 0 || *bikes >= *capacity {
+                let delta = (initial_bikes(*capacity) as isize) - (*bikes as isize);
+                if delta != 0 {
+                    needs.push((*dock, delta));
+                }
+            }
+        }
+        needs
+    }
+
+    pub fn collect_events(&mut self) -> Vec<Event> {
+        std::mem::replace(&mut self.events, Vec::new())
+    }
+}
+
+fn initial_bikes(capacity: usize) -> usize {
+    ((capacity as f64) * INITIAL_FILL).ceil() as usize
+}
@@ -0,0 +1,132 @@
+use crate::VehicleType;
+use geom::{Distance, Duration};
+use serde::{Deserialize, Serialize};
+use std::ops;
+
+// Rough estimates of what a vehicle burned and emitted. electric_kwh is what the same movement
+// would take in an electric version of the vehicle, so the two can be compared.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Emissions {
+    pub fuel_liters: f64,
+    pub co2_grams: f64,
+    pub nox_grams: f64,
+    pub electric_kwh: f64,
+}
+
+impl Emissions {
+    pub fn zero() -> Emissions {
+        Emissions {
+            fuel_liters: 0.0,
+            co2_grams: 0.0,
+            nox_grams: 0.0,
+            electric_kwh: 0.0,
+        }
+    }
+
+    // A simple speed-based emission factor model. Per kilometer, fuel use grows with the square of
+    // the average speed (drag). Per hour, the engine burns fuel idling, which is what makes slow,
+    // stop-and-go movement so expensive. Electric vehicles don't use anything while stopped.
+    pub fn estimate(vehicle_type: VehicleType, dist: Distance, time: Duration) -> Emissions {
+        let f = match EmissionFactors::new(vehicle_type) {
+            Some(f) => f,
+            None => {
+                return Emissions::zero();
+            }
+        };
+        let km = dist.inner_meters() / 1000.0;
+        let hours = time.inner_seconds() / 3600.0;
+        let kph = if hours > 0.0 { km / hours } else { 0.0 };
+
+        let fuel_liters = km * (f.base_fuel + f.fuel_per_kph2 * kph * kph) + hours * f.idle_fuel;
+        Emissions {
+            fuel_liters,
+            co2_grams: fuel_liters * f.co2_per_liter,
+            nox_grams: km * f.nox_per_km + hours * f.idle_nox,
+            electric_kwh: km * (f.base_kwh + f.kwh_per_kph2 * kph * kph),
+        }
+    }
+}
+
+impl ops::AddAssign for Emissions {
+    fn add_assign(&mut self, other: Emissions) {
+        self.fuel_liters += other.fuel_liters;
+        self.co2_grams += other.co2_grams;
+        self.nox_grams += other.nox_grams;
+        self.electric_kwh += other.electric_kwh;
+    }
+}
+
+// Ballpark numbers for a typical vehicle of each type. Liters per km, liters per hour, grams per
+// km or hour, kWh per km.
+struct EmissionFactors {
+    base_fuel: f64,
+    fuel_per_kph2: f64,
+    idle_fuel: f64,
+    co2_per_liter: f64,
+    nox_per_km: f64,
+    idle_nox: f64,
+    base_kwh: f64,
+    kwh_per_kph2: f64,
+}
+
+const GASOLINE_CO2_PER_LITER: f64 = 2310.0;
+const DIESEL_CO2_PER_LITER: f64 = 2680.0;
+
+impl EmissionFactors {
+    fn new(vehicle_type: VehicleType) -> Option<EmissionFactors> {
+        match vehicle_type {
+            VehicleType::Car | VehicleType::RideHail => Some(EmissionFactors {
+                base_fuel: 0.06,
+                fuel_per_kph2: 0.000004,
+                idle_fuel: 0.6,
+                co2_per_liter: GASOLINE_CO2_PER_LITER,
+                nox_per_km: 0.06,
+                idle_nox: 0.5,
+                base_kwh: 0.12,
+                kwh_per_kph2: 0.000008,
+            }),
+            VehicleType::Van => Some(EmissionFactors {
+                base_fuel: 0.09,
+                fuel_per_kph2: 0.000005,
+                idle_fuel: 0.9,
+                co2_per_liter: DIESEL_CO2_PER_LITER,
+                nox_per_km: 0.3,
+                idle_nox: 3.0,
+                base_kwh: 0.22,
+                kwh_per_kph2: 0.00001,
+            }),
+            VehicleType::Truck => Some(EmissionFactors {
+                base_fuel: 0.25,
+                fuel_per_kph2: 0.00001,
+                idle_fuel: 2.5,
+                co2_per_liter: DIESEL_CO2_PER_LITER,
+                nox_per_km: 3.0,
+                idle_nox: 20.0,
+                base_kwh: 1.0,
+                kwh_per_kph2: 0.00003,
+            }),
+            VehicleType::Bus => Some(EmissionFactors {
+                base_fuel: 0.35,
+                fuel_per_kph2: 0.00001,
+                idle_fuel: 3.0,
+                co2_per_liter: DIESEL_CO2_PER_LITER,
+                nox_per_km: 4.0,
+                idle_nox: 25.0,
+                base_kwh: 1.1,
+                kwh_per_kph2: 0.00003,
+            }),
+            // Light rail is already electric.
+            VehicleType::Train => Some(EmissionFactors {
+                base_fuel: 0.0,
+                fuel_per_kph2: 0.0,
+                idle_fuel: 0.0,
+                co2_per_liter: 0.0,
+                nox_per_km: 0.0,
+                idle_nox: 0.0,
+                base_kwh: 4.0,
+                kwh_per_kph2: 0.0,
+            }),
+            VehicleType::Bike => None,
+        }
+    }
+}
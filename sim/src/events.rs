@@ -1,9 +1,10 @@
 use crate::{
     AgentID, CarID, OffMapLocation, ParkingSpot, PedestrianID, PersonID, TripID, TripMode,
 };
-use geom::Duration;
+use geom::{Distance, Duration};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path, PathRequest, Traversable,
+    BikeShareDockID, BuildingID, BusRouteID, BusStopID, IntersectionID, LaneID, Map, Path,
+    PathRequest, Position, Traversable,
 };
 use serde::{Deserialize, Serialize};
 
@@ -37,10 +38,32 @@ pub enum Event {
     PedReachedParkingSpot(PedestrianID, ParkingSpot),
 
     BikeStoppedAtSidewalk(CarID, LaneID),
+    // A delivery vehicle double-parked near a building for some duration
+    FreightStop(CarID, BuildingID, Position, Duration),
+    // A ride-hail vehicle finished a ride. Measures how long the passenger waited to be picked up,
+    // how far the vehicle drove empty to reach them, and how long the vehicle was busy with this
+    // ride, from being dispatched to the dropoff.
+    RideHailDropoff {
+        car: CarID,
+        person: PersonID,
+        wait: Duration,
+        deadhead: Distance,
+        busy: Duration,
+    },
+    // Somebody took the last bike from a dock
+    BikeShareDockEmpty(BikeShareDockID),
+    // Somebody returned a bike, filling the dock
+    BikeShareDockFull(BikeShareDockID),
+    // Somebody arrived at an empty dock and had to walk instead
+    BikeShareUnavailable(BikeShareDockID),
+    // A vehicle finished the driving or biking part of a trip
+    VehicleFinishedTripLeg(CarID, TripID),
 
     // If the agent is a transit vehicle, then include a count of how many passengers are on
     // board.
     AgentEntersTraversable(AgentID, Traversable, Option<usize>),
+    // Somebody stepped onto a sidewalk, and now this many people are on it
+    SidewalkOccupancy(LaneID, usize),
     IntersectionDelayMeasured(IntersectionID, Duration, AgentID),
 
     TripFinished {
@@ -76,6 +99,8 @@ pub enum TripPhaseType {
     WaitingForBus(BusRouteID, BusStopID),
     // What stop did they board at?
     RidingBus(BusRouteID, BusStopID, CarID),
+    WaitingForRideHail,
+    RidingRideHail(CarID),
     Aborted,
     Finished,
     DelayedStart,
@@ -93,6 +118,8 @@ impl TripPhaseType {
                 format!("waiting for bus {}", map.get_br(r).full_name)
             }
             TripPhaseType::RidingBus(r, _, _) => format!("riding bus {}", map.get_br(r).full_name),
+            TripPhaseType::WaitingForRideHail => "waiting to be picked up".to_string(),
+            TripPhaseType::RidingRideHail(car) => format!("riding in {}", car),
             TripPhaseType::Aborted => "trip aborted due to some bug".to_string(),
             TripPhaseType::Finished => "trip finished".to_string(),
             TripPhaseType::DelayedStart => "delayed by previous trip taking too long".to_string(),
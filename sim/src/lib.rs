@@ -1,9 +1,12 @@
 mod analytics;
+mod bikeshare;
+mod emissions;
 mod events;
 mod make;
 mod mechanics;
 mod pandemic;
 mod render;
+mod ridehail;
 mod router;
 mod scheduler;
 mod sim;
@@ -11,32 +14,39 @@ mod transit;
 mod trips;
 
 pub use self::analytics::{Analytics, TripPhase};
+pub(crate) use self::bikeshare::BikeShareSimState;
+pub use self::emissions::Emissions;
 pub(crate) use self::events::Event;
 pub use self::events::{AlertLocation, TripPhaseType};
 pub use self::make::{
-    BorderSpawnOverTime, IndividTrip, OffMapLocation, OriginDestination, PersonSpec, Scenario,
-    ScenarioGenerator, ScenarioModifier, SimFlags, SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
+    AgeBand, BorderSpawnOverTime, DevelopmentSize, FreightOverTime, FreightTour, IncomeBand,
+    IndividTrip, LandUse, Mobility, NewDevelopment, ODFlow, ODMatrix, ODZone, OffMapLocation,
+    OriginDestination, ParkingObservation, ParkingSurvey, ParkingSurveyComparison,
+    PersonAttributes, PersonSpec, Scenario, ScenarioGenerator, ScenarioModifier, SimFlags,
+    SpawnOverTime, SpawnTrip, TripSpawner, TripSpec,
 };
 pub(crate) use self::mechanics::{
     DrivingSimState, IntersectionSimState, ParkingSimState, WalkingSimState,
 };
 pub(crate) use self::pandemic::PandemicModel;
-pub(crate) use self::router::{ActionAtEnd, Router};
+pub use self::ridehail::DispatchPolicy;
+pub(crate) use self::ridehail::RideHailSimState;
+pub(crate) use self::router::{ActionAtEnd, DeliveryLeg, Router};
 pub(crate) use self::scheduler::{Command, Scheduler};
 pub use self::sim::{AgentProperties, AlertHandler, Sim, SimCallback, SimOptions};
 pub(crate) use self::transit::TransitSimState;
 pub use self::trips::{Person, PersonState, TripInfo, TripResult};
-pub use self::trips::{TripEndpoint, TripMode};
+pub use self::trips::{TripEndpoint, TripMode, TripPurpose};
 pub(crate) use self::trips::{TripLeg, TripManager};
 pub use crate::render::{
     CarStatus, DontDrawAgents, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, GetDrawAgents,
     PedCrowdLocation, UnzoomedAgent,
 };
 use abstutil::{deserialize_usize, serialize_usize, Cloneable};
-use geom::{Distance, Pt2D, Speed, Time};
+use geom::{Distance, Duration, Pt2D, Speed, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, DirectedRoadID, IntersectionID, LaneID, Map, ParkingLotID,
-    Path, PathConstraints, PathRequest, Position,
+    BikeShareDockID, BuildingID, BusRouteID, BusStopID, DirectedRoadID, IntersectionID, LaneID,
+    Map, ParkingFacility, ParkingLotID, Path, PathConstraints, PathRequest, Position, Traversable,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -49,6 +59,25 @@ pub const MAX_CAR_LENGTH: Distance = Distance::const_meters(6.5);
 // Note this is more than MAX_CAR_LENGTH
 pub const BUS_LENGTH: Distance = Distance::const_meters(12.5);
 pub const LIGHT_RAIL_LENGTH: Distance = Distance::const_meters(60.0);
+// Vans fit in one parking spot, so they can use loading zones. Trucks are longer, so they always
+// double-park.
+pub const VAN_LENGTH: Distance = Distance::const_meters(7.0);
+pub const TRUCK_LENGTH: Distance = Distance::const_meters(11.0);
+// Must be less than the 7m buffer used to pull up to the curb
+pub const RIDE_HAIL_LENGTH: Distance = Distance::const_meters(5.0);
+
+// Max acceleration and normal braking, in meters per second squared. Heavier vehicles are slower
+// to get going, which matters most for how fast a queue discharges.
+pub const CAR_ACCEL: f64 = 2.5;
+pub const CAR_DECEL: f64 = 3.5;
+pub const BIKE_ACCEL: f64 = 1.0;
+pub const BIKE_DECEL: f64 = 2.0;
+pub const BUS_ACCEL: f64 = 1.2;
+pub const BUS_DECEL: f64 = 2.0;
+pub const VAN_ACCEL: f64 = 1.5;
+pub const VAN_DECEL: f64 = 3.0;
+pub const TRUCK_ACCEL: f64 = 0.8;
+pub const TRUCK_DECEL: f64 = 2.0;
 
 // At all speeds (including at rest), cars must be at least this far apart, measured from front of
 // one car to the back of the other.
@@ -78,6 +107,9 @@ impl fmt::Display for CarID {
             VehicleType::Bus => write!(f, "Bus #{}", self.0),
             VehicleType::Train => write!(f, "Train #{}", self.0),
             VehicleType::Bike => write!(f, "Bike #{}", self.0),
+            VehicleType::Van => write!(f, "Van #{}", self.0),
+            VehicleType::Truck => write!(f, "Truck #{}", self.0),
+            VehicleType::RideHail => write!(f, "Ride-hail #{}", self.0),
         }
     }
 }
@@ -101,7 +133,7 @@ impl fmt::Display for PedestrianID {
 pub enum AgentID {
     Car(CarID),
     Pedestrian(PedestrianID),
-    // TODO Rename...
+    // TODO Rename... Anybody riding in a vehicle they don't drive, including ride-hailing.
     BusPassenger(PersonID, CarID),
 }
 
@@ -116,7 +148,11 @@ impl AgentID {
     pub fn to_type(self) -> AgentType {
         match self {
             AgentID::Car(c) => match c.1 {
-                VehicleType::Car => AgentType::Car,
+                // Freight and ride-hailing count as car traffic
+                VehicleType::Car
+                | VehicleType::Van
+                | VehicleType::Truck
+                | VehicleType::RideHail => AgentType::Car,
                 VehicleType::Bike => AgentType::Bike,
                 VehicleType::Bus => AgentType::Bus,
                 VehicleType::Train => AgentType::Train,
@@ -231,6 +267,11 @@ pub enum VehicleType {
     Bus,
     Train,
     Bike,
+    // Delivery vehicles, making stops that block a driving lane
+    Van,
+    Truck,
+    // Owned by a fleet, picking up and dropping off passengers
+    RideHail,
 }
 
 impl fmt::Display for VehicleType {
@@ -240,6 +281,9 @@ impl fmt::Display for VehicleType {
             VehicleType::Bus => write!(f, "bus"),
             VehicleType::Train => write!(f, "train"),
             VehicleType::Bike => write!(f, "bike"),
+            VehicleType::Van => write!(f, "van"),
+            VehicleType::Truck => write!(f, "truck"),
+            VehicleType::RideHail => write!(f, "ride-hail vehicle"),
         }
     }
 }
@@ -247,7 +291,9 @@ impl fmt::Display for VehicleType {
 impl VehicleType {
     pub fn to_constraints(self) -> PathConstraints {
         match self {
-            VehicleType::Car => PathConstraints::Car,
+            VehicleType::Car | VehicleType::Van | VehicleType::Truck | VehicleType::RideHail => {
+                PathConstraints::Car
+            }
             VehicleType::Bus => PathConstraints::Bus,
             VehicleType::Train => PathConstraints::Train,
             VehicleType::Bike => PathConstraints::Bike,
@@ -260,8 +306,14 @@ impl VehicleType {
             VehicleType::Bus => true,
             VehicleType::Train => true,
             VehicleType::Bike => false,
+            VehicleType::Van | VehicleType::Truck => false,
+            VehicleType::RideHail => false,
         }
     }
+
+    pub fn is_freight(self) -> bool {
+        self == VehicleType::Van || self == VehicleType::Truck
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -271,6 +323,30 @@ pub struct Vehicle {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    pub max_accel: f64,
+    pub max_decel: f64,
+}
+
+impl Vehicle {
+    // Cyclists slow down going uphill and speed up a bit going downhill. Turns are treated as flat.
+    pub fn max_speed_on(&self, on: Traversable, map: &Map) -> Option<Speed> {
+        let speed = self.max_speed?;
+        if self.vehicle_type != VehicleType::Bike {
+            return Some(speed);
+        }
+        match on {
+            Traversable::Lane(l) => Some(
+                speed
+                    * (1.0
+                        / map.get_l(l).grade_time_factor(
+                            false,
+                            map_model::biking_speed_on_grade,
+                            map,
+                        )),
+            ),
+            Traversable::Turn(_) => Some(speed),
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -278,6 +354,8 @@ pub struct VehicleSpec {
     pub vehicle_type: VehicleType,
     pub length: Distance,
     pub max_speed: Option<Speed>,
+    pub max_accel: f64,
+    pub max_decel: f64,
 }
 
 impl VehicleSpec {
@@ -289,6 +367,8 @@ impl VehicleSpec {
             vehicle_type: self.vehicle_type,
             length: self.length,
             max_speed: self.max_speed,
+            max_accel: self.max_accel,
+            max_decel: self.max_decel,
         }
     }
 }
@@ -302,6 +382,17 @@ pub enum ParkingSpot {
     Lot(ParkingLotID, usize),
 }
 
+impl ParkingSpot {
+    // Offstreet spots belong to buildings and are always free.
+    pub fn facility(&self) -> Option<ParkingFacility> {
+        match self {
+            ParkingSpot::Onstreet(l, _) => Some(ParkingFacility::Onstreet(*l)),
+            ParkingSpot::Offstreet(_, _) => None,
+            ParkingSpot::Lot(pl, _) => Some(ParkingFacility::Lot(*pl)),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct ParkedCar {
     pub vehicle: Vehicle,
@@ -347,13 +438,20 @@ impl DrivingGoal {
         }
     }
 
-    pub(crate) fn make_router(&self, owner: CarID, path: Path, map: &Map) -> Router {
+    // The expected stay is only used to weigh the price of parking.
+    pub(crate) fn make_router(
+        &self,
+        owner: CarID,
+        path: Path,
+        stay: Duration,
+        map: &Map,
+    ) -> Router {
         match self {
             DrivingGoal::ParkNear(b) => {
                 if owner.1 == VehicleType::Bike {
                     Router::bike_then_stop(owner, path, SidewalkSpot::bike_rack(*b, map).unwrap())
                 } else {
-                    Router::park_near(owner, path, *b)
+                    Router::park_near(owner, path, *b, stay)
                 }
             }
             DrivingGoal::Border(i, last_lane, _) => {
@@ -388,6 +486,10 @@ pub enum SidewalkPOI {
     Border(IntersectionID, Option<OffMapLocation>),
     // The bikeable position
     BikeRack(Position),
+    // Where a ride-hail vehicle pulls up to the curb
+    RideHailStop(Position),
+    // The bikeable position next to the dock
+    BikeShareDock(BikeShareDockID, Position),
     SuddenlyAppear,
 }
 
@@ -429,6 +531,32 @@ impl SidewalkSpot {
         })
     }
 
+    pub fn bike_share_dock(dock: BikeShareDockID, map: &Map) -> Option<SidewalkSpot> {
+        let dock = map.get_bsd(dock);
+        Some(SidewalkSpot {
+            connection: SidewalkPOI::BikeShareDock(dock.id, dock.biking_connection(map)?),
+            sidewalk_pos: dock.sidewalk_pos,
+        })
+    }
+
+    // Wait right here on the sidewalk for a ride. Fails if there's no nearby driving lane to pull
+    // up to.
+    pub fn ride_hail_stop(sidewalk_pos: Position, map: &Map) -> Option<SidewalkSpot> {
+        let sidewalk = sidewalk_pos.lane();
+        let lane = map.get_parent(sidewalk).find_closest_lane(
+            sidewalk,
+            |l| PathConstraints::Car.can_use(l, map),
+            map,
+        )?;
+        let driving_pos = sidewalk_pos
+            .equiv_pos(lane, map)
+            .buffer_dist(Distance::meters(7.0), map)?;
+        Some(SidewalkSpot {
+            connection: SidewalkPOI::RideHailStop(driving_pos),
+            sidewalk_pos,
+        })
+    }
+
     pub fn bus_stop(stop: BusStopID, map: &Map) -> SidewalkSpot {
         SidewalkSpot {
             sidewalk_pos: map.get_bs(stop).sidewalk_pos,
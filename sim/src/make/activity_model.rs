@@ -1,6 +1,6 @@
 use crate::{
-    IndividTrip, PersonID, PersonSpec, Scenario, ScenarioGenerator, SpawnTrip, TripEndpoint,
-    TripMode,
+    IndividTrip, PersonAttributes, PersonID, PersonSpec, Scenario, ScenarioGenerator, SpawnTrip,
+    TripEndpoint, TripMode, TripPurpose,
 };
 use abstutil::Timer;
 use geom::{Distance, Duration, Time};
@@ -45,82 +45,17 @@ impl ScenarioGenerator {
                 // Make a person going from their home to a random workplace, then back again later.
 
                 let work = *workplaces.choose(rng).unwrap();
-                // Decide mode based on walking distance.
-                let dist = if let Some(path) = map.pathfind(PathRequest {
-                    start: map.get_b(home).sidewalk_pos,
-                    end: map.get_b(work).sidewalk_pos,
-                    constraints: PathConstraints::Pedestrian,
-                }) {
-                    path.total_length()
-                } else {
-                    // Woops, the buildings aren't connected. Probably a bug in importing. Just skip
-                    // this person.
-                    continue;
-                };
-                if home == work {
-                    // working and living in the same building
-                    continue;
+                if let Some(trips) = commute(home, work, map, rng) {
+                    s.people.push(PersonSpec {
+                        id: PersonID(s.people.len()),
+                        orig_id: None,
+                        trips,
+                        attributes: PersonAttributes {
+                            home: Some(home),
+                            ..Default::default()
+                        },
+                    });
                 }
-                // TODO If home or work is in an access-restricted zone (like a living street),
-                // then probably don't drive there. Actually, it depends on the specific tagging;
-                // access=no in the US usually means a gated community.
-                let mode = select_trip_mode(dist, rng);
-
-                // TODO This will cause a single morning and afternoon rush. Outside of these times,
-                // it'll be really quiet. Probably want a normal distribution centered around these
-                // peak times, but with a long tail.
-                let mut depart_am = rand_time(
-                    rng,
-                    Time::START_OF_DAY + Duration::hours(7),
-                    Time::START_OF_DAY + Duration::hours(10),
-                );
-                let mut depart_pm = rand_time(
-                    rng,
-                    Time::START_OF_DAY + Duration::hours(17),
-                    Time::START_OF_DAY + Duration::hours(19),
-                );
-
-                if rng.gen_bool(0.1) {
-                    // hacky hack to get some background traffic
-                    depart_am = rand_time(
-                        rng,
-                        Time::START_OF_DAY + Duration::hours(0),
-                        Time::START_OF_DAY + Duration::hours(12),
-                    );
-                    depart_pm = rand_time(
-                        rng,
-                        Time::START_OF_DAY + Duration::hours(12),
-                        Time::START_OF_DAY + Duration::hours(24),
-                    );
-                }
-
-                let (goto_work, return_home) = match (
-                    SpawnTrip::new(
-                        TripEndpoint::Bldg(home),
-                        TripEndpoint::Bldg(work),
-                        mode,
-                        map,
-                    ),
-                    SpawnTrip::new(
-                        TripEndpoint::Bldg(work),
-                        TripEndpoint::Bldg(home),
-                        mode,
-                        map,
-                    ),
-                ) {
-                    (Some(t1), Some(t2)) => (t1, t2),
-                    // Skip the person if either trip can't be created.
-                    _ => continue,
-                };
-
-                s.people.push(PersonSpec {
-                    id: PersonID(s.people.len()),
-                    orig_id: None,
-                    trips: vec![
-                        IndividTrip::new(depart_am, goto_work),
-                        IndividTrip::new(depart_pm, return_home),
-                    ],
-                });
             }
         }
 
@@ -180,17 +115,92 @@ impl ScenarioGenerator {
                 id: PersonID(s.people.len()),
                 orig_id: None,
                 trips: vec![
-                    IndividTrip::new(depart_am, goto_work),
-                    IndividTrip::new(depart_pm, return_home),
+                    IndividTrip::new(depart_am, TripPurpose::Work, goto_work),
+                    IndividTrip::new(depart_pm, TripPurpose::Home, return_home),
                 ],
+                attributes: PersonAttributes::default(),
             });
         }
 
+        s.synthesize_attributes(map, rng);
         s
     }
 }
 
-fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
+// Go from home to work in the morning, then back again later. None if the buildings aren't
+// connected, or they're the same.
+pub(crate) fn commute(
+    home: BuildingID,
+    work: BuildingID,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Option<Vec<IndividTrip>> {
+    // Decide mode based on walking distance. If the buildings aren't connected, it's probably a bug
+    // in importing. Just skip this person.
+    let dist = map
+        .pathfind(PathRequest {
+            start: map.get_b(home).sidewalk_pos,
+            end: map.get_b(work).sidewalk_pos,
+            constraints: PathConstraints::Pedestrian,
+        })?
+        .total_length();
+    if home == work {
+        // working and living in the same building
+        return None;
+    }
+    // TODO If home or work is in an access-restricted zone (like a living street), then probably
+    // don't drive there. Actually, it depends on the specific tagging; access=no in the US usually
+    // means a gated community.
+    let mode = select_trip_mode(dist, rng);
+
+    // TODO This will cause a single morning and afternoon rush. Outside of these times, it'll be
+    // really quiet. Probably want a normal distribution centered around these peak times, but with
+    // a long tail.
+    let mut depart_am = rand_time(
+        rng,
+        Time::START_OF_DAY + Duration::hours(7),
+        Time::START_OF_DAY + Duration::hours(10),
+    );
+    let mut depart_pm = rand_time(
+        rng,
+        Time::START_OF_DAY + Duration::hours(17),
+        Time::START_OF_DAY + Duration::hours(19),
+    );
+
+    if rng.gen_bool(0.1) {
+        // hacky hack to get some background traffic
+        depart_am = rand_time(
+            rng,
+            Time::START_OF_DAY + Duration::hours(0),
+            Time::START_OF_DAY + Duration::hours(12),
+        );
+        depart_pm = rand_time(
+            rng,
+            Time::START_OF_DAY + Duration::hours(12),
+            Time::START_OF_DAY + Duration::hours(24),
+        );
+    }
+
+    // Skip the person if either trip can't be created.
+    let goto_work = SpawnTrip::new(
+        TripEndpoint::Bldg(home),
+        TripEndpoint::Bldg(work),
+        mode,
+        map,
+    )?;
+    let return_home = SpawnTrip::new(
+        TripEndpoint::Bldg(work),
+        TripEndpoint::Bldg(home),
+        mode,
+        map,
+    )?;
+    Some(vec![
+        IndividTrip::new(depart_am, TripPurpose::Work, goto_work),
+        IndividTrip::new(depart_pm, TripPurpose::Home, return_home),
+    ])
+}
+
+pub(crate) fn select_trip_mode(distance: Distance, rng: &mut XorShiftRng) -> TripMode {
     // TODO Make this probabilistic
     // for example probability of walking currently has massive differences
     // at thresholds, it would be nicer to change this graduall
@@ -0,0 +1,342 @@
+use crate::make::activity_model::select_trip_mode;
+use crate::{
+    IndividTrip, PersonAttributes, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint,
+    TripPurpose,
+};
+use geom::{Distance, Duration, Pt2D, Time};
+use map_model::{BuildingID, BuildingType, Map, PathConstraints, PathRequest};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+// Travel demand for a proposed development, generated from its size and land use with standard
+// trip rates. Destinations are distributed with a gravity model over existing buildings.
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
+pub struct NewDevelopment {
+    pub bldgs: BTreeSet<BuildingID>,
+    pub land_use: LandUse,
+    pub size: DevelopmentSize,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum LandUse {
+    Residential,
+    Office,
+    Retail,
+}
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum DevelopmentSize {
+    // Dwelling units for residential, employees for offices, and shops for retail
+    Units(usize),
+    // Square meters
+    FloorArea(usize),
+}
+
+// Used to convert between units and floor area
+const SQ_METERS_PER_DWELLING: f64 = 80.0;
+const SQ_METERS_PER_EMPLOYEE: f64 = 25.0;
+const SQ_METERS_PER_SHOP: f64 = 200.0;
+
+// One kind of round trip, either leaving from the development and coming back, or coming to the
+// development from elsewhere and returning.
+struct TripRate {
+    // Of the first trip; the return trip is always going home
+    purpose: TripPurpose,
+    // Round trips per day per 100 square meters of floor area
+    per_100_sq_meters: f64,
+    // Relative chance of the first trip departing during each hour of the day
+    hourly: [usize; 24],
+    // How long people stay before the return trip
+    stay: (Duration, Duration),
+    // Controls how quickly the gravity model's attraction falls off with straight-line distance
+    avg_dist: Distance,
+}
+
+// These are loosely based on typical trip generation rates for the US, converted from vehicle
+// trips to person round trips. They should be calibrated per city.
+fn trip_rates(land_use: LandUse) -> Vec<TripRate> {
+    match land_use {
+        LandUse::Residential => vec![
+            // Commuting
+            TripRate {
+                purpose: TripPurpose::Work,
+                per_100_sq_meters: 1.5,
+                hourly: [
+                    0, 0, 0, 0, 0, 2, 6, 14, 16, 10, 4, 2, 2, 2, 2, 1, 1, 1, 0, 0, 0, 0, 0, 0,
+                ],
+                stay: (Duration::hours(7), Duration::hours(10)),
+                avg_dist: Distance::meters(4000.0),
+            },
+            // Errands
+            TripRate {
+                purpose: TripPurpose::Shopping,
+                per_100_sq_meters: 2.0,
+                hourly: [
+                    0, 0, 0, 0, 0, 0, 1, 2, 3, 4, 6, 8, 8, 7, 6, 6, 7, 8, 8, 6, 4, 2, 1, 0,
+                ],
+                stay: (Duration::minutes(20), Duration::hours(2)),
+                avg_dist: Distance::meters(1500.0),
+            },
+        ],
+        LandUse::Office => vec![
+            // Employees
+            TripRate {
+                purpose: TripPurpose::Work,
+                per_100_sq_meters: 3.5,
+                hourly: [
+                    0, 0, 0, 0, 0, 1, 4, 12, 16, 12, 4, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+                ],
+                stay: (Duration::hours(7), Duration::hours(10)),
+                avg_dist: Distance::meters(5000.0),
+            },
+            // Visitors
+            TripRate {
+                purpose: TripPurpose::PersonalBusiness,
+                per_100_sq_meters: 0.5,
+                hourly: [
+                    0, 0, 0, 0, 0, 0, 0, 1, 3, 5, 5, 4, 3, 4, 5, 4, 2, 1, 0, 0, 0, 0, 0, 0,
+                ],
+                stay: (Duration::minutes(30), Duration::hours(2)),
+                avg_dist: Distance::meters(3000.0),
+            },
+        ],
+        LandUse::Retail => vec![
+            // Customers
+            TripRate {
+                purpose: TripPurpose::Shopping,
+                per_100_sq_meters: 6.0,
+                hourly: [
+                    0, 0, 0, 0, 0, 0, 0, 1, 2, 4, 6, 8, 9, 8, 7, 7, 8, 9, 9, 7, 5, 3, 1, 0,
+                ],
+                stay: (Duration::minutes(10), Duration::hours(1)),
+                avg_dist: Distance::meters(1500.0),
+            },
+            // Employees
+            TripRate {
+                purpose: TripPurpose::Work,
+                per_100_sq_meters: 1.5,
+                hourly: [
+                    0, 0, 0, 0, 0, 1, 3, 6, 8, 6, 3, 2, 2, 2, 3, 3, 2, 1, 0, 0, 0, 0, 0, 0,
+                ],
+                stay: (Duration::hours(4), Duration::hours(9)),
+                avg_dist: Distance::meters(4000.0),
+            },
+        ],
+    }
+}
+
+impl NewDevelopment {
+    pub fn floor_area(&self) -> f64 {
+        match self.size {
+            DevelopmentSize::FloorArea(area) => area as f64,
+            DevelopmentSize::Units(units) => {
+                (units as f64)
+                    * match self.land_use {
+                        LandUse::Residential => SQ_METERS_PER_DWELLING,
+                        LandUse::Office => SQ_METERS_PER_EMPLOYEE,
+                        LandUse::Retail => SQ_METERS_PER_SHOP,
+                    }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let size = match self.size {
+            DevelopmentSize::Units(units) => format!(
+                "{} {}",
+                units,
+                match self.land_use {
+                    LandUse::Residential => "dwelling units",
+                    LandUse::Office => "office employees",
+                    LandUse::Retail => "shops",
+                }
+            ),
+            DevelopmentSize::FloorArea(area) => format!(
+                "{} square meters of {}",
+                area,
+                match self.land_use {
+                    LandUse::Residential => "housing",
+                    LandUse::Office => "offices",
+                    LandUse::Retail => "retail",
+                }
+            ),
+        };
+        format!("add {} to {} buildings", size, self.bldgs.len())
+    }
+
+    // Appends new people to the scenario, returning how many were added. Each person makes one
+    // round trip to or from the development.
+    pub fn add_to(&self, scenario: &mut Scenario, map: &Map, rng: &mut XorShiftRng) -> usize {
+        let bldgs: Vec<BuildingID> = self.bldgs.iter().cloned().collect();
+        if bldgs.is_empty() {
+            return 0;
+        }
+        // People living in a new residential development go to jobs and shops. Everything else
+        // draws people from existing homes.
+        let others = if self.land_use == LandUse::Residential {
+            Attractions::workplaces(map, &self.bldgs)
+        } else {
+            Attractions::homes(map, &self.bldgs)
+        };
+        if others.bldgs.is_empty() {
+            return 0;
+        }
+
+        let orig_count = scenario.people.len();
+        for rate in trip_rates(self.land_use) {
+            let hourly = WeightedIndex::new(rate.hourly.iter()).unwrap();
+            let num_trips = rand_round(self.floor_area() / 100.0 * rate.per_100_sq_meters, rng);
+            // The gravity model only depends on which of the development's buildings is
+            // involved, so calculate it once per building.
+            let gravity: Vec<Option<WeightedIndex<f64>>> = bldgs
+                .iter()
+                .map(|b| others.gravity(map.get_b(*b).label_center, rate.avg_dist))
+                .collect();
+
+            for _ in 0..num_trips {
+                let idx = rng.gen_range(0, bldgs.len());
+                let other = match gravity[idx] {
+                    Some(ref dist) => others.bldgs[dist.sample(rng)],
+                    None => *others.bldgs.choose(rng).unwrap(),
+                };
+                let (from, to) = if self.land_use == LandUse::Residential {
+                    (bldgs[idx], other)
+                } else {
+                    (other, bldgs[idx])
+                };
+
+                let hour = hourly.sample(rng);
+                let depart = Time::START_OF_DAY
+                    + Duration::hours(hour)
+                    + Duration::seconds(rng.gen_range(0.0, 3600.0));
+                let stay = Duration::seconds(
+                    rng.gen_range(rate.stay.0.inner_seconds(), rate.stay.1.inner_seconds()),
+                );
+                if let Some(trips) =
+                    round_trip(from, to, rate.purpose, depart, depart + stay, map, rng)
+                {
+                    // Either the development or an existing home
+                    let mut attributes = PersonAttributes {
+                        home: Some(from),
+                        ..Default::default()
+                    };
+                    attributes.synthesize(&trips, map, rng);
+                    scenario.people.push(PersonSpec {
+                        id: PersonID(scenario.people.len()),
+                        orig_id: None,
+                        trips,
+                        attributes,
+                    });
+                }
+            }
+        }
+        scenario.people.len() - orig_count
+    }
+}
+
+// Existing buildings at the other end of trips, and how strongly they attract trips.
+struct Attractions {
+    bldgs: Vec<BuildingID>,
+    pts: Vec<Pt2D>,
+    weights: Vec<f64>,
+}
+
+impl Attractions {
+    // Weighted by footprint
+    fn workplaces(map: &Map, exclude: &BTreeSet<BuildingID>) -> Attractions {
+        let mut a = Attractions {
+            bldgs: Vec::new(),
+            pts: Vec::new(),
+            weights: Vec::new(),
+        };
+        for b in map.all_buildings() {
+            if exclude.contains(&b.id) {
+                continue;
+            }
+            if b.bldg_type.has_residents() && b.amenities.is_empty() {
+                continue;
+            }
+            a.bldgs.push(b.id);
+            a.pts.push(b.label_center);
+            a.weights.push(b.polygon.area().max(1.0));
+        }
+        a
+    }
+
+    // Weighted by number of residents
+    fn homes(map: &Map, exclude: &BTreeSet<BuildingID>) -> Attractions {
+        let mut a = Attractions {
+            bldgs: Vec::new(),
+            pts: Vec::new(),
+            weights: Vec::new(),
+        };
+        for b in map.all_buildings() {
+            if exclude.contains(&b.id) {
+                continue;
+            }
+            let residents = match b.bldg_type {
+                BuildingType::Residential(n) | BuildingType::ResidentialCommercial(n) => n,
+                BuildingType::Commercial | BuildingType::Empty => 0,
+            };
+            if residents == 0 {
+                continue;
+            }
+            a.bldgs.push(b.id);
+            a.pts.push(b.label_center);
+            a.weights.push(residents as f64);
+        }
+        a
+    }
+
+    // Attraction decays exponentially with straight-line distance. None if everything is too far
+    // away to have any weight.
+    fn gravity(&self, from: Pt2D, avg_dist: Distance) -> Option<WeightedIndex<f64>> {
+        let weights: Vec<f64> = self
+            .pts
+            .iter()
+            .zip(self.weights.iter())
+            .map(|(pt, w)| w * (-(pt.dist_to(from) / avg_dist)).exp())
+            .collect();
+        WeightedIndex::new(weights).ok()
+    }
+}
+
+fn round_trip(
+    from: BuildingID,
+    to: BuildingID,
+    purpose: TripPurpose,
+    depart: Time,
+    depart_back: Time,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Option<Vec<IndividTrip>> {
+    // Decide mode based on walking distance.
+    let dist = map
+        .pathfind(PathRequest {
+            start: map.get_b(from).sidewalk_pos,
+            end: map.get_b(to).sidewalk_pos,
+            constraints: PathConstraints::Pedestrian,
+        })?
+        .total_length();
+    let mode = select_trip_mode(dist, rng);
+    let there = SpawnTrip::new(TripEndpoint::Bldg(from), TripEndpoint::Bldg(to), mode, map)?;
+    let back = SpawnTrip::new(TripEndpoint::Bldg(to), TripEndpoint::Bldg(from), mode, map)?;
+    Some(vec![
+        IndividTrip::new(depart, purpose, there),
+        IndividTrip::new(depart_back, TripPurpose::Home, back),
+    ])
+}
+
+// Round up or down, so the expected value matches.
+fn rand_round(x: f64, rng: &mut XorShiftRng) -> usize {
+    let whole = x.floor();
+    if rng.gen_bool(x - whole) {
+        whole as usize + 1
+    } else {
+        whole as usize
+    }
+}
@@ -0,0 +1,131 @@
+use crate::{
+    VehicleSpec, VehicleType, TRUCK_ACCEL, TRUCK_DECEL, TRUCK_LENGTH, VAN_ACCEL, VAN_DECEL,
+    VAN_LENGTH,
+};
+use abstutil::Timer;
+use geom::{Duration, Speed, Time};
+use map_model::{BuildingID, DirectedRoadID, Map, PathConstraints};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+// A delivery vehicle entering the map from a border, stopping in front of each building, then
+// leaving the map. These don't belong to any person and aren't trips, just like buses.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct FreightTour {
+    pub depart: Time,
+    // Van or Truck
+    pub vehicle_type: VehicleType,
+    pub start: DirectedRoadID,
+    // Each building and how long to stop there
+    pub stops: Vec<(BuildingID, Duration)>,
+    pub end: DirectedRoadID,
+    // If true, pull into a free on-street parking spot next to the stop when there is one.
+    // Otherwise (or if there isn't one, or the vehicle is too long for one spot), double-park,
+    // blocking the lane for the whole stop.
+    pub use_loading_zones: bool,
+}
+
+impl FreightTour {
+    pub fn vehicle_spec(&self) -> VehicleSpec {
+        match self.vehicle_type {
+            VehicleType::Van => VehicleSpec {
+                vehicle_type: VehicleType::Van,
+                length: VAN_LENGTH,
+                max_speed: None,
+                max_accel: VAN_ACCEL,
+                max_decel: VAN_DECEL,
+            },
+            VehicleType::Truck => VehicleSpec {
+                vehicle_type: VehicleType::Truck,
+                length: TRUCK_LENGTH,
+                max_speed: Some(Speed::miles_per_hour(50.0)),
+                max_accel: TRUCK_ACCEL,
+                max_decel: TRUCK_DECEL,
+            },
+            x => panic!("{} isn't a freight vehicle", x),
+        }
+    }
+}
+
+// For ScenarioGenerator
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct FreightOverTime {
+    pub num_tours: usize,
+    pub start_time: Time,
+    pub stop_time: Time,
+    pub stops_per_tour: usize,
+    pub percent_trucks: f64,
+    // How long each stop takes, picked uniformly
+    pub min_dwell: Duration,
+    pub max_dwell: Duration,
+    // See FreightTour
+    pub use_loading_zones: bool,
+}
+
+impl FreightOverTime {
+    pub fn make_tours(
+        &self,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Vec<FreightTour> {
+        let starts: Vec<DirectedRoadID> = map
+            .all_incoming_borders()
+            .into_iter()
+            .filter_map(|i| i.some_outgoing_road(map))
+            .filter(|dr| !dr.lanes(PathConstraints::Car, map).is_empty())
+            .collect();
+        let ends: Vec<DirectedRoadID> = map
+            .all_outgoing_borders()
+            .into_iter()
+            .filter_map(|i| i.some_incoming_road(map))
+            .filter(|dr| !dr.lanes(PathConstraints::Car, map).is_empty())
+            .collect();
+        if starts.is_empty() || ends.is_empty() {
+            timer.warn("No borders for freight to enter or leave the map".to_string());
+            return Vec::new();
+        }
+        let bldgs = map.all_buildings();
+        if bldgs.is_empty() && self.stops_per_tour > 0 {
+            timer.warn("No buildings for freight to stop at".to_string());
+            return Vec::new();
+        }
+
+        let mut tours = Vec::new();
+        for _ in 0..self.num_tours {
+            let depart = Time::START_OF_DAY
+                + Duration::seconds(rng.gen_range(
+                    self.start_time.inner_seconds(),
+                    self.stop_time.inner_seconds(),
+                ));
+            let vehicle_type = if rng.gen_bool(self.percent_trucks) {
+                VehicleType::Truck
+            } else {
+                VehicleType::Van
+            };
+            let mut stops = Vec::new();
+            for _ in 0..self.stops_per_tour {
+                let dwell = if self.max_dwell > self.min_dwell {
+                    Duration::seconds(rng.gen_range(
+                        self.min_dwell.inner_seconds(),
+                        self.max_dwell.inner_seconds(),
+                    ))
+                } else {
+                    self.min_dwell
+                };
+                stops.push((bldgs.choose(rng).unwrap().id, dwell));
+            }
+            tours.push(FreightTour {
+                depart,
+                vehicle_type,
+                start: *starts.choose(rng).unwrap(),
+                stops,
+                end: *ends.choose(rng).unwrap(),
+                use_loading_zones: self.use_loading_zones,
+            });
+        }
+        tours
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{DrivingGoal, IndividTrip, PersonID, PersonSpec, Scenario, SidewalkSpot, SpawnTrip};
+use crate::{
+    DrivingGoal, FreightOverTime, IndividTrip, PersonAttributes, PersonID, PersonSpec, Scenario,
+    SidewalkSpot, SpawnTrip, TripPurpose,
+};
 use abstutil::Timer;
 use geom::{Duration, Time};
 use map_model::{BuildingID, DirectedRoadID, Map, PathConstraints};
@@ -16,6 +19,7 @@ pub struct ScenarioGenerator {
     pub only_seed_buses: Option<BTreeSet<String>>,
     pub spawn_over_time: Vec<SpawnOverTime>,
     pub border_spawn_over_time: Vec<BorderSpawnOverTime>,
+    pub freight: Vec<FreightOverTime>,
 }
 
 // SpawnOverTime and BorderSpawnOverTime should be kept separate. Agents in SpawnOverTime pick
@@ -84,6 +88,10 @@ impl ScenarioGenerator {
             );
         }
 
+        for f in &self.freight {
+            scenario.freight.extend(f.make_tours(map, rng, timer));
+        }
+
         timer.stop(format!("Generating scenario {}", self.scenario_name));
         scenario
     }
@@ -117,6 +125,7 @@ impl ScenarioGenerator {
                     percent_use_transit: 0.5,
                 })
                 .collect(),
+            freight: Vec::new(),
         };
         for i in map.all_outgoing_borders() {
             s.spawn_over_time.push(SpawnOverTime {
@@ -138,6 +147,7 @@ impl ScenarioGenerator {
             only_seed_buses: Some(BTreeSet::new()),
             spawn_over_time: Vec::new(),
             border_spawn_over_time: Vec::new(),
+            freight: Vec::new(),
         }
     }
 
@@ -156,6 +166,7 @@ impl ScenarioGenerator {
                 percent_use_transit: 0.5,
             }],
             border_spawn_over_time: Vec::new(),
+            freight: Vec::new(),
         }
     }
 }
@@ -184,8 +195,10 @@ impl SpawnOverTime {
                     orig_id: None,
                     trips: vec![IndividTrip::new(
                         depart,
+                        TripPurpose::Unknown,
                         SpawnTrip::UsingParkedCar(from_bldg, goal),
                     )],
+                    attributes: PersonAttributes::default(),
                 });
                 return;
             }
@@ -201,8 +214,10 @@ impl SpawnOverTime {
                     orig_id: None,
                     trips: vec![IndividTrip::new(
                         depart,
+                        TripPurpose::Unknown,
                         SpawnTrip::UsingBike(from_bldg, goal),
                     )],
+                    attributes: PersonAttributes::default(),
                 });
                 return;
             }
@@ -226,8 +241,10 @@ impl SpawnOverTime {
                         orig_id: None,
                         trips: vec![IndividTrip::new(
                             depart,
+                            TripPurpose::Unknown,
                             SpawnTrip::UsingTransit(start_spot, goal, route, stop1, maybe_stop2),
                         )],
+                        attributes: PersonAttributes::default(),
                     });
                     return;
                 }
@@ -238,8 +255,10 @@ impl SpawnOverTime {
                 orig_id: None,
                 trips: vec![IndividTrip::new(
                     depart,
+                    TripPurpose::Unknown,
                     SpawnTrip::JustWalking(start_spot, goal),
                 )],
+                attributes: PersonAttributes::default(),
             });
             return;
         }
@@ -287,6 +306,7 @@ impl BorderSpawnOverTime {
                             orig_id: None,
                             trips: vec![IndividTrip::new(
                                 depart,
+                                TripPurpose::Unknown,
                                 SpawnTrip::UsingTransit(
                                     start.clone(),
                                     goal,
@@ -295,6 +315,7 @@ impl BorderSpawnOverTime {
                                     maybe_stop2,
                                 ),
                             )],
+                            attributes: PersonAttributes::default(),
                         });
                         continue;
                     }
@@ -305,8 +326,10 @@ impl BorderSpawnOverTime {
                     orig_id: None,
                     trips: vec![IndividTrip::new(
                         depart,
+                        TripPurpose::Unknown,
                         SpawnTrip::JustWalking(start.clone(), goal),
                     )],
+                    attributes: PersonAttributes::default(),
                 });
             }
         }
@@ -330,6 +353,7 @@ impl BorderSpawnOverTime {
                     orig_id: None,
                     trips: vec![IndividTrip::new(
                         depart,
+                        TripPurpose::Unknown,
                         SpawnTrip::FromBorder {
                             dr: self.start_from_border,
                             goal,
@@ -337,6 +361,7 @@ impl BorderSpawnOverTime {
                             origin: None,
                         },
                     )],
+                    attributes: PersonAttributes::default(),
                 });
             }
         }
@@ -1,4 +1,4 @@
-use crate::{AlertHandler, Scenario, Sim, SimOptions};
+use crate::{AlertHandler, DispatchPolicy, Scenario, Sim, SimOptions};
 use abstutil::CmdArgs;
 use map_model::{Map, MapEdits};
 use rand::SeedableRng;
@@ -31,6 +31,7 @@ impl SimFlags {
                 use_freeform_policy_everywhere: args.enabled("--freeform_policy"),
                 dont_block_the_box: !args.enabled("--disable_block_the_box"),
                 recalc_lanechanging: !args.enabled("--disable_recalc_lc"),
+                mid_block_lanechanging: args.enabled("--mid_block_lc"),
                 break_turn_conflict_cycles: !args.enabled("--disable_break_turn_conflict_cycles"),
                 enable_pandemic_model: if args.enabled("--pandemic") {
                     Some(XorShiftRng::from_seed([rng_seed; 16]))
@@ -47,6 +48,17 @@ impl SimFlags {
                     })
                     .unwrap_or(AlertHandler::Print),
                 pathfinding_upfront: args.enabled("--pathfinding_upfront"),
+                ride_hail_dispatch: args
+                    .optional("--ride_hail_dispatch")
+                    .map(|x| match x.as_ref() {
+                        "nearest" => DispatchPolicy::Nearest,
+                        "longest_idle" => DispatchPolicy::LongestIdle,
+                        _ => panic!(
+                            "Bad --ride_hail_dispatch={}. Must be nearest|longest_idle",
+                            x
+                        ),
+                    })
+                    .unwrap_or(DispatchPolicy::Nearest),
             },
         }
     }
@@ -1,14 +1,24 @@
 mod activity_model;
+mod development;
+mod freight;
 mod generator;
 mod load;
 mod modifier;
+mod od;
+mod parking_survey;
+mod population;
 mod scenario;
 mod spawner;
 
+pub use self::development::{DevelopmentSize, LandUse, NewDevelopment};
+pub use self::freight::{FreightOverTime, FreightTour};
 pub use self::generator::{
     BorderSpawnOverTime, OriginDestination, ScenarioGenerator, SpawnOverTime,
 };
 pub use self::load::SimFlags;
 pub use self::modifier::ScenarioModifier;
+pub use self::od::{ODFlow, ODMatrix, ODZone};
+pub use self::parking_survey::{ParkingObservation, ParkingSurvey, ParkingSurveyComparison};
+pub use self::population::{AgeBand, IncomeBand, Mobility, PersonAttributes};
 pub use self::scenario::{IndividTrip, OffMapLocation, PersonSpec, Scenario, SpawnTrip};
 pub use self::spawner::{TripSpawner, TripSpec};
@@ -1,11 +1,15 @@
-use crate::{IndividTrip, Scenario, SpawnTrip, TripMode};
-use geom::{Duration, Time};
-use map_model::Map;
+use crate::{
+    DrivingGoal, IndividTrip, NewDevelopment, PersonID, Scenario, SpawnTrip, TripEndpoint, TripMode,
+};
+use geom::{Duration, LonLat, PolyLine, Pt2D, Ring, Time};
+use map_model::{BuildingID, Map};
+use rand::seq::IteratorRandom;
 use rand::Rng;
 use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeSet;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize, Debug)]
 pub enum ScenarioModifier {
     RepeatDays(usize),
     CancelPeople(usize),
@@ -15,6 +19,30 @@ pub enum ScenarioModifier {
         departure_filter: (Time, Time),
         from_modes: BTreeSet<TripMode>,
     },
+    // Scale the number of people to this percent of the original. Above 100, people are
+    // duplicated; below, they're removed.
+    ScaleDemand(usize),
+    // Peak spreading. Trips departing in the window are shifted up to max_shift earlier or later.
+    SpreadDepartures {
+        departure_filter: (Time, Time),
+        max_shift: Duration,
+    },
+    // Some trips ending at one set of buildings go to a random building in the other set instead.
+    // The person's next trip starts from the new place.
+    RedirectDestinations {
+        from: BTreeSet<BuildingID>,
+        to: BTreeSet<BuildingID>,
+        pct_trips: usize,
+    },
+    // Add trips to and from a proposed development.
+    NewDevelopment(NewDevelopment),
+    // Only keep people with some trip starting, ending, or passing through this area. Trips are
+    // approximated as a straight line between their endpoints.
+    OnlyPeopleCrossing(Vec<LonLat>),
+    // Change how many vehicles serve ride-hailing trips
+    RideHailFleet(usize),
+    // This percent of bike trips use a shared bike instead, when there are docks near both ends
+    BikeShare(usize),
 }
 
 impl ScenarioModifier {
@@ -52,6 +80,42 @@ impl ScenarioModifier {
                 }
                 s
             }
+            ScenarioModifier::ScaleDemand(pct) => scale_demand(s, *pct, rng),
+            ScenarioModifier::BikeShare(pct) => use_bike_share(s, *pct, map, rng),
+            ScenarioModifier::RideHailFleet(n) => {
+                s.scenario_name = format!("{} ({} ride-hail vehicles)", s.scenario_name, n);
+                s.ride_hail_fleet = *n;
+                s
+            }
+            ScenarioModifier::SpreadDepartures {
+                departure_filter,
+                max_shift,
+            } => spread_departures(s, *departure_filter, *max_shift, rng),
+            ScenarioModifier::RedirectDestinations {
+                from,
+                to,
+                pct_trips,
+            } => redirect_destinations(s, from, to, *pct_trips, map, rng),
+            ScenarioModifier::NewDevelopment(dev) => new_development(s, dev, map, rng),
+            ScenarioModifier::OnlyPeopleCrossing(pts) => only_people_crossing(s, pts, map),
+        }
+    }
+
+    // Modifiers come from the UI or hand-written JSON, so check the ones that could be impossible
+    // to apply.
+    pub fn validate(&self, map: &Map) -> Result<(), String> {
+        match self {
+            ScenarioModifier::RedirectDestinations { from, to, .. } => {
+                if from.is_empty() || to.is_empty() {
+                    return Err("redirecting trips needs buildings to go from and to".to_string());
+                }
+                Ok(())
+            }
+            ScenarioModifier::OnlyPeopleCrossing(pts) => {
+                Ring::new(map.get_gps_bounds().convert(pts)).map_err(|err| err.to_string())?;
+                Ok(())
+            }
+            _ => Ok(()),
         }
     }
 
@@ -74,6 +138,34 @@ impl ScenarioModifier {
                 departure_filter.1.ampm_tostring(),
                 to_mode.verb()
             ),
+            ScenarioModifier::ScaleDemand(pct) => format!("scale the number of people to {}%", pct),
+            ScenarioModifier::BikeShare(pct) => format!("{}% of bike trips use bike-share", pct),
+            ScenarioModifier::RideHailFleet(n) => {
+                format!("use a ride-hail fleet of {} vehicles", n)
+            }
+            ScenarioModifier::SpreadDepartures {
+                departure_filter,
+                max_shift,
+            } => format!(
+                "shift trips leaving between {} and {} by up to {}",
+                departure_filter.0.ampm_tostring(),
+                departure_filter.1.ampm_tostring(),
+                max_shift
+            ),
+            ScenarioModifier::RedirectDestinations {
+                from,
+                to,
+                pct_trips,
+            } => format!(
+                "redirect {}% of trips going to {} buildings to {} other buildings",
+                pct_trips,
+                from.len(),
+                to.len()
+            ),
+            ScenarioModifier::NewDevelopment(dev) => dev.describe(),
+            ScenarioModifier::OnlyPeopleCrossing(_) => {
+                "only keep people passing through an area".to_string()
+            }
         }
     }
 }
@@ -93,7 +185,8 @@ fn repeat_days(mut s: Scenario, days: usize) -> Scenario {
         let mut offset = Duration::ZERO;
         for _ in 0..days {
             for trip in &person.trips {
-                let mut new = IndividTrip::new(trip.depart + offset, trip.trip.clone());
+                let mut new =
+                    IndividTrip::new(trip.depart + offset, trip.purpose, trip.trip.clone());
                 new.modified = true;
                 trips.push(new);
             }
@@ -118,3 +211,198 @@ fn cancel_people(mut s: Scenario, pct: usize, rng: &mut XorShiftRng) -> Scenario
     }
     s
 }
+
+fn scale_demand(mut s: Scenario, pct: usize, rng: &mut XorShiftRng) -> Scenario {
+    s.scenario_name = format!("{} (scaled to {}%)", s.scenario_name, pct);
+    let mut people = Vec::new();
+    for person in s.people {
+        // Every person is copied this many times, then once more with some probability.
+        let mut copies = pct / 100;
+        if rng.gen_bool(((pct % 100) as f64) / 100.0) {
+            copies += 1;
+        }
+        for idx in 0..copies {
+            let mut copy = person.clone();
+            copy.id = PersonID(people.len());
+            if idx > 0 {
+                for trip in &mut copy.trips {
+                    trip.modified = true;
+                }
+            }
+            people.push(copy);
+        }
+    }
+    s.people = people;
+    s
+}
+
+fn use_bike_share(mut s: Scenario, pct: usize, map: &Map, rng: &mut XorShiftRng) -> Scenario {
+    let pct = (pct as f64) / 100.0;
+    for person in &mut s.people {
+        for trip in &mut person.trips {
+            if let SpawnTrip::UsingBike(from, DrivingGoal::ParkNear(to)) = trip.trip {
+                if !rng.gen_bool(pct) {
+                    continue;
+                }
+                if let Some(new) = SpawnTrip::bike_share(from, to, map) {
+                    trip.trip = new;
+                    trip.modified = true;
+                }
+            }
+        }
+    }
+    s
+}
+
+fn spread_departures(
+    mut s: Scenario,
+    departure_filter: (Time, Time),
+    max_shift: Duration,
+    rng: &mut XorShiftRng,
+) -> Scenario {
+    if max_shift == Duration::ZERO {
+        return s;
+    }
+    let max_shift = max_shift.inner_seconds();
+    for person in &mut s.people {
+        for idx in 0..person.trips.len() {
+            let depart = person.trips[idx].depart;
+            if depart < departure_filter.0 || depart > departure_filter.1 {
+                continue;
+            }
+            let shift = rng.gen_range(-max_shift, max_shift);
+            let new_secs = depart.inner_seconds() + shift;
+            if new_secs < 0.0 {
+                continue;
+            }
+            let new_depart = Time::START_OF_DAY + Duration::seconds(new_secs);
+            // Don't reorder the person's schedule
+            if idx > 0 && new_depart <= person.trips[idx - 1].depart {
+                continue;
+            }
+            if idx < person.trips.len() - 1 && new_depart >= person.trips[idx + 1].depart {
+                continue;
+            }
+            person.trips[idx].depart = new_depart;
+            person.trips[idx].modified = true;
+        }
+    }
+    s
+}
+
+fn redirect_destinations(
+    mut s: Scenario,
+    from: &BTreeSet<BuildingID>,
+    to: &BTreeSet<BuildingID>,
+    pct_trips: usize,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Scenario {
+    if to.is_empty() {
+        println!("WARNING: Can't redirect trips to an empty set of buildings");
+        return s;
+    }
+    let pct_trips = (pct_trips as f64) / 100.0;
+    for person in &mut s.people {
+        // Work on a copy, so a failure partway leaves the person unmodified.
+        let mut trips = person.trips.clone();
+        let mut ok = true;
+        for idx in 0..trips.len() {
+            let old_dst = match trips[idx].trip.end(map) {
+                TripEndpoint::Bldg(b) if from.contains(&b) => b,
+                _ => continue,
+            };
+            if !rng.gen_bool(pct_trips) {
+                continue;
+            }
+            let new_dst = *to.iter().choose(rng).unwrap();
+            if let Some(trip) = SpawnTrip::new(
+                trips[idx].trip.start(map),
+                TripEndpoint::Bldg(new_dst),
+                trips[idx].trip.mode(),
+                map,
+            ) {
+                trips[idx].trip = trip;
+                trips[idx].modified = true;
+            } else {
+                ok = false;
+                break;
+            }
+
+            // The next trip leaves from the new destination
+            if let Some(next) = trips.get_mut(idx + 1) {
+                if next.trip.start(map) == TripEndpoint::Bldg(old_dst) {
+                    if let Some(trip) = SpawnTrip::new(
+                        TripEndpoint::Bldg(new_dst),
+                        next.trip.end(map),
+                        next.trip.mode(),
+                        map,
+                    ) {
+                        next.trip = trip;
+                        next.modified = true;
+                    } else {
+                        ok = false;
+                        break;
+                    }
+                }
+            }
+        }
+        if ok {
+            person.trips = trips;
+        }
+    }
+    s
+}
+
+fn new_development(
+    mut s: Scenario,
+    dev: &NewDevelopment,
+    map: &Map,
+    rng: &mut XorShiftRng,
+) -> Scenario {
+    s.scenario_name = format!("{} (with a new development)", s.scenario_name);
+    let orig_count = s.people.len();
+    dev.add_to(&mut s, map, rng);
+    for person in &mut s.people[orig_count..] {
+        for trip in &mut person.trips {
+            trip.modified = true;
+        }
+    }
+    s
+}
+
+fn only_people_crossing(mut s: Scenario, pts: &Vec<LonLat>, map: &Map) -> Scenario {
+    let ring = match Ring::new(map.get_gps_bounds().convert(pts)) {
+        Ok(ring) => ring,
+        Err(err) => {
+            println!("WARNING: Not filtering people by a bad area: {}", err);
+            return s;
+        }
+    };
+    s.scenario_name = format!("{} (crossing an area)", s.scenario_name);
+    let polygon = ring.clone().to_polygon();
+    let endpoint_pt = |endpt: TripEndpoint| -> Pt2D {
+        match endpt {
+            TripEndpoint::Bldg(b) => map.get_b(b).label_center,
+            TripEndpoint::Border(i, _) => map.get_i(i).polygon.center(),
+        }
+    };
+
+    s.people.retain(|person| {
+        person.trips.iter().any(|trip| {
+            let pt1 = endpoint_pt(trip.trip.start(map));
+            let pt2 = endpoint_pt(trip.trip.end(map));
+            if polygon.contains_pt(pt1) || polygon.contains_pt(pt2) {
+                return true;
+            }
+            PolyLine::new(vec![pt1, pt2])
+                .map(|pl| !ring.all_intersections(&pl).is_empty())
+                .unwrap_or(false)
+        })
+    });
+    // Fix up IDs
+    for (idx, person) in s.people.iter_mut().enumerate() {
+        person.id = PersonID(idx);
+    }
+    s
+}
@@ -0,0 +1,241 @@
+use crate::{
+    IndividTrip, PersonAttributes, PersonID, PersonSpec, Scenario, SpawnTrip, TripEndpoint,
+    TripMode, TripPurpose,
+};
+use abstutil::{prettyprint_usize, Counter, Timer};
+use geom::{Duration, Polygon, Pt2D, Time};
+use map_model::{BuildingID, Intersection, IntersectionID, Map};
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// Zone-based travel demand, the format that regional travel models usually exchange. Each flow
+// expands into that many people, each making a single trip.
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ODMatrix {
+    pub zones: Vec<ODZone>,
+    pub flows: Vec<ODFlow>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct ODZone {
+    pub name: String,
+    // In map-space. Zones can extend past the map boundary, or be completely off-map.
+    pub polygon: Polygon,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ODFlow {
+    // Zone names
+    pub origin: String,
+    pub destination: String,
+    pub mode: TripMode,
+    // Departures are spread uniformly over this period.
+    pub period: (Time, Time),
+    pub count: usize,
+}
+
+impl ODMatrix {
+    pub fn make_scenario(
+        &self,
+        scenario_name: &str,
+        map: &Map,
+        rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) -> Scenario {
+        let mut s = Scenario::empty(map, scenario_name);
+        // Include all buses/trains
+        s.only_seed_buses = None;
+
+        let mut endpoints: BTreeMap<&str, ZoneEndpoints> = BTreeMap::new();
+        timer.start_iter("match zones to the map", self.zones.len());
+        for zone in &self.zones {
+            timer.next();
+            endpoints.insert(&zone.name, ZoneEndpoints::new(&zone.polygon, map));
+        }
+
+        let mut failed = 0;
+        timer.start_iter("expand OD flows", self.flows.len());
+        for flow in &self.flows {
+            timer.next();
+            let (from, to) = match (
+                endpoints.get(flow.origin.as_str()),
+                endpoints.get(flow.destination.as_str()),
+            ) {
+                (Some(from), Some(to)) => (from, to),
+                _ => {
+                    timer.warn(format!("{:?} refers to an unknown zone", flow));
+                    failed += flow.count;
+                    continue;
+                }
+            };
+            for _ in 0..flow.count {
+                let depart = rand_time(rng, flow.period.0, flow.period.1);
+                let trip = match (from.pick_origin(rng), to.pick_destination(rng)) {
+                    (Some(o), Some(d)) if o != d => SpawnTrip::new(o, d, flow.mode, map),
+                    _ => None,
+                };
+                if let Some(trip) = trip {
+                    // OD matrices don't say why people are traveling, so just pick something.
+                    s.people.push(PersonSpec {
+                        id: PersonID(s.people.len()),
+                        orig_id: None,
+                        trips: vec![IndividTrip::new(depart, TripPurpose::Unknown, trip)],
+                        attributes: PersonAttributes::default(),
+                    });
+                } else {
+                    failed += 1;
+                }
+            }
+        }
+        timer.note(format!(
+            "Expanded {} OD flows into {} people. {} trips couldn't be placed on the map",
+            prettyprint_usize(self.flows.len()),
+            prettyprint_usize(s.people.len()),
+            prettyprint_usize(failed)
+        ));
+
+        s.synthesize_attributes(map, rng);
+        s
+    }
+
+    // The demand implied by an existing scenario. Departures are bucketed into periods of a fixed
+    // length, starting at midnight. Trips starting or ending outside of every zone are skipped.
+    pub fn from_scenario(
+        scenario: &Scenario,
+        zones: Vec<ODZone>,
+        period: Duration,
+        map: &Map,
+        timer: &mut Timer,
+    ) -> ODMatrix {
+        assert!(period > Duration::ZERO);
+
+        // (origin zone, destination zone, mode, period)
+        let mut counts: Counter<(usize, usize, TripMode, usize)> = Counter::new();
+        let mut skipped = 0;
+        timer.start_iter("summarize trips by zone", scenario.people.len());
+        for person in &scenario.people {
+            timer.next();
+            for trip in &person.trips {
+                if trip.cancelled {
+                    continue;
+                }
+                let from = find_zone(&zones, endpoint_pt(&trip.trip.start(map), map));
+                let to = find_zone(&zones, endpoint_pt(&trip.trip.end(map), map));
+                if let (Some(from), Some(to)) = (from, to) {
+                    let bucket = ((trip.depart - Time::START_OF_DAY) / period).floor() as usize;
+                    counts.inc((from, to, trip.trip.mode(), bucket));
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+        timer.note(format!(
+            "{} trips don't start or end in any zone",
+            prettyprint_usize(skipped)
+        ));
+
+        let flows = counts
+            .consume()
+            .into_iter()
+            .map(|((from, to, mode, bucket), count)| {
+                let start = Time::START_OF_DAY + (bucket as f64) * period;
+                ODFlow {
+                    origin: zones[from].name.clone(),
+                    destination: zones[to].name.clone(),
+                    mode,
+                    period: (start, start + period),
+                    count,
+                }
+            })
+            .collect();
+        ODMatrix { zones, flows }
+    }
+}
+
+// Where trips starting or ending in one zone can be placed on the map.
+struct ZoneEndpoints {
+    bldgs: Vec<BuildingID>,
+    // Only used if the zone has no buildings, meaning it's off-map or covers something like a
+    // park. Either the borders inside the zone, or just the closest one.
+    incoming_borders: Vec<IntersectionID>,
+    outgoing_borders: Vec<IntersectionID>,
+}
+
+impl ZoneEndpoints {
+    fn new(polygon: &Polygon, map: &Map) -> ZoneEndpoints {
+        let bldgs: Vec<BuildingID> = map
+            .all_buildings()
+            .iter()
+            .filter(|b| polygon.contains_pt(b.label_center))
+            .map(|b| b.id)
+            .collect();
+        if !bldgs.is_empty() {
+            return ZoneEndpoints {
+                bldgs,
+                incoming_borders: Vec::new(),
+                outgoing_borders: Vec::new(),
+            };
+        }
+
+        let center = polygon.center();
+        let pick_borders = |borders: Vec<&Intersection>| -> Vec<IntersectionID> {
+            let inside: Vec<IntersectionID> = borders
+                .iter()
+                .filter(|i| polygon.contains_pt(i.polygon.center()))
+                .map(|i| i.id)
+                .collect();
+            if !inside.is_empty() {
+                return inside;
+            }
+            borders
+                .into_iter()
+                .min_by_key(|i| i.polygon.center().dist_to(center))
+                .map(|i| vec![i.id])
+                .unwrap_or_else(Vec::new)
+        };
+        ZoneEndpoints {
+            bldgs,
+            incoming_borders: pick_borders(map.all_incoming_borders()),
+            outgoing_borders: pick_borders(map.all_outgoing_borders()),
+        }
+    }
+
+    fn pick_origin(&self, rng: &mut XorShiftRng) -> Option<TripEndpoint> {
+        if let Some(b) = self.bldgs.choose(rng) {
+            return Some(TripEndpoint::Bldg(*b));
+        }
+        self.incoming_borders
+            .choose(rng)
+            .map(|i| TripEndpoint::Border(*i, None))
+    }
+
+    fn pick_destination(&self, rng: &mut XorShiftRng) -> Option<TripEndpoint> {
+        if let Some(b) = self.bldgs.choose(rng) {
+            return Some(TripEndpoint::Bldg(*b));
+        }
+        self.outgoing_borders
+            .choose(rng)
+            .map(|i| TripEndpoint::Border(*i, None))
+    }
+}
+
+fn endpoint_pt(endpt: &TripEndpoint, map: &Map) -> Pt2D {
+    match endpt {
+        TripEndpoint::Bldg(b) => map.get_b(*b).label_center,
+        // Off-map trips remember where they really started or ended
+        TripEndpoint::Border(_, Some(loc)) => Pt2D::from_gps(loc.gps, map.get_gps_bounds()),
+        TripEndpoint::Border(i, None) => map.get_i(*i).polygon.center(),
+    }
+}
+
+fn find_zone(zones: &Vec<ODZone>, pt: Pt2D) -> Option<usize> {
+    zones.iter().position(|z| z.polygon.contains_pt(pt))
+}
+
+fn rand_time(rng: &mut XorShiftRng, low: Time, high: Time) -> Time {
+    assert!(high > low);
+    Time::START_OF_DAY + Duration::seconds(rng.gen_range(low.inner_seconds(), high.inner_seconds()))
+}
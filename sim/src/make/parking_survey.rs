@@ -0,0 +1,213 @@
+use crate::{Analytics, Scenario, Sim};
+use abstutil::{prettyprint_usize, Timer};
+use geom::Time;
+use map_model::{LaneID, LaneType, Map, ParkingFacility};
+use rand::seq::SliceRandom;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A block-face parking occupancy survey, like the ones some cities publish: how many cars were
+// parked along an OSM way during different hours of the day. This is keyed by OSM way, so it
+// survives map edits and re-importing.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ParkingSurvey {
+    pub observations: Vec<ParkingObservation>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct ParkingObservation {
+    pub osm_way_id: i64,
+    // 0 covers midnight to 1am
+    pub hour: usize,
+    pub occupied: usize,
+    // How many spots the surveyors counted. If this doesn't match the map, the observed occupancy
+    // is scaled to the map's capacity.
+    pub capacity: Option<usize>,
+}
+
+pub struct ParkingSurveyComparison {
+    pub osm_way_id: i64,
+    pub hour: usize,
+    // The number of on-street spots along the way in the map
+    pub capacity: usize,
+    // Scaled to the map's capacity
+    pub observed: f64,
+    // Average occupied spots during the hour
+    pub simulated: f64,
+}
+
+impl ParkingSurvey {
+    // Scaled to the map's capacity, and never more than it.
+    pub fn observed_occupancy(&self, obs: &ParkingObservation, capacity: usize) -> f64 {
+        let occupied = match obs.capacity {
+            Some(surveyed) if surveyed > 0 => {
+                (obs.occupied as f64) / (surveyed as f64) * (capacity as f64)
+            }
+            _ => obs.occupied as f64,
+        };
+        occupied.min(capacity as f64)
+    }
+
+    // All the parking lanes along each surveyed way. Ways that don't match anything in the map
+    // are omitted.
+    pub fn lanes_per_way(&self, map: &Map) -> BTreeMap<i64, Vec<LaneID>> {
+        let mut ways: BTreeMap<i64, Vec<LaneID>> = self
+            .observations
+            .iter()
+            .map(|obs| (obs.osm_way_id, Vec::new()))
+            .collect();
+        for r in map.all_roads() {
+            if let Some(lanes) = ways.get_mut(&r.orig_id.osm_way_id) {
+                for (l, lt) in r.children(true).iter().chain(r.children(false).iter()) {
+                    if *lt == LaneType::Parking {
+                        lanes.push(*l);
+                    }
+                }
+            }
+        }
+        ways.retain(|_, lanes| !lanes.is_empty());
+        ways
+    }
+
+    // The observed occupancy of one parking lane per hour, splitting each observation of the way
+    // among its lanes by capacity.
+    pub fn observed_for_lane(&self, l: LaneID, map: &Map) -> Vec<(usize, f64)> {
+        let lane = map.get_l(l);
+        let way = map.get_r(lane.parent).orig_id.osm_way_id;
+        let lanes_per_way = self.lanes_per_way(map);
+        let way_capacity = match lanes_per_way.get(&way) {
+            Some(lanes) => capacity(lanes, map),
+            None => {
+                return Vec::new();
+            }
+        };
+        if way_capacity == 0 {
+            return Vec::new();
+        }
+        let share = (lane.number_parking_spots() as f64) / (way_capacity as f64);
+        let mut results: Vec<(usize, f64)> = self
+            .observations
+            .iter()
+            .filter(|obs| obs.osm_way_id == way)
+            .map(|obs| (obs.hour, share * self.observed_occupancy(obs, way_capacity)))
+            .collect();
+        results.sort_by_key(|(hour, _)| *hour);
+        results
+    }
+
+    // Cars that stay parked along a way all day, because they're never used by any trip in the
+    // scenario. This is the occupancy of the least busy surveyed hour; trips move cars in and out
+    // on top of that.
+    pub fn background_cars(&self, map: &Map) -> BTreeMap<i64, usize> {
+        let lanes_per_way = self.lanes_per_way(map);
+        let mut result: BTreeMap<i64, usize> = BTreeMap::new();
+        for obs in &self.observations {
+            if let Some(lanes) = lanes_per_way.get(&obs.osm_way_id) {
+                let capacity = capacity(lanes, map);
+                let cars = self.observed_occupancy(obs, capacity).round() as usize;
+                let min = result.entry(obs.osm_way_id).or_insert(cars);
+                *min = (*min).min(cars);
+            }
+        }
+        result
+    }
+
+    // Called after the scenario seeds parked cars belonging to people. Tops up each surveyed way
+    // with ownerless cars that never move.
+    pub(crate) fn seed_background_cars(
+        &self,
+        sim: &mut Sim,
+        map: &Map,
+        base_rng: &mut XorShiftRng,
+        timer: &mut Timer,
+    ) {
+        let lanes_per_way = self.lanes_per_way(map);
+        let targets = self.background_cars(map);
+        timer.start_iter("seed background parked cars", targets.len());
+        let mut seeded = 0;
+        for (way, target) in targets {
+            timer.next();
+            // Changing parking along one way shouldn't affect others.
+            let mut rng = abstutil::fork_rng(base_rng);
+            let lanes = &lanes_per_way[&way];
+            let mut free = Vec::new();
+            for l in lanes {
+                free.extend(sim.get_free_onstreet_spots(*l));
+            }
+            let already_parked = capacity(lanes, map) - free.len();
+            if already_parked >= target {
+                continue;
+            }
+            free.shuffle(&mut rng);
+            for spot in free.into_iter().take(target - already_parked) {
+                sim.seed_background_parked_car(Scenario::rand_car(&mut rng), spot);
+                seeded += 1;
+            }
+        }
+        timer.note(format!(
+            "Seeded {} background parked cars to match the parking survey",
+            prettyprint_usize(seeded)
+        ));
+    }
+
+    // After running a simulation (up to now), compare each observation against the average
+    // simulated occupancy during that hour.
+    pub fn compare(
+        &self,
+        map: &Map,
+        analytics: &Analytics,
+        now: Time,
+    ) -> Vec<ParkingSurveyComparison> {
+        let lanes_per_way = self.lanes_per_way(map);
+        // Per lane, the average fraction of filled spots per hour
+        let mut occupancy_per_lane: BTreeMap<LaneID, Vec<f64>> = BTreeMap::new();
+        for lanes in lanes_per_way.values() {
+            for l in lanes {
+                occupancy_per_lane.insert(
+                    *l,
+                    analytics.parking_occupancy_per_hour(
+                        now,
+                        ParkingFacility::Onstreet(*l),
+                        map.get_l(*l).number_parking_spots(),
+                    ),
+                );
+            }
+        }
+
+        let mut results = Vec::new();
+        for obs in &self.observations {
+            let lanes = match lanes_per_way.get(&obs.osm_way_id) {
+                Some(lanes) => lanes,
+                None => {
+                    continue;
+                }
+            };
+            // Haven't simulated this hour yet
+            if obs.hour > now.get_hours() {
+                continue;
+            }
+            let capacity = capacity(lanes, map);
+            let mut simulated = 0.0;
+            for l in lanes {
+                let pct = occupancy_per_lane[l].get(obs.hour).cloned().unwrap_or(0.0);
+                simulated += pct * (map.get_l(*l).number_parking_spots() as f64);
+            }
+            results.push(ParkingSurveyComparison {
+                osm_way_id: obs.osm_way_id,
+                hour: obs.hour,
+                capacity,
+                observed: self.observed_occupancy(obs, capacity),
+                simulated,
+            });
+        }
+        results
+    }
+}
+
+fn capacity(lanes: &Vec<LaneID>, map: &Map) -> usize {
+    lanes
+        .iter()
+        .map(|l| map.get_l(*l).number_parking_spots())
+        .sum()
+}
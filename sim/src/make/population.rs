@@ -0,0 +1,193 @@
+use crate::{IndividTrip, TripEndpoint, TripMode};
+use map_model::{BuildingID, Map};
+use rand::Rng;
+use rand_xorshift::XorShiftRng;
+use serde::{Deserialize, Serialize};
+
+// Optional demographics about a person, used to break down results for equity analysis. Imported
+// when the travel demand model has them, synthesized otherwise.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PersonAttributes {
+    pub home: Option<BuildingID>,
+    pub age: Option<AgeBand>,
+    pub income: Option<IncomeBand>,
+    pub has_car: Option<bool>,
+    pub mobility: Option<Mobility>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AgeBand {
+    Under18,
+    From18To34,
+    From35To64,
+    Over65,
+}
+
+// Annual household income in US dollars
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum IncomeBand {
+    Under35k,
+    From35kTo75k,
+    From75kTo150k,
+    Over150k,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Mobility {
+    Unrestricted,
+    // Walks slowly or has trouble with stairs
+    Limited,
+    Wheelchair,
+}
+
+impl AgeBand {
+    pub fn from_years(age: usize) -> AgeBand {
+        if age < 18 {
+            AgeBand::Under18
+        } else if age < 35 {
+            AgeBand::From18To34
+        } else if age < 65 {
+            AgeBand::From35To64
+        } else {
+            AgeBand::Over65
+        }
+    }
+
+    pub fn all() -> Vec<AgeBand> {
+        vec![
+            AgeBand::Under18,
+            AgeBand::From18To34,
+            AgeBand::From35To64,
+            AgeBand::Over65,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            AgeBand::Under18 => "under 18",
+            AgeBand::From18To34 => "18 to 34",
+            AgeBand::From35To64 => "35 to 64",
+            AgeBand::Over65 => "65 and older",
+        }
+    }
+}
+
+impl IncomeBand {
+    pub fn from_dollars(income: usize) -> IncomeBand {
+        if income < 35_000 {
+            IncomeBand::Under35k
+        } else if income < 75_000 {
+            IncomeBand::From35kTo75k
+        } else if income < 150_000 {
+            IncomeBand::From75kTo150k
+        } else {
+            IncomeBand::Over150k
+        }
+    }
+
+    pub fn all() -> Vec<IncomeBand> {
+        vec![
+            IncomeBand::Under35k,
+            IncomeBand::From35kTo75k,
+            IncomeBand::From75kTo150k,
+            IncomeBand::Over150k,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            IncomeBand::Under35k => "under $35k",
+            IncomeBand::From35kTo75k => "$35k to $75k",
+            IncomeBand::From75kTo150k => "$75k to $150k",
+            IncomeBand::Over150k => "$150k and up",
+        }
+    }
+}
+
+impl Mobility {
+    pub fn all() -> Vec<Mobility> {
+        vec![
+            Mobility::Unrestricted,
+            Mobility::Limited,
+            Mobility::Wheelchair,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Mobility::Unrestricted => "no mobility restrictions",
+            Mobility::Limited => "limited mobility",
+            Mobility::Wheelchair => "uses a wheelchair",
+        }
+    }
+}
+
+impl PersonAttributes {
+    // Fill in anything missing. Home and car ownership are inferred from the trips when possible;
+    // everything else is drawn from rough US-wide distributions. Nothing already present is
+    // changed.
+    pub fn synthesize(&mut self, trips: &Vec<IndividTrip>, map: &Map, rng: &mut XorShiftRng) {
+        if self.home.is_none() {
+            self.home = guess_home(trips, map);
+        }
+        if self.age.is_none() {
+            self.age = Some(match rng.gen_range(0, 100) {
+                x if x < 22 => AgeBand::Under18,
+                x if x < 45 => AgeBand::From18To34,
+                x if x < 84 => AgeBand::From35To64,
+                _ => AgeBand::Over65,
+            });
+        }
+        if self.income.is_none() {
+            self.income = Some(match rng.gen_range(0, 100) {
+                x if x < 27 => IncomeBand::Under35k,
+                x if x < 55 => IncomeBand::From35kTo75k,
+                x if x < 85 => IncomeBand::From75kTo150k,
+                _ => IncomeBand::Over150k,
+            });
+        }
+        if self.has_car.is_none() {
+            self.has_car = Some(if trips.iter().any(|t| t.trip.mode() == TripMode::Drive) {
+                true
+            } else {
+                // Lower income households are less likely to have a car.
+                let pct = match self.income.unwrap() {
+                    IncomeBand::Under35k => 0.6,
+                    IncomeBand::From35kTo75k => 0.85,
+                    IncomeBand::From75kTo150k | IncomeBand::Over150k => 0.95,
+                };
+                rng.gen_bool(pct)
+            });
+        }
+        if self.mobility.is_none() {
+            let (limited, wheelchair) = if self.age == Some(AgeBand::Over65) {
+                (0.2, 0.04)
+            } else {
+                (0.05, 0.01)
+            };
+            let x: f64 = rng.gen();
+            self.mobility = Some(if x < wheelchair {
+                Mobility::Wheelchair
+            } else if x < wheelchair + limited {
+                Mobility::Limited
+            } else {
+                Mobility::Unrestricted
+            });
+        }
+    }
+}
+
+// Where somebody starts their day, if they also end it there
+fn guess_home(trips: &Vec<IndividTrip>, map: &Map) -> Option<BuildingID> {
+    let first = match trips.first()?.trip.start(map) {
+        TripEndpoint::Bldg(b) => b,
+        TripEndpoint::Border(_, _) => {
+            return None;
+        }
+    };
+    if trips.last()?.trip.end(map) == TripEndpoint::Bldg(first) {
+        Some(first)
+    } else {
+        None
+    }
+}
@@ -1,13 +1,14 @@
 use crate::{
-    CarID, DrivingGoal, OrigPersonID, ParkingSpot, PersonID, SidewalkPOI, SidewalkSpot, Sim,
-    TripEndpoint, TripMode, TripSpec, Vehicle, VehicleSpec, VehicleType, BIKE_LENGTH,
+    CarID, DrivingGoal, FreightTour, OrigPersonID, ParkingSpot, ParkingSurvey, PersonAttributes,
+    PersonID, SidewalkPOI, SidewalkSpot, Sim, TripEndpoint, TripMode, TripPurpose, TripSpec,
+    Vehicle, VehicleSpec, VehicleType, BIKE_ACCEL, BIKE_DECEL, BIKE_LENGTH, CAR_ACCEL, CAR_DECEL,
     MAX_CAR_LENGTH, MIN_CAR_LENGTH, SPAWN_DIST,
 };
 use abstutil::{prettyprint_usize, Counter, Timer};
 use geom::{Distance, Duration, LonLat, Speed, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, DirectedRoadID, Map, OffstreetParking, PathConstraints,
-    Position, RoadID,
+    BikeShareDockID, BuildingID, BusRouteID, BusStopID, DirectedRoadID, Map, OffstreetParking,
+    PathConstraints, Position, RoadID,
 };
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
@@ -15,7 +16,9 @@ use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
 
-// How to start a simulation.
+// How to start a simulation. These're stored with bincode, which can't skip or default missing
+// fields, so after changing anything here or in the types below, existing scenario files have to
+// be regenerated with the importer.
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Scenario {
     pub scenario_name: String,
@@ -24,6 +27,12 @@ pub struct Scenario {
     pub people: Vec<PersonSpec>,
     // None means seed all buses. Otherwise the route name must be present here.
     pub only_seed_buses: Option<BTreeSet<String>>,
+    // If present, seed ownerless parked cars to match observed occupancy.
+    pub parking_survey: Option<ParkingSurvey>,
+    // Delivery vans and trucks. These don't belong to any person.
+    pub freight: Vec<FreightTour>,
+    // How many ride-hail vehicles serve people using TripMode::RideHail
+    pub ride_hail_fleet: usize,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -32,22 +41,25 @@ pub struct PersonSpec {
     // Just used for debugging
     pub orig_id: Option<OrigPersonID>,
     pub trips: Vec<IndividTrip>,
+    pub attributes: PersonAttributes,
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct IndividTrip {
     pub depart: Time,
     pub trip: SpawnTrip,
+    pub purpose: TripPurpose,
     pub cancelled: bool,
     // Did a ScenarioModifier affect this?
     pub modified: bool,
 }
 
 impl IndividTrip {
-    pub fn new(depart: Time, trip: SpawnTrip) -> IndividTrip {
+    pub fn new(depart: Time, purpose: TripPurpose, trip: SpawnTrip) -> IndividTrip {
         IndividTrip {
             depart,
             trip,
+            purpose,
             cancelled: false,
             modified: false,
         }
@@ -79,6 +91,9 @@ pub enum SpawnTrip {
         BusStopID,
         Option<BusStopID>,
     ),
+    UsingRideHail(SidewalkSpot, SidewalkSpot),
+    // Walk to the first dock, ride a shared bike, return it at the second dock, then walk
+    UsingBikeShare(BuildingID, BuildingID, BikeShareDockID, BikeShareDockID),
     // Completely off-map trip. Don't really simulate much of it.
     Remote {
         from: OffMapLocation,
@@ -129,6 +144,7 @@ impl Scenario {
             sim.new_person(
                 p.id,
                 p.orig_id,
+                p.attributes.clone(),
                 Scenario::rand_ped_speed(rng),
                 vehicle_specs,
             );
@@ -150,6 +166,7 @@ impl Scenario {
                     t.depart,
                     spec,
                     t.trip.start(map),
+                    t.purpose,
                     t.cancelled,
                     t.modified,
                     map,
@@ -160,6 +177,26 @@ impl Scenario {
         // parked_cars is stable over map edits, so don't fork.
         parked_cars.shuffle(rng);
         seed_parked_cars(parked_cars, sim, map, rng, timer);
+        if let Some(ref survey) = self.parking_survey {
+            survey.seed_background_cars(sim, map, rng, timer);
+            sim.set_parking_survey(survey.clone());
+        }
+
+        timer.start_iter("seed freight tours", self.freight.len());
+        for tour in &self.freight {
+            timer.next();
+            sim.seed_freight_tour(tour, map, timer);
+        }
+
+        // The driving connection of a building can change over edits, so fork.
+        let mut tmp_rng = abstutil::fork_rng(rng);
+        for _ in 0..self.ride_hail_fleet {
+            if let Some(b) = map.all_buildings().choose(&mut tmp_rng) {
+                if let Some((pos, _)) = b.driving_connection(map) {
+                    sim.seed_ride_hail_vehicle(pos);
+                }
+            }
+        }
 
         sim.flush_spawner(spawner, map, timer);
         timer.stop(format!("Instantiating {}", self.scenario_name));
@@ -178,6 +215,9 @@ impl Scenario {
             map_name: map.get_name().to_string(),
             people: Vec::new(),
             only_seed_buses: Some(BTreeSet::new()),
+            parking_survey: None,
+            freight: Vec::new(),
+            ride_hail_fleet: 0,
         }
     }
 
@@ -187,6 +227,8 @@ impl Scenario {
             vehicle_type: VehicleType::Car,
             length,
             max_speed: None,
+            max_accel: CAR_ACCEL,
+            max_decel: CAR_DECEL,
         }
     }
 
@@ -200,6 +242,8 @@ impl Scenario {
             vehicle_type: VehicleType::Bike,
             length: BIKE_LENGTH,
             max_speed,
+            max_accel: BIKE_ACCEL,
+            max_decel: BIKE_DECEL,
         }
     }
 
@@ -254,6 +298,13 @@ impl Scenario {
         }
         self
     }
+
+    // Fill in any demographics that the travel demand model didn't provide.
+    pub fn synthesize_attributes(&mut self, map: &Map, rng: &mut XorShiftRng) {
+        for person in &mut self.people {
+            person.attributes.synthesize(&person.trips, map, rng);
+        }
+    }
 }
 
 fn seed_parked_cars(
@@ -421,6 +472,13 @@ impl SpawnTrip {
                     maybe_stop2,
                 }
             }
+            SpawnTrip::UsingRideHail(start, goal) => TripSpec::UsingRideHail { start, goal },
+            SpawnTrip::UsingBikeShare(start, goal, dock1, dock2) => TripSpec::UsingBikeShare {
+                start,
+                goal,
+                dock1,
+                dock2,
+            },
             SpawnTrip::Remote {
                 from,
                 to,
@@ -456,6 +514,8 @@ impl SpawnTrip {
             SpawnTrip::UsingBike(_, _) => TripMode::Bike,
             SpawnTrip::JustWalking(_, _) => TripMode::Walk,
             SpawnTrip::UsingTransit(_, _, _, _, _) => TripMode::Transit,
+            SpawnTrip::UsingRideHail(_, _) => TripMode::RideHail,
+            SpawnTrip::UsingBikeShare(_, _, _, _) => TripMode::Bike,
             // TODO Uh...
             SpawnTrip::Remote { .. } => TripMode::Drive,
         }
@@ -471,16 +531,17 @@ impl SpawnTrip {
             }
             SpawnTrip::UsingParkedCar(b, _) => TripEndpoint::Bldg(*b),
             SpawnTrip::UsingBike(b, _) => TripEndpoint::Bldg(*b),
-            SpawnTrip::JustWalking(ref spot, _) | SpawnTrip::UsingTransit(ref spot, _, _, _, _) => {
-                match spot.connection {
-                    SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
-                    SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
-                    SidewalkPOI::SuddenlyAppear => {
-                        TripEndpoint::Border(map.get_l(spot.sidewalk_pos.lane()).src_i, None)
-                    }
-                    _ => unreachable!(),
+            SpawnTrip::UsingBikeShare(b, _, _, _) => TripEndpoint::Bldg(*b),
+            SpawnTrip::JustWalking(ref spot, _)
+            | SpawnTrip::UsingTransit(ref spot, _, _, _, _)
+            | SpawnTrip::UsingRideHail(ref spot, _) => match spot.connection {
+                SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
+                SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
+                SidewalkPOI::SuddenlyAppear => {
+                    TripEndpoint::Border(map.get_l(spot.sidewalk_pos.lane()).src_i, None)
                 }
-            }
+                _ => unreachable!(),
+            },
             // Pick an arbitrary border
             SpawnTrip::Remote { ref from, .. } => {
                 TripEndpoint::Border(map.all_outgoing_borders()[0].id, Some(from.clone()))
@@ -497,13 +558,14 @@ impl SpawnTrip {
                 DrivingGoal::ParkNear(b) => TripEndpoint::Bldg(*b),
                 DrivingGoal::Border(i, _, ref loc) => TripEndpoint::Border(*i, loc.clone()),
             },
-            SpawnTrip::JustWalking(_, ref spot) | SpawnTrip::UsingTransit(_, ref spot, _, _, _) => {
-                match spot.connection {
-                    SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
-                    SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
-                    _ => unreachable!(),
-                }
-            }
+            SpawnTrip::UsingBikeShare(_, b, _, _) => TripEndpoint::Bldg(*b),
+            SpawnTrip::JustWalking(_, ref spot)
+            | SpawnTrip::UsingTransit(_, ref spot, _, _, _)
+            | SpawnTrip::UsingRideHail(_, ref spot) => match spot.connection {
+                SidewalkPOI::Building(b) => TripEndpoint::Bldg(b),
+                SidewalkPOI::Border(i, ref loc) => TripEndpoint::Border(i, loc.clone()),
+                _ => unreachable!(),
+            },
             // Pick an arbitrary border
             SpawnTrip::Remote { ref to, .. } => {
                 TripEndpoint::Border(map.all_incoming_borders()[0].id, Some(to.clone()))
@@ -556,8 +618,33 @@ impl SpawnTrip {
                     SpawnTrip::JustWalking(start, goal)
                 }
             }
+            TripMode::RideHail => {
+                SpawnTrip::UsingRideHail(from.start_sidewalk_spot(map)?, to.end_sidewalk_spot(map)?)
+            }
         })
     }
+
+    // Fails if either building isn't close enough to a dock, or if the same dock is closest to
+    // both.
+    pub fn bike_share(from: BuildingID, to: BuildingID, map: &Map) -> Option<SpawnTrip> {
+        let dock1 = closest_bike_share_dock(from, map)?;
+        let dock2 = closest_bike_share_dock(to, map)?;
+        if dock1 == dock2 {
+            return None;
+        }
+        Some(SpawnTrip::UsingBikeShare(from, to, dock1, dock2))
+    }
+}
+
+// Nobody walks too far to grab a shared bike
+fn closest_bike_share_dock(b: BuildingID, map: &Map) -> Option<BikeShareDockID> {
+    let pt = map.get_b(b).sidewalk_pos.pt(map);
+    map.all_bike_share_docks()
+        .iter()
+        .map(|dock| (dock.id, dock.sidewalk_pos.pt(map).dist_to(pt)))
+        .filter(|(_, dist)| *dist <= Distance::meters(500.0))
+        .min_by_key(|(_, dist)| *dist)
+        .map(|(id, _)| id)
 }
 
 impl PersonSpec {
@@ -699,7 +786,10 @@ impl PersonSpec {
                     }
                     bike_idx
                 }
-                SpawnTrip::JustWalking(_, _) | SpawnTrip::UsingTransit(_, _, _, _, _) => None,
+                SpawnTrip::JustWalking(_, _)
+                | SpawnTrip::UsingTransit(_, _, _, _, _)
+                | SpawnTrip::UsingRideHail(_, _)
+                | SpawnTrip::UsingBikeShare(_, _, _, _) => None,
                 SpawnTrip::Remote { .. } => None,
             };
             vehicle_foreach_trip.push(use_for_trip);
@@ -1,11 +1,12 @@
 use crate::{
-    CarID, Command, DrivingGoal, OffMapLocation, Person, PersonID, Scheduler, SidewalkSpot,
-    TripEndpoint, TripLeg, TripManager, TripMode, VehicleType,
+    CarID, Command, DrivingGoal, OffMapLocation, Person, PersonID, Scheduler, SidewalkPOI,
+    SidewalkSpot, TripEndpoint, TripLeg, TripManager, TripMode, TripPurpose, VehicleType,
 };
 use abstutil::{Parallelism, Timer};
 use geom::{Duration, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints, PathRequest, Position,
+    BikeShareDockID, BuildingID, BusRouteID, BusStopID, IntersectionID, Map, PathConstraints,
+    PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 
@@ -52,6 +53,18 @@ pub enum TripSpec {
         stop1: BusStopID,
         maybe_stop2: Option<BusStopID>,
     },
+    // Walk to the nearest curb, get picked up by a ride-hail vehicle, and get dropped off at the
+    // curb nearest the goal.
+    UsingRideHail {
+        start: SidewalkSpot,
+        goal: SidewalkSpot,
+    },
+    UsingBikeShare {
+        start: BuildingID,
+        goal: BuildingID,
+        dock1: BikeShareDockID,
+        dock2: BikeShareDockID,
+    },
     // Completely off-map trip. Don't really simulate much of it.
     Remote {
         from: OffMapLocation,
@@ -63,7 +76,15 @@ pub enum TripSpec {
 
 // This structure is created temporarily by a Scenario or to interactively spawn agents.
 pub struct TripSpawner {
-    trips: Vec<(PersonID, Time, TripSpec, TripEndpoint, bool, bool)>,
+    trips: Vec<(
+        PersonID,
+        Time,
+        TripSpec,
+        TripEndpoint,
+        TripPurpose,
+        bool,
+        bool,
+    )>,
 }
 
 impl TripSpawner {
@@ -77,6 +98,7 @@ impl TripSpawner {
         start_time: Time,
         mut spec: TripSpec,
         trip_start: TripEndpoint,
+        purpose: TripPurpose,
         cancelled: bool,
         modified: bool,
         map: &Map,
@@ -176,11 +198,79 @@ impl TripSpawner {
                 }
             }
             TripSpec::UsingTransit { .. } => {}
+            TripSpec::UsingRideHail { start, goal } => {
+                let backup_plan = TripSpec::JustWalking {
+                    start: start.clone(),
+                    goal: goal.clone(),
+                };
+                match (
+                    SidewalkSpot::ride_hail_stop(start.sidewalk_pos, map),
+                    SidewalkSpot::ride_hail_stop(goal.sidewalk_pos, map),
+                ) {
+                    (Some(pickup), Some(dropoff)) => {
+                        if let (SidewalkPOI::RideHailStop(pos1), SidewalkPOI::RideHailStop(pos2)) =
+                            (pickup.connection, dropoff.connection)
+                        {
+                            if pos1.lane() == pos2.lane() {
+                                println!(
+                                    "Ride-hail trip from {:?} to {:?} will just walk; it's the \
+                                     same road!",
+                                    start, goal
+                                );
+                                spec = backup_plan;
+                            }
+                        }
+                    }
+                    _ => {
+                        println!(
+                            "Can't find a curb for a ride-hail trip from {:?} to {:?}. Walking \
+                             instead",
+                            start, goal
+                        );
+                        spec = backup_plan;
+                    }
+                }
+            }
+            TripSpec::UsingBikeShare {
+                start,
+                goal,
+                dock1,
+                dock2,
+            } => {
+                let backup_plan = TripSpec::JustWalking {
+                    start: SidewalkSpot::building(*start, map),
+                    goal: SidewalkSpot::building(*goal, map),
+                };
+                // Edits might've removed the way to bike away from a dock
+                match (
+                    SidewalkSpot::bike_share_dock(*dock1, map),
+                    SidewalkSpot::bike_share_dock(*dock2, map),
+                ) {
+                    (Some(spot1), Some(spot2)) => {
+                        if spot1.sidewalk_pos.lane() == spot2.sidewalk_pos.lane() {
+                            println!(
+                                "Bike-share trip from {} to {} will just walk; it's the same \
+                                 sidewalk!",
+                                start, goal
+                            );
+                            spec = backup_plan;
+                        }
+                    }
+                    _ => {
+                        println!(
+                            "Can't bike between {} and {} for a bike-share trip. Walking instead",
+                            dock1, dock2
+                        );
+                        spec = backup_plan;
+                    }
+                }
+            }
             TripSpec::Remote { .. } => {}
         };
 
-        self.trips
-            .push((person.id, start_time, spec, trip_start, cancelled, modified));
+        self.trips.push((
+            person.id, start_time, spec, trip_start, purpose, cancelled, modified,
+        ));
     }
 
     pub fn finalize(
@@ -217,7 +307,11 @@ impl TripSpawner {
         }
 
         timer.start_iter("spawn trips", paths.len());
-        for ((p, start_time, spec, trip_start, cancelled, modified), maybe_req, maybe_path) in paths
+        for (
+            (p, start_time, spec, trip_start, purpose, cancelled, modified),
+            maybe_req,
+            maybe_path,
+        ) in paths
         {
             timer.next();
 
@@ -243,6 +337,7 @@ impl TripSpawner {
                         } else {
                             TripMode::Drive
                         },
+                        purpose,
                         modified,
                         legs,
                         map,
@@ -264,6 +359,7 @@ impl TripSpawner {
                         } else {
                             TripMode::Drive
                         },
+                        purpose,
                         modified,
                         legs,
                         map,
@@ -285,6 +381,7 @@ impl TripSpawner {
                         start_time,
                         trip_start,
                         TripMode::Drive,
+                        purpose,
                         modified,
                         legs,
                         map,
@@ -295,6 +392,7 @@ impl TripSpawner {
                     start_time,
                     trip_start,
                     TripMode::Walk,
+                    purpose,
                     modified,
                     vec![TripLeg::Walk(goal.clone())],
                     map,
@@ -316,6 +414,7 @@ impl TripSpawner {
                         start_time,
                         trip_start,
                         TripMode::Bike,
+                        purpose,
                         modified,
                         legs,
                         map,
@@ -346,16 +445,56 @@ impl TripSpawner {
                         start_time,
                         trip_start,
                         TripMode::Transit,
+                        purpose,
                         modified,
                         legs,
                         map,
                     )
                 }
+                TripSpec::UsingRideHail { start, goal } => {
+                    let walk_to = SidewalkSpot::ride_hail_stop(start.sidewalk_pos, map).unwrap();
+                    let dropoff = SidewalkSpot::ride_hail_stop(goal.sidewalk_pos, map).unwrap();
+                    trips.new_trip(
+                        person.id,
+                        start_time,
+                        trip_start,
+                        TripMode::RideHail,
+                        purpose,
+                        modified,
+                        vec![
+                            TripLeg::Walk(walk_to),
+                            TripLeg::RideHail(dropoff),
+                            TripLeg::Walk(goal),
+                        ],
+                        map,
+                    )
+                }
+                TripSpec::UsingBikeShare {
+                    goal, dock1, dock2, ..
+                } => {
+                    // The shared bike only needs an ID while somebody's riding it
+                    let bike = CarID(trips.new_car_id(), VehicleType::Bike);
+                    trips.new_trip(
+                        person.id,
+                        start_time,
+                        trip_start,
+                        TripMode::Bike,
+                        purpose,
+                        modified,
+                        vec![
+                            TripLeg::Walk(SidewalkSpot::bike_share_dock(dock1, map).unwrap()),
+                            TripLeg::BikeShare(bike, dock2),
+                            TripLeg::Walk(SidewalkSpot::building(goal, map)),
+                        ],
+                        map,
+                    )
+                }
                 TripSpec::Remote { to, mode, .. } => trips.new_trip(
                     person.id,
                     start_time,
                     trip_start,
                     mode,
+                    purpose,
                     modified,
                     vec![TripLeg::Remote(to)],
                     map,
@@ -412,6 +551,18 @@ impl TripSpec {
                 end: SidewalkSpot::bus_stop(*stop1, map).sidewalk_pos,
                 constraints: PathConstraints::Pedestrian,
             }),
+            TripSpec::UsingBikeShare { start, dock1, .. } => Some(PathRequest {
+                start: map.get_b(*start).sidewalk_pos,
+                end: map.get_bsd(*dock1).sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            }),
+            TripSpec::UsingRideHail { start, .. } => Some(PathRequest {
+                start: start.sidewalk_pos,
+                end: SidewalkSpot::ride_hail_stop(start.sidewalk_pos, map)
+                    .unwrap()
+                    .sidewalk_pos,
+                constraints: PathConstraints::Pedestrian,
+            }),
             TripSpec::Remote { .. } => None,
         }
     }
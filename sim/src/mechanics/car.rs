@@ -2,7 +2,7 @@ use crate::{
     CarStatus, DistanceInterval, DrawCarInput, ParkingSpot, PersonID, Router, TimeInterval,
     TransitSimState, TripID, Vehicle, VehicleType,
 };
-use geom::{Distance, Duration, PolyLine, Time};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
 use map_model::{Map, Traversable};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
@@ -21,35 +21,72 @@ pub struct Car {
     // In reverse order -- most recently left is first. The sum length of these must be >=
     // vehicle.length.
     pub last_steps: VecDeque<Traversable>,
+    // How fast the car was going at the end of its most recent Crossing state. If the car
+    // immediately starts crossing something else without being blocked, it keeps this speed.
+    pub end_speed: Speed,
 }
 
 impl Car {
-    // Assumes the current head of the path is the thing to cross.
+    // Assumes the current head of the path is the thing to cross. The car only plans to brake at
+    // the very end of its path; DrivingSimState decides about stopping anywhere else.
     pub fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> CarState {
-        let dist_int = DistanceInterval::new_driving(
-            start_dist,
-            if self.router.last_step() {
-                self.router.get_end_dist()
-            } else {
-                self.router.head().length(map)
-            },
-        );
-        self.crossing_state_with_end_dist(dist_int, start_time, map)
+        let dist_int = DistanceInterval::new_driving(start_dist, self.end_of_current_step(map));
+        self.crossing_state_with_end_dist(dist_int, start_time, map, false)
     }
 
+    // If stop_at_end is false, the car still brakes if dist_int ends at the end of its path.
     pub fn crossing_state_with_end_dist(
         &self,
         dist_int: DistanceInterval,
         start_time: Time,
         map: &Map,
+        stop_at_end: bool,
     ) -> CarState {
         let on = self.router.head();
         let mut speed = on.speed_limit(map);
-        if let Some(s) = self.vehicle.max_speed {
+        if let Some(s) = self.vehicle.max_speed_on(on, map) {
             speed = speed.min(s);
         }
-        let dt = (dist_int.end - dist_int.start) / speed;
-        CarState::Crossing(TimeInterval::new(start_time, start_time + dt), dist_int)
+        let profile = SpeedProfile::new(
+            dist_int.end - dist_int.start,
+            self.current_speed(start_time),
+            speed,
+            stop_at_end || (self.router.last_step() && dist_int.end == self.router.get_end_dist()),
+            self.vehicle.max_accel,
+            self.vehicle.max_decel,
+        );
+        CarState::Crossing(
+            TimeInterval::new(start_time, start_time + profile.duration()),
+            dist_int,
+            profile,
+        )
+    }
+
+    pub fn end_of_current_step(&self, map: &Map) -> Distance {
+        if self.router.last_step() {
+            self.router.get_end_dist()
+        } else {
+            self.router.head().length(map)
+        }
+    }
+
+    // How fast is the car going right before it starts a new Crossing state?
+    fn current_speed(&self, now: Time) -> Speed {
+        match self.state {
+            CarState::Crossing(ref time_int, _, ref profile) => {
+                profile.speed_at(now - time_int.start)
+            }
+            CarState::Queued { blocked_since } | CarState::WaitingToAdvance { blocked_since } => {
+                if blocked_since == now {
+                    self.end_speed
+                } else {
+                    Speed::ZERO
+                }
+            }
+            CarState::Unparking(_, _, _)
+            | CarState::Parking(_, _, _)
+            | CarState::IdlingAtStop(_, _) => Speed::ZERO,
+        }
     }
 
     pub fn get_draw_car(
@@ -128,12 +165,8 @@ impl Car {
                 match spot {
                     ParkingSpot::Onstreet(parking_l, _) => {
                         let r = map.get_parent(*parking_l);
-                        let driving_offset = r.offset_from_left(self.router.head().as_lane());
-                        let parking_offset = r.offset_from_left(*parking_l);
-                        let mut diff = (parking_offset as isize) - (driving_offset as isize);
-                        if !r.is_forwards(self.router.head().as_lane()) {
-                            diff *= -1;
-                        }
+                        let diff =
+                            r.lanes_to_the_right(self.router.head().as_lane(), *parking_l, map);
                         // TODO Sum widths in between, don't assume they're all the same as the
                         // parking lane width!
                         let width = map.get_l(*parking_l).width * (diff as f64) * percent_time;
@@ -210,7 +243,7 @@ impl Car {
             status: match self.state {
                 CarState::Queued { .. } => CarStatus::Moving,
                 CarState::WaitingToAdvance { .. } => CarStatus::Moving,
-                CarState::Crossing(_, _) => CarStatus::Moving,
+                CarState::Crossing(_, _, _) => CarStatus::Moving,
                 // Eh they're technically moving, but this is a bit easier to spot
                 CarState::Unparking(_, _, _) => CarStatus::Parked,
                 CarState::Parking(_, _, _) => CarStatus::Parked,
@@ -244,7 +277,7 @@ impl Car {
 
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum CarState {
-    Crossing(TimeInterval, DistanceInterval),
+    Crossing(TimeInterval, DistanceInterval, SpeedProfile),
     Queued { blocked_since: Time },
     WaitingToAdvance { blocked_since: Time },
     // Where's the front of the car while this is happening?
@@ -256,7 +289,7 @@ pub enum CarState {
 impl CarState {
     pub fn get_end_time(&self) -> Time {
         match self {
-            CarState::Crossing(ref time_int, _) => time_int.end,
+            CarState::Crossing(ref time_int, _, _) => time_int.end,
             CarState::Queued { .. } => unreachable!(),
             CarState::WaitingToAdvance { .. } => unreachable!(),
             CarState::Unparking(_, _, ref time_int) => time_int.end,
@@ -265,3 +298,139 @@ impl CarState {
         }
     }
 }
+
+// How a car's speed changes while it crosses a DistanceInterval: accelerate from the initial speed
+// towards the cruising speed, hold it, then maybe brake at the end. Everything is solved up-front,
+// so the position at any time is a closed-form expression and the scheduler only needs to know
+// when the whole thing ends. Speeds are in meters per second, accelerations in meters per second
+// squared.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
+pub struct SpeedProfile {
+    length: f64,
+    initial_speed: f64,
+    peak_speed: f64,
+    final_speed: f64,
+    accel: f64,
+    decel: f64,
+    // Seconds spent in each phase
+    accel_time: f64,
+    cruise_time: f64,
+    decel_time: f64,
+}
+
+impl SpeedProfile {
+    pub fn new(
+        dist: Distance,
+        initial_speed: Speed,
+        max_speed: Speed,
+        stop_at_end: bool,
+        accel: f64,
+        mut decel: f64,
+    ) -> SpeedProfile {
+        assert!(accel > 0.0 && decel > 0.0);
+        let length = dist.inner_meters();
+        let max_speed = max_speed.inner_meters_per_second();
+        // If the speed limit just dropped, slow down instantly.
+        let v0 = initial_speed
+            .inner_meters_per_second()
+            .max(0.0)
+            .min(max_speed);
+        if length <= 0.0 {
+            return SpeedProfile {
+                length: 0.0,
+                initial_speed: v0,
+                peak_speed: v0,
+                final_speed: v0,
+                accel,
+                decel,
+                accel_time: 0.0,
+                cruise_time: 0.0,
+                decel_time: 0.0,
+            };
+        }
+
+        // If there isn't room to reach full speed, just accelerate the whole way.
+        let v_end = if stop_at_end {
+            0.0
+        } else {
+            max_speed.min((v0 * v0 + 2.0 * accel * length).sqrt())
+        };
+        // If there isn't room to stop comfortably, brake harder.
+        if stop_at_end && v0 * v0 > 2.0 * decel * length {
+            decel = v0 * v0 / (2.0 * length);
+        }
+        // The peak speed where the acceleration and braking curves meet, if the car never reaches
+        // max_speed.
+        let v_peak = ((2.0 * accel * decel * length + decel * v0 * v0 + accel * v_end * v_end)
+            / (accel + decel))
+            .sqrt()
+            .min(max_speed)
+            .max(v0)
+            .max(v_end);
+
+        let accel_time = (v_peak - v0) / accel;
+        let accel_dist = (v_peak * v_peak - v0 * v0) / (2.0 * accel);
+        let decel_time = (v_peak - v_end) / decel;
+        let decel_dist = (v_peak * v_peak - v_end * v_end) / (2.0 * decel);
+        let cruise_dist = (length - accel_dist - decel_dist).max(0.0);
+        let cruise_time = if v_peak > 0.0 {
+            cruise_dist / v_peak
+        } else {
+            0.0
+        };
+
+        SpeedProfile {
+            length,
+            initial_speed: v0,
+            peak_speed: v_peak,
+            final_speed: v_end,
+            accel,
+            decel,
+            accel_time,
+            cruise_time,
+            decel_time,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(self.accel_time + self.cruise_time + self.decel_time)
+    }
+
+    // How far along the interval is the car after this much time?
+    pub fn dist_at(&self, elapsed: Duration) -> Distance {
+        let t = elapsed.inner_seconds().max(0.0);
+        if t >= self.accel_time + self.cruise_time + self.decel_time {
+            return Distance::meters(self.length);
+        }
+        let accel_dist = self.initial_speed * self.accel_time
+            + 0.5 * self.accel * self.accel_time * self.accel_time;
+        let dist = if t < self.accel_time {
+            self.initial_speed * t + 0.5 * self.accel * t * t
+        } else if t < self.accel_time + self.cruise_time {
+            accel_dist + self.peak_speed * (t - self.accel_time)
+        } else {
+            let t3 = t - self.accel_time - self.cruise_time;
+            accel_dist + self.peak_speed * self.cruise_time + self.peak_speed * t3
+                - 0.5 * self.decel * t3 * t3
+        };
+        Distance::meters(dist.min(self.length))
+    }
+
+    pub fn speed_at(&self, elapsed: Duration) -> Speed {
+        let t = elapsed.inner_seconds().max(0.0);
+        let speed = if t < self.accel_time {
+            self.initial_speed + self.accel * t
+        } else if t < self.accel_time + self.cruise_time {
+            self.peak_speed
+        } else if t < self.accel_time + self.cruise_time + self.decel_time {
+            self.peak_speed - self.decel * (t - self.accel_time - self.cruise_time)
+        } else {
+            self.final_speed
+        };
+        Speed::meters_per_second(speed.max(0.0))
+    }
+
+    pub fn final_speed(&self) -> Speed {
+        Speed::meters_per_second(self.final_speed)
+    }
+}
@@ -1,14 +1,16 @@
 use crate::mechanics::car::{Car, CarState};
 use crate::mechanics::Queue;
 use crate::{
-    ActionAtEnd, AgentID, AgentProperties, CarID, Command, CreateCar, DistanceInterval,
-    DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState, ParkingSpot, PersonID,
-    Scheduler, TimeInterval, TransitSimState, TripManager, UnzoomedAgent, Vehicle, WalkingSimState,
-    FOLLOWING_DISTANCE,
+    ActionAtEnd, AgentID, AgentProperties, BikeShareSimState, CarID, Command, CreateCar,
+    DistanceInterval, DrawCarInput, Event, IntersectionSimState, ParkedCar, ParkingSimState,
+    ParkingSpot, PersonID, RideHailSimState, Scheduler, SidewalkPOI, TimeInterval, TransitSimState,
+    TripManager, UnzoomedAgent, Vehicle, VehicleType, WalkingSimState, FOLLOWING_DISTANCE,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap};
-use geom::{Distance, Duration, PolyLine, Time};
-use map_model::{LaneID, Map, Path, PathStep, Traversable};
+use geom::{Distance, Duration, PolyLine, Speed, Time};
+use map_model::{
+    LaneID, Map, Path, PathConstraints, PathRequest, PathStep, Position, Traversable, TurnID,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashSet, VecDeque};
 
@@ -17,10 +19,17 @@ const TIME_TO_PARK_ONSTREET: Duration = Duration::const_seconds(15.0);
 const TIME_TO_UNPARK_OFFSTREET: Duration = Duration::const_seconds(5.0);
 const TIME_TO_PARK_OFFSTREET: Duration = Duration::const_seconds(5.0);
 const TIME_TO_WAIT_AT_STOP: Duration = Duration::const_seconds(10.0);
+// Beyond FOLLOWING_DISTANCE, how much space must be free ahead of and behind a vehicle cutting into
+// an adjacent lane mid-block?
+const LANECHANGE_GAP: Distance = Distance::const_meters(2.0);
+// Don't bother changing lanes unless it gets the vehicle at least this much farther along.
+const MIN_LANECHANGE_GAIN: Distance = Distance::const_meters(10.0);
 
 // TODO Do something else.
 pub(crate) const BLIND_RETRY_TO_CREEP_FORWARDS: Duration = Duration::const_seconds(0.1);
 pub(crate) const BLIND_RETRY_TO_REACH_END_DIST: Duration = Duration::const_seconds(5.0);
+// How often a vehicle stuck behind a leader in the middle of a lane looks for a gap again
+const BLIND_RETRY_TO_LANECHANGE: Duration = Duration::const_seconds(5.0);
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct DrivingSimState {
@@ -37,15 +46,21 @@ pub struct DrivingSimState {
     events: Vec<Event>,
 
     recalc_lanechanging: bool,
+    mid_block_lanechanging: bool,
 }
 
 impl DrivingSimState {
-    pub fn new(map: &Map, recalc_lanechanging: bool) -> DrivingSimState {
+    pub fn new(
+        map: &Map,
+        recalc_lanechanging: bool,
+        mid_block_lanechanging: bool,
+    ) -> DrivingSimState {
         let mut sim = DrivingSimState {
             cars: BTreeMap::new(),
             queues: BTreeMap::new(),
             events: Vec::new(),
             recalc_lanechanging,
+            mid_block_lanechanging,
         };
 
         for l in map.all_lanes() {
@@ -82,6 +97,7 @@ impl DrivingSimState {
         if let Some(idx) = self.queues[&Traversable::Lane(first_lane)].get_idx_to_insert_car(
             params.start_dist,
             params.vehicle.length,
+            Distance::ZERO,
             now,
             &self.cars,
             &self.queues,
@@ -92,6 +108,12 @@ impl DrivingSimState {
                 // Temporary
                 state: CarState::Queued { blocked_since: now },
                 last_steps: VecDeque::new(),
+                // Vehicles appearing at a border are already moving.
+                end_speed: if map.get_i(map.get_l(first_lane).src_i).is_incoming_border() {
+                    Traversable::Lane(first_lane).speed_limit(map)
+                } else {
+                    Speed::ZERO
+                },
                 started_at: now,
                 total_blocked_time: Duration::ZERO,
                 trip_and_person: params.trip_and_person,
@@ -120,6 +142,10 @@ impl DrivingSimState {
                         &mut self.events,
                     ) {
                         None | Some(ActionAtEnd::GotoLaneEnd) => {}
+                        // A delivery vehicle's first stop might be right where it appears.
+                        // update_car handles that like any other stop.
+                        Some(ActionAtEnd::MakeDelivery(_, _))
+                        | Some(ActionAtEnd::StartLoading(_)) => {}
                         x => {
                             panic!(
                                 "Car with one-step route {:?} had unexpected result from \
@@ -142,10 +168,7 @@ impl DrivingSimState {
                         return false;
                     }
                 }
-
-                car.state = car.crossing_state(params.start_dist, now, map);
             }
-            scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             {
                 let queue = self.queues.get_mut(&Traversable::Lane(first_lane)).unwrap();
                 queue.cars.insert(idx, car.vehicle.id);
@@ -153,6 +176,12 @@ impl DrivingSimState {
                 // get_idx_to_insert_car does a more detailed check of the current space usage.
                 queue.reserved_length += car.vehicle.length + FOLLOWING_DISTANCE;
             }
+            // Still the temporary state, so the car isn't unparking. Now that the car is in the
+            // queue, see if it has to brake for somebody in front.
+            if let CarState::Queued { .. } = car.state {
+                car.state = self.crossing_state(&car, params.start_dist, now, map, intersections);
+            }
+            scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             self.cars.insert(car.vehicle.id, car);
             return true;
         }
@@ -170,13 +199,15 @@ impl DrivingSimState {
         scheduler: &mut Scheduler,
         transit: &mut TransitSimState,
         walking: &mut WalkingSimState,
+        ridehail: &mut RideHailSimState,
+        bikeshare: &mut BikeShareSimState,
     ) {
         // State transitions for this car:
         //
         // Crossing -> Queued or WaitingToAdvance
         // Unparking -> Crossing
         // IdlingAtStop -> Crossing
-        // Queued -> last step handling (Parking or done)
+        // Queued -> last step handling (Parking or done), or retry a mid-block lane-change
         // WaitingToAdvance -> try to advance to the next step of the path
         // Parking -> done
         //
@@ -196,9 +227,29 @@ impl DrivingSimState {
             match car.state {
                 CarState::Queued { .. } => car.router.last_step(),
                 CarState::Parking(_, _, _) => true,
+                // The car braked for a stopped leader partway along. The leader might've moved
+                // since, so figure out where the car actually is before it becomes Queued.
+                CarState::Crossing(_, ref dist_int, _) => {
+                    dist_int.end < car.end_of_current_step(map)
+                }
                 _ => false,
             }
         };
+        let was_crossing = match self.cars[&id].state {
+            CarState::Crossing(_, _, _) => true,
+            _ => false,
+        };
+        if self.mid_block_lanechanging {
+            let car = &self.cars[&id];
+            if let CarState::Queued { .. } = car.state {
+                if !car.router.last_step() {
+                    // Nothing else wakes up a car stuck behind a leader, besides the leader moving
+                    // out of the way. This is a retry to change lanes.
+                    self.try_mid_block_lanechange(id, now, map, intersections, scheduler);
+                    return;
+                }
+            }
+        }
 
         if !need_distances {
             // We need to mutate two different cars in one case. To avoid fighting the borrow
@@ -212,9 +263,18 @@ impl DrivingSimState {
                 parking,
                 intersections,
                 transit,
+                ridehail,
                 scheduler,
             );
+            let stuck_behind_leader = match car.state {
+                CarState::Queued { .. } => !car.router.last_step(),
+                _ => false,
+            };
             self.cars.insert(id, car);
+
+            if stuck_behind_leader && self.mid_block_lanechanging {
+                self.try_mid_block_lanechange(id, now, map, intersections, scheduler);
+            }
         }
 
         if need_distances {
@@ -231,13 +291,37 @@ impl DrivingSimState {
             let mut car = self.cars.remove(&id).unwrap();
             // Responsibility of update_car_with_distances to manage scheduling stuff!
             if self.update_car_with_distances(
-                &mut car, &dists, idx, now, map, parking, trips, scheduler, transit, walking,
+                &mut car,
+                &dists,
+                idx,
+                now,
+                map,
+                parking,
+                intersections,
+                trips,
+                scheduler,
+                transit,
+                walking,
+                ridehail,
+                bikeshare,
             ) {
+                let stuck_behind_leader = match car.state {
+                    CarState::Queued { .. } => !car.router.last_step(),
+                    _ => false,
+                };
                 self.cars.insert(id, car);
+
+                if stuck_behind_leader && self.mid_block_lanechanging {
+                    self.try_mid_block_lanechange(id, now, map, intersections, scheduler);
+                }
             } else {
                 self.delete_car(&mut car, dists, idx, now, map, scheduler, intersections);
             }
         }
+
+        if was_crossing && self.cars.contains_key(&id) {
+            self.brake_follower(id, now, map, intersections, scheduler);
+        }
     }
 
     // If this returns true, we need to immediately run update_car_with_distances. If we don't,
@@ -251,10 +335,12 @@ impl DrivingSimState {
         parking: &mut ParkingSimState,
         intersections: &mut IntersectionSimState,
         transit: &mut TransitSimState,
+        ridehail: &mut RideHailSimState,
         scheduler: &mut Scheduler,
     ) -> bool {
         match car.state {
-            CarState::Crossing(_, _) => {
+            CarState::Crossing(_, _, ref profile) => {
+                car.end_speed = profile.final_speed();
                 car.state = CarState::Queued { blocked_since: now };
                 if car.router.last_step() {
                     // Immediately run update_car_with_distances.
@@ -268,6 +354,10 @@ impl DrivingSimState {
                         car.router.opportunistically_lanechange(&self.queues, map);
                     }
                     scheduler.push(now, Command::UpdateCar(car.vehicle.id));
+                } else {
+                    // We're stuck behind somebody, so we didn't really reach the end of the
+                    // profile.
+                    car.end_speed = Speed::ZERO;
                 }
             }
             CarState::Unparking(front, _, _) => {
@@ -285,14 +375,20 @@ impl DrivingSimState {
                         &mut self.events,
                     );
                 }
-                car.state = car.crossing_state(front, now, map);
+                car.state = self.crossing_state(car, front, now, map, intersections);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
             }
             CarState::IdlingAtStop(dist, _) => {
-                car.router = transit.bus_departed_from_stop(car.vehicle.id, map);
+                if car.vehicle.vehicle_type.is_freight() {
+                    car.router.finished_delivery();
+                } else if car.vehicle.vehicle_type == VehicleType::RideHail {
+                    car.router = ridehail.vehicle_departed(car.vehicle.id);
+                } else {
+                    car.router = transit.bus_departed_from_stop(car.vehicle.id, map);
+                }
                 self.events
                     .push(Event::PathAmended(car.router.get_path().clone()));
-                car.state = car.crossing_state(dist, now, map);
+                car.state = self.crossing_state(car, dist, now, map, intersections);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
 
                 // Update our follower, so they know we stopped idling.
@@ -303,19 +399,23 @@ impl DrivingSimState {
                     .position(|c| *c == car.vehicle.id)
                     .unwrap();
                 if idx != queue.cars.len() - 1 {
-                    let mut follower = self.cars.get_mut(&queue.cars[idx + 1]).unwrap();
-                    match follower.state {
+                    let follower_id = queue.cars[idx + 1];
+                    match self.cars[&follower_id].state {
                         CarState::Queued { blocked_since } => {
                             // If they're on their last step, they might be ending early and not
                             // right behind us.
-                            if !follower.router.last_step() {
-                                follower.total_blocked_time += now - blocked_since;
-                                follower.state = follower.crossing_state(
+                            if !self.cars[&follower_id].router.last_step() {
+                                let state = self.crossing_state(
+                                    &self.cars[&follower_id],
                                     // Since the follower was Queued, this must be where they are.
                                     dist - car.vehicle.length - FOLLOWING_DISTANCE,
                                     now,
                                     map,
+                                    intersections,
                                 );
+                                let mut follower = self.cars.get_mut(&follower_id).unwrap();
+                                follower.total_blocked_time += now - blocked_since;
+                                follower.state = state;
                                 scheduler.update(
                                     follower.state.get_end_time(),
                                     Command::UpdateCar(follower.vehicle.id),
@@ -326,7 +426,7 @@ impl DrivingSimState {
                         // They weren't blocked. Note that there's no way the Crossing state could
                         // jump forwards here; the leader is still in front
                         // of them.
-                        CarState::Crossing(_, _)
+                        CarState::Crossing(_, _, _)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
                         | CarState::IdlingAtStop(_, _) => {}
@@ -342,7 +442,7 @@ impl DrivingSimState {
 
                 if let Traversable::Turn(t) = goto {
                     let mut speed = goto.speed_limit(map);
-                    if let Some(s) = car.vehicle.max_speed {
+                    if let Some(s) = car.vehicle.max_speed_on(goto, map) {
                         speed = speed.min(s);
                     }
                     if !intersections.maybe_start_turn(
@@ -376,7 +476,8 @@ impl DrivingSimState {
                     &mut self.events,
                 );
                 car.total_blocked_time += now - blocked_since;
-                car.state = car.crossing_state(Distance::ZERO, now, map);
+                // We're not in the next queue yet, so anybody there is in front of us.
+                car.state = self.crossing_state(car, Distance::ZERO, now, map, intersections);
                 scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                 self.events.push(Event::AgentEntersTraversable(
                     AgentID::Car(car.vehicle.id),
@@ -403,6 +504,7 @@ impl DrivingSimState {
                         ),
                         now,
                         map,
+                        false,
                     )
                     .get_end_time(),
                     Command::UpdateLaggyHead(car.vehicle.id),
@@ -428,18 +530,42 @@ impl DrivingSimState {
         now: Time,
         map: &Map,
         parking: &mut ParkingSimState,
+        intersections: &IntersectionSimState,
         trips: &mut TripManager,
         scheduler: &mut Scheduler,
         transit: &mut TransitSimState,
         walking: &mut WalkingSimState,
+        ridehail: &mut RideHailSimState,
+        bikeshare: &mut BikeShareSimState,
     ) -> bool {
         let our_dist = dists[idx].1;
 
         match car.state {
-            CarState::Crossing(_, _)
-            | CarState::Unparking(_, _, _)
+            CarState::Unparking(_, _, _)
             | CarState::IdlingAtStop(_, _)
             | CarState::WaitingToAdvance { .. } => unreachable!(),
+            CarState::Crossing(_, _, ref profile) => {
+                car.end_speed = profile.final_speed();
+                car.state = CarState::Queued { blocked_since: now };
+                let still_blocked = idx > 0 && {
+                    let (leader, leader_dist) = dists[idx - 1];
+                    leader_dist - self.cars[&leader].vehicle.length - FOLLOWING_DISTANCE <= our_dist
+                };
+                if !still_blocked {
+                    // The leader moved on while we were braking, so start moving again.
+                    car.state = self.crossing_state(car, our_dist, now, map, intersections);
+                    scheduler.push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                } else if car.router.last_step() {
+                    // Same as any other Queued car that hasn't reached its end distance yet
+                    scheduler.push(
+                        now + BLIND_RETRY_TO_REACH_END_DIST,
+                        Command::UpdateCar(car.vehicle.id),
+                    );
+                }
+                // Otherwise stay Queued right behind the leader, just like a car that reached the
+                // end of its Crossing state while stuck behind somebody.
+                true
+            }
             CarState::Queued { blocked_since } => {
                 match car.router.maybe_handle_end(
                     our_dist,
@@ -479,7 +605,9 @@ impl DrivingSimState {
                         );
                         false
                     }
-                    Some(ActionAtEnd::StartParking(spot)) => {
+                    // Delivery vehicles pull into a loading zone just like parking.
+                    Some(ActionAtEnd::StartParking(spot))
+                    | Some(ActionAtEnd::StartLoading(spot)) => {
                         car.total_blocked_time += now - blocked_since;
                         let delay = match spot {
                             ParkingSpot::Onstreet(_, _) => TIME_TO_PARK_ONSTREET,
@@ -499,13 +627,16 @@ impl DrivingSimState {
                     }
                     Some(ActionAtEnd::GotoLaneEnd) => {
                         car.total_blocked_time += now - blocked_since;
-                        car.state = car.crossing_state(our_dist, now, map);
+                        car.state = self.crossing_state(car, our_dist, now, map, intersections);
                         scheduler
                             .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
                         true
                     }
                     Some(ActionAtEnd::StopBiking(bike_rack)) => {
                         car.total_blocked_time += now - blocked_since;
+                        if let SidewalkPOI::BikeShareDock(dock, _) = bike_rack.connection {
+                            bikeshare.return_bike(dock);
+                        }
                         trips.bike_reached_end(
                             now,
                             car.vehicle.id,
@@ -539,6 +670,45 @@ impl DrivingSimState {
                             false
                         }
                     }
+                    Some(ActionAtEnd::MakeDelivery(bldg, dwell)) => {
+                        car.total_blocked_time += now - blocked_since;
+                        // Double-park, blocking anybody behind. Nobody tries to pass.
+                        car.state =
+                            CarState::IdlingAtStop(our_dist, TimeInterval::new(now, now + dwell));
+                        self.events.push(Event::FreightStop(
+                            car.vehicle.id,
+                            bldg,
+                            Position::new(car.router.head().as_lane(), our_dist),
+                            dwell,
+                        ));
+                        scheduler
+                            .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                        true
+                    }
+                    Some(ActionAtEnd::RideHailStop) => {
+                        car.total_blocked_time += now - blocked_since;
+                        if let Some(dwell) = ridehail.vehicle_arrived(
+                            now,
+                            car.vehicle.id,
+                            Position::new(car.router.head().as_lane(), our_dist),
+                            trips,
+                            walking,
+                            scheduler,
+                            map,
+                        ) {
+                            // Pull over, blocking the lane like a bus would
+                            car.state = CarState::IdlingAtStop(
+                                our_dist,
+                                TimeInterval::new(now, now + dwell),
+                            );
+                            scheduler
+                                .push(car.state.get_end_time(), Command::UpdateCar(car.vehicle.id));
+                            true
+                        } else {
+                            // Dropped somebody off; go idle off-road until the next dispatch
+                            false
+                        }
+                    }
                     None => {
                         scheduler.push(
                             now + BLIND_RETRY_TO_REACH_END_DIST,
@@ -550,7 +720,7 @@ impl DrivingSimState {
                         /*
                         // If this car wasn't blocked at all, when would it reach its goal?
                         let ideal_end_time = match car.crossing_state(our_dist, now, map) {
-                            CarState::Crossing(time_int, _) => time_int.end,
+                            CarState::Crossing(time_int, _, _) => time_int.end,
                             _ => unreachable!(),
                         };
                         if ideal_end_time == now {
@@ -573,6 +743,39 @@ impl DrivingSimState {
                     vehicle: car.vehicle.clone(),
                     spot,
                 });
+                if car.vehicle.vehicle_type.is_freight() {
+                    // Wait in the loading zone, then pull out and head towards the next stop,
+                    // starting from where the vehicle left the lane.
+                    let dwell = car.router.delivery_dwell();
+                    car.router.finished_delivery();
+                    let req = PathRequest {
+                        start: Position::new(car.router.head().as_lane(), our_dist),
+                        end: Position::new(
+                            car.router.get_path().last_step().as_lane(),
+                            car.router.get_end_dist(),
+                        ),
+                        constraints: PathConstraints::Car,
+                    };
+                    scheduler.push(
+                        now + dwell,
+                        Command::SpawnCar(
+                            CreateCar {
+                                start_dist: our_dist,
+                                router: car.router.clone(),
+                                vehicle: car.vehicle.clone(),
+                                req,
+                                maybe_parked_car: Some(ParkedCar {
+                                    vehicle: car.vehicle.clone(),
+                                    spot,
+                                }),
+                                trip_and_person: None,
+                                maybe_route: None,
+                            },
+                            true,
+                        ),
+                    );
+                    return false;
+                }
                 trips.car_reached_parking_spot(
                     now,
                     car.vehicle.id,
@@ -655,9 +858,105 @@ impl DrivingSimState {
         // We might've scheduled one of those using BLIND_RETRY_TO_CREEP_FORWARDS.
         scheduler.cancel(Command::UpdateLaggyHead(car.vehicle.id));
 
-        // Update the follower so that they don't suddenly jump forwards.
+        self.release_follower(&dists, idx, now, map, intersections, scheduler);
+    }
+
+    // Like Car::crossing_state, but the car also brakes ahead of time for anything it knows it'll
+    // have to stop for: a leader that's stopped, or an intersection it can't enter yet. If
+    // something else blocks the car, it still stops instantly.
+    fn crossing_state(
+        &self,
+        car: &Car,
+        start_dist: Distance,
+        now: Time,
+        map: &Map,
+        intersections: &IntersectionSimState,
+    ) -> CarState {
+        let end_dist = car.end_of_current_step(map);
+        let queue = &self.queues[&car.router.head()];
+        // The car might not be in the queue yet.
+        let idx = queue
+            .cars
+            .iter()
+            .position(|c| *c == car.vehicle.id)
+            .unwrap_or(queue.cars.len());
+        if let Some(stop_dist) = queue.stopped_leader_back(idx, &self.cars) {
+            if stop_dist > start_dist && stop_dist < end_dist {
+                return car.crossing_state_with_end_dist(
+                    DistanceInterval::new_driving(start_dist, stop_dist),
+                    now,
+                    map,
+                    true,
+                );
+            }
+        }
+
+        let dist_int = DistanceInterval::new_driving(start_dist, end_dist);
+        let unbraked = car.crossing_state_with_end_dist(dist_int.clone(), now, map, false);
+        // Once a car starts an uber-turn, it doesn't stop until the end.
+        if car.router.last_step() || car.router.get_path().currently_inside_ut().is_some() {
+            return unbraked;
+        }
+        match car.router.next() {
+            Traversable::Turn(t)
+                if intersections.must_stop_before(t, unbraked.get_end_time(), map) =>
+            {
+                car.crossing_state_with_end_dist(dist_int, now, map, true)
+            }
+            _ => unbraked,
+        }
+    }
+
+    // The car just stopped. If their follower is still moving, have them start braking now,
+    // instead of stopping instantly when they catch up.
+    fn brake_follower(
+        &mut self,
+        id: CarID,
+        now: Time,
+        map: &Map,
+        intersections: &IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) {
+        let queue = &self.queues[&self.cars[&id].router.head()];
+        let idx = queue.cars.iter().position(|c| *c == id).unwrap();
+        if idx == queue.cars.len() - 1 || queue.stopped_leader_back(idx + 1, &self.cars).is_none() {
+            return;
+        }
+        let follower_id = queue.cars[idx + 1];
+        if let CarState::Crossing(_, _, _) = self.cars[&follower_id].state {
+            let dists = queue.get_car_positions(now, &self.cars, &self.queues);
+            let state = self.crossing_state(
+                &self.cars[&follower_id],
+                dists[idx + 1].1,
+                now,
+                map,
+                intersections,
+            );
+            scheduler.update(state.get_end_time(), Command::UpdateCar(follower_id));
+            self.cars.get_mut(&follower_id).unwrap().state = state;
+        }
+    }
+
+    // The car at dists[idx] just left the queue. Update the follower so that they don't suddenly
+    // jump forwards.
+    fn release_follower(
+        &mut self,
+        dists: &Vec<(CarID, Distance)>,
+        idx: usize,
+        now: Time,
+        map: &Map,
+        intersections: &IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) {
         if idx != dists.len() - 1 {
             let (follower_id, follower_dist) = dists[idx + 1];
+            let state = self.crossing_state(
+                &self.cars[&follower_id],
+                follower_dist,
+                now,
+                map,
+                intersections,
+            );
             let mut follower = self.cars.get_mut(&follower_id).unwrap();
             // TODO If the leader vanished at a border node, this still jumps a bit -- the
             // lead car's back is still sticking out. Need to still be bound by them, even
@@ -666,17 +965,17 @@ impl DrivingSimState {
                 CarState::Queued { blocked_since } => {
                     // Prevent them from jumping forwards.
                     follower.total_blocked_time += now - blocked_since;
-                    follower.state = follower.crossing_state(follower_dist, now, map);
+                    follower.state = state;
                     scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
                     );
                 }
-                CarState::Crossing(_, _) => {
+                CarState::Crossing(_, _, _) => {
                     // If the follower was still Crossing, they might not've been blocked
-                    // by leader yet. In that case, recalculating their Crossing state is a
-                    // no-op.
-                    follower.state = follower.crossing_state(follower_dist, now, map);
+                    // by leader yet, or they might've been braking for them. Either way, plan
+                    // again from where they are now.
+                    follower.state = state;
                     scheduler.update(
                         follower.state.get_end_time(),
                         Command::UpdateCar(follower_id),
@@ -691,6 +990,99 @@ impl DrivingSimState {
         }
     }
 
+    // A vehicle that just got stuck behind a leader in the middle of a lane looks for a gap in an
+    // adjacent lane of the same road. The vehicle moves between queues instantly, but only when
+    // there's room for it, so nobody's position jumps. If there's no gap, it looks again later,
+    // since the leader and the other lanes might've moved by then.
+    fn try_mid_block_lanechange(
+        &mut self,
+        id: CarID,
+        now: Time,
+        map: &Map,
+        intersections: &mut IntersectionSimState,
+        scheduler: &mut Scheduler,
+    ) {
+        let car = &self.cars[&id];
+        let current = car.router.head();
+        let dists = self.queues[&current].get_car_positions(now, &self.cars, &self.queues);
+        let idx = dists.iter().position(|(c, _)| *c == id).unwrap();
+        // Only the laggy head is in front of us, and trim_last_steps will wake us up.
+        if idx == 0 {
+            return;
+        }
+        // If the back of the vehicle is still in the previous turn, don't try anything yet.
+        if !car.last_steps.is_empty() {
+            scheduler.push(now + BLIND_RETRY_TO_LANECHANGE, Command::UpdateCar(id));
+            return;
+        }
+        let our_dist = dists[idx].1;
+
+        let mut best: Option<(Distance, LaneID, TurnID, Distance, usize)> = None;
+        for (lane, turn) in car.router.mid_block_lanechange_candidates(map) {
+            let dist = Position::new(current.as_lane(), our_dist)
+                .equiv_pos(lane, map)
+                .dist_along();
+            if dist < car.vehicle.length + FOLLOWING_DISTANCE + LANECHANGE_GAP {
+                continue;
+            }
+            let queue = &self.queues[&Traversable::Lane(lane)];
+            if !queue.room_for_car(car) {
+                continue;
+            }
+            if let Some(insert_idx) = queue.get_idx_to_insert_car(
+                dist,
+                car.vehicle.length,
+                LANECHANGE_GAP,
+                now,
+                &self.cars,
+                &self.queues,
+            ) {
+                let target_dists = queue.get_car_positions(now, &self.cars, &self.queues);
+                let room = if insert_idx == 0 {
+                    queue.geom_len - dist
+                } else {
+                    let (leader, leader_dist) = target_dists[insert_idx - 1];
+                    leader_dist - self.cars[&leader].vehicle.length - FOLLOWING_DISTANCE - dist
+                };
+                if room >= MIN_LANECHANGE_GAIN
+                    && best.map(|(r, _, _, _, _)| room > r).unwrap_or(true)
+                {
+                    best = Some((room, lane, turn, dist, insert_idx));
+                }
+            }
+        }
+        let (_, lane, turn, dist, insert_idx) = match best {
+            Some(x) => x,
+            None => {
+                // If the leader starts moving first, release_follower or trim_last_steps will
+                // update this retry.
+                scheduler.push(now + BLIND_RETRY_TO_LANECHANGE, Command::UpdateCar(id));
+                return;
+            }
+        };
+
+        let mut car = self.cars.remove(&id).unwrap();
+        {
+            let queue = self.queues.get_mut(&current).unwrap();
+            assert_eq!(queue.cars.remove(idx).unwrap(), id);
+            queue.free_reserved_space(&car);
+            intersections.space_freed(now, map.get_l(current.as_lane()).src_i, scheduler, map);
+        }
+        self.release_follower(&dists, idx, now, map, intersections, scheduler);
+
+        car.router.change_lanes_mid_block(lane, turn, map);
+        {
+            let queue = self.queues.get_mut(&Traversable::Lane(lane)).unwrap();
+            queue.cars.insert(insert_idx, id);
+            assert!(queue.try_to_reserve_entry(&car, false));
+        }
+        car.state = self.crossing_state(&car, dist, now, map, intersections);
+        scheduler.push(car.state.get_end_time(), Command::UpdateCar(id));
+        self.events
+            .push(Event::PathAmended(car.router.get_path().clone()));
+        self.cars.insert(id, car);
+    }
+
     pub fn update_laggy_head(
         &mut self,
         id: CarID,
@@ -745,6 +1137,7 @@ impl DrivingSimState {
                     ),
                     now,
                     map,
+                    false,
                 )
                 .get_end_time();
             // Sometimes due to rounding, retry_at will be exactly time, but we really need to
@@ -815,14 +1208,16 @@ impl DrivingSimState {
                                         .router
                                         .opportunistically_lanechange(&self.queues, map);
                                 }
-                                scheduler.push(now, Command::UpdateCar(follower.vehicle.id));
+                                // This is update, not push, because they might be waiting to
+                                // retry a lane-change.
+                                scheduler.update(now, Command::UpdateCar(follower.vehicle.id));
                             }
                         }
                         CarState::WaitingToAdvance { .. } => unreachable!(),
                         // They weren't blocked. Note that there's no way the Crossing state
                         // could jump forwards here; the leader
                         // vanished from the end of the traversable.
-                        CarState::Crossing(_, _)
+                        CarState::Crossing(_, _, _)
                         | CarState::Unparking(_, _, _)
                         | CarState::Parking(_, _, _)
                         | CarState::IdlingAtStop(_, _) => {}
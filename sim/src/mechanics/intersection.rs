@@ -95,6 +95,28 @@ impl IntersectionSimState {
             .any(|req| req.turn.dst == lane)
     }
 
+    // Will a vehicle reaching this turn at the given time have to stop first, even if nobody else
+    // is around? Vehicles use this to start braking ahead of time.
+    pub fn must_stop_before(&self, turn: TurnID, arrival: Time, map: &Map) -> bool {
+        if self.use_freeform_policy_everywhere {
+            return false;
+        }
+        if let Some(ref signal) = map.maybe_get_traffic_signal(turn.parent) {
+            let state = &self.state[&turn.parent];
+            // Don't count on the next phase; if the light changes before we get there, plan to
+            // stop.
+            if arrival >= state.phase_ends_at {
+                return true;
+            }
+            signal.phases[state.current_phase].get_priority_of_turn(turn, signal)
+                != TurnPriority::Protected
+        } else if let Some(ref sign) = map.maybe_get_stop_sign(turn.parent) {
+            sign.get_priority(turn, map) == TurnPriority::Yield
+        } else {
+            false
+        }
+    }
+
     pub fn turn_finished(
         &mut self,
         now: Time,
@@ -9,4 +9,5 @@ pub use self::driving::DrivingSimState;
 pub use self::intersection::IntersectionSimState;
 pub use self::parking::ParkingSimState;
 pub use self::queue::Queue;
+pub(crate) use self::walking::walkable_area;
 pub use self::walking::WalkingSimState;
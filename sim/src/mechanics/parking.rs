@@ -3,7 +3,7 @@ use abstutil::{
     deserialize_btreemap, deserialize_multimap, serialize_btreemap, serialize_multimap, MultiMap,
     Timer,
 };
-use geom::{Distance, PolyLine, Pt2D};
+use geom::{Distance, Duration, PolyLine, Pt2D};
 use map_model::{
     BuildingID, Lane, LaneID, LaneType, Map, OffstreetParking, ParkingLotID, PathConstraints,
     PathStep, Position, Traversable, TurnID,
@@ -11,6 +11,14 @@ use map_model::{
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
 
+// About $15 per hour of walking, at typical walking speed
+const CENTS_PER_METER: f64 = 0.3;
+// The risk of getting a ticket for staying past a time limit
+const OVERSTAY_PENALTY_CENTS: usize = 5000;
+// Nobody drives further than this looking for parking, no matter how expensive the spots found so
+// far are.
+const MAX_PARKING_SEARCH_DIST: Distance = Distance::const_meters(2000.0);
+
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct ParkingSimState {
     #[serde(
@@ -330,6 +338,33 @@ impl ParkingSimState {
             .collect()
     }
 
+    // A free on-street spot right next to a vehicle stopped with its front at driving_pos, if
+    // there is one. Vehicles longer than one spot would overlap their neighbors, so they never get
+    // one.
+    pub fn get_free_spot_alongside(
+        &self,
+        driving_pos: Position,
+        vehicle: &Vehicle,
+        map: &Map,
+    ) -> Option<ParkingSpot> {
+        if vehicle.length > map_model::PARKING_SPOT_LENGTH {
+            return None;
+        }
+        for l in self.driving_to_parking_lanes.get(driving_pos.lane()) {
+            for spot in self.onstreet_lanes[l].spots() {
+                if self.is_free(spot)
+                    && (self.spot_to_driving_pos(spot, vehicle, map).dist_along()
+                        - driving_pos.dist_along())
+                    .abs()
+                        <= vehicle.length
+                {
+                    return Some(spot);
+                }
+            }
+        }
+        None
+    }
+
     pub fn spot_to_driving_pos(&self, spot: ParkingSpot, vehicle: &Vehicle, map: &Map) -> Position {
         match spot {
             ParkingSpot::Onstreet(l, idx) => {
@@ -397,6 +432,39 @@ impl ParkingSimState {
         (filled, available)
     }
 
+    // How bad a spot is for somebody going to the target building: the walk from the spot, plus
+    // the price expressed as an equivalent walking distance. When parking is free, this is just the
+    // walk. The walk is as the crow flies; pathfinding from every candidate is too slow.
+    pub fn spot_cost(
+        &self,
+        spot: ParkingSpot,
+        target: BuildingID,
+        stay: Duration,
+        map: &Map,
+    ) -> Distance {
+        let walk = self.spot_walk(spot, target, map);
+        let rules = match spot.facility() {
+            Some(facility) => map.get_parking_rules(facility),
+            None => {
+                return walk;
+            }
+        };
+        let mut cents = rules.cost(stay);
+        // Time limits aren't enforced; nobody gets moved or ticketed. Drivers just avoid staying
+        // past them, as if they expect a ticket.
+        if rules.max_stay.map(|max| stay > max).unwrap_or(false) {
+            cents += OVERSTAY_PENALTY_CENTS;
+        }
+        walk + Distance::meters((cents as f64) / CENTS_PER_METER)
+    }
+
+    // As the crow flies, from the spot to the target building
+    fn spot_walk(&self, spot: ParkingSpot, target: BuildingID, map: &Map) -> Distance {
+        self.spot_to_sidewalk_pos(spot, map)
+            .pt(map)
+            .dist_to(map.get_b(target).sidewalk_pos.pt(map))
+    }
+
     // Unrealistically assumes the driver has knowledge of currently free parking spots, even if
     // they're far away. Since they don't reserve the spot in advance, somebody else can still beat
     // them there, producing some nice, realistic churn if there's too much contention.
@@ -406,6 +474,7 @@ impl ParkingSimState {
         start: LaneID,
         vehicle: &Vehicle,
         target: BuildingID,
+        stay: Duration,
         map: &Map,
     ) -> Option<(Vec<PathStep>, ParkingSpot, Position)> {
         let mut backrefs: HashMap<LaneID, TurnID> = HashMap::new();
@@ -414,32 +483,33 @@ impl ParkingSimState {
         // deterministic.
         let mut queue: BinaryHeap<(Distance, LaneID)> = BinaryHeap::new();
         queue.push((Distance::ZERO, start));
+        // The spot with the lowest spot_cost so far
+        let mut best: Option<(Distance, LaneID, ParkingSpot, Position)> = None;
+        // How far it is to walk from that spot
+        let mut best_walk = MAX_PARKING_SEARCH_DIST;
 
         while !queue.is_empty() {
             let (dist_so_far, current) = queue.pop().unwrap();
+            // Don't bother driving further than the walk from the best spot; that's a long way to
+            // go to save some walking or money. Money isn't converted into distance here, or a
+            // penalty would make the search explore kilometers of the map.
+            if -dist_so_far >= best_walk {
+                break;
+            }
             // If the current lane has a spot open, we wouldn't be asking. This can happen if a spot
             // opens up on the 'start' lane, but behind the car.
             if current != start {
-                // Pick the closest to the start of the lane, since that's closest to where we came
-                // from
-                if let Some((spot, pos)) = self
+                if let Some((cost, spot, pos)) = self
                     .get_all_free_spots(Position::start(current), vehicle, target, map)
                     .into_iter()
-                    .min_by_key(|(_, pos)| pos.dist_along())
+                    .map(|(spot, pos)| (self.spot_cost(spot, target, stay, map), spot, pos))
+                    .min_by_key(|(cost, _, _)| *cost)
                 {
-                    let mut steps = vec![PathStep::Lane(current)];
-                    let mut current = current;
-                    loop {
-                        if current == start {
-                            // Don't include PathStep::Lane(start)
-                            steps.pop();
-                            steps.reverse();
-                            return Some((steps, spot, pos));
-                        }
-                        let turn = backrefs[&current];
-                        steps.push(PathStep::Turn(turn));
-                        steps.push(PathStep::Lane(turn.src));
-                        current = turn.src;
+                    if best.map(|(c, _, _, _)| cost < c).unwrap_or(true) {
+                        best = Some((cost, current, spot, pos));
+                        best_walk = self
+                            .spot_walk(spot, target, map)
+                            .min(MAX_PARKING_SEARCH_DIST);
                     }
                 }
             }
@@ -453,7 +523,21 @@ impl ParkingSimState {
             }
         }
 
-        None
+        let (_, current, spot, pos) = best?;
+        let mut steps = vec![PathStep::Lane(current)];
+        let mut current = current;
+        loop {
+            if current == start {
+                // Don't include PathStep::Lane(start)
+                steps.pop();
+                steps.reverse();
+                return Some((steps, spot, pos));
+            }
+            let turn = backrefs[&current];
+            steps.push(PathStep::Turn(turn));
+            steps.push(PathStep::Lane(turn.src));
+            current = turn.src;
+        }
     }
 
     pub fn collect_events(&mut self) -> Vec<Event> {
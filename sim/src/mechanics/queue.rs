@@ -143,10 +143,11 @@ impl Queue {
                     assert_eq!(bound, self.geom_len);
                     self.geom_len
                 }
-                CarState::Crossing(ref time_int, ref dist_int) => {
-                    // TODO Why percent_clamp_end? We process car updates in any order, so we might
-                    // calculate this before moving this car from Crossing to another state.
-                    dist_int.lerp(time_int.percent_clamp_end(now)).min(bound)
+                CarState::Crossing(ref time_int, ref dist_int, ref profile) => {
+                    // We process car updates in any order, so we might calculate this after
+                    // time_int.end, before moving this car from Crossing to another state.
+                    // dist_at clamps to the end of the interval.
+                    (dist_int.start + profile.dist_at(now - time_int.start)).min(bound)
                 }
                 CarState::Unparking(front, _, _) => front,
                 CarState::Parking(front, _, _) => front,
@@ -158,10 +159,50 @@ impl Queue {
         validate_positions(result, cars, now, self.id)
     }
 
+    // If the car in front of idx is stopped, returns where the next car has to stop behind them.
+    // Only looks at the cars in front of idx, so the car at idx can be missing from `cars`.
+    pub fn stopped_leader_back(&self, idx: usize, cars: &BTreeMap<CarID, Car>) -> Option<Distance> {
+        if idx == 0 {
+            return None;
+        }
+        // The car being updated right now is temporarily missing from cars. If that's the leader,
+        // they're about to start moving again.
+        let leader = cars.get(&self.cars[idx - 1])?;
+        let front = match leader.state {
+            CarState::Crossing(_, _, _) => {
+                return None;
+            }
+            CarState::WaitingToAdvance { .. } => self.geom_len,
+            CarState::Unparking(front, _, _)
+            | CarState::Parking(front, _, _)
+            | CarState::IdlingAtStop(front, _) => front,
+            CarState::Queued { .. } => {
+                // Queued cars stick to their leader, so they're only stopped if their leader is.
+                let bound = if idx - 1 == 0 {
+                    if self.laggy_head.is_some() {
+                        return None;
+                    }
+                    self.geom_len
+                } else {
+                    self.stopped_leader_back(idx - 1, cars)?
+                };
+                if leader.router.last_step() {
+                    leader.router.get_end_dist().min(bound)
+                } else {
+                    bound
+                }
+            }
+        };
+        Some(front - leader.vehicle.length - FOLLOWING_DISTANCE)
+    }
+
+    // extra_gap is additional space required in front of and behind the car, beyond the usual
+    // FOLLOWING_DISTANCE.
     pub fn get_idx_to_insert_car(
         &self,
         start_dist: Distance,
         vehicle_len: Distance,
+        extra_gap: Distance,
         now: Time,
         cars: &BTreeMap<CarID, Car>,
         queues: &BTreeMap<Traversable, Queue>,
@@ -184,13 +225,18 @@ impl Queue {
 
         // Are we too close to the leader?
         if idx != 0
-            && dists[idx - 1].1 - cars[&dists[idx - 1].0].vehicle.length - FOLLOWING_DISTANCE
+            && dists[idx - 1].1
+                - cars[&dists[idx - 1].0].vehicle.length
+                - FOLLOWING_DISTANCE
+                - extra_gap
                 < start_dist
         {
             return None;
         }
         // Or the follower?
-        if idx != dists.len() && start_dist - vehicle_len - FOLLOWING_DISTANCE < dists[idx].1 {
+        if idx != dists.len()
+            && start_dist - vehicle_len - FOLLOWING_DISTANCE - extra_gap < dists[idx].1
+        {
             return None;
         }
 
@@ -276,7 +322,7 @@ fn dump_cars(
         let car = &cars[id];
         println!("- {} @ {} (length {})", id, dist, car.vehicle.length);
         match car.state {
-            CarState::Crossing(ref time_int, ref dist_int) => {
+            CarState::Crossing(ref time_int, ref dist_int, _) => {
                 println!(
                     "  Going {} .. {} during {} .. {}",
                     dist_int.start, dist_int.end, time_int.start, time_int.end
@@ -1,19 +1,27 @@
 use crate::{
-    AgentID, AgentProperties, Command, CreatePedestrian, DistanceInterval, DrawPedCrowdInput,
-    DrawPedestrianInput, Event, IntersectionSimState, ParkingSimState, ParkingSpot,
-    PedCrowdLocation, PedestrianID, PersonID, Scheduler, SidewalkPOI, SidewalkSpot, TimeInterval,
-    TransitSimState, TripID, TripManager, UnzoomedAgent,
+    AgentID, AgentProperties, BikeShareSimState, Command, CreatePedestrian, DistanceInterval,
+    DrawPedCrowdInput, DrawPedestrianInput, Event, IntersectionSimState, ParkingSimState,
+    ParkingSpot, PedCrowdLocation, PedestrianID, PersonID, RideHailSimState, Scheduler,
+    SidewalkPOI, SidewalkSpot, TimeInterval, TransitSimState, TripID, TripManager, UnzoomedAgent,
 };
 use abstutil::{deserialize_multimap, serialize_multimap, MultiMap};
 use geom::{Distance, Duration, Line, PolyLine, Speed, Time};
 use map_model::{
-    BuildingID, BusRouteID, Map, ParkingLotID, Path, PathStep, Traversable, SIDEWALK_THICKNESS,
+    walking_speed_on_grade, BuildingID, BusRouteID, Map, ParkingLotID, Path, PathStep, Traversable,
+    TurnID, TurnType, SIDEWALK_THICKNESS,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
 const TIME_TO_START_BIKING: Duration = Duration::const_seconds(30.0);
 const TIME_TO_FINISH_BIKING: Duration = Duration::const_seconds(45.0);
+// If a crosswalk is full, try again after this long.
+const BLIND_RETRY_TO_ENTER_CROSSWALK: Duration = Duration::const_seconds(1.0);
+// Square meters per pedestrian. Any more crowded than this is level of service F; nobody else will
+// step onto a crosswalk this packed.
+const MIN_SPACE_PER_PED: f64 = 0.75;
+// Pedestrians per square meter where nobody can move at all
+const JAM_DENSITY: f64 = 5.4;
 
 #[derive(Serialize, Deserialize, PartialEq, Clone)]
 pub struct WalkingSimState {
@@ -79,12 +87,19 @@ impl WalkingSimState {
                 pl,
                 TimeInterval::new(now, now + map.get_pl(pl).sidewalk_line.length() / ped.speed),
             ),
-            SidewalkPOI::BikeRack(driving_pos) => PedState::FinishingBiking(
-                params.start.clone(),
-                Line::must_new(driving_pos.pt(map), params.start.sidewalk_pos.pt(map)),
-                TimeInterval::new(now, now + TIME_TO_FINISH_BIKING),
+            SidewalkPOI::BikeRack(driving_pos) | SidewalkPOI::BikeShareDock(_, driving_pos) => {
+                PedState::FinishingBiking(
+                    params.start.clone(),
+                    Line::must_new(driving_pos.pt(map), params.start.sidewalk_pos.pt(map)),
+                    TimeInterval::new(now, now + TIME_TO_FINISH_BIKING),
+                )
+            }
+            _ => ped.crossing_state(
+                params.start.sidewalk_pos.dist_along(),
+                now,
+                map,
+                &self.peds_per_traversable,
             ),
-            _ => ped.crossing_state(params.start.sidewalk_pos.dist_along(), now, map),
         };
 
         scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
@@ -93,6 +108,12 @@ impl WalkingSimState {
             Traversable::Lane(params.start.sidewalk_pos.lane()),
             params.id,
         );
+        self.events.push(Event::SidewalkOccupancy(
+            start_lane,
+            self.peds_per_traversable
+                .get(Traversable::Lane(start_lane))
+                .len(),
+        ));
     }
 
     pub fn get_draw_ped(
@@ -121,6 +142,8 @@ impl WalkingSimState {
         scheduler: &mut Scheduler,
         trips: &mut TripManager,
         transit: &mut TransitSimState,
+        ridehail: &mut RideHailSimState,
+        bikeshare: &mut BikeShareSimState,
     ) {
         let mut ped = self.peds.get_mut(&id).unwrap();
         match ped.state {
@@ -179,6 +202,24 @@ impl WalkingSimState {
                                 self.peds.remove(&id);
                             }
                         }
+                        SidewalkPOI::RideHailStop(pickup) => {
+                            if trips.ped_reached_ride_hail_stop(
+                                now,
+                                ped.id,
+                                pickup,
+                                ped.total_blocked_time,
+                                map,
+                                parking,
+                                scheduler,
+                                ridehail,
+                            ) {
+                                ped.state = PedState::WaitingForRide(now);
+                            } else {
+                                self.peds_per_traversable
+                                    .remove(ped.path.current_step().as_traversable(), ped.id);
+                                self.peds.remove(&id);
+                            }
+                        }
                         SidewalkPOI::Border(i, _) => {
                             self.peds_per_traversable
                                 .remove(ped.path.current_step().as_traversable(), ped.id);
@@ -193,6 +234,31 @@ impl WalkingSimState {
                             );
                             self.peds.remove(&id);
                         }
+                        SidewalkPOI::BikeShareDock(dock, driving_pos) => {
+                            if bikeshare.take_bike(dock) {
+                                let pt1 = ped.goal.sidewalk_pos.pt(map);
+                                let pt2 = driving_pos.pt(map);
+                                ped.state = PedState::StartingToBike(
+                                    ped.goal.clone(),
+                                    Line::must_new(pt1, pt2),
+                                    TimeInterval::new(now, now + TIME_TO_START_BIKING),
+                                );
+                                scheduler
+                                    .push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
+                            } else {
+                                self.peds_per_traversable
+                                    .remove(ped.path.current_step().as_traversable(), ped.id);
+                                trips.ped_found_no_bike(
+                                    now,
+                                    ped.id,
+                                    ped.goal.clone(),
+                                    ped.total_blocked_time,
+                                    map,
+                                    scheduler,
+                                );
+                                self.peds.remove(&id);
+                            }
+                        }
                         SidewalkPOI::BikeRack(driving_pos) => {
                             let pt1 = ped.goal.sidewalk_pos.pt(map);
                             let pt2 = driving_pos.pt(map);
@@ -247,7 +313,12 @@ impl WalkingSimState {
                 }
             }
             PedState::LeavingBuilding(b, _) => {
-                ped.state = ped.crossing_state(map.get_b(b).sidewalk_pos.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    map.get_b(b).sidewalk_pos.dist_along(),
+                    now,
+                    map,
+                    &self.peds_per_traversable,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
             PedState::EnteringBuilding(bldg, _) => {
@@ -265,7 +336,12 @@ impl WalkingSimState {
                 self.peds.remove(&id);
             }
             PedState::LeavingParkingLot(pl, _) => {
-                ped.state = ped.crossing_state(map.get_pl(pl).sidewalk_pos.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    map.get_pl(pl).sidewalk_pos.dist_along(),
+                    now,
+                    map,
+                    &self.peds_per_traversable,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
             PedState::EnteringParkingLot(_, _) => {
@@ -300,17 +376,22 @@ impl WalkingSimState {
                 self.peds.remove(&id);
             }
             PedState::FinishingBiking(ref spot, _, _) => {
-                ped.state = ped.crossing_state(spot.sidewalk_pos.dist_along(), now, map);
+                ped.state = ped.crossing_state(
+                    spot.sidewalk_pos.dist_along(),
+                    now,
+                    map,
+                    &self.peds_per_traversable,
+                );
                 scheduler.push(ped.state.get_end_time(), Command::UpdatePed(ped.id));
             }
-            PedState::WaitingForBus(_, _) => unreachable!(),
+            PedState::WaitingForBus(_, _) | PedState::WaitingForRide(_) => unreachable!(),
         }
     }
 
     pub fn ped_boarded_bus(&mut self, now: Time, id: PedestrianID) {
         let mut ped = self.peds.remove(&id).unwrap();
         match ped.state {
-            PedState::WaitingForBus(_, blocked_since) => {
+            PedState::WaitingForBus(_, blocked_since) | PedState::WaitingForRide(blocked_since) => {
                 self.peds_per_traversable
                     .remove(ped.path.current_step().as_traversable(), id);
                 ped.total_blocked_time += now - blocked_since;
@@ -331,7 +412,8 @@ impl WalkingSimState {
         let p = &self.peds[&id];
         let time_spent_waiting = match p.state {
             PedState::WaitingToTurn(_, blocked_since)
-            | PedState::WaitingForBus(_, blocked_since) => now - blocked_since,
+            | PedState::WaitingForBus(_, blocked_since)
+            | PedState::WaitingForRide(blocked_since) => now - blocked_since,
             _ => Duration::ZERO,
         };
 
@@ -438,7 +520,8 @@ impl WalkingSimState {
                 }
                 PedState::StartingToBike(_, _, _)
                 | PedState::FinishingBiking(_, _, _)
-                | PedState::WaitingForBus(_, _) => {
+                | PedState::WaitingForBus(_, _)
+                | PedState::WaitingForRide(_) => {
                     // The backwards half of the sidewalk is closer to the road.
                     backwards.push((*id, dist));
                 }
@@ -520,7 +603,15 @@ struct Pedestrian {
 }
 
 impl Pedestrian {
-    fn crossing_state(&self, start_dist: Distance, start_time: Time, map: &Map) -> PedState {
+    // Pedestrians slow down in crowds and on hills. The speed is fixed when they start crossing
+    // something, based on how many other people are already there.
+    fn crossing_state(
+        &self,
+        start_dist: Distance,
+        start_time: Time,
+        map: &Map,
+        peds_per_traversable: &MultiMap<Traversable, PedestrianID>,
+    ) -> PedState {
         let end_dist = if self.path.is_last_step() {
             self.goal.sidewalk_pos.dist_along()
         } else {
@@ -532,7 +623,24 @@ impl Pedestrian {
             }
         };
         let dist_int = DistanceInterval::new_walking(start_dist, end_dist);
-        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / self.speed);
+        let on = self.path.current_step().as_traversable();
+        let grade_time_factor = match self.path.current_step() {
+            PathStep::Lane(l) => map
+                .get_l(l)
+                .grade_time_factor(false, walking_speed_on_grade, map),
+            PathStep::ContraflowLane(l) => {
+                map.get_l(l)
+                    .grade_time_factor(true, walking_speed_on_grade, map)
+            }
+            PathStep::Turn(_) => 1.0,
+        };
+        let speed = crowded_speed(
+            self.speed * (1.0 / grade_time_factor),
+            // Count ourselves, whether or not we've been added yet
+            peds_per_traversable.get(on).len().max(1),
+            walkable_area(on, map),
+        );
+        let time_int = TimeInterval::new(start_time, start_time + dist_int.length() / speed);
         PedState::Crossing(dist_int, time_int)
     }
 
@@ -548,7 +656,9 @@ impl Pedestrian {
             }
             PedState::StartingToBike(ref spot, _, _) => spot.sidewalk_pos.dist_along(),
             PedState::FinishingBiking(ref spot, _, _) => spot.sidewalk_pos.dist_along(),
-            PedState::WaitingForBus(_, _) => self.goal.sidewalk_pos.dist_along(),
+            PedState::WaitingForBus(_, _) | PedState::WaitingForRide(_) => {
+                self.goal.sidewalk_pos.dist_along()
+            }
         }
     }
 
@@ -637,7 +747,7 @@ impl Pedestrian {
                     .unwrap_or(line.pt1()),
                 line.angle(),
             ),
-            PedState::WaitingForBus(_, _) => {
+            PedState::WaitingForBus(_, _) | PedState::WaitingForRide(_) => {
                 let (pt, angle) = self.goal.sidewalk_pos.pt_and_angle(map);
                 // Stand on the far side of the sidewalk (by the bus stop), facing the road
                 (
@@ -659,7 +769,7 @@ impl Pedestrian {
                 _ => None,
             },
             preparing_bike: matches!(self.state, PedState::StartingToBike(_, _, _) | PedState::FinishingBiking(_, _, _)),
-            waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _)),
+            waiting_for_bus: matches!(self.state, PedState::WaitingForBus(_, _) | PedState::WaitingForRide(_)),
             on,
         }
     }
@@ -675,6 +785,14 @@ impl Pedestrian {
         scheduler: &mut Scheduler,
     ) -> bool {
         if let PathStep::Turn(t) = self.path.next_step() {
+            if peds_per_traversable.get(Traversable::Turn(t)).len() >= crosswalk_capacity(t, map) {
+                // The intersection doesn't know we're waiting, so it won't wake us up.
+                scheduler.update(
+                    now + BLIND_RETRY_TO_ENTER_CROSSWALK,
+                    Command::UpdatePed(self.id),
+                );
+                return false;
+            }
             if !intersections.maybe_start_turn(
                 AgentID::Pedestrian(self.id),
                 t,
@@ -695,8 +813,14 @@ impl Pedestrian {
             PathStep::ContraflowLane(l) => map.get_l(l).length(),
             PathStep::Turn(_) => Distance::ZERO,
         };
-        self.state = self.crossing_state(start_dist, now, map);
         peds_per_traversable.insert(self.path.current_step().as_traversable(), self.id);
+        self.state = self.crossing_state(start_dist, now, map, peds_per_traversable);
+        if let PathStep::Lane(l) | PathStep::ContraflowLane(l) = self.path.current_step() {
+            events.push(Event::SidewalkOccupancy(
+                l,
+                peds_per_traversable.get(Traversable::Lane(l)).len(),
+            ));
+        }
         events.push(Event::AgentEntersTraversable(
             AgentID::Pedestrian(self.id),
             self.path.current_step().as_traversable(),
@@ -718,6 +842,8 @@ enum PedState {
     StartingToBike(SidewalkSpot, Line, TimeInterval),
     FinishingBiking(SidewalkSpot, Line, TimeInterval),
     WaitingForBus(BusRouteID, Time),
+    // Waiting at the curb to be picked up. The Time is blocked_since.
+    WaitingForRide(Time),
 }
 
 impl PedState {
@@ -731,11 +857,43 @@ impl PedState {
             PedState::EnteringParkingLot(_, ref time_int) => time_int.end,
             PedState::StartingToBike(_, _, ref time_int) => time_int.end,
             PedState::FinishingBiking(_, _, ref time_int) => time_int.end,
-            PedState::WaitingForBus(_, _) => unreachable!(),
+            PedState::WaitingForBus(_, _) | PedState::WaitingForRide(_) => unreachable!(),
         }
     }
 }
 
+// Square meters of a sidewalk or crosswalk
+pub(crate) fn walkable_area(on: Traversable, map: &Map) -> f64 {
+    let width = match on {
+        Traversable::Lane(l) => map.get_l(l).width,
+        Traversable::Turn(t) => map.get_l(t.src).width,
+    };
+    on.length(map).inner_meters() * width.inner_meters()
+}
+
+// How many people can be on a crosswalk at once? Corners of the same sidewalk have no limit.
+fn crosswalk_capacity(t: TurnID, map: &Map) -> usize {
+    if map.get_t(t).turn_type != TurnType::Crosswalk {
+        return std::usize::MAX;
+    }
+    ((walkable_area(Traversable::Turn(t), map) / MIN_SPACE_PER_PED) as usize).max(1)
+}
+
+// Weidmann's speed-density relationship, so that people barely slow down until things get packed.
+fn crowded_speed(free_speed: Speed, num_peds: usize, area: f64) -> Speed {
+    if area <= 0.0 {
+        return free_speed;
+    }
+    let density = (num_peds as f64) / area;
+    let pct = if density >= JAM_DENSITY {
+        0.0
+    } else {
+        1.0 - (-1.913 * (1.0 / density - 1.0 / JAM_DENSITY)).exp()
+    };
+    // Don't stop completely
+    free_speed * pct.max(0.1)
+}
+
 // The crowds returned here may have low/high values extending up to radius past the real geometry.
 fn find_crowds(
     input: Vec<(PedestrianID, Distance)>,
@@ -0,0 +1,347 @@
+use crate::{
+    CarID, Command, CreateCar, Event, PedestrianID, PersonID, Router, Scheduler, TripID,
+    TripManager, TripPhaseType, Vehicle, WalkingSimState,
+};
+use abstutil::{deserialize_btreemap, serialize_btreemap};
+use geom::{Distance, Duration, Time};
+use map_model::{Map, Path, PathConstraints, PathRequest, PathStep, Position, TurnID};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+const TIME_TO_PICK_UP: Duration = Duration::const_seconds(30.0);
+
+// How to choose which idle vehicle serves a request
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DispatchPolicy {
+    // The vehicle closest to the pickup, as the crow flies
+    Nearest,
+    // The vehicle that's been waiting the longest for a ride, to spread out work
+    LongestIdle,
+}
+
+impl DispatchPolicy {
+    pub fn all() -> Vec<DispatchPolicy> {
+        vec![DispatchPolicy::Nearest, DispatchPolicy::LongestIdle]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            DispatchPolicy::Nearest => "nearest vehicle",
+            DispatchPolicy::LongestIdle => "longest-idle vehicle",
+        }
+    }
+
+    // Best first
+    fn rank_vehicles(
+        self,
+        mut idle: Vec<(CarID, Position, Time)>,
+        pickup: Position,
+        map: &Map,
+    ) -> Vec<CarID> {
+        match self {
+            DispatchPolicy::Nearest => {
+                let pt = pickup.pt(map);
+                idle.sort_by_key(|(_, pos, _)| pos.pt(map).dist_to(pt));
+            }
+            DispatchPolicy::LongestIdle => {
+                idle.sort_by_key(|(_, _, since)| *since);
+            }
+        }
+        idle.into_iter().map(|(car, _, _)| car).collect()
+    }
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+struct RideRequest {
+    trip: TripID,
+    person: PersonID,
+    ped: PedestrianID,
+    requested_at: Time,
+    pickup: Position,
+    dropoff: Position,
+    // Precomputed when the ride is requested, so we know it's possible
+    path: Path,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+struct Ride {
+    request: RideRequest,
+    dispatched_at: Time,
+    // How far the vehicle drove empty to reach the pickup
+    deadhead: Distance,
+    // From requesting the ride to being picked up. Filled out at pickup.
+    wait: Duration,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+enum VehicleState {
+    // Off-road somewhere, not drawn. The Time is when the vehicle became idle.
+    Idle(Position, Time),
+    ToPickup(Ride),
+    PickingUp(Ride),
+    ToDropoff(Ride),
+}
+
+// This kind of acts like TransitSimState, managing a fleet of vehicles that carry people around.
+#[derive(Serialize, Deserialize, PartialEq, Clone)]
+pub struct RideHailSimState {
+    policy: DispatchPolicy,
+    #[serde(
+        serialize_with = "serialize_btreemap",
+        deserialize_with = "deserialize_btreemap"
+    )]
+    vehicles: BTreeMap<CarID, (Vehicle, VehicleState)>,
+    // Served first-come, first-served
+    unassigned: VecDeque<RideRequest>,
+
+    events: Vec<Event>,
+}
+
+impl RideHailSimState {
+    pub fn new(policy: DispatchPolicy) -> RideHailSimState {
+        RideHailSimState {
+            policy,
+            vehicles: BTreeMap::new(),
+            unassigned: VecDeque::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn add_vehicle(&mut self, now: Time, vehicle: Vehicle, pos: Position) {
+        self.vehicles
+            .insert(vehicle.id, (vehicle, VehicleState::Idle(pos, now)));
+    }
+
+    // Returns false if no vehicle could ever drive from the pickup to the dropoff.
+    pub fn request_ride(
+        &mut self,
+        now: Time,
+        trip: TripID,
+        person: PersonID,
+        ped: PedestrianID,
+        pickup: Position,
+        dropoff: Position,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) -> bool {
+        // A one-step path backwards would never reach the end.
+        if pickup.lane() == dropoff.lane() && dropoff.dist_along() <= pickup.dist_along() {
+            return false;
+        }
+        let path = if let Some(path) = map.pathfind(PathRequest {
+            start: pickup,
+            end: dropoff,
+            constraints: PathConstraints::Car,
+        }) {
+            path
+        } else {
+            return false;
+        };
+        self.unassigned.push_back(RideRequest {
+            trip,
+            person,
+            ped,
+            requested_at: now,
+            pickup,
+            dropoff,
+            path,
+        });
+        self.dispatch(now, map, scheduler);
+        true
+    }
+
+    // Match waiting passengers with idle vehicles, in the order the rides were requested.
+    fn dispatch(&mut self, now: Time, map: &Map, scheduler: &mut Scheduler) {
+        let mut still_waiting = VecDeque::new();
+        while let Some(req) = self.unassigned.pop_front() {
+            let idle: Vec<(CarID, Position, Time)> = self
+                .vehicles
+                .iter()
+                .filter_map(|(id, (_, state))| match state {
+                    VehicleState::Idle(pos, since) => Some((*id, *pos, *since)),
+                    _ => None,
+                })
+                .collect();
+            if idle.is_empty() {
+                still_waiting.push_back(req);
+                still_waiting.extend(self.unassigned.drain(..));
+                break;
+            }
+
+            let mut assigned = false;
+            for car in self.policy.rank_vehicles(idle, req.pickup, map) {
+                let start = match self.vehicles[&car].1 {
+                    VehicleState::Idle(pos, _) => pos,
+                    _ => unreachable!(),
+                };
+                let vehicle = self.vehicles[&car].0.clone();
+                let path_req = PathRequest {
+                    start,
+                    end: req.pickup,
+                    constraints: PathConstraints::Car,
+                };
+                if let Some(path) = path_to_pickup(start, req.pickup, map) {
+                    // The path covers the full first and last lanes
+                    let deadhead = path.total_length() - start.dist_along()
+                        + req.pickup.dist_along()
+                        - map.get_l(req.pickup.lane()).length();
+                    scheduler.push(
+                        now,
+                        Command::SpawnCar(
+                            CreateCar {
+                                start_dist: start.dist_along(),
+                                router: Router::ride_hail(car, path, req.pickup.dist_along()),
+                                vehicle,
+                                req: path_req,
+                                maybe_parked_car: None,
+                                trip_and_person: None,
+                                maybe_route: None,
+                            },
+                            true,
+                        ),
+                    );
+                    self.vehicles.get_mut(&car).unwrap().1 = VehicleState::ToPickup(Ride {
+                        request: req.clone(),
+                        dispatched_at: now,
+                        deadhead,
+                        wait: Duration::ZERO,
+                    });
+                    assigned = true;
+                    break;
+                }
+            }
+            if !assigned {
+                // No idle vehicle can reach this passenger right now. Maybe one will free up
+                // somewhere better later.
+                still_waiting.push_back(req);
+            }
+        }
+        self.unassigned = still_waiting;
+    }
+
+    // If this returns a duration, the vehicle should idle at the curb for that long. Otherwise,
+    // it just dropped somebody off and should vanish until it's dispatched again.
+    pub fn vehicle_arrived(
+        &mut self,
+        now: Time,
+        id: CarID,
+        pos: Position,
+        trips: &mut TripManager,
+        walking: &mut WalkingSimState,
+        scheduler: &mut Scheduler,
+        map: &Map,
+    ) -> Option<Duration> {
+        let entry = self.vehicles.get_mut(&id).unwrap();
+        match std::mem::replace(&mut entry.1, VehicleState::Idle(pos, now)) {
+            VehicleState::ToPickup(mut ride) => {
+                ride.wait = now - ride.request.requested_at;
+                let (trip, person) =
+                    trips.ped_boarded_bus(now, ride.request.ped, id, ride.wait, walking);
+                self.events.push(Event::TripPhaseStarting(
+                    trip,
+                    person,
+                    Some(PathRequest {
+                        start: ride.request.pickup,
+                        end: ride.request.dropoff,
+                        constraints: PathConstraints::Car,
+                    }),
+                    TripPhaseType::RidingRideHail(id),
+                ));
+                entry.1 = VehicleState::PickingUp(ride);
+                Some(TIME_TO_PICK_UP)
+            }
+            VehicleState::ToDropoff(ride) => {
+                trips.person_left_bus(now, ride.request.person, id, map, scheduler);
+                self.events.push(Event::RideHailDropoff {
+                    car: id,
+                    person: ride.request.person,
+                    wait: ride.wait,
+                    deadhead: ride.deadhead,
+                    busy: now - ride.dispatched_at,
+                });
+                // Now that this vehicle is free, maybe somebody's waiting for it.
+                self.dispatch(now, map, scheduler);
+                None
+            }
+            VehicleState::Idle(_, _) | VehicleState::PickingUp(_) => unreachable!(),
+        }
+    }
+
+    // Returns the router to reach the dropoff.
+    pub fn vehicle_departed(&mut self, id: CarID) -> Router {
+        let state = &mut self.vehicles.get_mut(&id).unwrap().1;
+        let ride = match state {
+            VehicleState::PickingUp(ref ride) => ride.clone(),
+            _ => unreachable!(),
+        };
+        let router = Router::ride_hail(
+            id,
+            ride.request.path.clone(),
+            ride.request.dropoff.dist_along(),
+        );
+        *state = VehicleState::ToDropoff(ride);
+        router
+    }
+
+    pub fn describe_vehicle(&self, id: CarID) -> String {
+        match self.vehicles.get(&id) {
+            Some((_, VehicleState::Idle(_, since))) => {
+                format!("Waiting for a ride request since {}", since.ampm_tostring())
+            }
+            Some((_, VehicleState::ToPickup(ride))) => {
+                format!("Driving to pick up {}", ride.request.person)
+            }
+            Some((_, VehicleState::PickingUp(ride))) => {
+                format!("Picking up {}", ride.request.person)
+            }
+            Some((_, VehicleState::ToDropoff(ride))) => {
+                format!("Driving {} to their destination", ride.request.person)
+            }
+            None => "Not part of the ride-hail fleet".to_string(),
+        }
+    }
+
+    pub fn fleet_size(&self) -> usize {
+        self.vehicles.len()
+    }
+
+    // How many people are waiting for a vehicle to be dispatched?
+    pub fn num_unassigned(&self) -> usize {
+        self.unassigned.len()
+    }
+
+    pub fn collect_events(&mut self) -> Vec<Event> {
+        std::mem::replace(&mut self.events, Vec::new())
+    }
+}
+
+// If the vehicle is already at or past the pickup on the same lane, it has to circle the block.
+fn path_to_pickup(start: Position, pickup: Position, map: &Map) -> Option<Path> {
+    if start.lane() != pickup.lane() || start.dist_along() < pickup.dist_along() {
+        return map.pathfind(PathRequest {
+            start,
+            end: pickup,
+            constraints: PathConstraints::Car,
+        });
+    }
+
+    // Leave the lane through each possible turn, and keep the shortest way back.
+    let mut best: Option<(Distance, TurnID, Path)> = None;
+    for turn in map.get_turns_for(start.lane(), PathConstraints::Car) {
+        if let Some(rest) = map.pathfind(PathRequest {
+            start: Position::new(turn.id.dst, Distance::ZERO),
+            end: pickup,
+            constraints: PathConstraints::Car,
+        }) {
+            let dist = turn.geom.length() + rest.total_length();
+            if best.as_ref().map(|(d, _, _)| dist < *d).unwrap_or(true) {
+                best = Some((dist, turn.id, rest));
+            }
+        }
+    }
+    let (_, turn, rest) = best?;
+    let mut path = Path::one_step(start.lane(), map);
+    path.add(PathStep::Turn(turn), map);
+    path.append(rest, map);
+    Some(path)
+}
@@ -3,13 +3,13 @@ use crate::{
     CarID, Event, ParkingSimState, ParkingSpot, PersonID, SidewalkSpot, TripID, TripPhaseType,
     Vehicle, VehicleType,
 };
-use geom::Distance;
+use geom::{Distance, Duration};
 use map_model::{
     BuildingID, IntersectionID, LaneID, Map, Path, PathConstraints, PathRequest, PathStep,
     Position, Traversable, TurnID,
 };
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Router {
@@ -27,6 +27,12 @@ pub enum ActionAtEnd {
     StopBiking(SidewalkSpot),
     BusAtStop,
     GiveUpOnParking,
+    // Double-park for this long, blocking the lane
+    MakeDelivery(BuildingID, Duration),
+    // Pull into this free on-street spot next to the stop, so the lane is only blocked briefly
+    StartLoading(ParkingSpot),
+    // Reached a pickup or dropoff
+    RideHailStop,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -39,6 +45,8 @@ enum Goal {
         // No parking available at all!
         stuck_end_dist: Option<Distance>,
         started_looking: bool,
+        // How long the driver expects to stay, for weighing the price of spots
+        stay: Duration,
     },
     EndAtBorder {
         end_dist: Distance,
@@ -50,6 +58,31 @@ enum Goal {
     FollowBusRoute {
         end_dist: Distance,
     },
+    MakeDeliveries {
+        // The current leg ends at this building's curb...
+        end_dist: Distance,
+        bldg: BuildingID,
+        dwell: Duration,
+        // ...then the vehicle follows each of these legs, stopping at the end of each.
+        remaining: VecDeque<DeliveryLeg>,
+        // If there's a free on-street spot next to a stop, use it instead of double-parking.
+        use_loading_zones: bool,
+        // After the last stop, head here.
+        exit: (Path, Distance, IntersectionID),
+    },
+    // Pulling up to the curb to pick up or drop off a passenger
+    RideHail {
+        end_dist: Distance,
+    },
+}
+
+// Precomputed at spawn time, so the vehicle doesn't need to pathfind mid-tour.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DeliveryLeg {
+    pub path: Path,
+    pub end_dist: Distance,
+    pub bldg: BuildingID,
+    pub dwell: Duration,
 }
 
 impl Router {
@@ -77,7 +110,7 @@ impl Router {
         }
     }
 
-    pub fn park_near(owner: CarID, path: Path, bldg: BuildingID) -> Router {
+    pub fn park_near(owner: CarID, path: Path, bldg: BuildingID, stay: Duration) -> Router {
         Router {
             path,
             goal: Goal::ParkNearBuilding {
@@ -85,6 +118,7 @@ impl Router {
                 spot: None,
                 stuck_end_dist: None,
                 started_looking: false,
+                stay,
             },
             owner,
         }
@@ -98,6 +132,66 @@ impl Router {
         }
     }
 
+    // The first leg drives from the spawn point to the first stop.
+    pub fn make_deliveries(
+        owner: CarID,
+        mut legs: VecDeque<DeliveryLeg>,
+        exit: (Path, Distance, IntersectionID),
+        use_loading_zones: bool,
+    ) -> Router {
+        let first = legs.pop_front().unwrap();
+        Router {
+            path: first.path,
+            goal: Goal::MakeDeliveries {
+                end_dist: first.end_dist,
+                bldg: first.bldg,
+                dwell: first.dwell,
+                remaining: legs,
+                use_loading_zones,
+                exit,
+            },
+            owner,
+        }
+    }
+
+    // Called after a delivery vehicle finishes a stop. Switches to the next leg.
+    pub fn finished_delivery(&mut self) {
+        let goal = match self.goal {
+            Goal::MakeDeliveries {
+                ref mut remaining,
+                use_loading_zones,
+                ref exit,
+                ..
+            } => {
+                if let Some(leg) = remaining.pop_front() {
+                    self.path = leg.path;
+                    Goal::MakeDeliveries {
+                        end_dist: leg.end_dist,
+                        bldg: leg.bldg,
+                        dwell: leg.dwell,
+                        remaining: std::mem::replace(remaining, VecDeque::new()),
+                        use_loading_zones,
+                        exit: exit.clone(),
+                    }
+                } else {
+                    let (path, end_dist, i) = exit.clone();
+                    self.path = path;
+                    Goal::EndAtBorder { end_dist, i }
+                }
+            }
+            _ => unreachable!(),
+        };
+        self.goal = goal;
+    }
+
+    // How long the current delivery stop takes
+    pub fn delivery_dwell(&self) -> Duration {
+        match self.goal {
+            Goal::MakeDeliveries { dwell, .. } => dwell,
+            _ => unreachable!(),
+        }
+    }
+
     pub fn follow_bus_route(owner: CarID, path: Path, end_dist: Distance) -> Router {
         Router {
             path,
@@ -106,6 +200,14 @@ impl Router {
         }
     }
 
+    pub fn ride_hail(owner: CarID, path: Path, end_dist: Distance) -> Router {
+        Router {
+            path,
+            goal: Goal::RideHail { end_dist },
+            owner,
+        }
+    }
+
     pub fn head(&self) -> Traversable {
         self.path.current_step().as_traversable()
     }
@@ -138,6 +240,8 @@ impl Router {
             } => stuck_end_dist.unwrap_or_else(|| spot.unwrap().1),
             Goal::BikeThenStop { ref goal } => goal.sidewalk_pos.dist_along(),
             Goal::FollowBusRoute { end_dist } => end_dist,
+            Goal::MakeDeliveries { end_dist, .. } => end_dist,
+            Goal::RideHail { end_dist } => end_dist,
         }
     }
 
@@ -206,6 +310,7 @@ impl Router {
                 ref mut stuck_end_dist,
                 target,
                 ref mut started_looking,
+                stay,
             } => {
                 if let Some(d) = stuck_end_dist {
                     if *d == front {
@@ -228,26 +333,10 @@ impl Router {
                         target,
                         map,
                     );
-                    let best =
-                        if let Some((driving_pos, _)) = map.get_b(target).driving_connection(map) {
-                            if driving_pos.lane() == current_lane {
-                                let target_dist = driving_pos.dist_along();
-                                // Closest to the building
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| (pos.dist_along() - target_dist).abs())
-                            } else {
-                                // Closest to the road endpoint, I guess
-                                candidates
-                                    .into_iter()
-                                    .min_by_key(|(_, pos)| pos.dist_along())
-                            }
-                        } else {
-                            // Closest to the road endpoint, I guess
-                            candidates
-                                .into_iter()
-                                .min_by_key(|(_, pos)| pos.dist_along())
-                        };
+                    // Closest to the building, accounting for price
+                    let best = candidates
+                        .into_iter()
+                        .min_by_key(|(s, _)| parking.spot_cost(*s, target, stay, map));
                     if let Some((new_spot, new_pos)) = best {
                         if let Some((t, p)) = trip_and_person {
                             events.push(Event::TripPhaseStarting(
@@ -265,8 +354,8 @@ impl Router {
                         assert!(new_pos.dist_along() >= front);
                         *spot = Some((new_spot, new_pos.dist_along()));
                     } else {
-                        if let Some((new_path_steps, new_spot, new_pos)) =
-                            parking.path_to_free_parking_spot(current_lane, vehicle, target, map)
+                        if let Some((new_path_steps, new_spot, new_pos)) = parking
+                            .path_to_free_parking_spot(current_lane, vehicle, target, stay, map)
                         {
                             assert!(!new_path_steps.is_empty());
                             for step in new_path_steps {
@@ -319,6 +408,35 @@ impl Router {
                     None
                 }
             }
+            Goal::MakeDeliveries {
+                end_dist,
+                bldg,
+                dwell,
+                use_loading_zones,
+                ..
+            } => {
+                if end_dist == front {
+                    if use_loading_zones {
+                        if let Some(spot) = parking.get_free_spot_alongside(
+                            Position::new(self.head().as_lane(), front),
+                            vehicle,
+                            map,
+                        ) {
+                            return Some(ActionAtEnd::StartLoading(spot));
+                        }
+                    }
+                    Some(ActionAtEnd::MakeDelivery(bldg, dwell))
+                } else {
+                    None
+                }
+            }
+            Goal::RideHail { end_dist } => {
+                if end_dist == front {
+                    Some(ActionAtEnd::RideHailStop)
+                } else {
+                    None
+                }
+            }
         }
     }
 
@@ -413,6 +531,48 @@ impl Router {
         self.path.modify_step(3, PathStep::Turn(turn2.id), map);
     }
 
+    // Other lanes of the current road, going the same direction, that this vehicle could switch to
+    // in the middle of the block and still make the next turn in the path. Each candidate is
+    // paired with the turn to use from that lane.
+    pub fn mid_block_lanechange_candidates(&self, map: &Map) -> Vec<(LaneID, TurnID)> {
+        if self.path.approaching_uber_turn() || self.path.currently_inside_ut().is_some() {
+            return Vec::new();
+        }
+        let (current_lane, orig_turn) = {
+            let steps = self.path.get_steps();
+            if steps.len() < 3 {
+                return Vec::new();
+            }
+            match (steps[0], steps[1]) {
+                (PathStep::Lane(l), PathStep::Turn(t)) => (l, t),
+                _ => {
+                    return Vec::new();
+                }
+            }
+        };
+
+        let parent = map.get_parent(current_lane);
+        let constraints = self.owner.1.to_constraints();
+        parent
+            .children(parent.is_forwards(current_lane))
+            .iter()
+            .filter(|(l, _)| *l != current_lane && constraints.can_use(map.get_l(*l), map))
+            .filter_map(|(l, _)| {
+                let t = TurnID {
+                    parent: orig_turn.parent,
+                    src: *l,
+                    dst: orig_turn.dst,
+                };
+                map.maybe_get_t(t).map(|_| (*l, t))
+            })
+            .collect()
+    }
+
+    pub fn change_lanes_mid_block(&mut self, lane: LaneID, turn: TurnID, map: &Map) {
+        self.path.modify_current_step(PathStep::Lane(lane), map);
+        self.path.modify_step(1, PathStep::Turn(turn), map);
+    }
+
     pub fn replace_path_for_serialization(&mut self, path: Path) -> Path {
         std::mem::replace(&mut self.path, path)
     }
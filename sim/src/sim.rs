@@ -1,24 +1,26 @@
 use crate::analytics::Window;
 use crate::{
-    AgentID, AgentType, AlertLocation, Analytics, CarID, Command, CreateCar, DrawCarInput,
-    DrawPedCrowdInput, DrawPedestrianInput, DrivingSimState, Event, GetDrawAgents,
-    IntersectionSimState, OrigPersonID, PandemicModel, ParkedCar, ParkingSimState, ParkingSpot,
-    PedestrianID, Person, PersonID, PersonState, Router, Scheduler, SidewalkPOI, SidewalkSpot,
-    TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, TripResult, TripSpawner,
-    UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_LENGTH,
-    LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH, SPAWN_DIST,
+    AgentID, AgentType, AlertLocation, Analytics, BikeShareSimState, CarID, Command, CreateCar,
+    DeliveryLeg, DispatchPolicy, DrawCarInput, DrawPedCrowdInput, DrawPedestrianInput, DrivingGoal,
+    DrivingSimState, Event, FreightTour, GetDrawAgents, IntersectionSimState, OrigPersonID,
+    PandemicModel, ParkedCar, ParkingSimState, ParkingSpot, ParkingSurvey, PedestrianID, Person,
+    PersonAttributes, PersonID, PersonState, RideHailSimState, Router, Scheduler, SidewalkPOI,
+    SidewalkSpot, TransitSimState, TripID, TripInfo, TripManager, TripPhaseType, TripResult,
+    TripSpawner, UnzoomedAgent, Vehicle, VehicleSpec, VehicleType, WalkingSimState, BUS_ACCEL,
+    BUS_DECEL, BUS_LENGTH, CAR_ACCEL, CAR_DECEL, LIGHT_RAIL_LENGTH, MIN_CAR_LENGTH,
+    RIDE_HAIL_LENGTH, SPAWN_DIST,
 };
 use abstutil::{prettyprint_usize, serialized_size_bytes, Counter, Parallelism, Timer};
 use derivative::Derivative;
 use geom::{Distance, Duration, PolyLine, Pt2D, Speed, Time};
 use instant::Instant;
 use map_model::{
-    BuildingID, BusRoute, BusRouteID, BusStopID, IntersectionID, Lane, LaneID, Map, ParkingLotID,
-    Path, PathConstraints, PathRequest, Position, RoadID, Traversable,
+    BikeShareDockID, BuildingID, BusRoute, BusRouteID, BusStopID, IntersectionID, Lane, LaneID,
+    Map, ParkingLotID, Path, PathConstraints, PathRequest, Position, RoadID, Traversable,
 };
 use rand_xorshift::XorShiftRng;
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, HashSet};
+use std::collections::{BTreeMap, HashSet, VecDeque};
 use std::panic;
 
 // TODO Do something else.
@@ -32,6 +34,8 @@ pub struct Sim {
     walking: WalkingSimState,
     intersections: IntersectionSimState,
     transit: TransitSimState,
+    ridehail: RideHailSimState,
+    bikeshare: BikeShareSimState,
     trips: TripManager,
     #[derivative(PartialEq = "ignore")]
     #[serde(skip_serializing, skip_deserializing)]
@@ -48,6 +52,9 @@ pub struct Sim {
     run_name: String,
     #[derivative(PartialEq = "ignore")]
     step_count: usize,
+    // From the scenario, for comparing against simulated parking occupancy
+    #[derivative(PartialEq = "ignore")]
+    parking_survey: Option<ParkingSurvey>,
 
     // Don't serialize, to reduce prebaked savestate size. Analytics are saved once covering the
     // full day and can be trimmed to any time.
@@ -66,10 +73,12 @@ pub struct SimOptions {
     pub use_freeform_policy_everywhere: bool,
     pub dont_block_the_box: bool,
     pub recalc_lanechanging: bool,
+    pub mid_block_lanechanging: bool,
     pub break_turn_conflict_cycles: bool,
     pub enable_pandemic_model: Option<XorShiftRng>,
     pub alerts: AlertHandler,
     pub pathfinding_upfront: bool,
+    pub ride_hail_dispatch: DispatchPolicy,
 }
 
 #[derive(Clone)]
@@ -95,10 +104,12 @@ impl SimOptions {
             use_freeform_policy_everywhere: false,
             dont_block_the_box: true,
             recalc_lanechanging: true,
+            mid_block_lanechanging: false,
             break_turn_conflict_cycles: true,
             enable_pandemic_model: None,
             alerts: AlertHandler::Print,
             pathfinding_upfront: false,
+            ride_hail_dispatch: DispatchPolicy::Nearest,
         }
     }
 }
@@ -108,7 +119,11 @@ impl Sim {
     pub fn new(map: &Map, opts: SimOptions, timer: &mut Timer) -> Sim {
         let mut scheduler = Scheduler::new();
         Sim {
-            driving: DrivingSimState::new(map, opts.recalc_lanechanging),
+            driving: DrivingSimState::new(
+                map,
+                opts.recalc_lanechanging,
+                opts.mid_block_lanechanging,
+            ),
             parking: ParkingSimState::new(map, timer),
             walking: WalkingSimState::new(),
             intersections: IntersectionSimState::new(
@@ -119,6 +134,8 @@ impl Sim {
                 opts.break_turn_conflict_cycles,
             ),
             transit: TransitSimState::new(map),
+            ridehail: RideHailSimState::new(opts.ride_hail_dispatch),
+            bikeshare: BikeShareSimState::new(map),
             trips: TripManager::new(opts.pathfinding_upfront),
             pandemic: if let Some(rng) = opts.enable_pandemic_model {
                 Some(PandemicModel::new(rng))
@@ -133,6 +150,7 @@ impl Sim {
             edits_name: "untitled edits".to_string(),
             run_name: opts.run_name,
             step_count: 0,
+            parking_survey: None,
             alerts: opts.alerts,
 
             analytics: Analytics::new(),
@@ -181,6 +199,8 @@ impl Sim {
             vehicle_type: VehicleType::Car,
             length: MIN_CAR_LENGTH,
             max_speed: None,
+            max_accel: CAR_ACCEL,
+            max_decel: CAR_DECEL,
         };
         let driving_lane = map.find_driving_lane_near_building(b);
 
@@ -194,9 +214,13 @@ impl Sim {
         {
             spot.clone()
         } else {
-            let (_, spot, _) =
-                self.parking
-                    .path_to_free_parking_spot(driving_lane, &vehicle, b, map)?;
+            let (_, spot, _) = self.parking.path_to_free_parking_spot(
+                driving_lane,
+                &vehicle,
+                b,
+                Duration::ZERO,
+                map,
+            )?;
             spot
         };
 
@@ -215,25 +239,163 @@ impl Sim {
         &mut self,
         p: PersonID,
         orig_id: Option<OrigPersonID>,
+        attributes: PersonAttributes,
         ped_speed: Speed,
         vehicle_specs: Vec<VehicleSpec>,
     ) {
-        self.trips.new_person(p, orig_id, ped_speed, vehicle_specs);
+        self.trips
+            .new_person(p, orig_id, attributes, ped_speed, vehicle_specs);
     }
     pub fn random_person(&mut self, ped_speed: Speed, vehicle_specs: Vec<VehicleSpec>) -> &Person {
         self.trips.random_person(ped_speed, vehicle_specs)
     }
     pub(crate) fn seed_parked_car(&mut self, vehicle: Vehicle, spot: ParkingSpot) {
         self.parking.reserve_spot(spot);
+        // flush_spawner dispatches the CarReachedParkingSpot event, so analytics count this car.
         self.parking.add_parked_car(ParkedCar { vehicle, spot });
     }
 
+    // Nobody owns these cars, so they never move.
+    pub(crate) fn seed_background_parked_car(&mut self, spec: VehicleSpec, spot: ParkingSpot) {
+        let vehicle = spec.make(CarID(self.trips.new_car_id(), VehicleType::Car), None);
+        self.seed_parked_car(vehicle, spot);
+    }
+
+    pub(crate) fn set_parking_survey(&mut self, survey: ParkingSurvey) {
+        self.parking_survey = Some(survey);
+    }
+
     pub(crate) fn seed_bus_route(&mut self, route: &BusRoute) {
         for t in &route.spawn_times {
             self.scheduler.push(*t, Command::StartBus(route.id, *t));
         }
     }
 
+    // The vehicle starts off-road at this position, waiting for a ride request.
+    pub(crate) fn seed_ride_hail_vehicle(&mut self, pos: Position) {
+        let vehicle = VehicleSpec {
+            vehicle_type: VehicleType::RideHail,
+            length: RIDE_HAIL_LENGTH,
+            max_speed: None,
+            max_accel: CAR_ACCEL,
+            max_decel: CAR_DECEL,
+        }
+        .make(CarID(self.trips.new_car_id(), VehicleType::RideHail), None);
+        self.ridehail.add_vehicle(self.time, vehicle, pos);
+    }
+
+    // Precomputes the path for every leg of the tour, then schedules the vehicle to appear.
+    pub(crate) fn seed_freight_tour(&mut self, tour: &FreightTour, map: &Map, timer: &mut Timer) {
+        let start = match tour
+            .start
+            .lanes(PathConstraints::Car, map)
+            .into_iter()
+            .find(|l| SPAWN_DIST < map.get_l(*l).length())
+        {
+            Some(l) => Position::new(l, SPAWN_DIST),
+            None => {
+                timer.warn(format!(
+                    "Freight can't enter the map from {}",
+                    tour.start.id
+                ));
+                return;
+            }
+        };
+        let exit = match DrivingGoal::end_at_border(tour.end, PathConstraints::Car, None, map) {
+            Some(DrivingGoal::Border(i, l, _)) => (i, Position::end(l, map)),
+            _ => {
+                timer.warn(format!("Freight can't leave the map from {}", tour.end.id));
+                return;
+            }
+        };
+
+        let mut legs = VecDeque::new();
+        let mut pos = start;
+        for (b, dwell) in &tour.stops {
+            let end = match map.get_b(*b).driving_connection(map) {
+                Some((end, _)) => end,
+                None => {
+                    timer.warn(format!("Freight can't stop at {}; no driving lane", b));
+                    continue;
+                }
+            };
+            // Consecutive stops at the same building are just one longer stop. If the first stop
+            // is right where the vehicle appears, it still gets its own leg below.
+            if end == pos {
+                if let Some(leg) = legs.back_mut() {
+                    leg.dwell += *dwell;
+                    continue;
+                }
+            }
+            if let Some(path) = map.pathfind(PathRequest {
+                start: pos,
+                end,
+                constraints: PathConstraints::Car,
+            }) {
+                legs.push_back(DeliveryLeg {
+                    path,
+                    end_dist: end.dist_along(),
+                    bldg: *b,
+                    dwell: *dwell,
+                });
+                pos = end;
+            } else {
+                timer.warn(format!("Freight can't reach {} from {}", b, pos));
+            }
+        }
+        let exit_req = PathRequest {
+            start: pos,
+            end: exit.1,
+            constraints: PathConstraints::Car,
+        };
+        let exit_path = if let Some(path) = map.pathfind(exit_req.clone()) {
+            path
+        } else {
+            timer.warn(format!("Freight can't leave the map from {}", pos));
+            return;
+        };
+
+        let vehicle = tour
+            .vehicle_spec()
+            .make(CarID(self.trips.new_car_id(), tour.vehicle_type), None);
+        let (req, router) = if legs.is_empty() {
+            (
+                exit_req,
+                Router::end_at_border(vehicle.id, exit_path, exit.1.dist_along(), exit.0),
+            )
+        } else {
+            let req = PathRequest {
+                start,
+                end: Position::new(legs[0].path.last_step().as_lane(), legs[0].end_dist),
+                constraints: PathConstraints::Car,
+            };
+            (
+                req,
+                Router::make_deliveries(
+                    vehicle.id,
+                    legs,
+                    (exit_path, exit.1.dist_along(), exit.0),
+                    tour.use_loading_zones,
+                ),
+            )
+        };
+        self.scheduler.push(
+            tour.depart,
+            Command::SpawnCar(
+                CreateCar {
+                    start_dist: start.dist_along(),
+                    router,
+                    vehicle,
+                    req,
+                    maybe_parked_car: None,
+                    trip_and_person: None,
+                    maybe_route: None,
+                },
+                true,
+            ),
+        );
+    }
+
     fn start_bus(&mut self, route: &BusRoute, map: &Map) {
         // Spawn one bus for the first leg.
         let (req, path) = self.transit.create_empty_route(route, map);
@@ -249,6 +411,8 @@ impl Sim {
             vehicle_type,
             length,
             max_speed: None,
+            max_accel: BUS_ACCEL,
+            max_decel: BUS_DECEL,
         }
         .make(CarID(self.trips.new_car_id(), vehicle_type), None);
         let start_lane = map.get_l(path.current_step().as_lane());
@@ -524,6 +688,8 @@ impl Sim {
                     &mut self.scheduler,
                     &mut self.transit,
                     &mut self.walking,
+                    &mut self.ridehail,
+                    &mut self.bikeshare,
                 );
             }
             Command::UpdateLaggyHead(car) => {
@@ -545,6 +711,8 @@ impl Sim {
                     &mut self.scheduler,
                     &mut self.trips,
                     &mut self.transit,
+                    &mut self.ridehail,
+                    &mut self.bikeshare,
                 );
             }
             Command::UpdateIntersection(i) => {
@@ -587,6 +755,8 @@ impl Sim {
     fn dispatch_events(&mut self, mut events: Vec<Event>, map: &Map) {
         events.extend(self.trips.collect_events());
         events.extend(self.transit.collect_events());
+        events.extend(self.ridehail.collect_events());
+        events.extend(self.bikeshare.collect_events());
         events.extend(self.driving.collect_events());
         events.extend(self.walking.collect_events());
         events.extend(self.intersections.collect_events());
@@ -923,6 +1093,25 @@ impl Sim {
         self.transit.get_passengers(car).len()
     }
 
+    pub fn describe_ride_hail_vehicle(&self, car: CarID) -> String {
+        self.ridehail.describe_vehicle(car)
+    }
+
+    // (Fleet size, people waiting for a vehicle to be dispatched)
+    pub fn ride_hail_fleet(&self) -> (usize, usize) {
+        (self.ridehail.fleet_size(), self.ridehail.num_unassigned())
+    }
+
+    // (bikes docked, capacity)
+    pub fn bike_share_dock_status(&self, dock: BikeShareDockID) -> (usize, usize) {
+        self.bikeshare.dock_status(dock)
+    }
+
+    // Positive means the dock needs more bikes, negative means it has too many.
+    pub fn bike_share_rebalancing_needs(&self) -> Vec<(BikeShareDockID, isize)> {
+        self.bikeshare.rebalancing_needs()
+    }
+
     pub fn bus_route_id(&self, maybe_bus: CarID) -> Option<BusRouteID> {
         if maybe_bus.1 == VehicleType::Bus || maybe_bus.1 == VehicleType::Train {
             Some(self.transit.bus_route(maybe_bus))
@@ -995,6 +1184,9 @@ impl Sim {
             VehicleType::Bike,
             VehicleType::Bus,
             VehicleType::Train,
+            VehicleType::Van,
+            VehicleType::Truck,
+            VehicleType::RideHail,
         ] {
             let id = CarID(idx, *vt);
             if self.driving.does_car_exist(id) {
@@ -1095,6 +1287,10 @@ impl Sim {
         &self.analytics
     }
 
+    pub fn get_parking_survey(&self) -> Option<&ParkingSurvey> {
+        self.parking_survey.as_ref()
+    }
+
     pub fn find_blockage_front(&self, car: CarID, map: &Map) -> String {
         self.driving
             .find_blockage_front(car, map, &self.intersections)
@@ -1,14 +1,15 @@
 use crate::{
-    AgentID, AgentType, AlertLocation, CarID, Command, CreateCar, CreatePedestrian, DrivingGoal,
-    Event, OffMapLocation, OrigPersonID, ParkedCar, ParkingSimState, ParkingSpot, PedestrianID,
-    PersonID, Scheduler, SidewalkPOI, SidewalkSpot, TransitSimState, TripID, TripPhaseType,
-    TripSpec, Vehicle, VehicleSpec, VehicleType, WalkingSimState,
+    AgentID, AgentType, AlertLocation, BikeShareSimState, CarID, Command, CreateCar,
+    CreatePedestrian, DrivingGoal, Event, OffMapLocation, OrigPersonID, ParkedCar, ParkingSimState,
+    ParkingSpot, PedestrianID, PersonAttributes, PersonID, RideHailSimState, Router, Scheduler,
+    SidewalkPOI, SidewalkSpot, TransitSimState, TripID, TripPhaseType, TripSpec, Vehicle,
+    VehicleSpec, VehicleType, WalkingSimState,
 };
 use abstutil::{deserialize_btreemap, serialize_btreemap, Counter};
 use geom::{Duration, Speed, Time};
 use map_model::{
-    BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints, PathRequest,
-    Position,
+    BikeShareDockID, BuildingID, BusRouteID, BusStopID, IntersectionID, Map, Path, PathConstraints,
+    PathRequest, Position,
 };
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, VecDeque};
@@ -49,6 +50,7 @@ impl TripManager {
         &mut self,
         id: PersonID,
         orig_id: Option<OrigPersonID>,
+        attributes: PersonAttributes,
         ped_speed: Speed,
         vehicle_specs: Vec<VehicleSpec>,
     ) {
@@ -63,6 +65,7 @@ impl TripManager {
         self.people.push(Person {
             id,
             orig_id,
+            attributes,
             trips: Vec::new(),
             // The first new_trip will set this properly.
             state: PersonState::OffMap,
@@ -75,7 +78,13 @@ impl TripManager {
     }
     pub fn random_person(&mut self, ped_speed: Speed, vehicle_specs: Vec<VehicleSpec>) -> &Person {
         let id = PersonID(self.people.len());
-        self.new_person(id, None, ped_speed, vehicle_specs);
+        self.new_person(
+            id,
+            None,
+            PersonAttributes::default(),
+            ped_speed,
+            vehicle_specs,
+        );
         self.get_person(id).unwrap()
     }
 
@@ -91,6 +100,7 @@ impl TripManager {
         departure: Time,
         start: TripEndpoint,
         mode: TripMode,
+        purpose: TripPurpose,
         modified: bool,
         legs: Vec<TripLeg>,
         map: &Map,
@@ -126,6 +136,7 @@ impl TripManager {
                 mode,
                 start,
                 end,
+                purpose,
                 modified,
             },
             person,
@@ -185,7 +196,9 @@ impl TripManager {
         parking: &mut ParkingSimState,
         scheduler: &mut Scheduler,
     ) {
-        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        let id = self.active_trip_mode.remove(&AgentID::Car(car)).unwrap();
+        self.events.push(Event::VehicleFinishedTripLeg(car, id));
+        let trip = &mut self.trips[id.0];
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front() {
@@ -243,11 +256,12 @@ impl TripManager {
         scheduler: &mut Scheduler,
     ) {
         self.events.push(Event::PedReachedParkingSpot(ped, spot));
-        let trip = &mut self.trips[self
+        let id = self
             .active_trip_mode
             .remove(&AgentID::Pedestrian(ped))
-            .unwrap()
-            .0];
+            .unwrap();
+        let stay = self.expected_stay(id, now);
+        let trip = &mut self.trips[id.0];
         trip.total_blocked_time += blocked_time;
 
         trip.assert_walking_leg(SidewalkSpot::deferred_parking_spot());
@@ -296,7 +310,7 @@ impl TripManager {
             return;
         };
 
-        let router = drive_to.make_router(parked_car.vehicle.id, path, map);
+        let router = drive_to.make_router(parked_car.vehicle.id, path, stay, map);
         scheduler.push(
             now,
             Command::SpawnCar(
@@ -331,23 +345,30 @@ impl TripManager {
         trip.total_blocked_time += blocked_time;
 
         trip.assert_walking_leg(spot.clone());
-        let (bike, drive_to) = match trip.legs[0] {
-            TripLeg::Drive(bike, ref to) => (bike, to.clone()),
+        let driving_pos = match spot.connection {
+            SidewalkPOI::BikeRack(p) | SidewalkPOI::BikeShareDock(_, p) => p,
             _ => unreachable!(),
         };
-        let driving_pos = match spot.connection {
-            SidewalkPOI::BikeRack(p) => p,
+        let (vehicle, maybe_end) = match trip.legs[0] {
+            TripLeg::Drive(bike, ref to) => (
+                self.people[trip.person.0].get_vehicle(bike),
+                to.goal_pos(PathConstraints::Bike, map),
+            ),
+            TripLeg::BikeShare(bike, dock) => (
+                BikeShareSimState::make_bike(bike, trip.person),
+                map.get_bsd(dock).biking_connection(map),
+            ),
             _ => unreachable!(),
         };
 
-        let end = if let Some(end) = drive_to.goal_pos(PathConstraints::Bike, map) {
+        let end = if let Some(end) = maybe_end {
             end
         } else {
             self.events.push(Event::Alert(
                 AlertLocation::Person(trip.person),
                 format!(
                     "Aborting {} because no bike connection at {:?}",
-                    trip.id, drive_to
+                    trip.id, trip.legs[0]
                 ),
             ));
             let trip = trip.id;
@@ -359,15 +380,21 @@ impl TripManager {
             end,
             constraints: PathConstraints::Bike,
         };
-        if let Some(router) = map
-            .pathfind(req.clone())
-            .map(|path| drive_to.make_router(bike, path, map))
-        {
+        if let Some(router) = map.pathfind(req.clone()).map(|path| match trip.legs[0] {
+            // Bikes don't pay for parking
+            TripLeg::Drive(bike, ref to) => to.make_router(bike, path, Duration::ZERO, map),
+            TripLeg::BikeShare(bike, dock) => Router::bike_then_stop(
+                bike,
+                path,
+                SidewalkSpot::bike_share_dock(dock, map).unwrap(),
+            ),
+            _ => unreachable!(),
+        }) {
             scheduler.push(
                 now,
                 Command::SpawnCar(
                     CreateCar::for_appearing(
-                        self.people[trip.person.0].get_vehicle(bike),
+                        vehicle,
                         driving_pos,
                         router,
                         req,
@@ -391,6 +418,56 @@ impl TripManager {
         }
     }
 
+    // The shared bike dock was empty, so walk the rest of the way.
+    pub fn ped_found_no_bike(
+        &mut self,
+        now: Time,
+        ped: PedestrianID,
+        spot: SidewalkSpot,
+        blocked_time: Duration,
+        map: &Map,
+        scheduler: &mut Scheduler,
+    ) {
+        let trip = &mut self.trips[self
+            .active_trip_mode
+            .remove(&AgentID::Pedestrian(ped))
+            .unwrap()
+            .0];
+        trip.total_blocked_time += blocked_time;
+
+        trip.assert_walking_leg(spot.clone());
+        let dock = match trip.legs.pop_front() {
+            Some(TripLeg::BikeShare(_, _)) => match spot.connection {
+                SidewalkPOI::BikeShareDock(dock, _) => dock,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        self.events.push(Event::Alert(
+            AlertLocation::Person(trip.person),
+            format!(
+                "No bikes left at {}, so {} is walking instead",
+                dock, trip.id
+            ),
+        ));
+
+        let start = SidewalkSpot::suddenly_appear(
+            spot.sidewalk_pos.lane(),
+            spot.sidewalk_pos.dist_along(),
+            map,
+        );
+        if !trip.spawn_ped(
+            now,
+            start,
+            &self.people[trip.person.0],
+            map,
+            scheduler,
+            &mut self.events,
+        ) {
+            self.unfinished_trips -= 1;
+        }
+    }
+
     pub fn bike_reached_end(
         &mut self,
         now: Time,
@@ -404,11 +481,13 @@ impl TripManager {
             bike,
             bike_rack.sidewalk_pos.lane(),
         ));
-        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(bike)).unwrap().0];
+        let id = self.active_trip_mode.remove(&AgentID::Car(bike)).unwrap();
+        self.events.push(Event::VehicleFinishedTripLeg(bike, id));
+        let trip = &mut self.trips[id.0];
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front() {
-            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) => {
+            Some(TripLeg::Drive(c, DrivingGoal::ParkNear(_))) | Some(TripLeg::BikeShare(c, _)) => {
                 assert_eq!(c, bike);
             }
             _ => unreachable!(),
@@ -513,6 +592,68 @@ impl TripManager {
         }
     }
 
+    // Returns false if nobody can reach the pedestrian, in which case the trip is aborted and the
+    // pedestrian should vanish.
+    pub fn ped_reached_ride_hail_stop(
+        &mut self,
+        now: Time,
+        ped: PedestrianID,
+        pickup: Position,
+        blocked_time: Duration,
+        map: &Map,
+        parking: &mut ParkingSimState,
+        scheduler: &mut Scheduler,
+        ridehail: &mut RideHailSimState,
+    ) -> bool {
+        let trip = &mut self.trips[self.active_trip_mode[&AgentID::Pedestrian(ped)].0];
+        trip.total_blocked_time += blocked_time;
+
+        match trip.legs[0] {
+            TripLeg::Walk(ref spot) => {
+                assert_eq!(spot.connection, SidewalkPOI::RideHailStop(pickup));
+            }
+            _ => unreachable!(),
+        }
+        let dropoff = match trip.legs[1] {
+            TripLeg::RideHail(ref spot) => match spot.connection {
+                SidewalkPOI::RideHailStop(pos) => pos,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        if ridehail.request_ride(
+            now,
+            trip.id,
+            trip.person,
+            ped,
+            pickup,
+            dropoff,
+            map,
+            scheduler,
+        ) {
+            self.events.push(Event::TripPhaseStarting(
+                trip.id,
+                trip.person,
+                None,
+                TripPhaseType::WaitingForRideHail,
+            ));
+            true
+        } else {
+            let id = trip.id;
+            self.events.push(Event::Alert(
+                AlertLocation::Person(trip.person),
+                format!(
+                    "No way to drive from {} to {}, so {} can't get a ride",
+                    pickup, dropoff, trip.person
+                ),
+            ));
+            self.active_trip_mode.remove(&AgentID::Pedestrian(ped));
+            self.abort_trip(now, id, None, parking, scheduler, map);
+            false
+        }
+    }
+
+    // Also used when a ride-hail vehicle picks somebody up.
     pub fn ped_boarded_bus(
         &mut self,
         now: Time,
@@ -537,6 +678,7 @@ impl TripManager {
     }
 
     // TODO Need to characterize delay the bus experienced
+    // Also used when a ride-hail vehicle drops somebody off.
     pub fn person_left_bus(
         &mut self,
         now: Time,
@@ -555,6 +697,7 @@ impl TripManager {
                 maybe_stop2.expect("someone left a bus, even though they should've ridden off-map"),
                 map,
             ),
+            TripLeg::RideHail(spot) => spot,
             _ => unreachable!(),
         };
         self.people[person.0].on_bus.take().unwrap();
@@ -665,7 +808,9 @@ impl TripManager {
         parking: &mut ParkingSimState,
         scheduler: &mut Scheduler,
     ) {
-        let trip = &mut self.trips[self.active_trip_mode.remove(&AgentID::Car(car)).unwrap().0];
+        let id = self.active_trip_mode.remove(&AgentID::Car(car)).unwrap();
+        self.events.push(Event::VehicleFinishedTripLeg(car, id));
+        let trip = &mut self.trips[id.0];
         trip.total_blocked_time += blocked_time;
 
         match trip.legs.pop_front().unwrap() {
@@ -784,7 +929,13 @@ impl TripManager {
                         .map(|(spot, _)| spot.clone())
                         .or_else(|| {
                             parking
-                                .path_to_free_parking_spot(driving_lane, &vehicle, b, map)
+                                .path_to_free_parking_spot(
+                                    driving_lane,
+                                    &vehicle,
+                                    b,
+                                    Duration::ZERO,
+                                    map,
+                                )
                                 .map(|(_, spot, _)| spot)
                         })
                     {
@@ -811,7 +962,7 @@ impl TripManager {
         } else {
             // If the trip was aborted because we'e totally out of parking, don't forget to clean
             // this up.
-            if let TripLeg::Drive(c, _) = &trip.legs[0] {
+            if let TripLeg::Drive(c, _) | TripLeg::BikeShare(c, _) = &trip.legs[0] {
                 if let Some(t) = self.active_trip_mode.remove(&AgentID::Car(*c)) {
                     assert_eq!(t, trip.id);
                 }
@@ -853,8 +1004,10 @@ impl TripManager {
         let person = &self.people[trip.person.0];
         let a = match &trip.legs[0] {
             TripLeg::Walk(_) => AgentID::Pedestrian(person.ped),
-            TripLeg::Drive(c, _) => AgentID::Car(*c),
-            TripLeg::RideBus(_, _) => AgentID::BusPassenger(person.id, person.on_bus.unwrap()),
+            TripLeg::Drive(c, _) | TripLeg::BikeShare(c, _) => AgentID::Car(*c),
+            TripLeg::RideBus(_, _) | TripLeg::RideHail(_) => {
+                AgentID::BusPassenger(person.id, person.on_bus.unwrap())
+            }
             TripLeg::Remote(_) => {
                 return TripResult::RemoteTrip;
             }
@@ -918,6 +1071,24 @@ impl TripManager {
         self.unfinished_trips == 0
     }
 
+    // Drivers weigh the price of parking by how long they expect to stay: until their next trip
+    // starts, or the end of the day.
+    fn expected_stay(&self, trip: TripID, now: Time) -> Duration {
+        let person = &self.people[self.trips[trip.0].person.0];
+        let next_departure = person
+            .trips
+            .iter()
+            .skip_while(|t| **t != trip)
+            .nth(1)
+            .map(|t| self.trips[t.0].info.departure)
+            .unwrap_or(Time::START_OF_DAY + Duration::hours(24));
+        if next_departure > now {
+            next_departure - now
+        } else {
+            Duration::ZERO
+        }
+    }
+
     pub fn collect_events(&mut self) -> Vec<Event> {
         std::mem::replace(&mut self.events, Vec::new())
     }
@@ -998,6 +1169,7 @@ impl TripManager {
             maybe_path = map.pathfind(maybe_req.clone().unwrap());
         }
 
+        let stay = self.expected_stay(trip, now);
         let person = &mut self.people[self.trips[trip.0].person.0];
         if let PersonState::Trip(_) = person.state {
             // Previous trip isn't done. Defer this one!
@@ -1043,7 +1215,8 @@ impl TripManager {
                 let vehicle = person.get_vehicle(use_vehicle);
                 assert!(parking.lookup_parked_car(vehicle.id).is_none());
                 let req = maybe_req.unwrap();
-                if let Some(router) = maybe_path.map(|path| goal.make_router(vehicle.id, path, map))
+                if let Some(router) =
+                    maybe_path.map(|path| goal.make_router(vehicle.id, path, stay, map))
                 {
                     scheduler.push(
                         now,
@@ -1230,6 +1403,35 @@ impl TripManager {
                     self.abort_trip(now, trip, None, parking, scheduler, map);
                 }
             }
+            TripSpec::UsingBikeShare { start, dock1, .. } => {
+                assert_eq!(person.state, PersonState::Inside(start));
+                person.state = PersonState::Trip(trip);
+
+                // schedule_trip made sure this exists
+                let walk_to = SidewalkSpot::bike_share_dock(dock1, map).unwrap();
+                let req = maybe_req.unwrap();
+                if let Some(path) = maybe_path {
+                    scheduler.push(
+                        now,
+                        Command::SpawnPed(CreatePedestrian {
+                            id: person.ped,
+                            speed: person.ped_speed,
+                            start: SidewalkSpot::building(start, map),
+                            goal: walk_to,
+                            path,
+                            req,
+                            trip,
+                            person: person.id,
+                        }),
+                    );
+                } else {
+                    self.events.push(Event::Alert(
+                        AlertLocation::Person(person.id),
+                        format!("UsingBikeShare trip couldn't find the first path {}", req),
+                    ));
+                    self.abort_trip(now, trip, None, parking, scheduler, map);
+                }
+            }
             TripSpec::UsingTransit { start, stop1, .. } => {
                 assert_eq!(
                     person.state,
@@ -1284,6 +1486,61 @@ impl TripManager {
                     self.abort_trip(now, trip, None, parking, scheduler, map);
                 }
             }
+            TripSpec::UsingRideHail { start, .. } => {
+                assert_eq!(
+                    person.state,
+                    match start.connection {
+                        SidewalkPOI::Building(b) => PersonState::Inside(b),
+                        SidewalkPOI::Border(i, ref loc) => {
+                            self.events.push(Event::PersonEntersMap(
+                                person.id,
+                                AgentID::Pedestrian(person.ped),
+                                i,
+                                loc.clone(),
+                            ));
+                            PersonState::OffMap
+                        }
+                        SidewalkPOI::SuddenlyAppear => {
+                            // Unclear which end of the sidewalk this person should be associated
+                            // with. For interactively spawned people, doesn't really matter.
+                            self.events.push(Event::PersonEntersMap(
+                                person.id,
+                                AgentID::Pedestrian(person.ped),
+                                map.get_l(start.sidewalk_pos.lane()).src_i,
+                                None,
+                            ));
+                            PersonState::OffMap
+                        }
+                        _ => unreachable!(),
+                    }
+                );
+                person.state = PersonState::Trip(trip);
+
+                // schedule_trip made sure this exists
+                let walk_to = SidewalkSpot::ride_hail_stop(start.sidewalk_pos, map).unwrap();
+                let req = maybe_req.unwrap();
+                if let Some(path) = maybe_path {
+                    scheduler.push(
+                        now,
+                        Command::SpawnPed(CreatePedestrian {
+                            id: person.ped,
+                            speed: person.ped_speed,
+                            start,
+                            goal: walk_to,
+                            path,
+                            req,
+                            trip,
+                            person: person.id,
+                        }),
+                    );
+                } else {
+                    self.events.push(Event::Alert(
+                        AlertLocation::Person(person.id),
+                        format!("UsingRideHail trip couldn't find the first path {}", req),
+                    ));
+                    self.abort_trip(now, trip, None, parking, scheduler, map);
+                }
+            }
             TripSpec::Remote {
                 trip_time, from, ..
             } => {
@@ -1318,6 +1575,7 @@ impl TripManager {
                         // TODO Not true for long. People will be able to spawn at borders already
                         // on a bus.
                         TripMode::Transit => AgentType::Pedestrian,
+                        TripMode::RideHail => AgentType::Pedestrian,
                     };
                     times.push((t.info.departure, agent_type));
                 }
@@ -1348,6 +1606,7 @@ pub struct TripInfo {
     pub mode: TripMode,
     pub start: TripEndpoint,
     pub end: TripEndpoint,
+    pub purpose: TripPurpose,
     // Did a ScenarioModifier apply to this?
     pub modified: bool,
 }
@@ -1421,6 +1680,11 @@ pub enum TripLeg {
     Drive(CarID, DrivingGoal),
     // Maybe get off at a stop, maybe ride off-map
     RideBus(BusRouteID, Option<BusStopID>),
+    // Get dropped off here
+    RideHail(SidewalkSpot),
+    // Ride a shared bike, then return it to this dock. Like buses, the CarID only refers to a
+    // vehicle while it's being ridden.
+    BikeShare(CarID, BikeShareDockID),
     Remote(OffMapLocation),
 }
 
@@ -1430,6 +1694,7 @@ pub enum TripMode {
     Bike,
     Transit,
     Drive,
+    RideHail,
 }
 
 impl TripMode {
@@ -1439,6 +1704,7 @@ impl TripMode {
             TripMode::Bike,
             TripMode::Transit,
             TripMode::Drive,
+            TripMode::RideHail,
         ]
     }
 
@@ -1448,6 +1714,7 @@ impl TripMode {
             TripMode::Bike => "bike",
             TripMode::Transit => "use transit",
             TripMode::Drive => "drive",
+            TripMode::RideHail => "use ride-hailing",
         }
     }
 
@@ -1458,6 +1725,7 @@ impl TripMode {
             TripMode::Bike => "biking",
             TripMode::Transit => "using transit",
             TripMode::Drive => "driving",
+            TripMode::RideHail => "riding in a ride-hail vehicle",
         }
     }
 
@@ -1467,6 +1735,7 @@ impl TripMode {
             TripMode::Bike => "Bike",
             TripMode::Transit => "Bus",
             TripMode::Drive => "Car",
+            TripMode::RideHail => "Ride-hail",
         }
     }
 
@@ -1476,7 +1745,7 @@ impl TripMode {
             TripMode::Bike => PathConstraints::Bike,
             // TODO WRONG
             TripMode::Transit => PathConstraints::Bus,
-            TripMode::Drive => PathConstraints::Car,
+            TripMode::Drive | TripMode::RideHail => PathConstraints::Car,
         }
     }
 
@@ -1491,6 +1760,60 @@ impl TripMode {
     }
 }
 
+// The activity at the destination of a trip. Based on Soundcast's categories.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, Copy, PartialOrd, Ord)]
+pub enum TripPurpose {
+    Home,
+    Work,
+    School,
+    Escort,
+    PersonalBusiness,
+    Shopping,
+    Meal,
+    Social,
+    Recreation,
+    Medical,
+    ParkAndRideTransfer,
+    // Trips from sources that don't say why somebody's traveling, like generated or OD demand
+    Unknown,
+}
+
+impl TripPurpose {
+    // Everything except Unknown
+    pub fn all() -> Vec<TripPurpose> {
+        vec![
+            TripPurpose::Home,
+            TripPurpose::Work,
+            TripPurpose::School,
+            TripPurpose::Escort,
+            TripPurpose::PersonalBusiness,
+            TripPurpose::Shopping,
+            TripPurpose::Meal,
+            TripPurpose::Social,
+            TripPurpose::Recreation,
+            TripPurpose::Medical,
+            TripPurpose::ParkAndRideTransfer,
+        ]
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            TripPurpose::Home => "going home",
+            TripPurpose::Work => "work",
+            TripPurpose::School => "school",
+            TripPurpose::Escort => "escorting somebody",
+            TripPurpose::PersonalBusiness => "personal business",
+            TripPurpose::Shopping => "shopping",
+            TripPurpose::Meal => "eating out",
+            TripPurpose::Social => "social",
+            TripPurpose::Recreation => "recreation",
+            TripPurpose::Medical => "medical",
+            TripPurpose::ParkAndRideTransfer => "park-and-ride transfer",
+            TripPurpose::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
 pub enum TripEndpoint {
     Bldg(BuildingID),
@@ -1535,7 +1858,7 @@ pub struct Person {
     pub id: PersonID,
     pub orig_id: Option<OrigPersonID>,
     pub trips: Vec<TripID>,
-    // TODO home
+    pub attributes: PersonAttributes,
     pub state: PersonState,
 
     pub ped: PedestrianID,
@@ -1544,6 +1867,7 @@ pub struct Person {
     pub vehicles: Vec<Vehicle>,
 
     delayed_trips: Vec<(TripID, TripSpec, Option<PathRequest>, Option<Path>)>,
+    // A bus, train, or ride-hail vehicle
     on_bus: Option<CarID>,
 }
 